@@ -0,0 +1,100 @@
+//! Build script generating the Stable ABI symbol table and compressing
+//! the embedded Module-Definition files when the `compress` feature is enabled.
+
+use std::collections::BTreeMap;
+use std::fs::{read_to_string, write};
+use std::path::PathBuf;
+
+/// CPython minor versions with the version-specific definitions
+const CPYTHON_MINOR_VERSIONS: std::ops::RangeInclusive<u8> = 7..=13;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    generate_stable_abi_symbols();
+
+    #[cfg(feature = "compress")]
+    compress_def_files();
+}
+
+/// Returns the exported symbol names and the `DATA` flags of the `.def` file.
+fn def_exports(path: &str) -> Vec<(String, bool)> {
+    println!("cargo:rerun-if-changed={}", path);
+
+    let content = read_to_string(path).unwrap();
+
+    content
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != "EXPORTS")
+        .skip(1)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(|line| {
+            let mut tokens = line.split_whitespace();
+            let name = tokens.next().unwrap().to_owned();
+            (name, tokens.any(|token| token == "DATA"))
+        })
+        .collect()
+}
+
+/// Writes the `STABLE_ABI_SYMBOLS` table into `OUT_DIR/stable_abi_symbols.rs`.
+///
+/// The symbols are sorted by name and annotated with the earliest
+/// version-specific CPython definitions exporting them.
+fn generate_stable_abi_symbols() {
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+
+    let mut added_in = BTreeMap::new();
+    for minor in CPYTHON_MINOR_VERSIONS {
+        for (name, _) in def_exports(&format!("src/python3{}.def", minor)) {
+            added_in.entry(name).or_insert(minor);
+        }
+    }
+
+    let symbols: BTreeMap<_, _> = def_exports("src/python3.def").into_iter().collect();
+
+    let mut table = String::from("&[\n");
+    for (name, data) in symbols {
+        let kind = if data { "Data" } else { "Function" };
+        let added_in = match added_in.get(&name) {
+            Some(minor) => format!("Some((3, {}))", minor),
+            None => "None".to_owned(),
+        };
+
+        table.push_str(&format!(
+            "    Symbol {{ name: \"{}\", kind: SymbolKind::{}, added_in: {} }},\n",
+            name, kind, added_in
+        ));
+    }
+    table.push(']');
+
+    write(out_dir.join("stable_abi_symbols.rs"), table).unwrap();
+}
+
+/// Deflate-compresses `src/*.def` into `OUT_DIR/*.def.deflate`.
+#[cfg(feature = "compress")]
+fn compress_def_files() {
+    use std::fs::{read, read_dir};
+
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+
+    println!("cargo:rerun-if-changed=src");
+
+    for entry in read_dir("src").unwrap() {
+        let path = entry.unwrap().path();
+
+        if path.extension() != Some("def".as_ref()) {
+            continue;
+        }
+
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        let data = read(&path).unwrap();
+        let compressed = miniz_oxide::deflate::compress_to_vec(&data, 10);
+
+        let mut name = path.file_name().unwrap().to_owned();
+        name.push(".deflate");
+
+        write(out_dir.join(name), compressed).unwrap();
+    }
+}
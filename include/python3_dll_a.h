@@ -0,0 +1,46 @@
+/*
+ * python3-dll-a C API
+ *
+ * Build the library with the `capi` crate feature enabled:
+ *
+ *     cargo rustc --release --lib --features capi --crate-type cdylib
+ *     cargo rustc --release --lib --features capi --crate-type staticlib
+ */
+
+#ifndef PYTHON3_DLL_A_H
+#define PYTHON3_DLL_A_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * Generates the Python DLL import library for the compile `target` triple
+ * (e.g. "x86_64-pc-windows-msvc") and the Python `version` ("abi3", "X.Y",
+ * "X.Yt" or "pypyX.Y") in the `out_dir` directory.
+ *
+ * The Stable ABI `python3.dll` import library is generated if `version`
+ * is NULL.
+ *
+ * Returns 0 on success and the failure category code on failure:
+ * 1 for the I/O errors, 2 for the invalid arguments, 3 for a missing
+ * `dlltool`, 4 for an unsupported target, 5 for an unsupported Python
+ * version, 6 for a failed `dlltool` run and 7 for a non-Windows target.
+ * A panic inside the library is caught and reported as an I/O error.
+ */
+int python3_dll_a_generate(const char *target, const char *version, const char *out_dir);
+
+/*
+ * Returns the error message of the last failed `python3_dll_a_generate()`
+ * call on the calling thread, or NULL if it succeeded.
+ *
+ * The string remains valid until the next `python3_dll_a_generate()` call
+ * on the same thread.
+ */
+const char *python3_dll_a_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PYTHON3_DLL_A_H */
@@ -0,0 +1,201 @@
+//! `cargo python3-dll` subcommand
+//!
+//! Generates the Python DLL import libraries for the cargo build target
+//! into `<TARGET_DIR>/python3-dll-a/<TRIPLE>` by running the `python3-dll-a`
+//! executable installed alongside this one.
+//!
+//! This binary is only built with the `cli` crate feature enabled.
+
+#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::io_other_error)]
+
+use std::env;
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+/// Command line usage summary
+const USAGE: &str = "\
+Usage: cargo python3-dll [--target <TRIPLE>] [--manifest-path <PATH>] [GENERATE OPTIONS]
+
+Generates the Python DLL import libraries for the cargo build target
+into <TARGET_DIR>/python3-dll-a/<TRIPLE>, where <TARGET_DIR> is the cargo
+target directory of the current package. Point `PYO3_CROSS_LIB_DIR`
+at the printed directory to use them in the PyO3 cross builds.
+
+Options:
+  --target <TRIPLE>       Windows target triple, defaults to `CARGO_BUILD_TARGET`
+  --manifest-path <PATH>  Path to the package `Cargo.toml`
+  -h, --help              Print this help message
+
+The remaining options (`--python-version`, `--all-versions`, `--format`)
+are passed to `python3-dll-a generate`.
+";
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Resolves the target and the output directory and runs the generator.
+fn run() -> Result<ExitCode> {
+    let mut args = env::args().skip(1).peekable();
+
+    // Cargo passes the subcommand name as the first argument.
+    if args.peek().map(String::as_str) == Some("python3-dll") {
+        args.next();
+    }
+
+    let mut target = env::var("CARGO_BUILD_TARGET").ok();
+    let mut manifest_path = None;
+    let mut generate_args = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = Some(option_value(&arg, &mut args)?),
+            "--manifest-path" => manifest_path = Some(option_value(&arg, &mut args)?),
+            "--targets" | "--out-dir" => {
+                let msg = format!("'{}' is set by cargo python3-dll, use --target", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return Ok(ExitCode::SUCCESS);
+            }
+            _ => generate_args.push(arg),
+        }
+    }
+
+    let target = target.ok_or_else(|| {
+        let msg = "No build target specified, use --target or set CARGO_BUILD_TARGET";
+        Error::new(ErrorKind::InvalidInput, msg)
+    })?;
+
+    let out_dir = target_directory(manifest_path.as_deref())?.join("python3-dll-a");
+
+    let status = Command::new(generator_path())
+        .arg("generate")
+        .arg("--targets")
+        .arg(&target)
+        .arg("--out-dir")
+        .arg(&out_dir)
+        .args(&generate_args)
+        .status()
+        .map_err(|e| {
+            let msg = format!("Failed to run python3-dll-a: {}", e);
+            Error::new(e.kind(), msg)
+        })?;
+
+    if !status.success() {
+        return Ok(ExitCode::FAILURE);
+    }
+
+    eprintln!("PYO3_CROSS_LIB_DIR={}", out_dir.join(&target).display());
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Returns the `python3-dll-a` executable path next to the current one,
+/// or just the executable name to be looked up in `PATH`.
+fn generator_path() -> PathBuf {
+    let name = format!("python3-dll-a{}", env::consts::EXE_SUFFIX);
+
+    match env::current_exe() {
+        Ok(exe) => match exe.with_file_name(&name) {
+            path if path.is_file() => path,
+            _ => name.into(),
+        },
+        Err(_) => name.into(),
+    }
+}
+
+/// Returns the cargo target directory of the current package.
+fn target_directory(manifest_path: Option<&str>) -> Result<PathBuf> {
+    if let Some(dir) = env::var_os("CARGO_TARGET_DIR") {
+        return Ok(dir.into());
+    }
+
+    // Cargo sets `CARGO` to its own path when running subcommands.
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+
+    let mut command = Command::new(cargo);
+    command.args(["metadata", "--format-version", "1", "--no-deps"]);
+    if let Some(path) = manifest_path {
+        command.arg("--manifest-path").arg(path);
+    }
+
+    let output = command.output()?;
+    if !output.status.success() {
+        let msg = format!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+        return Err(Error::other(msg));
+    }
+
+    let metadata = String::from_utf8_lossy(&output.stdout);
+
+    json_string_value(&metadata, "target_directory")
+        .map(PathBuf::from)
+        .ok_or_else(|| {
+            let msg = "No target_directory in the cargo metadata output";
+            Error::new(ErrorKind::InvalidData, msg)
+        })
+}
+
+/// Extracts the string value of the top level `key` from the compact
+/// `cargo metadata` JSON output.
+fn json_string_value(json: &str, key: &str) -> Option<String> {
+    let pattern = format!("\"{}\":\"", key);
+    let start = json.rfind(&pattern)? + pattern.len();
+
+    let mut value = String::new();
+    let mut chars = json[start..].chars();
+
+    loop {
+        match chars.next()? {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+/// Returns the value of the command line option `name`.
+fn option_value(name: &str, args: &mut impl Iterator<Item = String>) -> Result<String> {
+    args.next().ok_or_else(|| {
+        let msg = format!("Missing value for the '{}' option", name);
+        Error::new(ErrorKind::InvalidInput, msg)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_target_directory() {
+        let metadata = r#"{"packages":[],"target_directory":"C:\\work\\target","version":1}"#;
+
+        assert_eq!(
+            json_string_value(metadata, "target_directory").as_deref(),
+            Some("C:\\work\\target")
+        );
+        assert_eq!(json_string_value(metadata, "workspace_root"), None);
+    }
+}
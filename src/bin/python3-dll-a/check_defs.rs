@@ -0,0 +1,45 @@
+//! `check-defs` command implementation
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use python3_dll_a::check_def_consistency;
+
+use crate::option_value;
+
+/// Checks the version-specific definitions against `python3.def`.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut def_dir = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--def-dir" => def_dir = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    let missing = check_def_consistency(def_dir.as_deref())?;
+
+    for symbol in &missing {
+        let (major, minor) = symbol.version;
+        let (since_major, since_minor) = symbol.since;
+
+        println!(
+            "python{}{}.def: missing Stable ABI symbol {} (exported since {}.{})",
+            major, minor, symbol.name, since_major, since_minor
+        );
+    }
+
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        let msg = format!(
+            "{} Stable ABI symbols missing from the versioned definitions",
+            missing.len()
+        );
+        Err(Error::new(ErrorKind::InvalidData, msg))
+    }
+}
@@ -0,0 +1,127 @@
+//! `cross-env` command implementation
+
+use std::env;
+use std::fs::write;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use python3_dll_a::ImportLibraryGenerator;
+
+use crate::generate::{parse_python_version, parse_target};
+use crate::option_value;
+
+/// Environment variable export syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shell {
+    /// POSIX `sh`: `export NAME='value'`
+    Sh,
+    /// Pwsh: `$env:NAME = 'value'`
+    Pwsh,
+    /// Windows `cmd.exe`: `set "NAME=value"`
+    Cmd,
+}
+
+/// Generates the PyO3 cross-compilation import libraries for the target
+/// and prints the environment variable exports for the `cargo build`.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut target = env::var("CARGO_BUILD_TARGET").ok();
+    let mut flavor = None;
+    let mut out_dir = PathBuf::from(".");
+    let mut shell = Shell::Sh;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => target = Some(option_value(&arg, &mut args)?),
+            "--python-version" => {
+                flavor = Some(parse_python_version(&option_value(&arg, &mut args)?)?);
+            }
+            "--out-dir" => out_dir = PathBuf::from(option_value(&arg, &mut args)?),
+            "--shell" => shell = parse_shell(&option_value(&arg, &mut args)?)?,
+            "--output" => output = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    let target = target.ok_or_else(|| {
+        let msg = "No build target specified, use --target or set CARGO_BUILD_TARGET";
+        Error::new(ErrorKind::InvalidInput, msg)
+    })?;
+    let (arch, env) = parse_target(&target)?;
+
+    let mut generator = ImportLibraryGenerator::new(&arch, &env);
+    if let Some(flavor) = &flavor {
+        generator
+            .implementation(flavor.implementation)
+            .version(flavor.version)
+            .abiflags(flavor.abiflags.as_deref());
+    }
+
+    // The exports are printed on stdout, the progress goes to stderr.
+    let dir = out_dir.join(&target);
+    for report in generator.generate_cross_lib_dir(&dir)? {
+        eprintln!("Generated {}", report.implib.display());
+    }
+
+    let mut vars = vec![("CARGO_BUILD_TARGET", target.clone())];
+    vars.extend(generator.pyo3_cross_env(&dir)?);
+
+    let exports: String = vars
+        .iter()
+        .map(|(name, value)| export_line(shell, name, value))
+        .collect();
+
+    match output {
+        Some(path) => {
+            write(&path, exports)?;
+            eprintln!("Wrote the environment variables to {}", path.display());
+        }
+        None => print!("{}", exports),
+    }
+
+    Ok(())
+}
+
+/// Parses the `sh`, `powershell` or `cmd` shell name.
+fn parse_shell(value: &str) -> Result<Shell> {
+    match value {
+        "sh" => Ok(Shell::Sh),
+        "powershell" => Ok(Shell::Pwsh),
+        "cmd" => Ok(Shell::Cmd),
+        _ => {
+            let msg = format!("Unsupported shell '{}'", value);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+}
+
+/// Formats the `shell` command setting the environment variable `name`.
+fn export_line(shell: Shell, name: &str, value: &str) -> String {
+    match shell {
+        Shell::Sh => format!("export {}='{}'\n", name, value.replace('\'', r"'\''")),
+        Shell::Pwsh => format!("$env:{} = '{}'\n", name, value.replace('\'', "''")),
+        Shell::Cmd => format!("set \"{}={}\"\r\n", name, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn export_line() {
+        let line = super::export_line(Shell::Sh, "PYO3_CROSS_LIB_DIR", "/tmp/it's here");
+        assert_eq!(line, "export PYO3_CROSS_LIB_DIR='/tmp/it'\\''s here'\n");
+
+        let line = super::export_line(Shell::Pwsh, "PYO3_CROSS_LIB_DIR", "C:\\it's");
+        assert_eq!(line, "$env:PYO3_CROSS_LIB_DIR = 'C:\\it''s'\n");
+
+        let line = super::export_line(Shell::Cmd, "PYO3_CROSS_PYTHON_VERSION", "3.12");
+        assert_eq!(line, "set \"PYO3_CROSS_PYTHON_VERSION=3.12\"\r\n");
+
+        assert!(parse_shell("fish").is_err());
+    }
+}
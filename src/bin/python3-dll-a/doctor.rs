@@ -0,0 +1,100 @@
+//! `doctor` command implementation
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use python3_dll_a::{probe_tools, ErrorCategory, ImportLibraryGenerator, ToolFlavor, ToolProbe};
+
+use crate::generate::parse_target;
+use crate::option_value;
+
+/// Probes the import library generation tools for every requested target.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut targets = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--target" => targets.push(option_value(&arg, &mut args)?),
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    if targets.is_empty() {
+        targets.push("x86_64-pc-windows-msvc".to_owned());
+    }
+
+    let mut unusable = 0;
+
+    for target in &targets {
+        let (arch, env) = parse_target(target)?;
+        let probes = probe_tools(&arch, &env);
+
+        println!("{}:", target);
+        for probe in &probes {
+            print_probe(probe);
+        }
+
+        if probes.iter().any(ToolProbe::is_usable) {
+            // Same discovery as `generate`, explicit tools included.
+            let resolved = ImportLibraryGenerator::new(&arch, &env).resolve(Path::new("."))?;
+            if let (Some(tool), Some(source)) = (resolved.tool, resolved.tool_source) {
+                println!("  selected  {} ({})", tool, source);
+            }
+        } else {
+            unusable += 1;
+            println!("  no usable tool for this target");
+            if let Some(probe) = probes.iter().find(|probe| probe.supports_target) {
+                println!("  {}", probe.install_hint(&arch));
+            }
+        }
+    }
+
+    if unusable == 0 {
+        Ok(())
+    } else {
+        let msg = format!(
+            "No usable import library generation tool for {} of {} targets",
+            unusable,
+            targets.len()
+        );
+        Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg))
+    }
+}
+
+/// Prints the tool probe result line.
+fn print_probe(probe: &ToolProbe) {
+    let status = match (&probe.path, probe.supports_target) {
+        (_, false) => "n/a",
+        (Some(_), true) => "ok",
+        (None, true) => "missing",
+    };
+
+    let mut location = match &probe.path {
+        Some(path) => path.display().to_string(),
+        None => probe.command.clone(),
+    };
+    if let Some(version) = &probe.version {
+        location = format!("{}  {}", location, version);
+    }
+
+    println!(
+        "  {:<9} {:<6} {}",
+        status,
+        flavor_name(probe.flavor),
+        location
+    );
+}
+
+/// Returns the `--require-tool` name of the tool `flavor`.
+fn flavor_name(flavor: ToolFlavor) -> &'static str {
+    match flavor {
+        ToolFlavor::Mingw => "mingw",
+        ToolFlavor::Llvm => "llvm",
+        ToolFlavor::LibExe => "lib",
+        ToolFlavor::Zig => "zig",
+        _ => "other",
+    }
+}
@@ -0,0 +1,641 @@
+//! Import library generation (batch mode) implementation
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use python3_dll_a::{
+    ErrorCategory, GenerateConfig, GenerateMatrix, GenerationReport, LineEnding, OverwritePolicy,
+    PythonImplementation, ResolvedConfig, TargetInfo, ToolFlavor, UnknownVersionPolicy,
+};
+
+use crate::json::Json;
+use crate::option_value;
+
+/// Python implementation, version and ABI flags
+type VersionEntry = (PythonImplementation, Option<(u8, u8)>, Option<&'static str>);
+
+/// Python DLL flavors with the embedded definitions
+const ALL_VERSIONS: [VersionEntry; 13] = [
+    (PythonImplementation::CPython, None, None),
+    (PythonImplementation::CPython, Some((3, 7)), None),
+    (PythonImplementation::CPython, Some((3, 8)), None),
+    (PythonImplementation::CPython, Some((3, 9)), None),
+    (PythonImplementation::CPython, Some((3, 10)), None),
+    (PythonImplementation::CPython, Some((3, 11)), None),
+    (PythonImplementation::CPython, Some((3, 12)), None),
+    (PythonImplementation::CPython, Some((3, 13)), None),
+    (PythonImplementation::CPython, Some((3, 13)), Some("t")),
+    (PythonImplementation::PyPy, Some((3, 7)), None),
+    (PythonImplementation::PyPy, Some((3, 8)), None),
+    (PythonImplementation::PyPy, Some((3, 9)), None),
+    (PythonImplementation::PyPy, Some((3, 10)), None),
+];
+
+/// Python DLL flavor to generate the import library for
+#[derive(Debug, Clone)]
+pub struct Flavor {
+    /// Python interpreter implementation
+    pub implementation: PythonImplementation,
+    /// Python version, `None` for the Stable ABI `python3.dll`
+    pub version: Option<(u8, u8)>,
+    /// Python ABI flags
+    pub abiflags: Option<String>,
+}
+
+impl Flavor {
+    /// Returns the human-readable flavor name, e.g. `abi3`, `3.13t` or `pypy3.10`.
+    fn label(&self) -> String {
+        let prefix = match self.implementation {
+            PythonImplementation::CPython => "",
+            PythonImplementation::PyPy => "pypy",
+        };
+
+        match self.version {
+            None => "abi3".to_owned(),
+            Some((major, minor)) => format!(
+                "{}{}.{}{}",
+                prefix,
+                major,
+                minor,
+                self.abiflags.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// Generates the import libraries for all requested targets and versions.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut config_file = None;
+    let mut matrix_file = None;
+    let mut out_dir = None;
+    let mut targets = Vec::new();
+    let mut flavors = Vec::new();
+    let mut json = false;
+    let mut cmake = false;
+    let mut pkg_config = false;
+    let mut pyo3_config = false;
+    let mut delay_load = false;
+    let mut export_file = false;
+    let mut read_only = false;
+    let mut report_file = false;
+    let mut symbols_file = false;
+    let mut depfile = false;
+    let mut c_header = false;
+    let mut deny_tool_warnings = false;
+    let mut verbose = false;
+    let mut dlltool = None;
+    let mut lib_exe = None;
+    let mut msvc_lib = false;
+    let mut reuse_existing = false;
+    let mut require_tool = None;
+    let mut unknown_version = None;
+    let mut overwrite = None;
+    let mut line_ending = None;
+    let mut hermetic = false;
+    let mut stub_dll = false;
+    let mut print_config = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config_file = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--matrix" => matrix_file = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--out-dir" => out_dir = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--targets" => {
+                let value = option_value(&arg, &mut args)?;
+                targets.extend(value.split(',').map(|t| t.trim().to_owned()));
+            }
+            "--all-versions" => flavors.extend(ALL_VERSIONS.iter().map(
+                |(implementation, version, abiflags)| Flavor {
+                    implementation: *implementation,
+                    version: *version,
+                    abiflags: abiflags.map(ToOwned::to_owned),
+                },
+            )),
+            "--format" => match option_value(&arg, &mut args)?.as_str() {
+                "text" => json = false,
+                "json" => json = true,
+                format => {
+                    let msg = format!("Unsupported output format '{}'", format);
+                    return Err(Error::new(ErrorKind::InvalidInput, msg));
+                }
+            },
+            "--cmake" => cmake = true,
+            "--pkg-config" => pkg_config = true,
+            "--pyo3-config" => pyo3_config = true,
+            "--delay-load" => delay_load = true,
+            "--export-file" => export_file = true,
+            "--read-only" => read_only = true,
+            "--report-file" => report_file = true,
+            "--symbols-file" => symbols_file = true,
+            "--depfile" => depfile = true,
+            "--c-header" => c_header = true,
+            "--deny-tool-warnings" => deny_tool_warnings = true,
+            "--verbose" => verbose = true,
+            "--dlltool" => dlltool = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--lib-exe" => lib_exe = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+            "--msvc-lib" => msvc_lib = true,
+            "--reuse-existing" => reuse_existing = true,
+            "--unknown-version" => {
+                unknown_version = Some(parse_unknown_version(&option_value(&arg, &mut args)?)?);
+            }
+            "--overwrite" => {
+                overwrite = Some(parse_overwrite(&option_value(&arg, &mut args)?)?);
+            }
+            "--line-ending" => {
+                line_ending = Some(parse_line_ending(&option_value(&arg, &mut args)?)?);
+            }
+            "--require-tool" => {
+                require_tool = Some(parse_tool_flavor(&option_value(&arg, &mut args)?)?);
+            }
+            "--hermetic" => hermetic = true,
+            "--stub-dll" => stub_dll = true,
+            "--print-config" => print_config = true,
+            "--python-version" => {
+                flavors.push(parse_python_version(&option_value(&arg, &mut args)?)?);
+            }
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    // The command line options take precedence over the config and matrix files.
+    let mut configs = match (matrix_file, config_file) {
+        (Some(_), Some(_)) => {
+            let msg = "The --matrix and --config options are mutually exclusive";
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+        (Some(path), None) => GenerateMatrix::from_path(&path)?.builds,
+        (None, Some(path)) => vec![GenerateConfig::from_path(&path)?],
+        (None, None) => vec![GenerateConfig::default()],
+    };
+
+    for config in &mut configs {
+        if let Some(out_dir) = &out_dir {
+            config.out_dir = out_dir.clone();
+        }
+        if !targets.is_empty() {
+            config.targets = targets.clone();
+        }
+        if dlltool.is_some() {
+            config.dlltool = dlltool.clone();
+        }
+        if lib_exe.is_some() {
+            config.lib_exe = lib_exe.clone();
+        }
+        if require_tool.is_some() {
+            config.require_tool = require_tool;
+        }
+        if let Some(policy) = unknown_version {
+            config.unknown_version = policy;
+        }
+        if let Some(policy) = overwrite {
+            config.overwrite = policy;
+        }
+        if let Some(line_ending) = line_ending {
+            config.line_ending = line_ending;
+        }
+        config.cmake_script |= cmake;
+        config.pkg_config_file |= pkg_config;
+        config.pyo3_config_file |= pyo3_config;
+        config.delay_load |= delay_load;
+        config.msvc_lib |= msvc_lib;
+        config.reuse_existing |= reuse_existing;
+        config.read_only |= read_only;
+        config.report_file |= report_file;
+        config.symbols_file |= symbols_file;
+        config.depfile |= depfile;
+        config.c_header |= c_header;
+        config.deny_tool_warnings |= deny_tool_warnings;
+        config.hermetic |= hermetic;
+    }
+
+    let mut summary = Vec::new();
+    let mut resolved = Vec::new();
+
+    for config in &configs {
+        let mut config_flavors = flavors.clone();
+        if config_flavors.is_empty() {
+            for version in &config.versions {
+                config_flavors.push(parse_python_version(version)?);
+            }
+        }
+
+        for target in &config.targets {
+            let (arch, env) = parse_target(target)?;
+            for flavor in config_flavors.iter().cloned() {
+                // The generator places the artifacts according to the layout.
+                let dir = config.out_dir_for(flavor.implementation);
+
+                let mut generator = config.generator(&arch, &env);
+                generator
+                    .implementation(flavor.implementation)
+                    .version(flavor.version)
+                    .abiflags(flavor.abiflags.as_deref())
+                    .export_file(export_file);
+                if verbose {
+                    generator.verbosity(2);
+                }
+
+                if print_config {
+                    resolved.push((target.clone(), flavor, generator.resolve(&dir)));
+                    continue;
+                }
+
+                let mut result = generator.generate_with_report(&dir);
+                if stub_dll && result.is_ok() {
+                    if let Err(e) = generator.generate_stub_dll(&dir) {
+                        result = Err(e);
+                    }
+                }
+
+                summary.push((target.clone(), flavor, result));
+            }
+        }
+    }
+
+    if print_config {
+        if json {
+            print_config_json(&resolved);
+        } else {
+            print_config_text(&resolved);
+        }
+
+        return match resolved.into_iter().find_map(|(_, _, result)| result.err()) {
+            Some(e) => Err(e),
+            None => Ok(()),
+        };
+    }
+
+    let failed = summary
+        .iter()
+        .filter(|(_, _, result)| result.is_err())
+        .count();
+
+    if json {
+        print_json(&summary);
+    } else {
+        print_summary(&summary, verbose);
+    }
+
+    if failed == 0 {
+        return Ok(());
+    }
+
+    eprintln!("{} of {} import libraries failed", failed, summary.len());
+
+    // Exit with the status of the first failure.
+    match summary.into_iter().find_map(|(_, _, result)| result.err()) {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// Generation result for a target and Python flavor
+type Artifact = (String, Flavor, Result<GenerationReport>);
+
+/// Resolved generator decisions for a target and Python flavor
+type Resolution = (String, Flavor, Result<ResolvedConfig>);
+
+/// Prints the resolved generator decisions of every artifact.
+fn print_config_text(resolved: &[Resolution]) {
+    for (target, flavor, result) in resolved {
+        println!("{} {}:", target, flavor.label());
+
+        match result {
+            Ok(config) => {
+                println!("  target       {}", config.target);
+                println!("  python       {}", config.python);
+                println!("  dll          {}", config.dll_name);
+                println!("  definitions  {}", config.def_source);
+                println!("  backend      {}", backend_name(config));
+                if let (Some(tool), Some(source)) = (&config.tool, &config.tool_source) {
+                    println!("  tool         {} ({})", tool, source);
+                }
+                println!("  implib       {}", config.implib.display());
+            }
+            Err(e) => println!("  error        {}", e),
+        }
+    }
+}
+
+/// Prints the resolved generator decisions as a JSON object.
+fn print_config_json(resolved: &[Resolution]) {
+    let artifacts = resolved
+        .iter()
+        .map(|(target, flavor, result)| {
+            let mut members = vec![
+                ("triple", target.as_str().into()),
+                ("python", flavor.label().into()),
+            ];
+
+            match result {
+                Ok(config) => members.extend([
+                    ("target", config.target.as_str().into()),
+                    ("dll", config.dll_name.as_str().into()),
+                    ("definitions", config.def_source.as_str().into()),
+                    ("backend", backend_name(config).into()),
+                    ("tool", config.tool.as_deref().into()),
+                    ("tool_source", config.tool_source.as_deref().into()),
+                    (
+                        "implib",
+                        config.implib.to_string_lossy().into_owned().into(),
+                    ),
+                ]),
+                Err(e) => members.push(("error", e.to_string().into())),
+            }
+
+            Json::Object(members)
+        })
+        .collect();
+
+    println!(
+        "{}",
+        Json::Object(vec![("artifacts", Json::Array(artifacts))])
+    );
+}
+
+/// Returns the lowercase backend name, e.g. `dlltool`.
+fn backend_name(config: &ResolvedConfig) -> String {
+    format!("{:?}", config.backend).to_lowercase()
+}
+
+/// Prints the human-readable per-artifact summary.
+///
+/// The `verbose` summary also lists the generation phase timings.
+fn print_summary(summary: &[Artifact], verbose: bool) {
+    println!("Summary:");
+    for (target, flavor, result) in summary {
+        let label = flavor.label();
+
+        match result {
+            Ok(report) => {
+                println!(
+                    "  ok      {} {}: {}",
+                    target,
+                    label,
+                    report.implib.display()
+                );
+
+                for warning in &report.warnings {
+                    println!("          {}", warning);
+                }
+
+                if verbose {
+                    let timings = &report.timings;
+                    println!(
+                        "          discovery {:?}, def write {:?}, tool {:?}, total {:?}",
+                        timings.discovery,
+                        timings.def_write,
+                        timings.tool,
+                        timings.total()
+                    );
+                }
+            }
+            Err(e) => println!("  FAILED  {} {}: {}", target, label, e),
+        }
+    }
+}
+
+/// Prints the generation report as a single JSON object.
+fn print_json(summary: &[Artifact]) {
+    let artifacts = summary
+        .iter()
+        .map(|(target, flavor, result)| {
+            let implementation = match flavor.implementation {
+                PythonImplementation::CPython => "CPython",
+                PythonImplementation::PyPy => "PyPy",
+            };
+            let version = flavor
+                .version
+                .map(|(major, minor)| format!("{}.{}", major, minor));
+
+            let mut members = vec![
+                ("target", target.as_str().into()),
+                ("implementation", implementation.into()),
+                ("version", version.into()),
+                ("abiflags", flavor.abiflags.clone().into()),
+            ];
+
+            match result {
+                Ok(report) => {
+                    let warnings = report.warnings.iter().map(|w| w.as_str().into());
+                    let micros = |duration: Duration| Json::Number(duration.as_micros() as u64);
+                    let timings = Json::Object(vec![
+                        ("discovery", micros(report.timings.discovery)),
+                        ("def_write", micros(report.timings.def_write)),
+                        ("tool", micros(report.timings.tool)),
+                        ("total", micros(report.timings.total())),
+                    ]);
+                    let export_file = report
+                        .export_file
+                        .as_ref()
+                        .map(|path| path.display().to_string());
+
+                    members.extend([
+                        ("status", "ok".into()),
+                        ("implib", report.implib.display().to_string().into()),
+                        ("def", report.def.display().to_string().into()),
+                        ("export_file", export_file.into()),
+                        ("tool", report.tool.as_str().into()),
+                        ("implib_sha256", report.implib_sha256().ok().into()),
+                        ("def_sha256", report.def_sha256().ok().into()),
+                        ("warnings", Json::Array(warnings.collect())),
+                        ("timings_us", timings),
+                    ]);
+                }
+                Err(e) => {
+                    members.extend([("status", "failed".into()), ("error", e.to_string().into())]);
+                }
+            }
+
+            Json::Object(members)
+        })
+        .collect();
+
+    let failed = summary
+        .iter()
+        .filter(|(_, _, result)| result.is_err())
+        .count();
+
+    let report = Json::Object(vec![
+        (
+            "generator",
+            format!("python3-dll-a {}", env!("CARGO_PKG_VERSION")).into(),
+        ),
+        ("success", Json::Bool(failed == 0)),
+        ("failed", Json::Number(failed as u64)),
+        ("artifacts", Json::Array(artifacts)),
+    ]);
+
+    println!("{}", report);
+}
+
+/// Parses the `error`, `use-latest` or `stable-abi` unknown version policy name.
+fn parse_unknown_version(value: &str) -> Result<UnknownVersionPolicy> {
+    match value {
+        "error" => Ok(UnknownVersionPolicy::Error),
+        "use-latest" => Ok(UnknownVersionPolicy::UseLatest),
+        "stable-abi" => Ok(UnknownVersionPolicy::StableAbi),
+        _ => {
+            let msg = format!("Unsupported unknown version policy '{}'", value);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+}
+
+/// Parses the `overwrite`, `skip-if-exists` or `error-if-exists` overwrite policy name.
+fn parse_overwrite(value: &str) -> Result<OverwritePolicy> {
+    match value {
+        "overwrite" => Ok(OverwritePolicy::Overwrite),
+        "skip-if-exists" => Ok(OverwritePolicy::SkipIfExists),
+        "error-if-exists" => Ok(OverwritePolicy::ErrorIfExists),
+        _ => {
+            let msg = format!("Unsupported overwrite policy '{}'", value);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+}
+
+/// Parses the `auto`, `lf` or `crlf` line ending name.
+fn parse_line_ending(value: &str) -> Result<LineEnding> {
+    match value {
+        "auto" => Ok(LineEnding::Auto),
+        "lf" => Ok(LineEnding::Lf),
+        "crlf" => Ok(LineEnding::CrLf),
+        _ => {
+            let msg = format!("Unsupported line ending '{}'", value);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+}
+
+/// Parses the `mingw`, `llvm`, `lib` or `zig` tool flavor name.
+fn parse_tool_flavor(value: &str) -> Result<ToolFlavor> {
+    match value {
+        "mingw" => Ok(ToolFlavor::Mingw),
+        "llvm" => Ok(ToolFlavor::Llvm),
+        "lib" => Ok(ToolFlavor::LibExe),
+        "zig" => Ok(ToolFlavor::Zig),
+        _ => {
+            let msg = format!("Unsupported tool flavor '{}'", value);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+}
+
+/// Parses the `abi3`, `X.Y`, `X.Yt` or `pypyX.Y` Python version string.
+pub fn parse_python_version(value: &str) -> Result<Flavor> {
+    if value == "abi3" {
+        return Ok(Flavor {
+            implementation: PythonImplementation::CPython,
+            version: None,
+            abiflags: None,
+        });
+    }
+
+    let invalid = || {
+        let msg = format!("Invalid Python version '{}'", value);
+        Error::new(ErrorKind::InvalidInput, msg)
+    };
+
+    let (implementation, version) = match value.strip_prefix("pypy") {
+        Some(version) => (PythonImplementation::PyPy, version),
+        None => (PythonImplementation::CPython, value),
+    };
+
+    let (version, abiflags) = match version.strip_suffix('t') {
+        Some(version) => (version, Some("t".to_owned())),
+        None => (version, None),
+    };
+
+    let mut parts = version.split('.');
+    let major = parts
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(invalid)?;
+    let minor = parts
+        .next()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(invalid)?;
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+
+    Ok(Flavor {
+        implementation,
+        version: Some((major, minor)),
+        abiflags,
+    })
+}
+
+/// Splits the Windows target triple into the `CARGO_CFG_TARGET_ARCH`
+/// and `CARGO_CFG_TARGET_ENV` names.
+///
+/// The other triples are resolved by `rustc` and the custom target
+/// specification `.json` files are read directly, see `TargetInfo::resolve()`.
+pub fn parse_target(target: &str) -> Result<(String, String)> {
+    let parts: Vec<_> = target.split('-').collect();
+
+    if parts.len() != 4 || parts[2] != "windows" || target.ends_with(".json") {
+        return match TargetInfo::resolve(target) {
+            // The `gnullvm` targets are told apart by the `llvm` target ABI.
+            Ok(info) if info.is_windows() && info.env == "gnu" && info.abi == "llvm" => {
+                Ok((info.arch, "gnullvm".to_owned()))
+            }
+            Ok(info) if info.is_windows() => Ok((info.arch, info.env)),
+            // Report the unreadable target specification files as such.
+            Err(e) if target.ends_with(".json") => Err(e),
+            _ => {
+                let msg = format!("Unsupported target triple '{}'", target);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::InvalidInput, msg))
+            }
+        };
+    }
+
+    let arch = match parts[0] {
+        "i686" | "i586" => "x86",
+        "thumbv7a" => "arm",
+        arch => arch,
+    };
+
+    Ok((arch.to_owned(), parts[3].to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let flavor = parse_python_version("3.13t").unwrap();
+        assert_eq!(flavor.version, Some((3, 13)));
+        assert_eq!(flavor.abiflags.as_deref(), Some("t"));
+        assert_eq!(flavor.label(), "3.13t");
+
+        let flavor = parse_python_version("pypy3.10").unwrap();
+        assert_eq!(flavor.label(), "pypy3.10");
+
+        assert!(parse_python_version("3").is_err());
+
+        let (arch, env) = parse_target("i686-pc-windows-gnu").unwrap();
+        assert_eq!((arch.as_str(), env.as_str()), ("x86", "gnu"));
+
+        assert!(parse_target("x86_64-unknown-linux-gnu").is_err());
+
+        let mut spec = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        spec.push("target");
+        spec.push("x86_64-custom-windows-msvc.json");
+        std::fs::write(
+            &spec,
+            r#"{"arch": "x86_64", "os": "windows", "is-like-msvc": true}"#,
+        )
+        .unwrap();
+        let (arch, env) = parse_target(spec.to_str().unwrap()).unwrap();
+        assert_eq!((arch.as_str(), env.as_str()), ("x86_64", "msvc"));
+
+        assert!(parse_target("target/missing-windows.json").is_err());
+    }
+}
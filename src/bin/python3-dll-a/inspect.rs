@@ -0,0 +1,138 @@
+//! `inspect` command implementation
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{stdout, Error, ErrorKind, Read, Result, Write};
+use std::path::Path;
+
+use python3_dll_a::{read_dll_exports, read_implib, DefFile, DefMetadata};
+
+/// Input file kind detected from the file signature
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileKind {
+    /// PE DLL image
+    Dll,
+    /// `.lib` or `.dll.a` import library archive
+    ImportLibrary,
+    /// Module-Definition file
+    Def,
+}
+
+/// Prints the exports of a DLL or the imports of an import library.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let path = match (args.next(), args.next()) {
+        (Some(path), None) if !path.starts_with('-') => path,
+        (None, _) => {
+            let msg = "Missing the file to inspect, see --help";
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+        (Some(arg), _) => {
+            let msg = format!("Unexpected argument '{}', see --help", arg);
+            return Err(Error::new(ErrorKind::InvalidInput, msg));
+        }
+    };
+    let path = Path::new(&path);
+
+    let mut magic = [0; 8];
+    let len = File::open(path)?.read(&mut magic)?;
+
+    let mut out = stdout().lock();
+
+    match detect(&magic[..len]) {
+        FileKind::Dll => {
+            let dll = read_dll_exports(path)?;
+
+            writeln!(out, "DLL name: {}", dll.dll_name)?;
+            writeln!(out, "Architecture: {}", dll.arch)?;
+            writeln!(out, "Exports: {}", dll.exports.len())?;
+
+            for export in &dll.exports {
+                match (&export.forwarder, export.data) {
+                    (Some(forwarder), _) => writeln!(out, "{} -> {}", export.name, forwarder)?,
+                    (None, Some(true)) => writeln!(out, "{} DATA", export.name)?,
+                    (None, _) => writeln!(out, "{}", export.name)?,
+                }
+            }
+        }
+        FileKind::ImportLibrary => {
+            let imports = read_implib(path)?;
+
+            let dll_names: BTreeSet<_> = imports.iter().map(|i| i.dll.as_str()).collect();
+            let archs: BTreeSet<_> = imports.iter().map(|i| i.arch()).collect();
+
+            writeln!(out, "DLL name: {}", join(dll_names))?;
+            writeln!(
+                out,
+                "Architecture: {}",
+                join(archs.iter().map(|a| a.as_str()))
+            )?;
+            writeln!(out, "Imports: {}", imports.len())?;
+
+            for import in &imports {
+                let data = if import.data { " DATA" } else { "" };
+
+                if import.symbol == import.name {
+                    writeln!(out, "{}{}", import.name, data)?;
+                } else {
+                    writeln!(out, "{}{} (symbol {})", import.name, data, import.symbol)?;
+                }
+            }
+        }
+        FileKind::Def => {
+            let def = DefFile::from_path(path)?;
+
+            writeln!(out, "DLL name: {}", def.dll_name().unwrap_or_default())?;
+            if let Some(metadata) = DefMetadata::from_path(path)? {
+                writeln!(
+                    out,
+                    "Generated by: python3-dll-a {} ({} {}, {} definitions, sha256 {})",
+                    metadata.version,
+                    metadata.target,
+                    metadata.python,
+                    metadata.source,
+                    metadata.source_sha256
+                )?;
+                if !metadata.options.is_empty() {
+                    writeln!(out, "Options: {}", metadata.options.join(" "))?;
+                }
+            }
+            writeln!(out, "Exports: {}", def.exports.len())?;
+
+            for export in &def.exports {
+                let data = if export.data { " DATA" } else { "" };
+                writeln!(out, "{}{}", export.name, data)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Detects the input file kind from its first bytes.
+fn detect(magic: &[u8]) -> FileKind {
+    if magic.starts_with(b"MZ") {
+        FileKind::Dll
+    } else if magic.starts_with(b"!<arch>\n") {
+        FileKind::ImportLibrary
+    } else {
+        FileKind::Def
+    }
+}
+
+/// Joins the distinct values with commas.
+fn join<'a>(values: impl IntoIterator<Item = &'a str>) -> String {
+    values.into_iter().collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_kind() {
+        assert_eq!(detect(b"MZ\x90\0"), FileKind::Dll);
+        assert_eq!(detect(b"!<arch>\n"), FileKind::ImportLibrary);
+        assert_eq!(detect(b"LIBRARY "), FileKind::Def);
+        assert_eq!(detect(b""), FileKind::Def);
+    }
+}
@@ -0,0 +1,178 @@
+//! `python3-dll-a` command line utility
+//!
+//! This binary is only built with the `cli` crate feature enabled.
+
+#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::io_other_error)]
+
+use std::env;
+use std::io::{Error, ErrorKind, Result};
+use std::process::ExitCode;
+
+use python3_dll_a::ErrorCategory;
+
+mod check_defs;
+mod cross_env;
+mod doctor;
+mod generate;
+mod inspect;
+mod json;
+mod symbols;
+mod update_defs;
+mod verify;
+
+/// Command line usage summary
+const USAGE: &str = "\
+Usage: python3-dll-a <COMMAND> [OPTIONS]
+
+Commands:
+  generate [--config <FILE> | --matrix <FILE>] [--all-versions]
+           [--python-version <abi3|X.Y[t]|pypyX.Y>]...
+           [--targets <TRIPLE>[,<TRIPLE>...]] [--out-dir <DIR>]
+           [--format <text|json>] [--cmake] [--pkg-config] [--delay-load]
+           [--dlltool <PATH>] [--lib-exe <PATH>] [--hermetic] [--stub-dll]
+           [--export-file] [--msvc-lib] [--reuse-existing]
+           [--read-only] [--report-file] [--symbols-file] [--c-header]
+           [--depfile] [--pyo3-config]
+           [--deny-tool-warnings] [--verbose]
+           [--require-tool <mingw|llvm|lib|zig>]
+           [--unknown-version <error|use-latest|stable-abi>]
+           [--overwrite <overwrite|skip-if-exists|error-if-exists>]
+           [--line-ending <auto|lf|crlf>] [--print-config]
+        Generate the import libraries for every listed target and Python
+        version into <DIR>/<TRIPLE> (<DIR>/<TRIPLE>/pypy for PyPy)
+        and print the per-artifact summary (or the JSON report).
+        `--cmake` and `--pkg-config` also write the CMake scripts
+        and the pkg-config (Meson) files describing the libraries.
+        `--pyo3-config` writes the `PYO3_CONFIG_FILE` interpreter configs.
+        `--delay-load` writes the `/DELAYLOAD` linker response files
+        for the MSVC targets. `--export-file` retains the `.exp` files
+        written by `lib.exe`. `--read-only` write-protects the generated
+        artifacts. `--report-file` writes the JSON provenance reports.
+        `--symbols-file` and `--c-header` write the plain text symbol
+        lists and the C headers listing the exports. `--depfile` writes
+        the Make/Ninja depfiles listing the import library inputs.
+        `--deny-tool-warnings` fails on the `dlltool` or `lib.exe` warnings.
+        `--verbose` prints the tool commands and the generation phase
+        timings, which are always included in the JSON report.
+        `--hermetic` disables the tool discovery, `--dlltool` must be
+        an absolute path then. `--lib-exe` sets the exact `lib.exe` for
+        the MSVC targets. `--msvc-lib` writes the MSVC-format `.lib` files
+        for the MinGW targets. `--reuse-existing` keeps the genuine Windows
+        Python `.lib` files found in the output directories after checking
+        them. `--require-tool` fails instead of falling
+        back to the other tool flavors. `--stub-dll` also writes the stub
+        DLLs for the link and load testing. `--unknown-version` selects
+        the handling of the Python versions without embedded definitions.
+        `--overwrite` selects keeping the existing import libraries
+        or failing instead of replacing them. `--line-ending` sets
+        the `.def` file line terminators. `--print-config` prints
+        the resolved target, Python version, definitions, backend, tool
+        and output path of every import library without generating them.
+        `--config` loads the defaults from a `python3-dll-a.toml` file,
+        `--matrix` runs all the `[[build]]` configurations of a matrix
+        file, the other options take precedence.
+        The command name may be omitted. Defaults to the Stable ABI
+        `python3.dll` import library for x86_64-pc-windows-msvc.
+
+  inspect <FILE>
+        Print the DLL name, architecture and the exported (or imported)
+        symbols of a `.dll`, `.lib`, `.dll.a` or `.def` file.
+
+  symbols [--python-version <X.Y[t]|pypyX.Y>] [--abi3] [--added-in] [--rust]
+        Print the exported symbols of the Python DLL, the Stable ABI
+        `python3.dll` by default. `--abi3` limits the listing to the Stable
+        ABI symbols available in the given version, `--added-in` annotates
+        the symbols with the earliest embedded CPython version exporting them.
+        `--rust` prints the Rust source file declaring the symbol name tables.
+
+  check-defs [--def-dir <DIR>]
+        Check that the version-specific CPython definitions export every
+        Stable ABI `python3.def` symbol exported by an earlier version.
+        `--def-dir` checks the replacement `.def` files from <DIR>
+        in place of the embedded ones.
+
+  cross-env [--target <TRIPLE>] [--python-version <abi3|X.Y[t]|pypyX.Y>]
+            [--out-dir <DIR>] [--shell <sh|powershell|cmd>] [--output <FILE>]
+        Generate the PyO3 cross-compilation import libraries into
+        <DIR>/<TRIPLE> and print the `CARGO_BUILD_TARGET`, `PYO3_CROSS_LIB_DIR`
+        and `PYO3_CROSS_PYTHON_*` environment variable exports for the
+        `cargo build`, e.g. `eval \"$(python3-dll-a cross-env ...)\"`.
+        The target defaults to `CARGO_BUILD_TARGET`. `--shell` selects
+        the export syntax (sh by default), `--output` writes the exports
+        into <FILE> instead of printing them.
+
+  verify <FILE> [--python-version <abi3|X.Y[t]|pypyX.Y>] [--target <TRIPLE>]
+        Check an existing import library against the embedded definitions
+        for the Python version (abi3 by default) and the machine type
+        of the target (x86_64-pc-windows-msvc by default). Fails if
+        any symbol is missing, extra or imported with the wrong DLL name,
+        import type or machine type.
+
+  doctor [--target <TRIPLE>]...
+        Probe every import library generation tool flavor on this host,
+        report the usable ones with their versions and the tool selected
+        for each target (x86_64-pc-windows-msvc by default), or suggest
+        the minimal install for the targets without a usable tool.
+
+  update-defs [--def-dir <DIR>] [--work-dir <DIR>] <CHECKOUT|TAG>...
+        Regenerate the embedded Module-Definition files from CPython
+        source checkouts or git tags and report the symbol-level diff.
+
+Options:
+  -h, --help    Print this help message
+
+Exit status:
+  0    Success
+  1    I/O or other error
+  2    Invalid command line arguments
+  3    The dlltool executable was not found
+  4    Unsupported compile target
+  5    Unsupported Python version
+  6    The dlltool executable failed
+  7    Not a Windows compile target
+";
+
+fn main() -> ExitCode {
+    let args: Vec<_> = env::args().skip(1).collect();
+    let command = args.first().cloned();
+
+    let result = match command.as_deref() {
+        Some("generate") => generate::run(args.into_iter().skip(1)),
+        Some("inspect") => inspect::run(args.into_iter().skip(1)),
+        Some("symbols") => symbols::run(args.into_iter().skip(1)),
+        Some("check-defs") => check_defs::run(args.into_iter().skip(1)),
+        Some("cross-env") => cross_env::run(args.into_iter().skip(1)),
+        Some("doctor") => doctor::run(args.into_iter().skip(1)),
+        Some("verify") => verify::run(args.into_iter().skip(1)),
+        Some("update-defs") => update_defs::run(args.into_iter().skip(1)),
+        Some("-h") | Some("--help") | None => {
+            print!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        // Batch mode: `python3-dll-a --all-versions --targets ...`
+        Some(option) if option.starts_with("--") => generate::run(args.into_iter()),
+        Some(command) => {
+            let msg = format!("Unknown command '{}', see --help", command);
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // The output was piped into `head` or similar.
+        Err(e) if e.kind() == ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::from(ErrorCategory::exit_code(&e))
+        }
+    }
+}
+
+/// Returns the value of the command line option `name`.
+fn option_value(name: &str, args: &mut impl Iterator<Item = String>) -> Result<String> {
+    args.next().ok_or_else(|| {
+        let msg = format!("Missing value for the '{}' option", name);
+        Error::new(ErrorKind::InvalidInput, msg)
+    })
+}
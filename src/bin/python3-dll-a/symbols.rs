@@ -0,0 +1,124 @@
+//! `symbols` command implementation
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{stdout, Error, ErrorKind, Result, Write};
+
+use python3_dll_a::{symbol_history, ImportLibraryGenerator, PythonImplementation};
+
+use crate::generate::parse_python_version;
+use crate::option_value;
+
+/// Oldest and newest CPython minor versions with the embedded definitions
+const CPYTHON_MINOR_VERSIONS: std::ops::RangeInclusive<u8> = 7..=13;
+
+/// Prints the exported symbols of a Python DLL.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut flavor = None;
+    let mut abi3 = false;
+    let mut added_in = false;
+    let mut rust = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--python-version" => {
+                flavor = Some(parse_python_version(&option_value(&arg, &mut args)?)?);
+            }
+            "--abi3" => abi3 = true,
+            "--added-in" => added_in = true,
+            "--rust" => rust = true,
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+
+    if let Some(flavor) = &flavor {
+        generator
+            .implementation(flavor.implementation)
+            .version(flavor.version)
+            .abiflags(flavor.abiflags.as_deref());
+    }
+
+    let mut def = generator.def_file()?;
+
+    // Limit the Stable ABI symbols to the ones available in the version.
+    if abi3 {
+        let stable_abi = ImportLibraryGenerator::new("x86_64", "msvc").def_file()?;
+
+        if flavor.is_some() {
+            let names: BTreeSet<_> = def.exports.iter().map(|e| e.name.clone()).collect();
+
+            def = stable_abi;
+            def.exports.retain(|e| names.contains(&e.name));
+        } else {
+            def = stable_abi;
+        }
+    }
+
+    if rust {
+        print!("{}", def.rust_symbol_table());
+        return Ok(());
+    }
+
+    let versions = if added_in {
+        added_in_versions()?
+    } else {
+        BTreeMap::new()
+    };
+
+    // The annotations are derived from the CPython definitions only.
+    let cpython = match &flavor {
+        Some(flavor) => matches!(flavor.implementation, PythonImplementation::CPython),
+        None => true,
+    };
+
+    let mut out = stdout().lock();
+
+    for export in &def.exports {
+        let mut line = export.name.clone();
+
+        if export.data {
+            line.push_str(" DATA");
+        }
+
+        if added_in && cpython {
+            match versions.get(&export.name) {
+                Some((3, minor)) if *minor == *CPYTHON_MINOR_VERSIONS.start() => {
+                    line.push_str(&format!(" (3.{} or earlier)", minor))
+                }
+                Some((major, minor)) => line.push_str(&format!(" (added in {}.{})", major, minor)),
+                None => line.push_str(" (not in the versioned definitions)"),
+            }
+        }
+
+        writeln!(out, "{}", line)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the earliest embedded CPython version exporting each symbol.
+///
+/// The versions disabled by the crate features are skipped.
+fn added_in_versions() -> Result<BTreeMap<String, (u8, u8)>> {
+    Ok(symbol_history()?
+        .into_iter()
+        .map(|entry| (entry.name, entry.added_in))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn added_in() {
+        let versions = added_in_versions().unwrap();
+
+        assert_eq!(versions.get("Py_Version"), Some(&(3, 11)));
+        assert_eq!(versions.get("Py_Initialize"), Some(&(3, 7)));
+    }
+}
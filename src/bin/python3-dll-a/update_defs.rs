@@ -0,0 +1,144 @@
+//! `update-defs` command implementation
+
+use std::env;
+use std::fs::{read_to_string, write};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use python3_dll_a::defgen::{CPythonExports, DefDiff, StableAbiManifest};
+
+use crate::option_value;
+
+/// CPython upstream git repository URL
+const CPYTHON_REPO: &str = "https://github.com/python/cpython.git";
+
+/// Default embedded Module-Definition files directory
+const DEF_DIR: &str = "src";
+
+/// Regenerates the embedded `.def` files from CPython checkouts or tags.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut def_dir = PathBuf::from(DEF_DIR);
+    let mut work_dir = env::temp_dir();
+    let mut sources = Vec::new();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--def-dir" => def_dir = option_value(&arg, &mut args)?.into(),
+            "--work-dir" => work_dir = option_value(&arg, &mut args)?.into(),
+            _ => sources.push(arg),
+        }
+    }
+
+    if sources.is_empty() {
+        let msg = "No CPython checkout or tag specified";
+        return Err(Error::new(ErrorKind::InvalidInput, msg));
+    }
+
+    for source in sources {
+        let checkout = resolve_checkout(&source, &work_dir)?;
+
+        update_from_checkout(&checkout, &def_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the CPython checkout path for `source`.
+///
+/// `source` is either an existing checkout directory or a git tag,
+/// which is shallow-cloned into `work_dir` unless already present there.
+fn resolve_checkout(source: &str, work_dir: &Path) -> Result<PathBuf> {
+    let path = Path::new(source);
+    if path.is_dir() {
+        return Ok(path.to_owned());
+    }
+
+    let checkout = work_dir.join(format!("cpython-{}", source));
+    if checkout.is_dir() {
+        return Ok(checkout);
+    }
+
+    let mut command = Command::new("git");
+    command
+        .args(["clone", "--depth", "1", "--branch", source, CPYTHON_REPO])
+        .arg(&checkout);
+
+    eprintln!("Cloning CPython {} into {}", source, checkout.display());
+
+    let status = command.status().map_err(|e| {
+        let msg = format!("{:?} failed with {}", command, e);
+        Error::new(e.kind(), msg)
+    })?;
+
+    if status.success() {
+        Ok(checkout)
+    } else {
+        let msg = format!("{:?} failed with {}", command, status);
+        Err(Error::other(msg))
+    }
+}
+
+/// Regenerates all `.def` files derived from the CPython `checkout`.
+fn update_from_checkout(checkout: &Path, def_dir: &Path) -> Result<()> {
+    let stable_abi = checkout.join("Misc").join("stable_abi.toml");
+    if stable_abi.exists() {
+        let manifest = StableAbiManifest::from_path(&stable_abi)?;
+
+        update_def(&def_dir.join("python3.def"), &manifest.to_def())?;
+    } else {
+        eprintln!("Skipping python3.def: {} not found", stable_abi.display());
+    }
+
+    let mut exports = CPythonExports::from_checkout(checkout)?;
+    update_def(
+        &def_dir.join(format!("{}.def", exports.dll_stem())),
+        &exports.to_def(),
+    )?;
+
+    // The free-threaded build is available since CPython v3.13.
+    if exports.version() >= (3, 13) {
+        exports.abiflags(Some("t"));
+        update_def(
+            &def_dir.join(format!("{}.def", exports.dll_stem())),
+            &exports.to_def(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the `.def` file at `path` with `def` and prints the diff summary.
+fn update_def(path: &Path, def: &str) -> Result<()> {
+    let old = read_to_string(path).unwrap_or_default();
+    let diff = DefDiff::new(&old, def);
+
+    if old.is_empty() {
+        println!("{}: new file", path.display());
+        return write(path, def);
+    }
+
+    if diff.is_empty() {
+        println!("{}: no symbol changes", path.display());
+    } else {
+        println!(
+            "{}: {} added, {} removed, {} changed",
+            path.display(),
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        );
+    }
+
+    for name in &diff.added {
+        println!("  + {}", name);
+    }
+    for name in &diff.removed {
+        println!("  - {}", name);
+    }
+    for name in &diff.changed {
+        println!("  ~ {}", name);
+    }
+
+    write(path, def)
+}
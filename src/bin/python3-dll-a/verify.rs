@@ -0,0 +1,84 @@
+//! `verify` command implementation
+
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use python3_dll_a::ImportLibraryGenerator;
+
+use crate::generate::{parse_python_version, parse_target};
+use crate::option_value;
+
+/// Checks an existing import library against the embedded definitions.
+pub fn run(mut args: impl Iterator<Item = String>) -> Result<()> {
+    let mut path = None;
+    let mut flavor = None;
+    let mut target = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--python-version" => {
+                flavor = Some(parse_python_version(&option_value(&arg, &mut args)?)?);
+            }
+            "--target" => target = Some(option_value(&arg, &mut args)?),
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(PathBuf::from(arg)),
+            _ => {
+                let msg = format!("Unexpected argument '{}', see --help", arg);
+                return Err(Error::new(ErrorKind::InvalidInput, msg));
+            }
+        }
+    }
+
+    let Some(path) = path else {
+        let msg = "Missing the import library to verify, see --help";
+        return Err(Error::new(ErrorKind::InvalidInput, msg));
+    };
+
+    let target = target.as_deref().unwrap_or("x86_64-pc-windows-msvc");
+    let (arch, env) = parse_target(target)?;
+
+    let mut generator = ImportLibraryGenerator::new(&arch, &env);
+    if let Some(flavor) = &flavor {
+        generator
+            .implementation(flavor.implementation)
+            .version(flavor.version)
+            .abiflags(flavor.abiflags.as_deref());
+    }
+
+    let report = generator.verify_implib(&path)?;
+
+    for (problem, symbols) in [
+        ("missing", &report.missing),
+        ("extra", &report.extra),
+        ("wrong DLL", &report.wrong_dll),
+        ("wrong type", &report.wrong_type),
+        ("wrong machine", &report.wrong_machine),
+    ] {
+        for symbol in symbols {
+            println!("{}: {}", problem, symbol);
+        }
+    }
+
+    if report.is_ok() {
+        println!(
+            "{}: matches the {} definitions for {}",
+            path.display(),
+            generator.dll_name()?,
+            target
+        );
+        return Ok(());
+    }
+
+    let mismatched = report.missing.len()
+        + report.extra.len()
+        + report.wrong_dll.len()
+        + report.wrong_type.len()
+        + report.wrong_machine.len();
+    let msg = format!(
+        "{}: {} symbols do not match the {} definitions for {}",
+        path.display(),
+        mismatched,
+        generator.dll_name()?,
+        target
+    );
+    Err(Error::new(ErrorKind::InvalidData, msg))
+}
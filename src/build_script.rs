@@ -0,0 +1,177 @@
+//! Complete build script logic behind the [`generate!`](crate::generate!) macro
+
+use std::env;
+use std::io::{self, Error, ErrorKind, Result, Write};
+use std::path::Path;
+
+use crate::settings::{
+    BACKEND_ENV, CACHE_DIR_ENV, DEF_DIR_ENV, DLLTOOL_ENV, LIB_EXE_ENV, VERBOSITY_ENV,
+};
+use crate::{GenerationReport, ImportLibraryGenerator};
+
+/// Environment variables affecting the build script output
+const RERUN_VARS: [&str; 10] = [
+    "PYO3_CROSS_LIB_DIR",
+    "PYO3_CROSS_PYTHON_VERSION",
+    "PYO3_CROSS_PYTHON_IMPLEMENTATION",
+    "PYO3_MINGW_DLLTOOL",
+    DLLTOOL_ENV,
+    LIB_EXE_ENV,
+    DEF_DIR_ENV,
+    CACHE_DIR_ENV,
+    BACKEND_ENV,
+    VERBOSITY_ENV,
+];
+
+/// Generates the Python DLL import library from a cargo build script
+/// and prints the cargo directives linking it.
+///
+/// Does nothing but print the `cargo:rerun-if-*` directives for the
+/// non-Windows `CARGO_CFG_TARGET_OS` targets. Otherwise the generator is
+/// created for `CARGO_CFG_TARGET_ARCH` and `CARGO_CFG_TARGET_ENV`,
+/// configured from the PyO3 cross-compilation environment variables
+/// (see [`ImportLibraryGenerator::from_pyo3_env()`]) and passed
+/// to `configure` for the further customization.
+///
+/// The import library is written into `PYO3_CROSS_LIB_DIR`, or into
+/// `OUT_DIR` if it is not set. The tool warnings are printed as
+/// `cargo:warning=` directives, and the output directory and the import
+/// library name as the `cargo:rustc-link-search=` and `cargo:rustc-link-lib=`
+/// directives.
+///
+/// Returns the generation report for the Windows targets.
+pub fn run_build_script(
+    configure: impl FnOnce(&mut ImportLibraryGenerator),
+) -> Result<Option<GenerationReport>> {
+    run(
+        |name| env::var(name).ok(),
+        configure,
+        &mut io::stdout().lock(),
+    )
+}
+
+/// Runs the build script logic with the environment variable values
+/// returned by `var`, printing the cargo directives to `out`.
+fn run(
+    var: impl Fn(&str) -> Option<String>,
+    configure: impl FnOnce(&mut ImportLibraryGenerator),
+    out: &mut impl Write,
+) -> Result<Option<GenerationReport>> {
+    writeln!(out, "cargo:rerun-if-changed=build.rs")?;
+    for name in RERUN_VARS {
+        writeln!(out, "cargo:rerun-if-env-changed={}", name)?;
+    }
+
+    let required = |name: &str| {
+        var(name).ok_or_else(|| {
+            let msg = format!("{} is not set, not running from a build script?", name);
+            Error::new(ErrorKind::NotFound, msg)
+        })
+    };
+
+    let os = required("CARGO_CFG_TARGET_OS")?;
+    if os != "windows" {
+        return Ok(None);
+    }
+
+    let arch = required("CARGO_CFG_TARGET_ARCH")?;
+    let env = var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+
+    let mut generator = ImportLibraryGenerator::new(&arch, &env);
+    generator.gnullvm = var("CARGO_CFG_TARGET_ABI").as_deref() == Some("llvm");
+    generator.target_os(Some(&os));
+    generator.apply_pyo3_env(&var)?;
+    configure(&mut generator);
+
+    let out_dir = match var("PYO3_CROSS_LIB_DIR").filter(|v| !v.is_empty()) {
+        Some(dir) => dir,
+        None => required("OUT_DIR")?,
+    };
+
+    let report = generator.generate_with_report(Path::new(&out_dir))?;
+
+    for warning in &report.warnings {
+        writeln!(out, "cargo:warning={}", warning)?;
+    }
+
+    let lib_dir = report.implib.parent().unwrap_or(Path::new(&out_dir));
+    writeln!(out, "cargo:rustc-link-search=native={}", lib_dir.display())?;
+    writeln!(out, "cargo:rustc-link-lib={}", generator.import_name()?)?;
+
+    Ok(Some(report))
+}
+
+/// Runs the complete recommended build script logic generating
+/// the Python DLL import library for the Windows targets.
+///
+/// Expands to a [`run_build_script()`](crate::run_build_script) call
+/// panicking on failure, as the build scripts do. The optional argument
+/// is a closure customizing the [`ImportLibraryGenerator`]:
+///
+/// ```no_run
+/// // build.rs
+/// fn main() {
+///     python3_dll_a::generate!(|generator| {
+///         generator.also_stable_abi(true);
+///     });
+/// }
+/// ```
+///
+/// The `cargo:rerun-if-*` directives printed by the macro disable the default
+/// cargo behavior of rerunning the build script on any package file change.
+#[macro_export]
+macro_rules! generate {
+    () => {
+        $crate::generate!(|_| {})
+    };
+    ($configure:expr) => {
+        if let Err(e) = $crate::run_build_script($configure) {
+            panic!("python3-dll-a: {}", e);
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn run_build_script() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("build-script");
+        let out_dir = dir.to_string_lossy().into_owned();
+
+        let var = |name: &str| match name {
+            "CARGO_CFG_TARGET_OS" => Some("windows".to_owned()),
+            "CARGO_CFG_TARGET_ARCH" => Some("x86_64".to_owned()),
+            "CARGO_CFG_TARGET_ENV" => Some("msvc".to_owned()),
+            "PYO3_CROSS_PYTHON_VERSION" => Some("3.12".to_owned()),
+            "OUT_DIR" => Some(out_dir.clone()),
+            _ => None,
+        };
+
+        let mut out = Vec::new();
+        let report = run(var, |generator| _ = generator.kill_at(true), &mut out)
+            .unwrap()
+            .unwrap();
+        assert_eq!(report.implib, dir.join("python312.lib"));
+
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("cargo:rerun-if-env-changed=PYO3_CROSS_LIB_DIR\n"));
+        assert!(out.contains(&format!("cargo:rustc-link-search=native={}\n", out_dir)));
+        assert!(out.ends_with("cargo:rustc-link-lib=python312\n"));
+
+        let mut out = Vec::new();
+        let report = run(
+            |name| (name == "CARGO_CFG_TARGET_OS").then(|| "linux".to_owned()),
+            |_| panic!("not a Windows target"),
+            &mut out,
+        )
+        .unwrap();
+        assert!(report.is_none());
+        assert!(!String::from_utf8(out).unwrap().contains("rustc-link"));
+    }
+}
@@ -0,0 +1,184 @@
+//! C API for the non-Rust build systems
+//!
+//! Exposes the import library generator as a small C ABI, declared
+//! in the `include/python3_dll_a.h` header, so that CMake, Bazel C++ rules
+//! and the other non-Rust build systems can embed it without running
+//! the `python3-dll-a` command line utility.
+//!
+//! Build the shared or the static library with:
+//!
+//! ```sh
+//! cargo rustc --release --lib --features capi --crate-type cdylib
+//! cargo rustc --release --lib --features capi --crate-type staticlib
+//! ```
+//!
+//! The generation honors the `PYTHON3_DLL_A_*` and the tool discovery
+//! environment variables the same way the Rust API does.
+//!
+//! This module is only available with the `capi` crate feature enabled.
+//!
+//! Example usage
+//! -------------
+//!
+//! ```c
+//! #include <stdio.h>
+//! #include "python3_dll_a.h"
+//!
+//! if (python3_dll_a_generate("x86_64-pc-windows-msvc", "3.12", "out") != 0) {
+//!     fprintf(stderr, "%s\n", python3_dll_a_last_error());
+//! }
+//! ```
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::io::{Error, ErrorKind, Result};
+use std::panic;
+use std::path::Path;
+use std::ptr;
+
+use crate::config::{parse_target, parse_version};
+use crate::{ErrorCategory, ImportLibraryGenerator};
+
+thread_local! {
+    /// Error message of the last failed call on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Generates the Python DLL import library for the compile `target` triple
+/// and the Python `version` in the `out_dir` directory.
+///
+/// `version` is `abi3`, `X.Y`, `X.Yt` or `pypyX.Y`. The Stable ABI
+/// `python3.dll` import library is generated if it is `NULL`.
+///
+/// Returns 0 on success and the failure category code on failure,
+/// which has the same values as the `python3-dll-a` exit status.
+/// A panic inside the library is caught and reported as 1.
+/// The error message can be retrieved with `python3_dll_a_last_error()`.
+///
+/// # Safety
+///
+/// `target` and `out_dir` must be valid NUL-terminated strings,
+/// `version` must be either `NULL` or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn python3_dll_a_generate(
+    target: *const c_char,
+    version: *const c_char,
+    out_dir: *const c_char,
+) -> c_int {
+    // Unwinding across the C ABI boundary aborts the host process.
+    let result = panic::catch_unwind(|| {
+        generate(
+            c_str("target", target),
+            if version.is_null() {
+                Ok("abi3")
+            } else {
+                c_str("version", version)
+            },
+            c_str("out_dir", out_dir),
+        )
+    });
+
+    match result {
+        Ok(Ok(())) => {
+            set_last_error(None);
+            0
+        }
+        Ok(Err(e)) => {
+            set_last_error(Some(e.to_string()));
+            ErrorCategory::exit_code(&e).into()
+        }
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown panic payload");
+            set_last_error(Some(format!("python3-dll-a panicked: {}", msg)));
+            // Reported as an uncategorized I/O error.
+            1
+        }
+    }
+}
+
+/// Returns the error message of the last failed `python3_dll_a_generate()`
+/// call on the calling thread, or `NULL` if it succeeded.
+///
+/// The string is owned by the library and remains valid until the next
+/// `python3_dll_a_generate()` call on the same thread.
+#[no_mangle]
+pub extern "C" fn python3_dll_a_last_error() -> *const c_char {
+    LAST_ERROR.with(|last_error| match &*last_error.borrow() {
+        Some(msg) => msg.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+/// Generates the import library from the parsed C API arguments.
+fn generate(target: Result<&str>, version: Result<&str>, out_dir: Result<&str>) -> Result<()> {
+    let (arch, env) = parse_target(target?)?;
+    let (implementation, version, abiflags) = parse_version(version?)?;
+
+    ImportLibraryGenerator::new(&arch, &env)
+        .target_os(Some("windows"))
+        .implementation(implementation)
+        .version(version)
+        .abiflags(abiflags)
+        .generate(Path::new(out_dir?))
+}
+
+/// Converts the NUL-terminated C string argument `name` to UTF-8.
+///
+/// # Safety
+///
+/// `value` must be either `NULL` or a valid NUL-terminated string.
+unsafe fn c_str<'a>(name: &str, value: *const c_char) -> Result<&'a str> {
+    if value.is_null() {
+        let msg = format!("'{}' must not be NULL", name);
+        return Err(Error::new(ErrorKind::InvalidInput, msg));
+    }
+
+    CStr::from_ptr(value).to_str().map_err(|_| {
+        let msg = format!("'{}' is not valid UTF-8", name);
+        Error::new(ErrorKind::InvalidInput, msg)
+    })
+}
+
+/// Stores the error message returned by `python3_dll_a_last_error()`.
+fn set_last_error(msg: Option<String>) {
+    // The error messages never contain NUL characters, strip them just in case.
+    let msg = msg.map(|msg| CString::new(msg.replace('\0', "")).unwrap_or_default());
+
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn generate() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("capi");
+        let out_dir = CString::new(dir.to_str().unwrap()).unwrap();
+
+        let target = CString::new("x86_64-pc-windows-gnu").unwrap();
+        let status =
+            unsafe { python3_dll_a_generate(target.as_ptr(), ptr::null(), out_dir.as_ptr()) };
+        assert_eq!(status, 0);
+        assert!(python3_dll_a_last_error().is_null());
+        assert!(dir.join("python3.dll.a").is_file());
+
+        let target = CString::new("x86_64-unknown-linux-gnu").unwrap();
+        let status =
+            unsafe { python3_dll_a_generate(target.as_ptr(), ptr::null(), out_dir.as_ptr()) };
+        assert_eq!(status, 4);
+        let msg = unsafe { CStr::from_ptr(python3_dll_a_last_error()) };
+        assert!(msg.to_str().unwrap().contains("x86_64-unknown-linux-gnu"));
+
+        let status = unsafe { python3_dll_a_generate(ptr::null(), ptr::null(), ptr::null()) };
+        assert_eq!(status, 2);
+    }
+}
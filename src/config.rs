@@ -0,0 +1,662 @@
+//! `python3-dll-a.toml` generation config file support
+//!
+//! This module is only available with the `config` crate feature enabled.
+
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+use crate::{
+    Backend, ErrorCategory, GenerationReport, ImportLibraryGenerator, LineEnding, OutputLayout,
+    OverwritePolicy, PythonImplementation, TargetInfo, ToolFlavor, UnknownVersionPolicy,
+};
+
+/// Default compile target triple
+const DEFAULT_TARGET: &str = "x86_64-pc-windows-msvc";
+
+/// Import library generation policy loaded from a `python3-dll-a.toml` file
+///
+/// The config file lists the compile targets and Python versions
+/// to generate the import libraries for, the output layout and the tool
+/// overrides, e.g.:
+///
+/// ```toml
+/// out-dir = "target/python3-dll-a"
+/// layout = "per-target"
+/// targets = ["x86_64-pc-windows-msvc", "i686-pc-windows-gnu"]
+/// versions = ["abi3", "3.12", "3.13t", "pypy3.10"]
+/// unknown-version = "error"
+/// overwrite = "overwrite"
+/// line-ending = "auto"
+/// backend = "dlltool"
+/// require-tool = "llvm"
+/// dlltool = "/usr/bin/llvm-dlltool"
+/// lib-exe = "C:/BuildTools/VC/Tools/MSVC/14.40.33807/bin/Hostx64/x64/lib.exe"
+/// msvc-lib = false
+/// reuse-existing = false
+/// def-dir = "defs"
+/// hermetic = false
+/// checksum = true
+/// cmake = false
+/// pkg-config = false
+/// pyo3-config = false
+/// delay-load = false
+/// read-only = false
+/// report-file = false
+/// symbols-file = false
+/// depfile = false
+/// c-header = false
+/// deny-tool-warnings = false
+/// ```
+///
+/// The `per-target` layouts use the canonical target triple names,
+/// see [`ImportLibraryGenerator::layout()`].
+///
+/// All the keys are optional. The relative `out-dir`, `def-dir`, `dlltool`
+/// and `lib-exe` paths are resolved against the config file directory,
+/// except for the bare program names, which are looked up in `PATH`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateConfig {
+    /// Output directory (`out-dir`), `.` by default
+    pub out_dir: PathBuf,
+    /// Output directory layout (`layout`): `"per-target"` (default),
+    /// `"per-target-version"`, `"flat"` or `"hashed"`
+    pub layout: OutputLayout,
+    /// Compile target triples (`targets`), `x86_64-pc-windows-msvc` by default
+    pub targets: Vec<String>,
+    /// Python versions (`versions`): `abi3`, `X.Y`, `X.Yt` or `pypyX.Y`,
+    /// `abi3` by default
+    pub versions: Vec<String>,
+    /// Unknown Python version handling (`unknown-version`): `"error"` (default),
+    /// `"use-latest"` or `"stable-abi"`, see [`ImportLibraryGenerator::unknown_version()`]
+    pub unknown_version: UnknownVersionPolicy,
+    /// Existing import library handling (`overwrite`): `"overwrite"` (default),
+    /// `"skip-if-exists"` or `"error-if-exists"`, see [`ImportLibraryGenerator::overwrite()`]
+    pub overwrite: OverwritePolicy,
+    /// Written `.def` file line terminators (`line-ending`): `"auto"` (default),
+    /// `"lf"` or `"crlf"`, see [`ImportLibraryGenerator::line_ending()`]
+    pub line_ending: LineEnding,
+    /// Import library generation backend (`backend`, `"dlltool"`, `"builtin"` or `"mock"`)
+    pub backend: Option<Backend>,
+    /// Required tool flavor (`require-tool`): `"mingw"`, `"llvm"`, `"lib"`
+    /// or `"zig"`, see [`ImportLibraryGenerator::require_tool()`]
+    pub require_tool: Option<ToolFlavor>,
+    /// Explicit `dlltool` or `lib.exe` executable path (`dlltool`)
+    pub dlltool: Option<PathBuf>,
+    /// Explicit Visual Studio `lib.exe` executable path (`lib-exe`),
+    /// see [`ImportLibraryGenerator::lib_exe()`]
+    pub lib_exe: Option<PathBuf>,
+    /// Write the MSVC-format `.lib` files for the `gnu` targets (`msvc-lib`),
+    /// see [`ImportLibraryGenerator::msvc_lib()`]
+    pub msvc_lib: bool,
+    /// Reuse the genuine import libraries found in the output directories
+    /// (`reuse-existing`), see [`ImportLibraryGenerator::reuse_existing()`]
+    pub reuse_existing: bool,
+    /// Directory containing the replacement `.def` files (`def-dir`)
+    pub def_dir: Option<PathBuf>,
+    /// Enable the hermetic mode (`hermetic`)
+    pub hermetic: bool,
+    /// Write the `.sha256` checksum manifests (`checksum`)
+    pub checksum_manifest: bool,
+    /// Write the CMake scripts (`cmake`)
+    pub cmake_script: bool,
+    /// Write the pkg-config files (`pkg-config`)
+    pub pkg_config_file: bool,
+    /// Write the PyO3 interpreter config files (`pyo3-config`)
+    pub pyo3_config_file: bool,
+    /// Write the `/DELAYLOAD` linker response files (`delay-load`)
+    pub delay_load: bool,
+    /// Mark the generated artifacts read-only (`read-only`)
+    pub read_only: bool,
+    /// Write the JSON provenance reports (`report-file`)
+    pub report_file: bool,
+    /// Write the plain text symbol lists (`symbols-file`)
+    pub symbols_file: bool,
+    /// Write the Make/Ninja depfiles (`depfile`)
+    pub depfile: bool,
+    /// Write the C headers listing the exports (`c-header`)
+    pub c_header: bool,
+    /// Fail on the tool warnings (`deny-tool-warnings`)
+    pub deny_tool_warnings: bool,
+}
+
+impl Default for GenerateConfig {
+    fn default() -> Self {
+        GenerateConfig {
+            out_dir: PathBuf::from("."),
+            layout: OutputLayout::PerTarget,
+            targets: vec![DEFAULT_TARGET.to_owned()],
+            versions: vec!["abi3".to_owned()],
+            unknown_version: UnknownVersionPolicy::Error,
+            overwrite: OverwritePolicy::Overwrite,
+            line_ending: LineEnding::Auto,
+            backend: None,
+            require_tool: None,
+            dlltool: None,
+            lib_exe: None,
+            msvc_lib: false,
+            reuse_existing: false,
+            def_dir: None,
+            hermetic: false,
+            checksum_manifest: false,
+            cmake_script: false,
+            pkg_config_file: false,
+            pyo3_config_file: false,
+            delay_load: false,
+            read_only: false,
+            report_file: false,
+            symbols_file: false,
+            depfile: false,
+            c_header: false,
+            deny_tool_warnings: false,
+        }
+    }
+}
+
+impl GenerateConfig {
+    /// Parses the `python3-dll-a.toml` config file contents.
+    ///
+    /// The relative paths are kept as is.
+    pub fn parse(config: &str) -> Result<Self> {
+        Self::from_table(&parse_table(config)?)
+    }
+
+    /// Parses the config keys of the TOML `table`.
+    fn from_table(table: &toml::Table) -> Result<Self> {
+        let mut parsed = GenerateConfig::default();
+
+        for (key, value) in table {
+            match key.as_str() {
+                "out-dir" => parsed.out_dir = string_value(key, value)?.into(),
+                "layout" => {
+                    parsed.layout = match string_value(key, value)? {
+                        "per-target" => OutputLayout::PerTarget,
+                        "per-target-version" => OutputLayout::PerTargetVersion,
+                        "flat" => OutputLayout::Flat,
+                        "hashed" => OutputLayout::Hashed,
+                        layout => return Err(invalid_value(key, layout)),
+                    }
+                }
+                "targets" => parsed.targets = string_list(key, value)?,
+                "versions" => {
+                    parsed.versions = string_list(key, value)?;
+                    for version in &parsed.versions {
+                        parse_version(version)?;
+                    }
+                }
+                "backend" => {
+                    parsed.backend = match string_value(key, value)? {
+                        "dlltool" => Some(Backend::DllTool),
+                        #[cfg(feature = "builtin")]
+                        "builtin" => Some(Backend::Builtin),
+                        #[cfg(feature = "test-support")]
+                        "mock" => Some(Backend::Mock),
+                        backend => return Err(invalid_value(key, backend)),
+                    }
+                }
+                "unknown-version" => {
+                    parsed.unknown_version = match string_value(key, value)? {
+                        "error" => UnknownVersionPolicy::Error,
+                        "use-latest" => UnknownVersionPolicy::UseLatest,
+                        "stable-abi" => UnknownVersionPolicy::StableAbi,
+                        policy => return Err(invalid_value(key, policy)),
+                    }
+                }
+                "overwrite" => {
+                    parsed.overwrite = match string_value(key, value)? {
+                        "overwrite" => OverwritePolicy::Overwrite,
+                        "skip-if-exists" => OverwritePolicy::SkipIfExists,
+                        "error-if-exists" => OverwritePolicy::ErrorIfExists,
+                        policy => return Err(invalid_value(key, policy)),
+                    }
+                }
+                "line-ending" => {
+                    parsed.line_ending = match string_value(key, value)? {
+                        "auto" => LineEnding::Auto,
+                        "lf" => LineEnding::Lf,
+                        "crlf" => LineEnding::CrLf,
+                        line_ending => return Err(invalid_value(key, line_ending)),
+                    }
+                }
+                "require-tool" => {
+                    parsed.require_tool = match string_value(key, value)? {
+                        "mingw" => Some(ToolFlavor::Mingw),
+                        "llvm" => Some(ToolFlavor::Llvm),
+                        "lib" => Some(ToolFlavor::LibExe),
+                        "zig" => Some(ToolFlavor::Zig),
+                        flavor => return Err(invalid_value(key, flavor)),
+                    }
+                }
+                "dlltool" => parsed.dlltool = Some(string_value(key, value)?.into()),
+                "lib-exe" => parsed.lib_exe = Some(string_value(key, value)?.into()),
+                "msvc-lib" => parsed.msvc_lib = bool_value(key, value)?,
+                "reuse-existing" => parsed.reuse_existing = bool_value(key, value)?,
+                "def-dir" => parsed.def_dir = Some(string_value(key, value)?.into()),
+                "hermetic" => parsed.hermetic = bool_value(key, value)?,
+                "checksum" => parsed.checksum_manifest = bool_value(key, value)?,
+                "cmake" => parsed.cmake_script = bool_value(key, value)?,
+                "pkg-config" => parsed.pkg_config_file = bool_value(key, value)?,
+                "pyo3-config" => parsed.pyo3_config_file = bool_value(key, value)?,
+                "delay-load" => parsed.delay_load = bool_value(key, value)?,
+                "read-only" => parsed.read_only = bool_value(key, value)?,
+                "report-file" => parsed.report_file = bool_value(key, value)?,
+                "symbols-file" => parsed.symbols_file = bool_value(key, value)?,
+                "depfile" => parsed.depfile = bool_value(key, value)?,
+                "c-header" => parsed.c_header = bool_value(key, value)?,
+                "deny-tool-warnings" => parsed.deny_tool_warnings = bool_value(key, value)?,
+                _ => {
+                    let msg = format!("Unknown config key '{}'", key);
+                    return Err(Error::new(ErrorKind::InvalidData, msg));
+                }
+            }
+        }
+
+        for target in &parsed.targets {
+            parse_target(target)?;
+        }
+
+        Ok(parsed)
+    }
+
+    /// Reads and parses the `python3-dll-a.toml` config file.
+    ///
+    /// The relative paths are resolved against the config file directory.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let mut config = Self::parse(&read_to_string(path)?)?;
+
+        config.resolve_paths(path.parent().unwrap_or(Path::new("")));
+
+        Ok(config)
+    }
+
+    /// Resolves the relative paths against the config file directory `base_dir`.
+    fn resolve_paths(&mut self, base_dir: &Path) {
+        self.out_dir = base_dir.join(&self.out_dir);
+        self.def_dir = self.def_dir.take().map(|dir| base_dir.join(dir));
+        // The bare program names are looked up in `PATH`.
+        let resolve = |program: PathBuf| {
+            if program.components().count() > 1 {
+                base_dir.join(program)
+            } else {
+                program
+            }
+        };
+        self.dlltool = self.dlltool.take().map(resolve);
+        self.lib_exe = self.lib_exe.take().map(resolve);
+    }
+
+    /// Returns the output directory passed to the generators
+    /// for the Python implementation.
+    ///
+    /// PyPy import libraries are named like the CPython ones,
+    /// so with the flat layout they are written into a separate `pypy`
+    /// subdirectory. The generators place the artifacts according
+    /// to the configured layout below this directory.
+    pub fn out_dir_for(&self, implementation: PythonImplementation) -> PathBuf {
+        match (self.layout, implementation) {
+            (OutputLayout::Flat, PythonImplementation::PyPy) => self.out_dir.join("pypy"),
+            _ => self.out_dir.clone(),
+        }
+    }
+
+    /// Returns the import library generator for the target `arch`
+    /// and `env` configured with the layout, the tool overrides
+    /// and the extra outputs.
+    pub fn generator(&self, arch: &str, env: &str) -> ImportLibraryGenerator {
+        let mut generator = ImportLibraryGenerator::new(arch, env);
+
+        if let Some(backend) = self.backend {
+            generator.backend(backend);
+        }
+
+        // The targets are validated to be Windows triples.
+        generator
+            .target_os(Some("windows"))
+            .layout(self.layout)
+            .require_tool(self.require_tool)
+            .unknown_version(self.unknown_version)
+            .overwrite(self.overwrite)
+            .line_ending(self.line_ending)
+            .dlltool(self.dlltool.as_deref())
+            .lib_exe(self.lib_exe.as_deref())
+            .msvc_lib(self.msvc_lib)
+            .reuse_existing(self.reuse_existing)
+            .def_dir(self.def_dir.as_deref())
+            .hermetic(self.hermetic)
+            .checksum_manifest(self.checksum_manifest)
+            .cmake_script(self.cmake_script)
+            .pkg_config_file(self.pkg_config_file)
+            .pyo3_config_file(self.pyo3_config_file)
+            .delay_load(self.delay_load)
+            .read_only(self.read_only)
+            .report_file(self.report_file)
+            .symbols_file(self.symbols_file)
+            .depfile(self.depfile)
+            .c_header(self.c_header)
+            .deny_tool_warnings(self.deny_tool_warnings);
+
+        generator
+    }
+
+    /// Generates the import libraries for all the configured targets
+    /// and Python versions.
+    ///
+    /// Stops at the first failure. Returns the generation reports.
+    pub fn generate(&self) -> Result<Vec<GenerationReport>> {
+        let mut reports = Vec::new();
+
+        for target in &self.targets {
+            let (arch, env) = parse_target(target)?;
+
+            for version in &self.versions {
+                let (implementation, version, abiflags) = parse_version(version)?;
+
+                let mut generator = self.generator(&arch, &env);
+                generator
+                    .implementation(implementation)
+                    .version(version)
+                    .abiflags(abiflags);
+
+                let out_dir = self.out_dir_for(implementation);
+                reports.push(generator.generate_with_report(&out_dir)?);
+            }
+        }
+
+        Ok(reports)
+    }
+}
+
+/// Import library build matrix loaded from a matrix file
+///
+/// The matrix file lists the build combinations as the `[[build]]` tables,
+/// each one with its own targets, Python versions and output directory.
+/// The top-level keys are the defaults shared by all the builds, e.g.:
+///
+/// ```toml
+/// layout = "per-target"
+/// checksum = true
+///
+/// [[build]]
+/// out-dir = "dist/msvc"
+/// targets = ["x86_64-pc-windows-msvc", "aarch64-pc-windows-msvc"]
+/// versions = ["abi3", "3.12", "3.13t"]
+///
+/// [[build]]
+/// out-dir = "dist/mingw"
+/// targets = ["x86_64-pc-windows-gnu"]
+/// versions = ["3.12", "pypy3.10"]
+/// ```
+///
+/// Both the top-level and the `[[build]]` keys are the `python3-dll-a.toml`
+/// config file keys, see [`GenerateConfig`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerateMatrix {
+    /// Build configurations (`[[build]]`)
+    pub builds: Vec<GenerateConfig>,
+}
+
+impl GenerateMatrix {
+    /// Parses the matrix file contents.
+    ///
+    /// The relative paths are kept as is.
+    pub fn parse(matrix: &str) -> Result<Self> {
+        let mut defaults = parse_table(matrix)?;
+
+        let builds = match defaults.remove("build") {
+            Some(toml::Value::Array(builds)) if !builds.is_empty() => builds,
+            Some(_) => return Err(invalid_type("build", "an array of tables")),
+            None => {
+                let msg = "Matrix file has no [[build]] tables";
+                return Err(Error::new(ErrorKind::InvalidData, msg));
+            }
+        };
+
+        let builds = builds
+            .into_iter()
+            .map(|build| match build {
+                toml::Value::Table(build) => {
+                    // The build keys override the defaults.
+                    let mut table = defaults.clone();
+                    table.extend(build);
+                    GenerateConfig::from_table(&table)
+                }
+                _ => Err(invalid_type("build", "an array of tables")),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(GenerateMatrix { builds })
+    }
+
+    /// Reads and parses the matrix file.
+    ///
+    /// The relative paths are resolved against the matrix file directory.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let mut matrix = Self::parse(&read_to_string(path)?)?;
+
+        for build in &mut matrix.builds {
+            build.resolve_paths(path.parent().unwrap_or(Path::new("")));
+        }
+
+        Ok(matrix)
+    }
+
+    /// Generates the import libraries for all the builds.
+    ///
+    /// Stops at the first failure. Returns the generation reports
+    /// of all the builds in order.
+    pub fn generate(&self) -> Result<Vec<GenerationReport>> {
+        let mut reports = Vec::new();
+
+        for build in &self.builds {
+            reports.extend(build.generate()?);
+        }
+
+        Ok(reports)
+    }
+}
+
+/// Parses the TOML document `content`.
+fn parse_table(content: &str) -> Result<toml::Table> {
+    content
+        .parse()
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Returns the TOML string `value` of the config `key`.
+fn string_value<'a>(key: &str, value: &'a toml::Value) -> Result<&'a str> {
+    value.as_str().ok_or_else(|| invalid_type(key, "a string"))
+}
+
+/// Returns the TOML boolean `value` of the config `key`.
+fn bool_value(key: &str, value: &toml::Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| invalid_type(key, "a boolean"))
+}
+
+/// Returns the TOML string array `value` of the config `key`.
+fn string_list(key: &str, value: &toml::Value) -> Result<Vec<String>> {
+    let items = value
+        .as_array()
+        .ok_or_else(|| invalid_type(key, "an array of strings"))?;
+
+    items
+        .iter()
+        .map(|item| string_value(key, item).map(ToOwned::to_owned))
+        .collect()
+}
+
+/// Builds the config value type mismatch error.
+fn invalid_type(key: &str, expected: &str) -> Error {
+    let msg = format!("Config key '{}' must be {}", key, expected);
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Builds the unsupported config value error.
+fn invalid_value(key: &str, value: &str) -> Error {
+    let msg = format!("Invalid config key '{}' value '{}'", key, value);
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// Python implementation, version and ABI flags
+pub(crate) type VersionEntry<'a> = (PythonImplementation, Option<(u8, u8)>, Option<&'a str>);
+
+/// Parses the `abi3`, `X.Y`, `X.Yt` or `pypyX.Y` Python version string.
+pub(crate) fn parse_version(value: &str) -> Result<VersionEntry<'_>> {
+    if value == "abi3" {
+        return Ok((PythonImplementation::CPython, None, None));
+    }
+
+    let (implementation, version) = match value.strip_prefix("pypy") {
+        Some(version) => (PythonImplementation::PyPy, version),
+        None => (PythonImplementation::CPython, value),
+    };
+
+    let (version, abiflags) = match version.strip_suffix('t') {
+        Some(version) => (version, Some("t")),
+        None => (version, None),
+    };
+
+    match version.split_once('.') {
+        Some((major, minor)) => match (major.parse(), minor.parse()) {
+            (Ok(major), Ok(minor)) => Ok((implementation, Some((major, minor)), abiflags)),
+            _ => Err(invalid_value("versions", value)),
+        },
+        None => Err(invalid_value("versions", value)),
+    }
+}
+
+/// Splits the Windows target triple into the `CARGO_CFG_TARGET_ARCH`
+/// and `CARGO_CFG_TARGET_ENV` names.
+///
+/// The other triples are resolved by `rustc` and the custom target
+/// specification `.json` files are read directly, see [`TargetInfo::resolve()`].
+pub(crate) fn parse_target(target: &str) -> Result<(String, String)> {
+    let parts: Vec<_> = target.split('-').collect();
+
+    if parts.len() != 4 || parts[2] != "windows" || target.ends_with(".json") {
+        return match TargetInfo::resolve(target) {
+            // The `gnullvm` targets are told apart by the `llvm` target ABI.
+            Ok(info) if info.is_windows() && info.env == "gnu" && info.abi == "llvm" => {
+                Ok((info.arch, "gnullvm".to_owned()))
+            }
+            Ok(info) if info.is_windows() => Ok((info.arch, info.env)),
+            // Report the unreadable target specification files as such.
+            Err(e) if target.ends_with(".json") => Err(e),
+            _ => {
+                let msg = format!("Unsupported target triple '{}'", target);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::InvalidInput, msg))
+            }
+        };
+    }
+
+    let arch = match parts[0] {
+        "i686" | "i586" => "x86",
+        "thumbv7a" => "arm",
+        arch => arch,
+    };
+
+    Ok((arch.to_owned(), parts[3].to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config = GenerateConfig::parse(
+            "out-dir = \"out\"\n\
+             layout = \"flat\"\n\
+             targets = [\"i686-pc-windows-gnu\"]\n\
+             versions = [\"abi3\", \"3.13t\", \"pypy3.10\"]\n\
+             require-tool = \"mingw\"\n\
+             unknown-version = \"use-latest\"\n\
+             overwrite = \"skip-if-exists\"\n\
+             line-ending = \"crlf\"\n\
+             checksum = true\n\
+             pyo3-config = true\n\
+             msvc-lib = true\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.out_dir, Path::new("out"));
+        assert_eq!(config.layout, OutputLayout::Flat);
+        assert_eq!(config.targets, ["i686-pc-windows-gnu"]);
+        assert_eq!(config.versions.len(), 3);
+        assert_eq!(config.require_tool, Some(ToolFlavor::Mingw));
+        assert_eq!(config.unknown_version, UnknownVersionPolicy::UseLatest);
+        assert_eq!(config.overwrite, OverwritePolicy::SkipIfExists);
+        assert_eq!(config.line_ending, LineEnding::CrLf);
+        assert!(config.checksum_manifest);
+        assert!(config.msvc_lib);
+        assert!(config.pyo3_config_file);
+        assert!(!config.cmake_script);
+
+        assert_eq!(
+            config.out_dir_for(PythonImplementation::PyPy),
+            Path::new("out").join("pypy")
+        );
+
+        let default = GenerateConfig::parse("").unwrap();
+        assert_eq!(default, GenerateConfig::default());
+
+        assert!(GenerateConfig::parse("version = [\"3.12\"]").is_err());
+        assert!(GenerateConfig::parse("versions = [\"3\"]").is_err());
+        assert!(GenerateConfig::parse("cmake = \"yes\"").is_err());
+        assert!(GenerateConfig::parse("require-tool = \"gcc\"").is_err());
+        assert!(GenerateConfig::parse("targets = [\"x86_64-unknown-linux-gnu\"]").is_err());
+    }
+
+    #[test]
+    fn matrix() {
+        let matrix = GenerateMatrix::parse(
+            "checksum = true\n\
+             layout = \"flat\"\n\
+             [[build]]\n\
+             out-dir = \"dist/msvc\"\n\
+             targets = [\"x86_64-pc-windows-msvc\", \"aarch64-pc-windows-msvc\"]\n\
+             [[build]]\n\
+             out-dir = \"dist/mingw\"\n\
+             targets = [\"x86_64-pc-windows-gnu\"]\n\
+             versions = [\"3.12\", \"pypy3.10\"]\n\
+             checksum = false\n",
+        )
+        .unwrap();
+
+        assert_eq!(matrix.builds.len(), 2);
+        assert_eq!(matrix.builds[0].out_dir, Path::new("dist/msvc"));
+        assert_eq!(matrix.builds[0].targets.len(), 2);
+        assert_eq!(matrix.builds[0].versions, ["abi3"]);
+        assert!(matrix.builds[0].checksum_manifest);
+        assert_eq!(matrix.builds[1].layout, OutputLayout::Flat);
+        assert_eq!(matrix.builds[1].versions, ["3.12", "pypy3.10"]);
+        assert!(!matrix.builds[1].checksum_manifest);
+
+        assert!(GenerateMatrix::parse("checksum = true").is_err());
+        assert!(GenerateMatrix::parse("build = 1").is_err());
+        assert!(GenerateMatrix::parse("[[build]]\nversions = [\"3\"]").is_err());
+    }
+
+    #[test]
+    fn from_path() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("config");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("python3-dll-a.toml");
+        std::fs::write(
+            &path,
+            "out-dir = \"out\"\ndlltool = \"llvm-dlltool\"\nlib-exe = \"vs/lib.exe\"\n\
+             def-dir = \"defs\"\n",
+        )
+        .unwrap();
+
+        let config = GenerateConfig::from_path(&path).unwrap();
+        assert_eq!(config.out_dir, dir.join("out"));
+        assert_eq!(config.def_dir, Some(dir.join("defs")));
+        assert_eq!(config.dlltool, Some(PathBuf::from("llvm-dlltool")));
+        assert_eq!(config.lib_exe, Some(dir.join("vs").join("lib.exe")));
+    }
+}
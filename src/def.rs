@@ -0,0 +1,895 @@
+//! Module-Definition (`.def`) file parser and writer
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::symlist;
+
+/// Parsed Module-Definition file
+///
+/// Only the statements relevant for the import libraries are kept:
+/// the DLL name and the `EXPORTS` section entries, along with
+/// the comments, so that the curated `.def` files survive
+/// the [`DefFile::parse()`], modify and [`DefFile::to_def()`] cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefFile {
+    /// DLL name from the `LIBRARY` statement
+    pub library: Option<String>,
+    /// `LIBRARY` statement attributes following the DLL name,
+    /// e.g. `BASE=0x1e000000`
+    pub library_attributes: Option<String>,
+    /// `EXPORTS` section entries in the file order
+    pub exports: Vec<DefExport>,
+    /// Comment lines preceding the `EXPORTS` entries, without the leading `;`
+    pub comments: Vec<String>,
+    /// Comment lines following the last `EXPORTS` entry, without the leading `;`
+    pub trailing_comments: Vec<String>,
+}
+
+/// `python3-dll-a` generation metadata header of the written `.def` files
+///
+/// The header is a block of `; python3-dll-a.<key> = <value>` comment lines
+/// preceding the definitions, which traces the files (and the import
+/// libraries built from them) back to the generator configuration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefMetadata {
+    /// `python3-dll-a` crate version (`version`)
+    pub version: String,
+    /// Definitions source (`source`), `embedded` or `override`
+    pub source: String,
+    /// SHA-256 hash of the definitions without the header (`source-sha256`)
+    pub source_sha256: String,
+    /// Compile target `<arch>-<env>` (`target`), e.g. `x86_64-msvc`
+    pub target: String,
+    /// Python version (`python`), e.g. `abi3`, `3.13t` or `pypy3.10`
+    pub python: String,
+    /// Enabled generation options (`options`), e.g. `exclude-private`
+    pub options: Vec<String>,
+}
+
+/// `DefMetadata` header line prefix
+const METADATA_PREFIX: &str = "; python3-dll-a.";
+
+impl DefMetadata {
+    /// Parses the metadata header of the Module-Definition file contents.
+    ///
+    /// Returns `None` for the files not written by `python3-dll-a`.
+    pub fn parse(def: &str) -> Option<Self> {
+        let mut metadata = DefMetadata::default();
+        let mut found = false;
+
+        for line in def.lines().map(str::trim_end) {
+            let entry = match line.strip_prefix(METADATA_PREFIX) {
+                Some(entry) => entry,
+                // The header precedes the other comments and statements.
+                None => break,
+            };
+
+            let (key, value) = match entry.split_once(" = ") {
+                Some((key, value)) => (key, value.to_owned()),
+                None => continue,
+            };
+
+            match key {
+                "version" => {
+                    metadata.version = value;
+                    found = true;
+                }
+                "source" => metadata.source = value,
+                "source-sha256" => metadata.source_sha256 = value,
+                "target" => metadata.target = value,
+                "python" => metadata.python = value,
+                "options" => {
+                    metadata.options = value.split_ascii_whitespace().map(str::to_owned).collect()
+                }
+                // Skip the keys written by the newer crate versions.
+                _ => {}
+            }
+        }
+
+        found.then_some(metadata)
+    }
+
+    /// Reads the metadata header of the Module-Definition file at `path`.
+    pub fn from_path(path: &Path) -> Result<Option<Self>> {
+        Ok(Self::parse(&read_to_string(path)?))
+    }
+
+    /// Returns the metadata header comment lines.
+    pub(crate) fn header(&self) -> String {
+        let mut header = String::new();
+
+        for (key, value) in [
+            ("version", self.version.clone()),
+            ("source", self.source.clone()),
+            ("source-sha256", self.source_sha256.clone()),
+            ("target", self.target.clone()),
+            ("python", self.python.clone()),
+        ] {
+            header.push_str(&format!("{}{} = {}\n", METADATA_PREFIX, key, value));
+        }
+
+        if !self.options.is_empty() {
+            let options = self.options.join(" ");
+            header.push_str(&format!("{}options = {}\n", METADATA_PREFIX, options));
+        }
+
+        header
+    }
+}
+
+/// Module-Definition file problem kinds found by [`DefFile::lint()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DefLintKind {
+    /// The statement or the `EXPORTS` entry syntax is invalid
+    Syntax,
+    /// There is no `LIBRARY` statement naming the DLL
+    MissingLibrary,
+    /// The symbol is exported more than once
+    DuplicateExport,
+    /// The symbol name is not a valid linker symbol identifier
+    InvalidIdentifier,
+    /// The ordinal is assigned to more than one symbol
+    ConflictingOrdinal,
+}
+
+/// Module-Definition file problem found by [`DefFile::lint()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefLint {
+    /// Problem kind
+    pub kind: DefLintKind,
+    /// Line number (starting from 1), `None` for the whole file problems
+    pub line: Option<usize>,
+    /// Problem description
+    pub message: String,
+}
+
+impl fmt::Display for DefLint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+/// Module-Definition file dialects of the import library tools
+///
+/// The MSVC `lib.exe` and the MinGW `dlltool` (as well as `llvm-dlltool`)
+/// `.def` syntax differs in the symbol decorations, the aliases
+/// and the export keywords accepted, see [`DefDialect::normalize()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DefDialect {
+    /// MSVC `lib.exe` dialect
+    Msvc,
+    /// MinGW `dlltool` dialect
+    Mingw,
+}
+
+impl DefDialect {
+    /// Normalizes the Module-Definition file contents `def` written
+    /// for either toolchain into the form accepted by this dialect.
+    ///
+    /// The `EXPORTS` entries are rewritten with the keywords in the
+    /// `name [@ordinal [NONAME]] [DATA] [PRIVATE]` order, the obsolete
+    /// `CONSTANT` keyword replaced with `DATA` and the `=internal_name`
+    /// parts dropped. The MinGW `name == import_name` aliases are kept
+    /// in the MinGW dialect and replaced with the undecorated `import_name`
+    /// in the MSVC one, as `lib.exe` decorates the names itself.
+    ///
+    /// The comments and the other statements are kept as is.
+    pub fn normalize(self, def: &str) -> Result<String> {
+        let mut normalized = String::with_capacity(def.len());
+        let mut in_exports = false;
+
+        for (lineno, line) in def.lines().enumerate() {
+            let (statement, comment) = match line.find(';') {
+                Some(pos) => line.split_at(pos),
+                None => (line, ""),
+            };
+            let statement = statement.trim();
+            let keyword = statement
+                .split_ascii_whitespace()
+                .next()
+                .unwrap_or_default();
+
+            let entry = match keyword {
+                "EXPORTS" => {
+                    in_exports = true;
+
+                    // Move the entry following the section keyword to its own line.
+                    let rest = statement[keyword.len()..].trim();
+                    if rest.is_empty() {
+                        None
+                    } else {
+                        normalized.push_str("EXPORTS\n");
+                        Some(rest)
+                    }
+                }
+                "LIBRARY" | "NAME" | "BASE" | "HEAPSIZE" | "STACKSIZE" | "SECTIONS" | "VERSION"
+                | "STUB" => {
+                    in_exports = false;
+                    None
+                }
+                "" => None,
+                _ if in_exports => Some(statement),
+                _ => None,
+            };
+
+            match entry {
+                Some(entry) => {
+                    normalized.push_str(&self.export_entry(parse_export(entry, lineno)?));
+                    if !comment.is_empty() {
+                        normalized.push(' ');
+                        normalized.push_str(comment);
+                    }
+                }
+                None => normalized.push_str(line),
+            }
+            normalized.push('\n');
+        }
+
+        Ok(normalized)
+    }
+
+    /// Writes the `EXPORTS` entry `export` in this dialect.
+    fn export_entry(self, mut export: DefExport) -> String {
+        export.internal_name = None;
+
+        if self == DefDialect::Msvc {
+            if let Some(import_name) = export.import_name.take() {
+                export.name = import_name;
+            }
+        }
+
+        export.to_def()
+    }
+}
+
+/// Exported symbol kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    /// Function (code) export
+    Function,
+    /// Data export (marked with the `DATA` keyword in the `.def` files)
+    Data,
+}
+
+/// Module-Definition file `EXPORTS` entry
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefExport {
+    /// Exported (link) symbol name
+    pub name: String,
+    /// Internal DLL symbol name given by the `name=internal_name` syntax
+    ///
+    /// Not used for the import libraries.
+    pub internal_name: Option<String>,
+    /// DLL import name given by the `name == import_name` syntax
+    pub import_name: Option<String>,
+    /// Export ordinal given by the `@<n>` syntax
+    pub ordinal: Option<u16>,
+    /// The `DATA` keyword is present
+    pub data: bool,
+    /// The `NONAME` keyword is present
+    pub noname: bool,
+    /// The `PRIVATE` keyword is present
+    pub private: bool,
+    /// Comment lines preceding the entry, without the leading `;`
+    pub comments: Vec<String>,
+    /// Comment following the entry on the same line, without the leading `;`
+    pub comment: Option<String>,
+}
+
+impl DefExport {
+    /// Writes the `EXPORTS` section entry line without the line terminator.
+    ///
+    /// The preceding `comments` are not included.
+    pub fn to_def(&self) -> String {
+        let mut entry = self.name.clone();
+
+        if let Some(internal_name) = &self.internal_name {
+            entry.push_str(&format!("={}", internal_name));
+        }
+        if let Some(import_name) = &self.import_name {
+            entry.push_str(&format!(" == {}", import_name));
+        }
+        if let Some(ordinal) = self.ordinal {
+            entry.push_str(&format!(" @{}", ordinal));
+        }
+        for (enabled, keyword) in [
+            (self.noname, " NONAME"),
+            (self.data, " DATA"),
+            (self.private, " PRIVATE"),
+        ] {
+            if enabled {
+                entry.push_str(keyword);
+            }
+        }
+        if let Some(comment) = &self.comment {
+            entry.push_str(&format!(" ;{}", comment));
+        }
+
+        entry
+    }
+}
+
+impl DefFile {
+    /// Parses the Module-Definition file contents.
+    ///
+    /// The comment lines are attached to the following `EXPORTS` entry,
+    /// the ones preceding the `EXPORTS` section are kept in `comments`
+    /// and the ones following the last entry in `trailing_comments`.
+    pub fn parse(def: &str) -> Result<Self> {
+        let mut def_file = DefFile::default();
+        let mut in_exports = false;
+        let mut comments = Vec::new();
+
+        for (lineno, line) in def.lines().enumerate() {
+            let (line, comment) = match line.split_once(';') {
+                Some((line, comment)) => (line.trim(), Some(comment.trim_end().to_owned())),
+                None => (line.trim(), None),
+            };
+
+            if line.is_empty() {
+                comments.extend(comment);
+                continue;
+            }
+
+            let mut push_export = |def_file: &mut DefFile, entry| -> Result<()> {
+                let mut export = parse_export(entry, lineno)?;
+                export.comments = std::mem::take(&mut comments);
+                export.comment = comment.clone();
+                def_file.exports.push(export);
+                Ok(())
+            };
+
+            let keyword = line.split_ascii_whitespace().next().unwrap_or_default();
+
+            match keyword {
+                "LIBRARY" | "NAME" => {
+                    in_exports = false;
+
+                    let (name, attributes) = library_args(&line[keyword.len()..], lineno)?;
+                    def_file.library = name;
+                    def_file.library_attributes = attributes;
+                }
+                "EXPORTS" => {
+                    in_exports = true;
+
+                    // Section keywords may be followed by the first entry.
+                    let rest = line[keyword.len()..].trim();
+                    if !rest.is_empty() {
+                        push_export(&mut def_file, rest)?;
+                    }
+                }
+                "BASE" | "HEAPSIZE" | "STACKSIZE" | "SECTIONS" | "VERSION" | "STUB" => {
+                    in_exports = false;
+                }
+                _ if in_exports => push_export(&mut def_file, line)?,
+                _ => {
+                    let msg = format!("Unexpected statement in .def line {}", lineno + 1);
+                    return Err(Error::new(ErrorKind::InvalidData, msg));
+                }
+            }
+
+            // The statement comments can only be kept with the file comments.
+            if def_file.exports.is_empty() {
+                def_file.comments.append(&mut comments);
+            }
+        }
+
+        match def_file.exports.is_empty() {
+            true => def_file.comments.append(&mut comments),
+            false => def_file.trailing_comments = comments,
+        }
+
+        Ok(def_file)
+    }
+
+    /// Checks the Module-Definition file contents for the problems
+    /// the import library tools would fail on or silently mishandle.
+    ///
+    /// Reports the invalid syntax, the missing `LIBRARY` statement,
+    /// the duplicate exports, the invalid symbol identifiers
+    /// and the ordinals assigned to more than one symbol.
+    /// Returns an empty list if no problems were found.
+    pub fn lint(def: &str) -> Vec<DefLint> {
+        let mut lints = Vec::new();
+        let mut has_library = false;
+        let mut in_exports = false;
+        let mut names = HashMap::new();
+        let mut ordinals = HashMap::new();
+
+        let mut lint = |kind, line, message| {
+            lints.push(DefLint {
+                kind,
+                line: Some(line),
+                message,
+            })
+        };
+
+        for (lineno, line) in def.lines().enumerate() {
+            let line = line.split(';').next().unwrap_or_default().trim();
+            let keyword = line.split_ascii_whitespace().next().unwrap_or_default();
+
+            let entry = match keyword {
+                "" => continue,
+                "LIBRARY" | "NAME" => {
+                    in_exports = false;
+                    has_library |= line[keyword.len()..].trim() != "";
+                    continue;
+                }
+                "EXPORTS" => {
+                    in_exports = true;
+                    match line[keyword.len()..].trim() {
+                        "" => continue,
+                        rest => rest,
+                    }
+                }
+                "BASE" | "HEAPSIZE" | "STACKSIZE" | "SECTIONS" | "VERSION" | "STUB" => {
+                    in_exports = false;
+                    continue;
+                }
+                _ if in_exports => line,
+                _ => {
+                    let msg = format!("unexpected statement '{}'", keyword);
+                    lint(DefLintKind::Syntax, lineno + 1, msg);
+                    continue;
+                }
+            };
+
+            let export = match parse_export(entry, lineno) {
+                Ok(export) => export,
+                Err(_) => {
+                    let msg = format!("invalid export entry '{}'", entry);
+                    lint(DefLintKind::Syntax, lineno + 1, msg);
+                    continue;
+                }
+            };
+
+            for name in Some(&export.name).into_iter().chain(&export.import_name) {
+                if !is_identifier(name) {
+                    let msg = format!("invalid symbol name '{}'", name);
+                    lint(DefLintKind::InvalidIdentifier, lineno + 1, msg);
+                }
+            }
+
+            if let Some(first) = names.insert(export.name.clone(), lineno + 1) {
+                let msg = format!(
+                    "duplicate export '{}', first exported at line {}",
+                    export.name, first
+                );
+                lint(DefLintKind::DuplicateExport, lineno + 1, msg);
+            }
+
+            if let Some(ordinal) = export.ordinal {
+                if let Some((first, name)) = ordinals.insert(ordinal, (lineno + 1, export.name)) {
+                    let msg = format!(
+                        "ordinal @{} is already assigned to '{}' at line {}",
+                        ordinal, name, first
+                    );
+                    lint(DefLintKind::ConflictingOrdinal, lineno + 1, msg);
+                }
+            }
+        }
+
+        if !has_library {
+            lints.push(DefLint {
+                kind: DefLintKind::MissingLibrary,
+                line: None,
+                message: "no LIBRARY statement naming the DLL".to_owned(),
+            });
+        }
+
+        lints
+    }
+
+    /// Parses the JSON symbol list, see [`DefFile::from_path()`] for the format.
+    pub fn from_symbol_list_json(input: &str) -> Result<Self> {
+        symlist::parse_json(input)
+    }
+
+    /// Parses the TOML symbol list, see [`DefFile::from_path()`] for the format.
+    ///
+    /// Requires the `toml-symbol-list` crate feature.
+    #[cfg(feature = "toml-symbol-list")]
+    pub fn from_symbol_list_toml(input: &str) -> Result<Self> {
+        symlist::parse_toml(input)
+    }
+
+    /// Reads and parses the Module-Definition file at `path`.
+    ///
+    /// The `.json` and `.toml` files are parsed as the structured symbol lists
+    /// instead, which are easier for the other tools to emit:
+    ///
+    /// ```json
+    /// {
+    ///     "library": "python3.dll",
+    ///     "symbols": [
+    ///         { "name": "Py_Initialize" },
+    ///         { "name": "PyExc_TypeError", "kind": "data" },
+    ///         { "name": "Py_Main", "kind": "function", "ordinal": 12 }
+    ///     ]
+    /// }
+    /// ```
+    ///
+    /// The `kind` is `function` by default, the `ordinal` is optional.
+    /// The TOML symbol lists have the same keys, with the symbols in
+    /// the `[[symbols]]` array of tables, and require
+    /// the `toml-symbol-list` crate feature.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let input = read_to_string(path)?;
+
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::from_symbol_list_json(&input),
+            #[cfg(feature = "toml-symbol-list")]
+            Some("toml") => Self::from_symbol_list_toml(&input),
+            #[cfg(not(feature = "toml-symbol-list"))]
+            Some("toml") => {
+                let msg = "TOML symbol lists require the `toml-symbol-list` feature";
+                Err(Error::new(ErrorKind::Unsupported, msg))
+            }
+            _ => Self::parse(&input),
+        };
+
+        parsed.map_err(|e| {
+            let msg = format!("{}: {}", path.display(), e);
+            Error::new(e.kind(), msg)
+        })
+    }
+
+    /// Writes the Module-Definition file contents.
+    pub fn to_def(&self) -> String {
+        let mut def = String::new();
+
+        for comment in &self.comments {
+            def.push_str(&format!(";{}\n", comment));
+        }
+
+        if let Some(library) = &self.library {
+            if library.contains(char::is_whitespace) {
+                def.push_str(&format!("LIBRARY \"{}\"", library));
+            } else {
+                def.push_str(&format!("LIBRARY {}", library));
+            }
+            if let Some(attributes) = &self.library_attributes {
+                def.push_str(&format!(" {}", attributes));
+            }
+            def.push('\n');
+        }
+
+        def.push_str("EXPORTS\n");
+
+        for export in &self.exports {
+            for comment in &export.comments {
+                def.push_str(&format!(";{}\n", comment));
+            }
+            def.push_str(&export.to_def());
+            def.push('\n');
+        }
+
+        for comment in &self.trailing_comments {
+            def.push_str(&format!(";{}\n", comment));
+        }
+
+        def
+    }
+
+    /// Returns the DLL name including the file extension.
+    ///
+    /// The `.dll` extension is added when missing in the `LIBRARY` statement.
+    pub fn dll_name(&self) -> Option<String> {
+        let library = self.library.as_deref()?;
+
+        if library.contains('.') {
+            Some(library.to_owned())
+        } else {
+            Some(format!("{}.dll", library))
+        }
+    }
+
+    /// Builds a Rust source file declaring the exported symbol name tables.
+    ///
+    /// The generated file defines the `DLL_NAME` constant (when the `LIBRARY`
+    /// statement is present) and the sorted `FUNCTIONS` and `DATA` name
+    /// slices, which the bindings crates can include to check their `extern`
+    /// declarations against. The function signatures are not a part
+    /// of the ABI data, so no `extern` blocks are generated.
+    pub fn rust_symbol_table(&self) -> String {
+        let names = |data| {
+            let mut names: Vec<_> = self
+                .exports
+                .iter()
+                .filter(|export| export.data == data)
+                .map(|export| export.name.as_str())
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+        };
+
+        let functions = names(false);
+        let data = names(true);
+
+        let mut source = format!(
+            "// Generated by python3-dll-a {}, do not edit.\n",
+            env!("CARGO_PKG_VERSION")
+        );
+
+        if let Some(dll_name) = self.dll_name() {
+            source.push_str("\n/// Python DLL name\n");
+            source.push_str(&format!("pub const DLL_NAME: &str = \"{}\";\n", dll_name));
+        }
+
+        for (doc, name, symbols) in [
+            ("Exported function names", "FUNCTIONS", functions),
+            ("Exported data names", "DATA", data),
+        ] {
+            source.push_str(&format!(
+                "\n/// {}\npub const {}: &[&str] = &[\n",
+                doc, name
+            ));
+            for symbol in symbols {
+                source.push_str(&format!("    \"{}\",\n", symbol));
+            }
+            source.push_str("];\n");
+        }
+
+        source
+    }
+}
+
+/// Returns whether `name` is a valid linker symbol identifier,
+/// including the decorated and the C++ mangled names.
+fn is_identifier(name: &str) -> bool {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || "_@$?.".contains(c);
+
+    name.chars().all(valid_char) && !name.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Splits the `LIBRARY` statement arguments `args` into the DLL name,
+/// which may be quoted and contain spaces, and the following attributes.
+fn library_args(args: &str, lineno: usize) -> Result<(Option<String>, Option<String>)> {
+    let args = args.trim();
+
+    let (name, rest) = match args.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"').ok_or_else(|| {
+            let msg = format!("Unterminated LIBRARY name in .def line {}", lineno + 1);
+            Error::new(ErrorKind::InvalidData, msg)
+        })?,
+        None => args.split_once(char::is_whitespace).unwrap_or((args, "")),
+    };
+
+    let name = Some(name.to_owned()).filter(|name| !name.is_empty());
+    let rest = rest.trim();
+    let attributes = Some(rest.to_owned()).filter(|rest| !rest.is_empty());
+
+    Ok((name, attributes))
+}
+
+/// Parses a single `EXPORTS` section entry.
+///
+/// The supported syntax is
+/// `name[=internal_name][==import_name] [@ordinal [NONAME]] [DATA] [PRIVATE]`.
+fn parse_export(entry: &str, lineno: usize) -> Result<DefExport> {
+    let invalid = || {
+        let msg = format!("Invalid export entry in .def line {}", lineno + 1);
+        Error::new(ErrorKind::InvalidData, msg)
+    };
+
+    // Normalize the `name == import_name` spacing variants.
+    let entry = entry.replace("==", " == ");
+    let mut tokens = entry.split_ascii_whitespace().peekable();
+
+    let name = tokens.next().ok_or_else(invalid)?;
+
+    let (name, internal_name) = match name.split_once('=') {
+        Some((name, internal_name)) => (name, Some(internal_name)),
+        None => (name, None),
+    };
+    if name.is_empty() {
+        return Err(invalid());
+    }
+
+    let mut export = DefExport {
+        name: name.to_owned(),
+        internal_name: internal_name
+            .filter(|internal_name| !internal_name.is_empty())
+            .map(str::to_owned),
+        ..DefExport::default()
+    };
+
+    while let Some(token) = tokens.next() {
+        match token {
+            "==" => export.import_name = Some(tokens.next().ok_or_else(invalid)?.to_owned()),
+            "DATA" | "CONSTANT" => export.data = true,
+            "NONAME" => export.noname = true,
+            "PRIVATE" => export.private = true,
+            "@" => {
+                let ordinal = tokens.next().ok_or_else(invalid)?;
+                export.ordinal = Some(ordinal.parse().map_err(|_| invalid())?);
+            }
+            _ if token.starts_with('@') => {
+                export.ordinal = Some(token[1..].parse().map_err(|_| invalid())?);
+            }
+            // The symbol `=internal_name` spaced out from the name.
+            _ if token.starts_with('=') && token.len() > 1 => {
+                export.internal_name = Some(token[1..].to_owned());
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(export)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_def() {
+        let def = ";\n; Definition file of python37.dll\n;\n\
+                   LIBRARY \"python37.dll\"\nEXPORTS\n\
+                   Py_Initialize\n\
+                   PyExc_TypeError DATA\n\
+                   _Py_Main == Py_Main @12\n";
+
+        let def_file = DefFile::parse(def).unwrap();
+        assert_eq!(def_file.dll_name().as_deref(), Some("python37.dll"));
+        assert_eq!(def_file.exports.len(), 3);
+        assert!(def_file.exports[1].data);
+        assert_eq!(def_file.exports[2].import_name.as_deref(), Some("Py_Main"));
+        assert_eq!(def_file.exports[2].ordinal, Some(12));
+
+        let def_file = DefFile::parse("LIBRARY python3\nEXPORTS\n").unwrap();
+        assert_eq!(def_file.dll_name().as_deref(), Some("python3.dll"));
+
+        assert!(DefFile::parse("Py_Initialize\n").is_err());
+
+        let written = DefFile::parse(def).unwrap().to_def();
+        assert_eq!(
+            written,
+            ";\n; Definition file of python37.dll\n;\n\
+             LIBRARY python37.dll\nEXPORTS\nPy_Initialize\nPyExc_TypeError DATA\n\
+             _Py_Main == Py_Main @12\n"
+        );
+        assert_eq!(
+            DefFile::parse(&written).unwrap(),
+            DefFile::parse(def).unwrap()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let def = "; Curated python3.dll definitions\n\
+                   LIBRARY python3.dll\nEXPORTS\n\
+                   ; Functions\n\
+                   Py_Main @3\n\
+                   Py_Initialize=Py_InitializeEx @1 ; keep\n\
+                   ; Data\n\
+                   PyExc_TypeError @2 DATA\n\
+                   ; End\n";
+
+        let mut def_file = DefFile::parse(def).unwrap();
+        assert_eq!(def_file.to_def(), def);
+        assert_eq!(def_file.comments, [" Curated python3.dll definitions"]);
+        assert_eq!(def_file.exports[0].comments, [" Functions"]);
+        assert_eq!(def_file.exports[1].comment.as_deref(), Some(" keep"));
+        assert_eq!(
+            def_file.exports[1].internal_name.as_deref(),
+            Some("Py_InitializeEx")
+        );
+        assert_eq!(def_file.trailing_comments, [" End"]);
+
+        let def = "LIBRARY \"my python.dll\" BASE=0x1e000000\nEXPORTS\nPy_Main\n";
+        let quoted = DefFile::parse(def).unwrap();
+        assert_eq!(quoted.dll_name().as_deref(), Some("my python.dll"));
+        assert_eq!(
+            quoted.library_attributes.as_deref(),
+            Some("BASE=0x1e000000")
+        );
+        assert_eq!(quoted.to_def(), def);
+        assert!(DefFile::parse("LIBRARY \"my python.dll\nEXPORTS\n").is_err());
+
+        def_file.exports.remove(0);
+        def_file.exports[1].private = true;
+        assert_eq!(
+            def_file.to_def(),
+            "; Curated python3.dll definitions\n\
+             LIBRARY python3.dll\nEXPORTS\n\
+             Py_Initialize=Py_InitializeEx @1 ; keep\n\
+             ; Data\n\
+             PyExc_TypeError @2 DATA PRIVATE\n\
+             ; End\n"
+        );
+    }
+
+    #[test]
+    fn lint() {
+        let def = "LIBRARY python3.dll\nEXPORTS\nPy_Main @1\nPyExc_TypeError DATA\n\
+                   Py_Main\n3rd-party @1\nPy_Initialize @@\n";
+
+        let lints = DefFile::lint(def);
+        let kinds: Vec<_> = lints.iter().map(|lint| (lint.kind, lint.line)).collect();
+        assert_eq!(
+            kinds,
+            [
+                (DefLintKind::DuplicateExport, Some(5)),
+                (DefLintKind::InvalidIdentifier, Some(6)),
+                (DefLintKind::ConflictingOrdinal, Some(6)),
+                (DefLintKind::Syntax, Some(7)),
+            ]
+        );
+        assert_eq!(
+            lints[0].to_string(),
+            "line 5: duplicate export 'Py_Main', first exported at line 3"
+        );
+
+        let lints = DefFile::lint("EXPORTS\nPy_Main\n");
+        assert_eq!(lints.len(), 1);
+        assert_eq!(lints[0].kind, DefLintKind::MissingLibrary);
+
+        assert!(DefFile::lint("LIBRARY python3.dll\nEXPORTS\n?Py@@YAXXZ\n").is_empty());
+    }
+
+    #[test]
+    fn normalize() {
+        let def = "; comment\nLIBRARY \"python3.dll\"\nEXPORTS _Py_Main == Py_Main\n\
+                   PyExc_TypeError CONSTANT ; data\n\
+                   Py_Initialize=py_init PRIVATE @ 7\n";
+
+        assert_eq!(
+            DefDialect::Mingw.normalize(def).unwrap(),
+            "; comment\nLIBRARY \"python3.dll\"\nEXPORTS\n_Py_Main == Py_Main\n\
+             PyExc_TypeError DATA ; data\nPy_Initialize @7 PRIVATE\n"
+        );
+        assert_eq!(
+            DefDialect::Msvc.normalize(def).unwrap(),
+            "; comment\nLIBRARY \"python3.dll\"\nEXPORTS\nPy_Main\n\
+             PyExc_TypeError DATA ; data\nPy_Initialize @7 PRIVATE\n"
+        );
+
+        assert!(DefDialect::Msvc.normalize("EXPORTS\nPy_Main @x\n").is_err());
+    }
+
+    #[test]
+    fn metadata() {
+        let metadata = DefMetadata {
+            version: "0.2.12".to_owned(),
+            source: "embedded".to_owned(),
+            source_sha256: "00ff".to_owned(),
+            target: "x86-gnu".to_owned(),
+            python: "3.13t".to_owned(),
+            options: vec!["debug".to_owned(), "exclude-private".to_owned()],
+        };
+
+        let def = format!("{}LIBRARY python313t_d.dll\nEXPORTS\n", metadata.header());
+        assert!(def.starts_with("; python3-dll-a.version = 0.2.12\n"));
+        assert!(def.contains("; python3-dll-a.options = debug exclude-private\n"));
+
+        assert_eq!(DefMetadata::parse(&def), Some(metadata));
+        assert!(DefFile::parse(&def).unwrap().exports.is_empty());
+
+        assert_eq!(DefMetadata::parse("LIBRARY python3.dll\nEXPORTS\n"), None);
+    }
+
+    #[test]
+    fn rust_symbol_table() {
+        let def = "LIBRARY python3.dll\nEXPORTS\nPy_Main\nPyExc_TypeError DATA\nPy_Initialize\n";
+
+        let source = DefFile::parse(def).unwrap().rust_symbol_table();
+
+        assert!(source.starts_with("// Generated by python3-dll-a "));
+        assert!(source.contains("pub const DLL_NAME: &str = \"python3.dll\";\n"));
+        assert!(source.contains(
+            "pub const FUNCTIONS: &[&str] = &[\n    \"Py_Initialize\",\n    \"Py_Main\",\n];\n"
+        ));
+        assert!(source.contains("pub const DATA: &[&str] = &[\n    \"PyExc_TypeError\",\n];\n"));
+    }
+}
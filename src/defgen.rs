@@ -0,0 +1,709 @@
+//! Module-Definition file generator
+//!
+//! Parses the CPython Stable ABI manifest `Misc/stable_abi.toml`
+//! and produces the embedded `python3.def` file contents.
+//!
+//! The version-specific `pythonXY.def` files can be produced
+//! from a CPython source checkout with [`CPythonExports`].
+//!
+//! This module is only available with the `defgen` crate feature enabled.
+//!
+//! Example usage
+//! -------------
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! use python3_dll_a::defgen::{CPythonExports, StableAbiManifest};
+//!
+//! let manifest = StableAbiManifest::from_path(Path::new("cpython/Misc/stable_abi.toml"))?;
+//!
+//! // Regenerate `python3.def` from the manifest.
+//! manifest.write_def(Path::new("src/python3.def"))?;
+//!
+//! // Generate `python313.def` from a CPython v3.13 checkout.
+//! let exports = CPythonExports::from_checkout(Path::new("cpython"))?;
+//! exports.write_def(Path::new("src"))?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::collections::BTreeMap;
+use std::fs::{read_dir, read_to_string, write};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+
+pub use crate::def::SymbolKind;
+
+/// Stable ABI DLL name written into the `LIBRARY` statement
+const STABLE_ABI_DLL: &str = "python3.dll";
+
+/// Stable ABI symbol description from `stable_abi.toml`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StableAbiSymbol {
+    /// Exported symbol name
+    pub name: String,
+    /// Exported symbol kind
+    pub kind: SymbolKind,
+    /// Python version the symbol was added to the Stable ABI in
+    pub added: Option<(u8, u8)>,
+    /// The symbol is a part of the ABI, but not of the Limited API
+    pub abi_only: bool,
+    /// Feature macro guarding the symbol availability (e.g. `HAVE_FORK`)
+    pub ifdef: Option<String>,
+}
+
+/// Parsed CPython Stable ABI manifest (`Misc/stable_abi.toml`)
+#[derive(Debug, Clone, Default)]
+pub struct StableAbiManifest {
+    /// Exported functions and data in the manifest order
+    symbols: Vec<StableAbiSymbol>,
+}
+
+impl StableAbiManifest {
+    /// Parses the `stable_abi.toml` manifest contents.
+    ///
+    /// Only the `[function.*]` and `[data.*]` manifest items
+    /// correspond to the DLL exports, all other items are skipped.
+    pub fn parse(manifest: &str) -> Result<Self> {
+        let table: toml::Table = manifest
+            .parse()
+            .map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+
+        let mut symbols = Vec::new();
+
+        // Match `parse-stable-abi.py`: all functions first, then all data.
+        for (section, kind) in [
+            ("function", SymbolKind::Function),
+            ("data", SymbolKind::Data),
+        ] {
+            let items = match table.get(section) {
+                Some(items) => items,
+                None => continue,
+            };
+
+            let items = items.as_table().ok_or_else(|| {
+                let msg = format!("Invalid Stable ABI manifest section '{}'", section);
+                Error::new(ErrorKind::InvalidData, msg)
+            })?;
+
+            for (name, item) in items {
+                symbols.push(parse_symbol(name, kind, item)?);
+            }
+        }
+
+        Ok(StableAbiManifest { symbols })
+    }
+
+    /// Reads and parses the `stable_abi.toml` manifest file.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let manifest = read_to_string(path)?;
+
+        Self::parse(&manifest)
+    }
+
+    /// Parses the Stable ABI export list from the CPython `PC/python3dll.c`
+    /// source file contents.
+    ///
+    /// The `EXPORT_FUNC(name)` and `EXPORT_DATA(name)` entries are collected
+    /// in the source order. The resulting symbols carry no version information.
+    pub fn parse_python3dll_c(source: &str) -> Self {
+        let symbols = parse_export_macros(source)
+            .into_iter()
+            .map(|(name, kind)| StableAbiSymbol {
+                name,
+                kind,
+                added: None,
+                abi_only: false,
+                ifdef: None,
+            })
+            .collect();
+
+        StableAbiManifest { symbols }
+    }
+
+    /// Returns the exported symbols in the manifest order.
+    pub fn symbols(&self) -> &[StableAbiSymbol] {
+        &self.symbols
+    }
+
+    /// Generates the `python3.def` Module-Definition file contents.
+    pub fn to_def(&self) -> String {
+        self.to_def_for_version(None)
+    }
+
+    /// Generates the `python3.def` Module-Definition file contents
+    /// limited to the symbols added in or before the Python `version`.
+    ///
+    /// All manifest symbols are included when `version` is `None`.
+    pub fn to_def_for_version(&self, version: Option<(u8, u8)>) -> String {
+        let mut def = format!("LIBRARY {}\nEXPORTS\n", STABLE_ABI_DLL);
+
+        let symbols = self
+            .symbols
+            .iter()
+            .filter(|symbol| match (version, symbol.added) {
+                (Some(version), Some(added)) => added <= version,
+                _ => true,
+            });
+
+        for symbol in symbols {
+            def.push_str(&symbol.name);
+
+            if symbol.kind == SymbolKind::Data {
+                def.push_str(" DATA");
+            }
+
+            def.push('\n');
+        }
+
+        def
+    }
+
+    /// Writes out the generated `python3.def` file to `path`.
+    pub fn write_def(&self, path: &Path) -> Result<()> {
+        write(path, self.to_def())
+    }
+}
+
+/// Version-specific Python DLL exports collected from the CPython sources
+///
+/// The export list is assembled from the `PyAPI_FUNC()` and `PyAPI_DATA()`
+/// declarations found in the public C headers (`Include/**/*.h`)
+/// and the Stable ABI exports listed in `PC/python3dll.c`.
+#[derive(Debug, Clone)]
+pub struct CPythonExports {
+    /// Major and minor Python version from `Include/patchlevel.h`
+    version: (u8, u8),
+    /// Optional Python ABI flags (e.g. `"t"`)
+    abiflags: Option<String>,
+    /// Exported symbols sorted by name (as in the DLL export table)
+    symbols: BTreeMap<String, SymbolKind>,
+}
+
+impl CPythonExports {
+    /// Collects the Python DLL exports from the CPython checkout at `path`.
+    ///
+    /// The Python version is read from `Include/patchlevel.h`.
+    pub fn from_checkout(path: &Path) -> Result<Self> {
+        let patchlevel = read_to_string(path.join("Include").join("patchlevel.h"))?;
+        let version = parse_patchlevel(&patchlevel)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Invalid CPython patchlevel.h"))?;
+
+        let mut headers = Vec::new();
+        find_headers(&path.join("Include"), &mut headers)?;
+        headers.sort();
+
+        let mut exports = CPythonExports {
+            version,
+            abiflags: None,
+            symbols: BTreeMap::new(),
+        };
+
+        for header in headers {
+            exports.add_header(&read_to_string(header)?);
+        }
+
+        let python3dll = path.join("PC").join("python3dll.c");
+        if python3dll.exists() {
+            for (name, kind) in parse_export_macros(&read_to_string(python3dll)?) {
+                exports.symbols.insert(name, kind);
+            }
+        }
+
+        Ok(exports)
+    }
+
+    /// Sets the ABI flags for the `pythonXY<abi>.dll` name.
+    ///
+    /// For example, `"t"` stands for the free-threaded CPython build.
+    pub fn abiflags(&mut self, flags: Option<&str>) -> &mut Self {
+        self.abiflags = flags.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Adds the `PyAPI_FUNC()` and `PyAPI_DATA()` declarations
+    /// from the C header file contents.
+    pub fn add_header(&mut self, header: &str) {
+        for (name, kind) in parse_api_declarations(header) {
+            self.symbols.insert(name, kind);
+        }
+    }
+
+    /// Returns the major and minor Python version.
+    pub fn version(&self) -> (u8, u8) {
+        self.version
+    }
+
+    /// Returns the collected exports sorted by name.
+    pub fn symbols(&self) -> impl Iterator<Item = (&str, SymbolKind)> {
+        self.symbols
+            .iter()
+            .map(|(name, kind)| (name.as_str(), *kind))
+    }
+
+    /// Returns the versioned DLL name stem, e.g. `python313t`.
+    pub fn dll_stem(&self) -> String {
+        let abiflags = self.abiflags.as_deref().unwrap_or_default();
+
+        format!("python{}{}{}", self.version.0, self.version.1, abiflags)
+    }
+
+    /// Generates the `pythonXY.def` Module-Definition file contents.
+    pub fn to_def(&self) -> String {
+        let mut def = format!("LIBRARY \"{}.dll\"\nEXPORTS\n", self.dll_stem());
+
+        for (name, kind) in self.symbols() {
+            def.push_str(name);
+
+            if kind == SymbolKind::Data {
+                def.push_str(" DATA");
+            }
+
+            def.push('\n');
+        }
+
+        def
+    }
+
+    /// Writes out the generated `pythonXY.def` file into the `out_dir` directory.
+    ///
+    /// Returns the newly created file path.
+    pub fn write_def(&self, out_dir: &Path) -> Result<PathBuf> {
+        let defpath = out_dir.join(format!("{}.def", self.dll_stem()));
+
+        write(&defpath, self.to_def())?;
+
+        Ok(defpath)
+    }
+}
+
+/// Symbol-level difference between two Module-Definition files
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DefDiff {
+    /// Exports present only in the new file
+    pub added: Vec<String>,
+    /// Exports present only in the old file
+    pub removed: Vec<String>,
+    /// Exports changing the kind (e.g. becoming `DATA`)
+    pub changed: Vec<String>,
+}
+
+impl DefDiff {
+    /// Compares the `old` and the `new` Module-Definition file contents.
+    ///
+    /// The reported symbol names are sorted alphabetically.
+    pub fn new(old: &str, new: &str) -> Self {
+        let old = parse_def_exports(old);
+        let new = parse_def_exports(new);
+
+        let mut diff = DefDiff::default();
+
+        for (name, kind) in &new {
+            match old.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(old_kind) if old_kind != kind => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+
+        for name in old.keys() {
+            if !new.contains_key(name) {
+                diff.removed.push(name.clone());
+            }
+        }
+
+        diff
+    }
+
+    /// Checks if both files export exactly the same symbols.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Collects the exported symbols from the Module-Definition file contents.
+fn parse_def_exports(def: &str) -> BTreeMap<String, SymbolKind> {
+    let mut exports = BTreeMap::new();
+    let mut in_exports = false;
+
+    for line in def.lines() {
+        let line = line.split(';').next().unwrap_or_default().trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line == "EXPORTS" {
+            in_exports = true;
+            continue;
+        }
+
+        if in_exports {
+            let mut fields = line.split_ascii_whitespace();
+
+            if let Some(name) = fields.next() {
+                let kind = if fields.any(|field| field == "DATA") {
+                    SymbolKind::Data
+                } else {
+                    SymbolKind::Function
+                };
+
+                exports.insert(name.to_owned(), kind);
+            }
+        }
+    }
+
+    exports
+}
+
+/// Recursively collects the C header files in `dir`.
+fn find_headers(dir: &Path, headers: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            find_headers(&path, headers)?;
+        } else if path.extension() == Some("h".as_ref()) {
+            headers.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts `PY_MAJOR_VERSION` and `PY_MINOR_VERSION` from `patchlevel.h`.
+fn parse_patchlevel(patchlevel: &str) -> Option<(u8, u8)> {
+    let define = |macro_name: &str| {
+        patchlevel.lines().find_map(|line| {
+            let mut tokens = line.split_ascii_whitespace();
+
+            match (tokens.next(), tokens.next(), tokens.next()) {
+                (Some("#define"), Some(name), Some(value)) if name == macro_name => {
+                    value.parse().ok()
+                }
+                _ => None,
+            }
+        })
+    };
+
+    Some((define("PY_MAJOR_VERSION")?, define("PY_MINOR_VERSION")?))
+}
+
+/// Parses the `EXPORT_FUNC(name)` and `EXPORT_DATA(name)` macro invocations.
+fn parse_export_macros(source: &str) -> Vec<(String, SymbolKind)> {
+    let source = strip_c_comments(source);
+
+    source
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+
+            let (args, kind) = if let Some(args) = line.strip_prefix("EXPORT_FUNC(") {
+                (args, SymbolKind::Function)
+            } else if let Some(args) = line.strip_prefix("EXPORT_DATA(") {
+                (args, SymbolKind::Data)
+            } else {
+                return None;
+            };
+
+            let name = args.split(')').next()?.trim();
+
+            Some((name.to_owned(), kind))
+        })
+        .collect()
+}
+
+/// Parses the `PyAPI_FUNC(type) name(...)` and `PyAPI_DATA(type) name`
+/// declarations in the C header file contents.
+fn parse_api_declarations(header: &str) -> Vec<(String, SymbolKind)> {
+    let header = strip_c_comments(header);
+
+    // Skip the preprocessor directives, e.g. `#define PyAPI_FUNC(RTYPE) ...`
+    let code: String = header
+        .lines()
+        .filter(|line| !line.trim_start().starts_with('#'))
+        .flat_map(|line| line.chars().chain(Some('\n')))
+        .collect();
+
+    let mut declarations = Vec::new();
+
+    for (marker, kind) in [
+        ("PyAPI_FUNC(", SymbolKind::Function),
+        ("PyAPI_DATA(", SymbolKind::Data),
+    ] {
+        let mut rest = code.as_str();
+
+        while let Some(start) = rest.find(marker) {
+            rest = &rest[start + marker.len()..];
+
+            // Skip the possibly parenthesized return type.
+            let mut depth = 1;
+            let mut end = None;
+            for (i, c) in rest.char_indices() {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                if depth == 0 {
+                    end = Some(i);
+                    break;
+                }
+            }
+            let end = match end {
+                Some(end) => end,
+                None => break,
+            };
+            rest = &rest[end + 1..];
+
+            // The declared name is the last identifier before `(`, `[`, `;` or `,`.
+            let decl_end = rest.find(['(', '[', ';', ',', '=']).unwrap_or(rest.len());
+            let name = rest[..decl_end]
+                .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .find(|token| !token.is_empty());
+
+            if let Some(name) = name {
+                declarations.push((name.to_owned(), kind));
+            }
+        }
+    }
+
+    declarations
+}
+
+/// Replaces the C comments with whitespace.
+fn strip_c_comments(source: &str) -> String {
+    let mut stripped = String::with_capacity(source.len());
+    let mut rest = source;
+
+    loop {
+        let block = rest.find("/*");
+        let line = rest.find("//");
+
+        // Find out which comment kind starts first.
+        let block = match (block, line) {
+            (Some(b), Some(l)) if l < b => None,
+            (block, _) => block,
+        };
+
+        match (block, line) {
+            (Some(b), _) => {
+                stripped.push_str(&rest[..b]);
+                stripped.push(' ');
+                rest = match rest[b + 2..].find("*/") {
+                    Some(end) => &rest[b + 2 + end + 2..],
+                    None => "",
+                };
+            }
+            (_, Some(l)) => {
+                stripped.push_str(&rest[..l]);
+                rest = match rest[l..].find('\n') {
+                    Some(end) => &rest[l + end..],
+                    None => "",
+                };
+            }
+            _ => {
+                stripped.push_str(rest);
+                break;
+            }
+        }
+    }
+
+    stripped
+}
+
+/// Parses a single `[function.<name>]` or `[data.<name>]` manifest item.
+fn parse_symbol(name: &str, kind: SymbolKind, item: &toml::Value) -> Result<StableAbiSymbol> {
+    let invalid = |key: &str| {
+        let msg = format!("Invalid '{}' value for Stable ABI symbol '{}'", key, name);
+        Error::new(ErrorKind::InvalidData, msg)
+    };
+
+    let added = match item.get("added") {
+        Some(added) => {
+            let added = added.as_str().ok_or_else(|| invalid("added"))?;
+            Some(parse_version(added).ok_or_else(|| invalid("added"))?)
+        }
+        None => None,
+    };
+
+    let abi_only = match item.get("abi_only") {
+        Some(abi_only) => abi_only.as_bool().ok_or_else(|| invalid("abi_only"))?,
+        None => false,
+    };
+
+    let ifdef = match item.get("ifdef") {
+        Some(ifdef) => Some(ifdef.as_str().ok_or_else(|| invalid("ifdef"))?.to_owned()),
+        None => None,
+    };
+
+    Ok(StableAbiSymbol {
+        name: name.to_owned(),
+        kind,
+        added,
+        abi_only,
+        ifdef,
+    })
+}
+
+/// Parses a `"3.13"`-like version string.
+fn parse_version(version: &str) -> Option<(u8, u8)> {
+    let (major, minor) = version.split_once('.')?;
+
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+# Comment
+[function.PyType_FromSpec]
+    added = '3.2'
+[data.PyExc_TypeError]
+    added = '3.2'
+[struct.PyObject]
+    added = '3.2'
+    members = ['ob_refcnt', 'ob_type']
+[function.PyOS_AfterFork_Child]
+    added = '3.13'
+    ifdef = 'HAVE_FORK'
+[function.PyEval_AcquireLock]
+    added = '3.2'
+    abi_only = true
+"#;
+
+    #[test]
+    fn parse_manifest() {
+        let manifest = StableAbiManifest::parse(MANIFEST).unwrap();
+
+        let names: Vec<_> = manifest.symbols().iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "PyType_FromSpec",
+                "PyOS_AfterFork_Child",
+                "PyEval_AcquireLock",
+                "PyExc_TypeError"
+            ]
+        );
+
+        let fork = &manifest.symbols()[1];
+        assert_eq!(fork.added, Some((3, 13)));
+        assert_eq!(fork.ifdef.as_deref(), Some("HAVE_FORK"));
+        assert!(manifest.symbols()[2].abi_only);
+        assert_eq!(manifest.symbols()[3].kind, SymbolKind::Data);
+    }
+
+    #[test]
+    fn generate_def() {
+        let manifest = StableAbiManifest::parse(MANIFEST).unwrap();
+
+        assert_eq!(
+            manifest.to_def(),
+            "LIBRARY python3.dll\nEXPORTS\nPyType_FromSpec\nPyOS_AfterFork_Child\n\
+             PyEval_AcquireLock\nPyExc_TypeError DATA\n"
+        );
+
+        assert_eq!(
+            manifest.to_def_for_version(Some((3, 12))),
+            "LIBRARY python3.dll\nEXPORTS\nPyType_FromSpec\n\
+             PyEval_AcquireLock\nPyExc_TypeError DATA\n"
+        );
+    }
+
+    #[test]
+    fn diff_defs() {
+        let old = "; gendef\nLIBRARY python3.dll\nEXPORTS\nPy_A\nPy_B\nPy_C\n";
+        let new = "LIBRARY python3.dll\nEXPORTS\nPy_A\nPy_C DATA\nPy_D\n";
+
+        let diff = DefDiff::new(old, new);
+        assert_eq!(diff.added, ["Py_D"]);
+        assert_eq!(diff.removed, ["Py_B"]);
+        assert_eq!(diff.changed, ["Py_C"]);
+
+        assert!(DefDiff::new(old, old).is_empty());
+    }
+
+    #[test]
+    fn parse_python3dll() {
+        let source = "// Re-export the stable ABI\n\
+                      EXPORT_FUNC(PyArg_Parse)\n\
+                      /* EXPORT_FUNC(Py_Removed) */\n\
+                      EXPORT_DATA(PyExc_TypeError)\n";
+        let manifest = StableAbiManifest::parse_python3dll_c(source);
+
+        assert_eq!(
+            manifest.to_def(),
+            "LIBRARY python3.dll\nEXPORTS\nPyArg_Parse\nPyExc_TypeError DATA\n"
+        );
+    }
+
+    #[test]
+    fn parse_headers() {
+        let header = r#"
+#define PyAPI_FUNC(RTYPE) __declspec(dllexport) RTYPE
+PyAPI_FUNC(PyObject *) PyObject_Call(PyObject *callable,
+                                     PyObject *args, PyObject *kwargs);
+/* PyAPI_FUNC(int) Py_Commented(void); */
+PyAPI_FUNC(void) _Py_NO_RETURN Py_FatalError(const char *message);
+PyAPI_FUNC(PyOS_sighandler_t) PyOS_getsig(int);
+PyAPI_DATA(PyTypeObject) PyType_Type;
+PyAPI_DATA(const unsigned char) _Py_ascii_whitespace[];
+"#;
+
+        let declarations = parse_api_declarations(header);
+        let names: Vec<_> = declarations.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "PyObject_Call",
+                "Py_FatalError",
+                "PyOS_getsig",
+                "PyType_Type",
+                "_Py_ascii_whitespace"
+            ]
+        );
+        assert_eq!(declarations[3].1, SymbolKind::Data);
+    }
+
+    #[test]
+    fn cpython_checkout() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("defgen");
+        dir.push("cpython");
+
+        std::fs::create_dir_all(dir.join("Include").join("cpython")).unwrap();
+        std::fs::create_dir_all(dir.join("PC")).unwrap();
+
+        write(
+            dir.join("Include").join("patchlevel.h"),
+            "#define PY_MAJOR_VERSION        3\n#define PY_MINOR_VERSION        13\n",
+        )
+        .unwrap();
+        write(
+            dir.join("Include").join("cpython").join("object.h"),
+            "PyAPI_FUNC(int) _PyObject_Private(void);\n",
+        )
+        .unwrap();
+        write(
+            dir.join("PC").join("python3dll.c"),
+            "EXPORT_FUNC(Py_Initialize)\nEXPORT_DATA(Py_None)\n",
+        )
+        .unwrap();
+
+        let mut exports = CPythonExports::from_checkout(&dir).unwrap();
+        assert_eq!(exports.version(), (3, 13));
+
+        exports.abiflags(Some("t"));
+        assert_eq!(
+            exports.to_def(),
+            "LIBRARY \"python313t.dll\"\nEXPORTS\nPy_Initialize\nPy_None DATA\n\
+             _PyObject_Private\n"
+        );
+
+        let defpath = exports.write_def(&dir).unwrap();
+        assert!(defpath.ends_with("python313t.def"));
+    }
+}
@@ -0,0 +1,1515 @@
+//! Import library generation tool discovery
+//!
+//! Without an explicitly configured tool, the `dlltool` flavor for the target
+//! is chosen from the tools installed on the host:
+//!
+//! - The MinGW-w64 `dlltool` with the target arch prefix (or the command set
+//!   with `PYO3_MINGW_DLLTOOL`) for the `gnu` targets. If only the unprefixed
+//!   multi-target binutils `dlltool` is installed, it is used for both 32-bit
+//!   and 64-bit targets with the `-m` machine option. If the MinGW-w64 binutils
+//!   are not installed, `llvm-dlltool` writes the `.dll.a` import libraries instead.
+//! - The Visual Studio `lib.exe` on the Windows hosts, or `llvm-dlltool`
+//!   elsewhere, for the `msvc` targets.
+//! - `zig dlltool` when `ZIG_COMMAND` is set.
+//!
+//! `llvm-dlltool` is looked up in `PATH` first. On macOS hosts,
+//! the Homebrew (`/opt/homebrew/opt/llvm/bin`, `/usr/local/opt/llvm/bin`)
+//! and MacPorts (`/opt/local/libexec/llvm-*/bin`) LLVM installations are also
+//! searched, newest LLVM version first. On Debian and Ubuntu hosts, the same
+//! applies to the `/usr/lib/llvm-*/bin` directories of the versioned LLVM packages.
+//!
+//! `lib.exe` is taken from an active Visual Studio developer environment
+//! (`VCToolsInstallDir` and `VSCMD_ARG_HOST_ARCH`) first, then from the `cc`
+//! crate registry lookup with the default `msvc-discovery` feature, and from
+//! `PATH` otherwise. On the ARM64 Windows hosts, the native `Hostarm64` tools are
+//! preferred over the emulated x64 ones, and the cross-arch lookups fall back
+//! to the host architecture `lib.exe`, which handles all the target machines.
+//!
+//! The discovery prefers `llvm-dlltool` when an LLVM linker (e.g. `lld-link`
+//! or `clang`) is configured with `-C linker` in `CARGO_ENCODED_RUSTFLAGS`
+//! or with `target.<triple>.linker` (`RUSTC_LINKER`).
+//!
+//! The discovered tool is cached per target for the lifetime of the process,
+//! so batch generation performs the `PATH` and registry lookups only once.
+//! Changing `PATH`, `PYO3_MINGW_DLLTOOL` or `ZIG_COMMAND` starts a new discovery.
+
+use std::collections::HashMap;
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fs::read_dir;
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+#[cfg(not(target_os = "wasi"))]
+use std::process::Stdio;
+use std::sync::{Mutex, OnceLock};
+
+#[cfg(feature = "download")]
+use crate::download;
+use crate::settings::{EnvSettings, DLLTOOL_ENV, LIB_EXE_ENV};
+#[cfg(not(target_os = "wasi"))]
+use crate::DllToolOptions;
+use crate::{
+    normalize_arch, normalize_env, tool_path, ErrorCategory, ImportLibraryGenerator, ToolFlavor,
+    ToolProbe, IMPLIB_EXT_GNU, IMPLIB_EXT_MSVC,
+};
+
+/// Canonical MinGW-w64 `dlltool` program name
+pub(crate) const DLLTOOL_GNU: &str = "x86_64-w64-mingw32-dlltool";
+
+/// Canonical MinGW-w64 `dlltool` program name (32-bit version)
+pub(crate) const DLLTOOL_GNU_32: &str = "i686-w64-mingw32-dlltool";
+
+/// Unprefixed multi-target binutils `dlltool` program name
+pub(crate) const DLLTOOL_GNU_MULTI: &str = "dlltool";
+
+/// Canonical `dlltool` program name for the MSVC environment ABI (LLVM dlltool)
+pub(crate) const DLLTOOL_MSVC: &str = "llvm-dlltool";
+
+/// Canonical `lib` program name for the MSVC environment ABI (MSVC lib.exe)
+#[cfg(windows)]
+const LIB_MSVC: &str = "lib.exe";
+
+/// Environment variables examined by the `dlltool` discovery
+///
+/// The cached discovery results are keyed by their values
+/// along with the target and the required tool flavor.
+const DISCOVERY_VARS: [&str; 8] = [
+    DLLTOOL_ENV,
+    "ZIG_COMMAND",
+    "PYO3_MINGW_DLLTOOL",
+    "PATH",
+    "CARGO_ENCODED_RUSTFLAGS",
+    "RUSTC_LINKER",
+    "VCToolsInstallDir",
+    "VSCMD_ARG_HOST_ARCH",
+];
+
+/// Process-wide `dlltool` discovery cache
+///
+/// Batch generation reuses the discovered tool for every import library
+/// of the same target instead of repeating the `PATH` and registry lookups.
+static DISCOVERY_CACHE: OnceLock<Mutex<HashMap<DiscoveryKey, CachedTool>>> = OnceLock::new();
+
+/// `dlltool` discovery cache key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DiscoveryKey {
+    /// Required tool flavor, if any
+    require: Option<ToolFlavor>,
+    /// Target architecture
+    arch: String,
+    /// Target environment ABI
+    env: String,
+    /// Values of the `DISCOVERY_VARS` environment variables
+    vars: Vec<Option<OsString>>,
+}
+
+/// Discovered `dlltool` command stored in the discovery cache
+#[derive(Debug, Clone)]
+struct CachedTool {
+    /// `dlltool` flavor
+    flavor: ToolFlavor,
+    /// Program path or name
+    program: OsString,
+    /// Leading arguments, e.g. `dlltool` for `zig`
+    args: Vec<OsString>,
+    /// Environment set up by the discovery, e.g. for `lib.exe`
+    envs: Vec<(OsString, Option<OsString>)>,
+    /// Target machine name (empty for the prefixed MinGW `dlltool`)
+    machine: String,
+}
+
+impl CachedTool {
+    /// Captures the discovered `dlltool` command.
+    fn new(command: &DllToolCommand) -> Self {
+        let (inner, machine) = match command {
+            DllToolCommand::Mingw { command, machine } => {
+                (command, machine.as_deref().unwrap_or_default())
+            }
+            DllToolCommand::Llvm { command, machine }
+            | DllToolCommand::LibExe { command, machine }
+            | DllToolCommand::Zig { command, machine } => (command, machine.as_str()),
+        };
+
+        CachedTool {
+            flavor: command.flavor(),
+            program: inner.get_program().to_owned(),
+            args: inner.get_args().map(ToOwned::to_owned).collect(),
+            envs: inner
+                .get_envs()
+                .map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+                .collect(),
+            machine: machine.to_owned(),
+        }
+    }
+
+    /// Recreates the discovered `dlltool` command.
+    fn to_command(&self) -> DllToolCommand {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+
+        for (key, value) in &self.envs {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+
+        let machine = self.machine.clone();
+        match self.flavor {
+            ToolFlavor::Mingw => DllToolCommand::Mingw {
+                command,
+                machine: Some(machine).filter(|machine| !machine.is_empty()),
+            },
+            ToolFlavor::Llvm => DllToolCommand::Llvm { command, machine },
+            ToolFlavor::LibExe => DllToolCommand::LibExe { command, machine },
+            ToolFlavor::Zig => DllToolCommand::Zig { command, machine },
+        }
+    }
+}
+
+/// `dlltool` utility command builder
+///
+/// Supports Visual Studio `lib.exe`, MinGW, LLVM and Zig `dlltool` flavors.
+#[derive(Debug)]
+pub(crate) enum DllToolCommand {
+    /// MinGW `dlltool` program (with prefix, or multi-target with `-m` machine)
+    Mingw {
+        command: Command,
+        machine: Option<String>,
+    },
+    /// LLVM `llvm-dlltool` program (no prefix)
+    Llvm { command: Command, machine: String },
+    /// MSVC `lib.exe` program (no prefix)
+    LibExe { command: Command, machine: String },
+    /// `zig dlltool` wrapper (no prefix)
+    Zig { command: Command, machine: String },
+}
+
+impl DllToolCommand {
+    /// Finds the `dlltool` command for the target, reusing the result
+    /// of the earlier discovery in this process.
+    ///
+    /// Looks for the `require` flavor only if it is set.
+    fn find_cached(require: Option<ToolFlavor>, arch: &str, env: &str) -> Result<DllToolCommand> {
+        let key = DiscoveryKey {
+            require,
+            arch: arch.to_owned(),
+            env: env.to_owned(),
+            vars: DISCOVERY_VARS.iter().map(env::var_os).collect(),
+        };
+
+        let cache = DISCOVERY_CACHE.get_or_init(Default::default);
+        if let Some(tool) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(&key) {
+            return Ok(tool.to_command());
+        }
+
+        let command = match require {
+            Some(flavor) => DllToolCommand::find_flavor(flavor, arch, env)?,
+            // Try to guess the `dlltool` executable name from the target triple.
+            None => DllToolCommand::find_for_target(arch, env)?,
+        };
+
+        // The discovery failures are not cached.
+        let tool = CachedTool::new(&command);
+        cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key, tool);
+
+        Ok(command)
+    }
+
+    /// Creates the `dlltool` command for an explicitly specified executable.
+    pub(crate) fn from_path(path: &Path, arch: &str, env: &str) -> Result<DllToolCommand> {
+        let command = Command::new(path);
+
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+
+        match env {
+            "gnu" => {
+                if stem.eq_ignore_ascii_case(DLLTOOL_MSVC) {
+                    let machine = llvm_machine(arch);
+                    Ok(DllToolCommand::Llvm { command, machine })
+                } else {
+                    Ok(DllToolCommand::Mingw {
+                        command,
+                        machine: None,
+                    })
+                }
+            }
+            "msvc" => {
+                if stem.eq_ignore_ascii_case("lib") || stem.eq_ignore_ascii_case("llvm-lib") {
+                    let machine = msvc_machine(arch);
+                    Ok(DllToolCommand::LibExe { command, machine })
+                } else {
+                    let machine = llvm_machine(arch);
+                    Ok(DllToolCommand::Llvm { command, machine })
+                }
+            }
+            _ => {
+                let msg = format!("Unsupported target env ABI '{}'", env);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Attempts to find the best matching `dlltool` flavor for the target.
+    fn find_for_target(arch: &str, env: &str) -> Result<DllToolCommand> {
+        let machine = llvm_machine(arch);
+
+        // If `zig cc` is used as the linker, `zig dlltool` is the best choice.
+        if let Some(command) = find_zig() {
+            return Ok(DllToolCommand::Zig { command, machine });
+        }
+
+        // Match the LLVM linkers (e.g. `-C linker=lld-link`) with `llvm-dlltool`.
+        let linker = configured_linker().and_then(|linker| linker_flavor(&linker));
+        if linker == Some(ToolFlavor::Llvm) && (env == "gnu" || env == "msvc") {
+            if let Some(command) = installed_llvm_dlltool() {
+                return Ok(DllToolCommand::Llvm { command, machine });
+            }
+        }
+
+        match env {
+            // 64-bit and 32-bit MinGW-w64 (aka `{x86_64,i686}-pc-windows-gnu`)
+            "gnu" => {
+                // Fall back to `llvm-dlltool` if the MinGW binutils are not installed.
+                let mingw_found = env::var_os("PYO3_MINGW_DLLTOOL").is_some()
+                    || mingw_dlltool_name(arch).and_then(find_in_path).is_some()
+                    || (binutils_machine(arch).is_some()
+                        && find_in_path(DLLTOOL_GNU_MULTI).is_some());
+
+                if let Some(command) = installed_llvm_dlltool().filter(|_| !mingw_found) {
+                    return Ok(DllToolCommand::Llvm { command, machine });
+                }
+
+                get_mingw_dlltool(arch)
+            }
+
+            // MSVC ABI (multiarch)
+            "msvc" => {
+                if let Some(command) = find_lib_exe(arch) {
+                    let machine = msvc_machine(arch);
+
+                    Ok(DllToolCommand::LibExe { command, machine })
+                } else {
+                    let command = find_llvm_dlltool();
+
+                    Ok(DllToolCommand::Llvm { command, machine })
+                }
+            }
+            _ => {
+                let msg = format!("Unsupported target env ABI '{}'", env);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Finds the `flavor` tool for the target without falling back
+    /// to the other tool flavors.
+    fn find_flavor(flavor: ToolFlavor, arch: &str, env: &str) -> Result<DllToolCommand> {
+        let supported = match flavor {
+            ToolFlavor::Mingw => env == "gnu",
+            ToolFlavor::LibExe => env == "msvc",
+            ToolFlavor::Llvm | ToolFlavor::Zig => env == "gnu" || env == "msvc",
+        };
+        if !supported {
+            let msg = format!("{} does not support the '{}' env", flavor.name(), env);
+            return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg));
+        }
+
+        let not_found = |what: &str| {
+            let msg = format!(
+                "Strict tool selection requires {}, {}; {}",
+                flavor.name(),
+                what,
+                install_hint(flavor, arch)
+            );
+            ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg)
+        };
+
+        match flavor {
+            ToolFlavor::Mingw => get_mingw_dlltool(arch),
+            ToolFlavor::Llvm => Ok(DllToolCommand::Llvm {
+                command: find_llvm_dlltool(),
+                machine: llvm_machine(arch),
+            }),
+            ToolFlavor::LibExe => match find_lib_exe(arch) {
+                Some(command) => Ok(DllToolCommand::LibExe {
+                    command,
+                    machine: msvc_machine(arch),
+                }),
+                None => Err(not_found("but it was not found")),
+            },
+            ToolFlavor::Zig => match find_zig() {
+                Some(command) => Ok(DllToolCommand::Zig {
+                    command,
+                    machine: llvm_machine(arch),
+                }),
+                None => Err(not_found("but ZIG_COMMAND is not set")),
+            },
+        }
+    }
+
+    /// Returns the tool flavor of this `dlltool` command.
+    pub(crate) fn flavor(&self) -> ToolFlavor {
+        match self {
+            Self::Mingw { .. } => ToolFlavor::Mingw,
+            Self::Llvm { .. } => ToolFlavor::Llvm,
+            Self::LibExe { .. } => ToolFlavor::LibExe,
+            Self::Zig { .. } => ToolFlavor::Zig,
+        }
+    }
+
+    /// Returns the `dlltool` program name for the diagnostics.
+    pub(crate) fn name(&self) -> String {
+        match self {
+            Self::Zig { .. } => format!("{} dlltool", self.program().to_string_lossy()),
+            _ => self.program().to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Returns the executed program, e.g. `zig` for `zig dlltool`.
+    pub(crate) fn program(&self) -> &OsStr {
+        match self {
+            Self::Mingw { command, .. }
+            | Self::Llvm { command, .. }
+            | Self::LibExe { command, .. }
+            | Self::Zig { command, .. } => command.get_program(),
+        }
+    }
+
+    /// Queries the `dlltool` version string.
+    ///
+    /// Always `None` on the WASI hosts, which can not run programs.
+    #[cfg(target_os = "wasi")]
+    pub(crate) fn version(&self) -> Option<String> {
+        None
+    }
+
+    /// Queries the `dlltool` version string.
+    ///
+    /// Returns the first output line if it looks like a version banner.
+    #[cfg(not(target_os = "wasi"))]
+    pub(crate) fn version(&self) -> Option<String> {
+        let (program, envs, args): (_, _, &[&str]) = match self {
+            Self::Mingw { command, .. } | Self::Llvm { command, .. } => {
+                (command.get_program(), command.get_envs(), &["--version"])
+            }
+            // `lib.exe` prints the banner with the usage summary.
+            Self::LibExe { command, .. } => (command.get_program(), command.get_envs(), &[]),
+            Self::Zig { command, .. } => (command.get_program(), command.get_envs(), &["version"]),
+        };
+
+        let mut command = Command::new(program);
+        for (key, value) in envs {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
+
+        let output = command.args(args).stdin(Stdio::null()).output().ok()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().find(|line| !line.trim().is_empty())?.trim();
+
+        // `llvm-dlltool` has no `--version` option and prints the usage instead.
+        if line.contains(|c: char| c.is_ascii_digit()) {
+            Some(line.to_owned())
+        } else {
+            None
+        }
+    }
+
+    /// Returns the import library file extension used by
+    /// this `dlltool` flavor for the target `env`.
+    ///
+    /// `llvm-dlltool` produces the `.dll.a` import libraries
+    /// for the `gnu` targets, same as the MinGW `dlltool`.
+    pub(crate) fn implib_file_ext(&self, env: &str) -> &'static str {
+        match self {
+            DllToolCommand::Mingw { .. } => IMPLIB_EXT_GNU,
+            DllToolCommand::Llvm { .. } if env == "gnu" => IMPLIB_EXT_GNU,
+            _ => IMPLIB_EXT_MSVC,
+        }
+    }
+
+    /// Generates the complete `dlltool` executable invocation command.
+    #[cfg(not(target_os = "wasi"))]
+    pub(crate) fn build(self, defpath: &Path, libpath: &Path, options: &DllToolOptions) -> Command {
+        match self {
+            Self::Mingw {
+                mut command,
+                machine,
+            } => {
+                // The multi-target `dlltool` defaults to the host machine.
+                if let Some(machine) = &machine {
+                    command.arg("-m").arg(machine);
+                }
+
+                command
+                    .arg("--input-def")
+                    .arg(defpath)
+                    .arg("--output-lib")
+                    .arg(libpath);
+
+                if options.kill_at {
+                    command.arg("--kill-at");
+                }
+
+                if options.no_leading_underscore {
+                    command.arg("--no-leading-underscore");
+                }
+
+                if let Some(prefix) = &options.temp_prefix {
+                    command.arg("--temp-prefix").arg(prefix);
+                }
+
+                if let Some(assembler) = &options.assembler {
+                    command.arg("--as").arg(assembler);
+                }
+
+                if let Some(flags) = &options.assembler_flags {
+                    command.arg("--as-flags").arg(flags);
+                } else if machine.as_deref() == Some("i386") {
+                    // The host `as` needs to be told to emit the 32-bit objects.
+                    command.arg("--as-flags").arg("--32");
+                }
+
+                command
+            }
+            Self::Llvm {
+                mut command,
+                machine,
+            } => {
+                command
+                    .arg("-m")
+                    .arg(machine)
+                    .arg("-d")
+                    .arg(defpath)
+                    .arg("-l")
+                    .arg(libpath);
+
+                options.add_llvm_args(&mut command);
+
+                command
+            }
+            Self::LibExe {
+                mut command,
+                machine,
+            } => {
+                command
+                    .arg(format!("/MACHINE:{}", machine))
+                    .arg(format!("/DEF:{}", defpath.display()))
+                    .arg(format!("/OUT:{}", libpath.display()));
+
+                command
+            }
+            Self::Zig {
+                mut command,
+                machine,
+            } => {
+                // Same as `llvm-dlltool`, but invoked as `zig dlltool`.
+                command
+                    .arg("dlltool")
+                    .arg("-m")
+                    .arg(machine)
+                    .arg("-d")
+                    .arg(defpath)
+                    .arg("-l")
+                    .arg(libpath);
+
+                options.add_llvm_args(&mut command);
+
+                command
+            }
+        }
+    }
+}
+
+/// Chooses the appropriate MinGW-w64 `dlltool` executable
+/// for the target architecture.
+///
+/// Examines the user-provided `PYO3_MINGW_DLLTOOL` environment variable first
+/// and falls back to the default MinGW-w64 arch prefixes.
+/// If only the unprefixed multi-target `dlltool` is installed,
+/// it is used with the `-m` machine option for the target.
+fn get_mingw_dlltool(arch: &str) -> Result<DllToolCommand> {
+    if let Ok(user_dlltool) = env::var("PYO3_MINGW_DLLTOOL") {
+        return Ok(DllToolCommand::Mingw {
+            command: Command::new(user_dlltool),
+            machine: None,
+        });
+    }
+
+    let prefix_dlltool = mingw_dlltool_name(arch).ok_or_else(|| {
+        let msg = format!("Unsupported MinGW target arch '{}'", arch);
+        ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg)
+    })?;
+
+    if find_in_path(prefix_dlltool).is_none() && find_in_path(DLLTOOL_GNU_MULTI).is_some() {
+        return Ok(DllToolCommand::Mingw {
+            command: Command::new(DLLTOOL_GNU_MULTI),
+            machine: binutils_machine(arch),
+        });
+    }
+
+    Ok(DllToolCommand::Mingw {
+        command: Command::new(prefix_dlltool),
+        machine: None,
+    })
+}
+
+/// Returns the binutils `dlltool -m` machine name for the target arch.
+fn binutils_machine(arch: &str) -> Option<String> {
+    match arch {
+        "x86_64" => Some("i386:x86-64".to_owned()),
+        "x86" => Some("i386".to_owned()),
+        _ => None,
+    }
+}
+
+/// Returns the MinGW-w64 `dlltool` program name with the arch prefix.
+fn mingw_dlltool_name(arch: &str) -> Option<&'static str> {
+    match arch {
+        // 64-bit MinGW-w64 (aka `x86_64-pc-windows-gnu`)
+        "x86_64" => Some(DLLTOOL_GNU),
+        // 32-bit MinGW-w64 (aka `i686-pc-windows-gnu`)
+        "x86" => Some(DLLTOOL_GNU_32),
+        // AArch64 is only supported by `llvm-dlltool`.
+        _ => None,
+    }
+}
+
+/// Returns the installation hint for the missing `flavor` tool
+/// selected by the host OS.
+pub(crate) fn install_hint(flavor: ToolFlavor, arch: &str) -> String {
+    let hint = match flavor {
+        ToolFlavor::Mingw => {
+            let install = if cfg!(target_os = "macos") {
+                "`brew install mingw-w64`".to_owned()
+            } else if cfg!(windows) {
+                "`pacman -S mingw-w64-x86_64-binutils` (MSYS2)".to_owned()
+            } else {
+                let fedora = if arch == "x86" {
+                    "mingw32-binutils"
+                } else {
+                    "mingw64-binutils"
+                };
+                format!(
+                    "`apt install binutils-mingw-w64` (Debian, Ubuntu) \
+                     or `dnf install {}` (Fedora)",
+                    fedora
+                )
+            };
+            format!(
+                "install the MinGW-w64 binutils with {}, \
+                 or set `PYO3_MINGW_DLLTOOL` to the `dlltool` path",
+                install
+            )
+        }
+        ToolFlavor::Llvm => {
+            let install = if cfg!(target_os = "macos") {
+                "`brew install llvm`"
+            } else if cfg!(windows) {
+                "`winget install LLVM.LLVM`"
+            } else {
+                "`apt install llvm` (Debian, Ubuntu) or `dnf install llvm` (Fedora)"
+            };
+            format!(
+                "install LLVM with {}, or run `rustup component add llvm-tools` \
+                 and copy its `llvm-ar` as `llvm-dlltool` into `PATH`",
+                install
+            )
+        }
+        ToolFlavor::LibExe => "install the Visual Studio Build Tools with the C++ workload, \
+             or run in a Visual Studio Developer Command Prompt"
+            .to_owned(),
+        ToolFlavor::Zig => "install Zig, e.g. with `pip install ziglang`, \
+             and check the `ZIG_COMMAND` value"
+            .to_owned(),
+    };
+
+    format!("hint: {}", hint)
+}
+
+/// Returns the LLVM `dlltool` machine name for the target architecture.
+fn llvm_machine(arch: &str) -> String {
+    // LLVM tools use their own target architecture names...
+    match arch {
+        "x86_64" => "i386:x86-64",
+        "x86" => "i386",
+        "aarch64" => "arm64",
+        arch => arch,
+    }
+    .to_owned()
+}
+
+/// Returns the `lib.exe` machine name for the target architecture.
+fn msvc_machine(arch: &str) -> String {
+    // MSVC tools use their own target architecture names...
+    match arch {
+        "x86_64" => "X64",
+        "x86" => "X86",
+        "aarch64" => "ARM64",
+        arch => arch,
+    }
+    .to_owned()
+}
+
+/// Builds the hermetic mode configuration error.
+fn hermetic_error(msg: &str) -> Error {
+    let msg = format!("Hermetic mode: {}", msg);
+    ErrorCategory::ToolMissing.error(ErrorKind::InvalidInput, msg)
+}
+
+/// Finds the LLVM `llvm-dlltool` executable.
+///
+/// Falls back to the plain program name if it is not installed.
+fn find_llvm_dlltool() -> Command {
+    installed_llvm_dlltool().unwrap_or_else(|| Command::new(DLLTOOL_MSVC))
+}
+
+/// Finds the installed LLVM `llvm-dlltool` executable.
+///
+/// Searches the well-known LLVM installation directories if `llvm-dlltool`
+/// is not in `PATH`.
+fn installed_llvm_dlltool() -> Option<Command> {
+    if find_in_path(DLLTOOL_MSVC).is_some() {
+        return Some(Command::new(DLLTOOL_MSVC));
+    }
+
+    let program = format!("{}{}", DLLTOOL_MSVC, env::consts::EXE_SUFFIX);
+
+    llvm_tool_dirs()
+        .into_iter()
+        .map(|dir| dir.join(&program))
+        .find(|path| path.is_file())
+        .map(Command::new)
+}
+
+/// Returns the well-known LLVM installation `bin` directories
+/// not in `PATH` by default, newest LLVM version first.
+fn llvm_tool_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    if cfg!(target_os = "macos") {
+        // Homebrew on Apple Silicon and on Intel Macs, `llvm` and `llvm@<N>`
+        for prefix in ["/opt/homebrew/opt", "/usr/local/opt"] {
+            let prefix = Path::new(prefix);
+            dirs.push(prefix.join("llvm").join("bin"));
+            dirs.extend(versioned_dirs(prefix, "llvm@").map(|dir| dir.join("bin")));
+        }
+
+        // MacPorts `llvm-<N>` ports
+        let libexec = Path::new("/opt/local/libexec");
+        dirs.extend(versioned_dirs(libexec, "llvm-").map(|dir| dir.join("bin")));
+    } else if cfg!(target_os = "linux") {
+        // Debian and Ubuntu `llvm-<N>` packages
+        let lib = Path::new("/usr/lib");
+        dirs.extend(versioned_dirs(lib, "llvm-").map(|dir| dir.join("bin")));
+    }
+
+    dirs
+}
+
+/// Returns the `<prefix><version>` subdirectories of `parent`,
+/// newest version first.
+fn versioned_dirs(parent: &Path, prefix: &str) -> impl Iterator<Item = PathBuf> {
+    let mut versioned: Vec<_> = read_dir(parent)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.ok()?.file_name();
+            let version = name.to_str()?.strip_prefix(prefix)?;
+            let version: Vec<u32> = version
+                .split('.')
+                .map(str::parse)
+                .collect::<std::result::Result<_, _>>()
+                .ok()?;
+
+            Some((version, parent.join(&name)))
+        })
+        .collect();
+
+    versioned.sort_by(|a, b| b.0.cmp(&a.0));
+
+    versioned.into_iter().map(|(_, dir)| dir)
+}
+
+/// Finds the `program` executable in `PATH`.
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    let path = env::var_os("PATH")?;
+    let program = format!("{}{}", program, env::consts::EXE_SUFFIX);
+
+    env::split_paths(&path)
+        .map(|dir| dir.join(&program))
+        .find(|path| path.is_file())
+}
+
+/// Returns the linker configured for the cargo build, if any.
+///
+/// Examines the `-C linker=<LINKER>` option in `CARGO_ENCODED_RUSTFLAGS` first
+/// and falls back to the `target.<triple>.linker` setting in `RUSTC_LINKER`.
+fn configured_linker() -> Option<String> {
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS").unwrap_or_default();
+
+    linker_from_rustflags(&rustflags).or_else(|| {
+        env::var("RUSTC_LINKER")
+            .ok()
+            .filter(|linker| !linker.is_empty())
+    })
+}
+
+/// Extracts the last `-C linker=<LINKER>` value from the `0x1f` separated
+/// `CARGO_ENCODED_RUSTFLAGS` value.
+fn linker_from_rustflags(rustflags: &str) -> Option<String> {
+    let mut linker = None;
+    let mut codegen = false;
+
+    for flag in rustflags.split('\x1f') {
+        let option = if codegen {
+            Some(flag)
+        } else {
+            flag.strip_prefix("-C")
+                .or_else(|| flag.strip_prefix("--codegen"))
+        };
+        codegen = option == Some("") || flag == "--codegen";
+
+        if let Some(value) =
+            option.and_then(|option| option.trim_start_matches('=').strip_prefix("linker="))
+        {
+            linker = Some(value.to_owned());
+        }
+    }
+
+    linker
+}
+
+/// Returns the import library tool flavor matching the `linker` program.
+fn linker_flavor(linker: &str) -> Option<ToolFlavor> {
+    // Handle the Windows paths on all the hosts.
+    let name = linker.rsplit(['/', '\\']).next()?.to_ascii_lowercase();
+    let stem = name.strip_suffix(".exe").unwrap_or(&name);
+
+    if stem == "link" {
+        Some(ToolFlavor::LibExe)
+    } else if stem.contains("lld") || stem.starts_with("clang") || stem.ends_with("-clang") {
+        Some(ToolFlavor::Llvm)
+    } else if stem.ends_with("-gcc") || stem == "gcc" {
+        Some(ToolFlavor::Mingw)
+    } else {
+        None
+    }
+}
+
+/// Finds the `zig` executable (when built by `maturin --zig`).
+///
+/// Examines the `ZIG_COMMAND` environment variable
+/// to find out if `zig cc` is being used as the linker.
+fn find_zig() -> Option<Command> {
+    // `ZIG_COMMAND` may contain simply `zig` or `/usr/bin/zig`,
+    // or a more complex construct like `python3 -m ziglang`.
+    let zig_command = env::var("ZIG_COMMAND").ok()?;
+
+    // Try to emulate `sh -c ${ZIG_COMMAND}`.
+    let mut zig_cmdlet = zig_command.split_ascii_whitespace();
+
+    // Extract the main program component (e.g. `zig` or `python3`).
+    let mut zig = Command::new(zig_cmdlet.next()?);
+
+    // Append the rest of the commandlet.
+    zig.args(zig_cmdlet);
+
+    Some(zig)
+}
+
+/// Finds Visual Studio `lib.exe` when running on Windows.
+///
+/// Uses the active Visual Studio developer environment if there is one.
+/// Prefers the host-native tools, e.g. `bin\Hostarm64\x64\lib.exe`
+/// over the emulated `bin\Hostx64\x64\lib.exe` on the ARM64 hosts.
+#[cfg(all(windows, feature = "msvc-discovery"))]
+fn find_lib_exe(arch: &str) -> Option<Command> {
+    let msvc_target = |arch: &str| match arch {
+        "x86_64" => Some("x86_64-pc-windows-msvc"),
+        "x86" => Some("i686-pc-windows-msvc"),
+        "aarch64" => Some("aarch64-pc-windows-msvc"),
+        _ => None,
+    };
+
+    if let Some(command) = find_dev_env_lib_exe(arch) {
+        return Some(command);
+    }
+
+    let host = env::consts::ARCH;
+
+    // The cross-arch lookups may fail, but any `lib.exe` can generate
+    // the import libraries for all the machines selected with `/MACHINE`.
+    let command = cc::windows_registry::find(msvc_target(arch)?, LIB_MSVC)
+        .or_else(|| cc::windows_registry::find(msvc_target(host)?, LIB_MSVC))?;
+
+    let Some(native) = native_lib_exe(Path::new(command.get_program()), host, arch) else {
+        return Some(command);
+    };
+
+    let mut native_command = Command::new(native);
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => native_command.env(key, value),
+            None => native_command.env_remove(key),
+        };
+    }
+
+    Some(native_command)
+}
+
+/// Returns the host-native Visual Studio `lib.exe` for the target
+/// architecture next to the `lib_exe` found for another host,
+/// if it is installed.
+///
+/// The Visual Studio tools live in `bin\Host<host>\<target>` directories.
+#[cfg(any(test, all(windows, feature = "msvc-discovery")))]
+fn native_lib_exe(lib_exe: &Path, host_arch: &str, target_arch: &str) -> Option<PathBuf> {
+    let host_dir = vs_host_dir(host_arch)?;
+    let target_dir = vs_target_dir(target_arch)?;
+
+    let found_host_dir = lib_exe.parent()?.parent()?;
+    let is_host_dir = found_host_dir
+        .file_name()?
+        .to_str()?
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("host"));
+    if !is_host_dir {
+        return None;
+    }
+
+    let native = found_host_dir
+        .parent()?
+        .join(host_dir)
+        .join(target_dir)
+        .join(lib_exe.file_name()?);
+
+    (native != lib_exe && native.is_file()).then_some(native)
+}
+
+/// Finds `lib.exe` of the active Visual Studio developer environment,
+/// e.g. in a Visual Studio Developer Command Prompt.
+///
+/// The environment is described by the `VCToolsInstallDir`
+/// and `VSCMD_ARG_HOST_ARCH` variables.
+#[cfg(windows)]
+fn find_dev_env_lib_exe(arch: &str) -> Option<Command> {
+    let tools_dir = env::var_os("VCToolsInstallDir")?;
+    let host = match env::var("VSCMD_ARG_HOST_ARCH") {
+        Ok(host) => normalize_arch(&host),
+        Err(_) => env::consts::ARCH.to_owned(),
+    };
+
+    dev_env_lib_exe(Path::new(&tools_dir), &host, arch).map(Command::new)
+}
+
+/// Returns the `lib.exe` path in the Visual Studio `VCToolsInstallDir`
+/// for the host and target architectures, if it is installed.
+///
+/// Falls back to the host architecture tools, which can generate
+/// the import libraries for all the target machines.
+#[cfg(any(test, windows))]
+fn dev_env_lib_exe(tools_dir: &Path, host_arch: &str, target_arch: &str) -> Option<PathBuf> {
+    let bin = tools_dir.join("bin").join(vs_host_dir(host_arch)?);
+
+    [target_arch, host_arch]
+        .into_iter()
+        .filter_map(vs_target_dir)
+        .map(|dir| bin.join(dir).join("lib.exe"))
+        .find(|lib_exe| lib_exe.is_file())
+}
+
+/// Returns the Visual Studio `bin\Host<host>` directory name for the host architecture.
+#[cfg(any(test, windows))]
+fn vs_host_dir(arch: &str) -> Option<&'static str> {
+    match arch {
+        "x86_64" => Some("Hostx64"),
+        "x86" => Some("Hostx86"),
+        "aarch64" => Some("Hostarm64"),
+        _ => None,
+    }
+}
+
+/// Returns the Visual Studio `bin\Host<host>\<target>` directory name
+/// for the target architecture.
+#[cfg(any(test, windows))]
+fn vs_target_dir(arch: &str) -> Option<&'static str> {
+    match arch {
+        "x86_64" => Some("x64"),
+        "x86" => Some("x86"),
+        "aarch64" => Some("arm64"),
+        _ => None,
+    }
+}
+
+/// Finds `lib.exe` in `PATH` when running on Windows,
+/// e.g. in a Visual Studio Developer Command Prompt.
+///
+/// This is the lightweight fallback used without the `cc` crate.
+#[cfg(all(windows, not(feature = "msvc-discovery")))]
+fn find_lib_exe(arch: &str) -> Option<Command> {
+    if let Some(command) = find_dev_env_lib_exe(arch) {
+        return Some(command);
+    }
+
+    let path = env::var_os("PATH")?;
+
+    env::split_paths(&path)
+        .map(|dir| dir.join(LIB_MSVC))
+        .find(|lib_exe| lib_exe.is_file())
+        .map(Command::new)
+}
+
+#[cfg(not(windows))]
+fn find_lib_exe(_arch: &str) -> Option<Command> {
+    None
+}
+
+/// Probes every import library generation tool flavor on this host
+/// for the `arch` and `env` target.
+///
+/// The tools are found the same way as by the automatic tool discovery
+/// and listed in the recommended installation order for the target,
+/// the MinGW `dlltool` first for the `gnu` targets and `lib.exe`
+/// (on Windows) or `llvm-dlltool` first for the `msvc` targets.
+/// Runs the installed tools to get their versions.
+pub fn probe_tools(arch: &str, env: &str) -> Vec<ToolProbe> {
+    let arch = normalize_arch(arch);
+    let env = normalize_env(env);
+
+    let order = match env.as_str() {
+        "gnu" => [
+            ToolFlavor::Mingw,
+            ToolFlavor::Llvm,
+            ToolFlavor::Zig,
+            ToolFlavor::LibExe,
+        ],
+        _ if cfg!(windows) => [
+            ToolFlavor::LibExe,
+            ToolFlavor::Llvm,
+            ToolFlavor::Zig,
+            ToolFlavor::Mingw,
+        ],
+        _ => [
+            ToolFlavor::Llvm,
+            ToolFlavor::LibExe,
+            ToolFlavor::Zig,
+            ToolFlavor::Mingw,
+        ],
+    };
+
+    order
+        .into_iter()
+        .map(|flavor| {
+            let supports_env = match flavor {
+                ToolFlavor::Mingw => env == "gnu",
+                ToolFlavor::LibExe => env == "msvc",
+                ToolFlavor::Llvm | ToolFlavor::Zig => env == "gnu" || env == "msvc",
+            };
+
+            let command = match flavor {
+                ToolFlavor::Mingw => get_mingw_dlltool(&arch).ok(),
+                ToolFlavor::Llvm => Some(DllToolCommand::Llvm {
+                    command: find_llvm_dlltool(),
+                    machine: llvm_machine(&arch),
+                }),
+                ToolFlavor::LibExe => find_lib_exe(&arch).map(|command| DllToolCommand::LibExe {
+                    command,
+                    machine: msvc_machine(&arch),
+                }),
+                ToolFlavor::Zig => find_zig().map(|command| DllToolCommand::Zig {
+                    command,
+                    machine: llvm_machine(&arch),
+                }),
+            };
+
+            let path = command.as_ref().and_then(|command| match command {
+                DllToolCommand::Mingw { command, .. }
+                | DllToolCommand::Llvm { command, .. }
+                | DllToolCommand::LibExe { command, .. }
+                | DllToolCommand::Zig { command, .. } => {
+                    tool_path(&command.get_program().to_string_lossy())
+                }
+            });
+
+            // The MinGW `dlltool` does not support the ARM targets.
+            let supports_target = supports_env
+                && (flavor != ToolFlavor::Mingw || mingw_dlltool_name(&arch).is_some());
+
+            ToolProbe {
+                flavor,
+                command: match &command {
+                    Some(command) => command.name(),
+                    None => flavor.name().to_owned(),
+                },
+                version: command.filter(|_| path.is_some()).and_then(|c| c.version()),
+                path,
+                supports_target,
+            }
+        })
+        .collect()
+}
+
+impl ImportLibraryGenerator {
+    /// Returns the explicitly configured `dlltool` path, if any.
+    ///
+    /// Fails in the hermetic mode if the path is missing or relative.
+    fn explicit_dlltool(&self, settings: &EnvSettings) -> Result<Option<PathBuf>> {
+        if !self.hermetic {
+            return Ok(self.dlltool.clone().or_else(|| settings.dlltool.clone()));
+        }
+
+        let dlltool = match (&self.dlltool, self.lib_env()) {
+            (Some(dlltool), _) => dlltool.clone(),
+            // Checked by `explicit_lib_exe()`.
+            (None, "msvc") if self.lib_exe.is_some() => return Ok(None),
+            (None, "gnu") => env::var_os("PYO3_MINGW_DLLTOOL")
+                .map(PathBuf::from)
+                .ok_or_else(|| hermetic_error("PYO3_MINGW_DLLTOOL is not set"))?,
+            (None, _) => return Err(hermetic_error("no dlltool path is set")),
+        };
+
+        if !dlltool.is_absolute() {
+            let msg = format!("'{}' is not an absolute path", dlltool.display());
+            return Err(hermetic_error(&msg));
+        }
+
+        Ok(Some(dlltool))
+    }
+
+    /// Selects the `dlltool` command to run for the target.
+    ///
+    /// Also returns how the command was selected: the builder method
+    /// or the environment variable name, `discovered` or `downloaded`.
+    pub(crate) fn select_dlltool(
+        &self,
+        settings: &EnvSettings,
+    ) -> Result<(DllToolCommand, String)> {
+        let explicit_dlltool = self.explicit_dlltool(settings)?;
+        let explicit_lib_exe = self.explicit_lib_exe(settings)?;
+
+        let (dlltool_command, source) = match (explicit_dlltool, explicit_lib_exe) {
+            (Some(path), _) => {
+                let source = match (&self.dlltool, &settings.dlltool) {
+                    (Some(_), _) => "dlltool()",
+                    (None, Some(_)) => DLLTOOL_ENV,
+                    (None, None) => "PYO3_MINGW_DLLTOOL",
+                };
+                let command = DllToolCommand::from_path(&path, &self.arch, self.lib_env())?;
+                (command, source)
+            }
+            (None, Some(path)) => {
+                let source = match self.lib_exe {
+                    Some(_) => "lib_exe()",
+                    None => LIB_EXE_ENV,
+                };
+                let command = DllToolCommand::LibExe {
+                    command: Command::new(path),
+                    machine: msvc_machine(&self.arch),
+                };
+                (command, source)
+            }
+            (None, None) => {
+                // The MinGW `dlltool` import libraries need `libgcc` to link.
+                let require = match self.require_tool {
+                    None if self.env_name() == "gnullvm" => Some(ToolFlavor::Llvm),
+                    require => require,
+                };
+                let command = DllToolCommand::find_cached(require, &self.arch, self.lib_env())?;
+                #[cfg(feature = "download")]
+                let (command, source) = {
+                    let discovered = command.name();
+                    let command = self.download_fallback(command, settings)?;
+                    match command.name() == discovered {
+                        true => (command, "discovered"),
+                        false => (command, "downloaded"),
+                    }
+                };
+                #[cfg(not(feature = "download"))]
+                let source = "discovered";
+                (command, source)
+            }
+        };
+
+        if let Some(flavor) = self.require_tool {
+            if dlltool_command.flavor() != flavor {
+                let msg = format!(
+                    "Strict tool selection requires {}, found {}",
+                    flavor.name(),
+                    dlltool_command.name()
+                );
+                return Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg));
+            }
+        }
+
+        Ok((dlltool_command, source.to_owned()))
+    }
+
+    /// Replaces the discovered `dlltool` command with the pinned `llvm-dlltool`
+    /// download if the discovered tool is not installed.
+    #[cfg(feature = "download")]
+    fn download_fallback(
+        &self,
+        command: DllToolCommand,
+        settings: &EnvSettings,
+    ) -> Result<DllToolCommand> {
+        let Some(tool) = &self.download_dlltool else {
+            return Ok(command);
+        };
+
+        let missing = match &command {
+            DllToolCommand::Llvm { command, .. } => {
+                command.get_program() == DLLTOOL_MSVC && installed_llvm_dlltool().is_none()
+            }
+            DllToolCommand::Mingw { command, .. } => {
+                self.require_tool.is_none()
+                    && env::var_os("PYO3_MINGW_DLLTOOL").is_none()
+                    && find_in_path(&command.get_program().to_string_lossy()).is_none()
+            }
+            DllToolCommand::LibExe { .. } | DllToolCommand::Zig { .. } => false,
+        };
+        if !missing {
+            return Ok(command);
+        }
+
+        let cache_dir = match self.cache_dir.as_ref().or(settings.cache_dir.as_ref()) {
+            Some(cache_dir) => cache_dir.clone(),
+            None => download::user_cache_dir().ok_or_else(|| {
+                let msg = "Can not download llvm-dlltool: no user cache directory";
+                ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg)
+            })?,
+        };
+
+        Ok(DllToolCommand::Llvm {
+            command: Command::new(tool.fetch(&cache_dir)?),
+            machine: llvm_machine(&self.arch),
+        })
+    }
+
+    /// Returns the explicitly set `lib.exe` path for the `msvc` targets.
+    ///
+    /// The hermetic mode ignores `PYTHON3_DLL_A_LIB_EXE` and requires
+    /// the absolute `lib.exe` path.
+    fn explicit_lib_exe(&self, settings: &EnvSettings) -> Result<Option<PathBuf>> {
+        if self.lib_env() != "msvc" {
+            return Ok(None);
+        }
+
+        let lib_exe = self.lib_exe.clone().or_else(|| settings.lib_exe.clone());
+
+        match lib_exe {
+            Some(lib_exe) if self.hermetic && !lib_exe.is_absolute() => {
+                let msg = format!("'{}' is not an absolute path", lib_exe.display());
+                Err(hermetic_error(&msg))
+            }
+            lib_exe => Ok(lib_exe),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{create_dir_all, write};
+
+    use super::*;
+
+    #[test]
+    fn multi_target_dlltool() {
+        assert_eq!(binutils_machine("x86_64").as_deref(), Some("i386:x86-64"));
+        assert_eq!(binutils_machine("x86").as_deref(), Some("i386"));
+        assert_eq!(binutils_machine("aarch64"), None);
+
+        let mingw = DllToolCommand::Mingw {
+            command: Command::new(DLLTOOL_GNU_MULTI),
+            machine: binutils_machine("x86"),
+        };
+        let command = mingw.build(
+            Path::new("python3.def"),
+            Path::new("python3.dll.a"),
+            &DllToolOptions::default(),
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args[..2], ["-m", "i386"]);
+        let flags = args.iter().position(|arg| *arg == "--as-flags");
+        assert_eq!(args[flags.unwrap() + 1], "--32");
+
+        let mingw = DllToolCommand::Mingw {
+            command: Command::new(DLLTOOL_GNU_MULTI),
+            machine: binutils_machine("x86_64"),
+        };
+        let command = mingw.build(
+            Path::new("python3.def"),
+            Path::new("python3.dll.a"),
+            &DllToolOptions::default(),
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args[..2], ["-m", "i386:x86-64"]);
+        assert!(!args.contains(&"--as-flags".as_ref()));
+    }
+
+    #[test]
+    fn dlltool_options() {
+        let options = DllToolOptions {
+            kill_at: true,
+            no_leading_underscore: true,
+            temp_prefix: Some(PathBuf::from("out/python3-dlltool")),
+            assembler: Some(PathBuf::from("/usr/bin/i686-w64-mingw32-as")),
+            assembler_flags: Some("--32".to_owned()),
+        };
+
+        let mingw = DllToolCommand::Mingw {
+            command: Command::new(DLLTOOL_GNU_32),
+            machine: None,
+        };
+        let command = mingw.build(
+            Path::new("python3.def"),
+            Path::new("python3.dll.a"),
+            &options,
+        );
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args.contains(&"--kill-at".as_ref()));
+        assert!(args.contains(&"--no-leading-underscore".as_ref()));
+        let prefix = args.iter().position(|arg| *arg == "--temp-prefix");
+        assert_eq!(args[prefix.unwrap() + 1], "out/python3-dlltool");
+        let assembler = args.iter().position(|arg| *arg == "--as");
+        assert_eq!(args[assembler.unwrap() + 1], "/usr/bin/i686-w64-mingw32-as");
+        let flags = args.iter().position(|arg| *arg == "--as-flags");
+        assert_eq!(args[flags.unwrap() + 1], "--32");
+
+        let llvm = DllToolCommand::Llvm {
+            command: Command::new(DLLTOOL_MSVC),
+            machine: "i386".to_owned(),
+        };
+        let command = llvm.build(Path::new("python3.def"), Path::new("python3.lib"), &options);
+        let args: Vec<_> = command.get_args().collect();
+        assert!(args.contains(&"-k".as_ref()));
+        assert!(args.contains(&"--no-leading-underscore".as_ref()));
+        assert!(!args.contains(&"--temp-prefix".as_ref()));
+        assert!(!args.contains(&"--as".as_ref()));
+    }
+
+    #[test]
+    fn lib_exe() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("lib-exe");
+
+        let missing = dir.join("missing").join("lib.exe");
+        let err = ImportLibraryGenerator::new("x86", "msvc")
+            .lib_exe(Some(&missing))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+        assert!(err.to_string().contains("/MACHINE:X86"));
+
+        // Only the `msvc` targets use `lib.exe`.
+        let generator = ImportLibraryGenerator::new("x86_64", "gnu")
+            .lib_exe(Some(&missing))
+            .clone();
+        assert_eq!(
+            generator.explicit_lib_exe(&EnvSettings::default()).unwrap(),
+            None
+        );
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .hermetic(true)
+            .lib_exe(Some(Path::new("lib.exe")))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn install_hints() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("install-hints");
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .dlltool(Some(Path::new("python3-dll-a-missing-dlltool")))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+        assert!(err
+            .to_string()
+            .contains("; hint: install the MinGW-w64 binutils"));
+        assert!(err.to_string().contains("PYO3_MINGW_DLLTOOL"));
+
+        let hint = install_hint(ToolFlavor::Llvm, "x86_64");
+        assert!(hint.contains("rustup component add llvm-tools"));
+        if cfg!(target_os = "linux") {
+            assert!(hint.contains("`apt install llvm`"));
+            assert!(install_hint(ToolFlavor::Mingw, "x86").contains("mingw32-binutils"));
+        }
+    }
+
+    #[test]
+    fn versioned_tool_dirs() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("llvm-tool-dirs");
+
+        for name in ["llvm-9", "llvm-17", "llvm-14.0", "llvm-dev", "clang-18"] {
+            create_dir_all(dir.join(name)).unwrap();
+        }
+
+        let dirs: Vec<_> = versioned_dirs(&dir, "llvm-").collect();
+        assert_eq!(
+            dirs,
+            [
+                dir.join("llvm-17"),
+                dir.join("llvm-14.0"),
+                dir.join("llvm-9")
+            ]
+        );
+
+        assert_eq!(versioned_dirs(&dir.join("missing"), "llvm-").count(), 0);
+        assert!(find_in_path("python3-dll-a-missing-tool").is_none());
+
+        if cfg!(target_os = "linux") {
+            for dir in llvm_tool_dirs() {
+                assert!(dir.starts_with("/usr/lib"));
+                assert!(dir.ends_with("bin"));
+            }
+        }
+    }
+
+    #[test]
+    fn host_native_lib_exe() {
+        let mut bin = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        bin.push("target");
+        bin.push("native-lib-exe");
+        bin.push("bin");
+
+        for dir in [
+            "Hostx64/x64",
+            "Hostx64/arm64",
+            "Hostarm64/x64",
+            "Hostarm64/arm64",
+        ] {
+            let dir = bin.join(dir);
+            create_dir_all(&dir).unwrap();
+            write(dir.join("lib.exe"), b"").unwrap();
+        }
+
+        let emulated = bin.join("Hostx64").join("x64").join("lib.exe");
+        assert_eq!(
+            native_lib_exe(&emulated, "aarch64", "x86_64"),
+            Some(bin.join("Hostarm64").join("x64").join("lib.exe"))
+        );
+        assert_eq!(
+            native_lib_exe(&emulated, "aarch64", "aarch64"),
+            Some(bin.join("Hostarm64").join("arm64").join("lib.exe"))
+        );
+
+        // Already native, or the native cross tools are not installed.
+        assert_eq!(native_lib_exe(&emulated, "x86_64", "x86_64"), None);
+        assert_eq!(native_lib_exe(&emulated, "aarch64", "x86"), None);
+
+        // Not a Visual Studio installation layout.
+        let llvm_lib = Path::new("C:/LLVM/bin/llvm-lib.exe");
+        assert_eq!(native_lib_exe(llvm_lib, "aarch64", "x86_64"), None);
+    }
+
+    #[test]
+    fn linker_flavors() {
+        let rustflags = "-Ctarget-cpu=native\x1f-C\x1flinker=lld-link\x1f-Copt-level=2";
+        assert_eq!(
+            linker_from_rustflags(rustflags).as_deref(),
+            Some("lld-link")
+        );
+        assert_eq!(
+            linker_from_rustflags("-Clinker=gcc\x1f--codegen=linker=C:/LLVM/bin/clang.exe")
+                .as_deref(),
+            Some("C:/LLVM/bin/clang.exe")
+        );
+        assert_eq!(linker_from_rustflags("-Copt-level=2\x1flinker=ld"), None);
+        assert_eq!(linker_from_rustflags(""), None);
+
+        assert_eq!(linker_flavor("lld-link"), Some(ToolFlavor::Llvm));
+        assert_eq!(linker_flavor("/usr/bin/rust-lld"), Some(ToolFlavor::Llvm));
+        assert_eq!(
+            linker_flavor("x86_64-w64-mingw32-clang"),
+            Some(ToolFlavor::Llvm)
+        );
+        assert_eq!(linker_flavor(r"C:\VS\link.exe"), Some(ToolFlavor::LibExe));
+        assert_eq!(
+            linker_flavor("i686-w64-mingw32-gcc"),
+            Some(ToolFlavor::Mingw)
+        );
+        assert_eq!(linker_flavor("cc"), None);
+    }
+
+    #[test]
+    fn vs_dev_env() {
+        let mut tools_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        tools_dir.push("target");
+        tools_dir.push("dev-env-lib-exe");
+        let bin = tools_dir.join("bin");
+
+        for dir in ["Hostx64/x64", "Hostx64/x86", "Hostarm64/arm64"] {
+            let dir = bin.join(dir);
+            create_dir_all(&dir).unwrap();
+            write(dir.join("lib.exe"), b"").unwrap();
+        }
+
+        assert_eq!(
+            dev_env_lib_exe(&tools_dir, "x86_64", "x86"),
+            Some(bin.join("Hostx64").join("x86").join("lib.exe"))
+        );
+
+        // The host tools handle the other target machines, too.
+        assert_eq!(
+            dev_env_lib_exe(&tools_dir, "aarch64", "x86_64"),
+            Some(bin.join("Hostarm64").join("arm64").join("lib.exe"))
+        );
+        assert_eq!(dev_env_lib_exe(&tools_dir, "x86", "x86"), None);
+    }
+
+    #[test]
+    fn discovery_cache() {
+        let first = DllToolCommand::find_cached(Some(ToolFlavor::Llvm), "x86_64", "msvc").unwrap();
+        let second = DllToolCommand::find_cached(Some(ToolFlavor::Llvm), "x86_64", "msvc").unwrap();
+        assert_eq!(second.name(), first.name());
+        assert!(
+            matches!(second, DllToolCommand::Llvm { ref machine, .. } if machine == "i386:x86-64")
+        );
+
+        let cached = |env: &str| {
+            let cache = DISCOVERY_CACHE.get().unwrap().lock().unwrap();
+            cache.keys().any(|key| {
+                key.require == Some(ToolFlavor::Llvm) && key.arch == "x86_64" && key.env == env
+            })
+        };
+        assert!(cached("msvc"));
+
+        // The other target environment is discovered and cached separately.
+        DllToolCommand::find_cached(Some(ToolFlavor::Llvm), "x86_64", "gnu").unwrap();
+        assert!(cached("gnu"));
+
+        {
+            let cache = DISCOVERY_CACHE.get().unwrap().lock().unwrap();
+            let key = cache
+                .keys()
+                .find(|key| key.require == Some(ToolFlavor::Llvm) && key.env == "gnu")
+                .unwrap();
+            let vars: Vec<_> = DISCOVERY_VARS.iter().map(env::var_os).collect();
+            assert_eq!(key.vars, vars);
+            assert!(DISCOVERY_VARS.contains(&"PATH"));
+            assert!(DISCOVERY_VARS.contains(&DLLTOOL_ENV));
+        }
+
+        DllToolCommand::find_cached(Some(ToolFlavor::LibExe), "x86_64", "gnu").unwrap_err();
+        let cache = DISCOVERY_CACHE.get().unwrap().lock().unwrap();
+        assert!(!cache
+            .keys()
+            .any(|key| key.require == Some(ToolFlavor::LibExe) && key.env == "gnu"));
+    }
+}
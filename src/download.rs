@@ -0,0 +1,293 @@
+//! Pinned `llvm-dlltool` download for the turnkey cross builds
+//!
+//! Downloads an `llvm-dlltool` binary for the host from the pinned URL,
+//! verifies its SHA-256 checksum and caches it under the user cache
+//! directory, so that it is downloaded only once per machine.
+//!
+//! The crate does not bundle any download URLs: the build scripts pin
+//! the URL and the checksum of an `llvm-dlltool` build they trust.
+//! The download is performed by the `curl` program, which is
+//! preinstalled on the current Windows, macOS and most Linux hosts.
+//!
+//! This module is only available with the `download` crate feature enabled.
+//!
+//! Example usage
+//! -------------
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! use python3_dll_a::download::PinnedTool;
+//! use python3_dll_a::ImportLibraryGenerator;
+//!
+//! let llvm_dlltool = PinnedTool::new(
+//!     "https://example.com/llvm-dlltool-x86_64-linux",
+//!     "<the SHA-256 checksum of the file>",
+//! )?;
+//!
+//! ImportLibraryGenerator::new("x86_64", "msvc")
+//!     .download_dlltool(Some(llvm_dlltool))
+//!     .generate(Path::new("target/python3-dll-a"))?;
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::env;
+#[cfg(not(target_os = "wasi"))]
+use std::fs::{create_dir_all, read, remove_file, rename};
+use std::io::{Error, ErrorKind, Result};
+use std::path::{Path, PathBuf};
+#[cfg(not(target_os = "wasi"))]
+use std::process::{self, Command, Stdio};
+#[cfg(not(target_os = "wasi"))]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::error::ErrorCategory;
+#[cfg(not(target_os = "wasi"))]
+use crate::sha256::sha256_hex;
+
+/// Downloaded program name
+#[cfg(not(target_os = "wasi"))]
+const LLVM_DLLTOOL: &str = "llvm-dlltool";
+
+/// Counter making the temporary download file names unique in the process
+#[cfg(not(target_os = "wasi"))]
+static DOWNLOAD_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Pinned `llvm-dlltool` download
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PinnedTool {
+    /// Download URL of the `llvm-dlltool` executable for the host
+    pub url: String,
+    /// Expected SHA-256 checksum of the executable (lowercase hex)
+    pub sha256: String,
+}
+
+impl PinnedTool {
+    /// Creates the pinned download of the `llvm-dlltool` executable
+    /// from `url` with the expected SHA-256 checksum `sha256`.
+    ///
+    /// Fails with `ErrorKind::InvalidInput` if `sha256` is not
+    /// 64 hexadecimal digits.
+    pub fn new(url: &str, sha256: &str) -> Result<Self> {
+        let tool = PinnedTool {
+            url: url.to_owned(),
+            sha256: sha256.to_ascii_lowercase(),
+        };
+        tool.check_sha256()?;
+
+        Ok(tool)
+    }
+
+    /// Checks the expected SHA-256 checksum syntax before using it
+    /// as the cache directory name.
+    fn check_sha256(&self) -> Result<()> {
+        if self.sha256.len() == 64 && self.sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            Ok(())
+        } else {
+            let msg = format!(
+                "Invalid SHA-256 checksum '{}' for {}",
+                self.sha256, self.url
+            );
+            Err(Error::new(ErrorKind::InvalidInput, msg))
+        }
+    }
+
+    /// Returns the cached `llvm-dlltool` executable path,
+    /// downloading and verifying it first if it is not cached yet.
+    ///
+    /// The executable is stored as `<cache_dir>/tools/<sha256>/llvm-dlltool`.
+    ///
+    /// Fails on the WASI hosts, which can neither run `curl` nor the downloaded tool.
+    #[cfg(target_os = "wasi")]
+    pub fn fetch(&self, _cache_dir: &Path) -> Result<PathBuf> {
+        let msg = format!("The WASI hosts can not download {}", self.url);
+        Err(ErrorCategory::ToolMissing.error(ErrorKind::Unsupported, msg))
+    }
+
+    /// Returns the cached `llvm-dlltool` executable path,
+    /// downloading and verifying it first if it is not cached yet.
+    ///
+    /// The executable is stored as `<cache_dir>/tools/<sha256>/llvm-dlltool`.
+    #[cfg(not(target_os = "wasi"))]
+    pub fn fetch(&self, cache_dir: &Path) -> Result<PathBuf> {
+        self.check_sha256()?;
+
+        let dir = cache_dir.join("tools").join(&self.sha256);
+        let path = dir.join(format!("{}{}", LLVM_DLLTOOL, env::consts::EXE_SUFFIX));
+
+        if path.is_file() && self.verify(&path).is_ok() {
+            return Ok(path);
+        }
+
+        create_dir_all(&dir)?;
+
+        // Download into a temporary file first to never cache a partial file.
+        // The parallel build scripts may be downloading the same tool.
+        let partial = dir.join(format!(
+            ".{}-{}-{}.download",
+            LLVM_DLLTOOL,
+            process::id(),
+            DOWNLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let status = Command::new("curl")
+            .args(["--fail", "--location", "--silent", "--show-error"])
+            .arg("--output")
+            .arg(&partial)
+            .arg(&self.url)
+            .stdin(Stdio::null())
+            .status()
+            .map_err(|e| {
+                let msg = format!("Failed to run curl to download {}: {}", self.url, e);
+                ErrorCategory::ToolMissing.error(e.kind(), msg)
+            })?;
+
+        if !status.success() {
+            let _ = remove_file(&partial);
+            let msg = format!("Failed to download {}: curl {}", self.url, status);
+            return Err(ErrorCategory::ToolMissing.error(ErrorKind::Other, msg));
+        }
+
+        if let Err(e) = self.verify(&partial) {
+            let _ = remove_file(&partial);
+            return Err(e);
+        }
+
+        set_executable(&partial)?;
+
+        // Another process may have put the same file in place meanwhile.
+        if let Err(e) = rename(&partial, &path) {
+            let _ = remove_file(&partial);
+            if !(path.is_file() && self.verify(&path).is_ok()) {
+                return Err(e);
+            }
+        }
+
+        Ok(path)
+    }
+
+    /// Checks the SHA-256 checksum of the downloaded file.
+    #[cfg(not(target_os = "wasi"))]
+    fn verify(&self, path: &Path) -> Result<()> {
+        let actual = sha256_hex(&read(path)?);
+
+        if actual == self.sha256 {
+            Ok(())
+        } else {
+            let msg = format!(
+                "Checksum mismatch for {}: expected {}, got {}",
+                self.url, self.sha256, actual
+            );
+            Err(ErrorCategory::ToolMissing.error(ErrorKind::InvalidData, msg))
+        }
+    }
+}
+
+/// Returns the `python3-dll-a` subdirectory of the user cache directory.
+///
+/// This is `%LOCALAPPDATA%` on Windows, `~/Library/Caches` on macOS
+/// and `$XDG_CACHE_HOME` (or `~/.cache`) elsewhere.
+pub fn user_cache_dir() -> Option<PathBuf> {
+    let non_empty = |name| {
+        env::var_os(name)
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    };
+
+    let base = if cfg!(windows) {
+        non_empty("LOCALAPPDATA")?
+    } else if cfg!(target_os = "macos") {
+        non_empty("HOME")?.join("Library").join("Caches")
+    } else {
+        non_empty("XDG_CACHE_HOME").or_else(|| Some(non_empty("HOME")?.join(".cache")))?
+    };
+
+    Some(base.join("python3-dll-a"))
+}
+
+/// Makes the downloaded file executable on Unix.
+#[cfg(not(target_os = "wasi"))]
+fn set_executable(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::fs::{metadata, set_permissions};
+        use std::os::unix::fs::PermissionsExt;
+
+        let mut permissions = metadata(path)?.permissions();
+        permissions.set_mode(permissions.mode() | 0o755);
+        set_permissions(path, permissions)?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::write;
+
+    use super::*;
+
+    #[test]
+    fn fetch() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("download");
+        let cache_dir = dir.join("cache");
+        let _ = std::fs::remove_dir_all(&cache_dir);
+        create_dir_all(&dir).unwrap();
+
+        let source = dir.join("llvm-dlltool-source");
+        write(&source, b"#!/bin/sh\n").unwrap();
+        let url = format!("file://{}", source.display());
+        let sha256 = sha256_hex(b"#!/bin/sh\n");
+
+        let path = PinnedTool::new(&url, &sha256.to_ascii_uppercase())
+            .unwrap()
+            .fetch(&cache_dir)
+            .unwrap();
+        assert!(path.starts_with(cache_dir.join("tools").join(&sha256)));
+        assert_eq!(read(&path).unwrap(), b"#!/bin/sh\n");
+
+        // The cached copy is reused.
+        remove_file(&source).unwrap();
+        assert_eq!(
+            PinnedTool::new(&url, &sha256)
+                .unwrap()
+                .fetch(&cache_dir)
+                .unwrap(),
+            path
+        );
+
+        write(&source, b"tampered\n").unwrap();
+        let err = PinnedTool::new(&url, &sha256_hex(b"expected\n"))
+            .unwrap()
+            .fetch(&cache_dir)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Checksum mismatch"));
+
+        let err = PinnedTool::new(&format!("{}.missing", url), &sha256)
+            .unwrap()
+            .fetch(&dir.join("other-cache"))
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+
+        // The checksum becomes a path component, so it must be plain hex.
+        for sha256 in ["../../evil", "abc", &"g".repeat(64)] {
+            let err = PinnedTool::new(&url, sha256).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        }
+        let tool = PinnedTool {
+            url: url.clone(),
+            sha256: "../tools".to_owned(),
+        };
+        assert_eq!(
+            tool.fetch(&cache_dir).unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+
+        // No temporary download files are left behind.
+        let tool_dir = cache_dir.join("tools").join(&sha256);
+        assert_eq!(std::fs::read_dir(tool_dir).unwrap().count(), 1);
+    }
+}
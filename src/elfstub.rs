@@ -0,0 +1,440 @@
+//! Link-time stub `libpython` ELF shared library writer
+//!
+//! Produces a minimal 64-bit ELF shared object defining all the symbols
+//! listed in a Module-Definition file as dynamic symbols, which is enough
+//! for the GNU and LLVM linkers to resolve the Python C API references
+//! when cross-compiling for the Linux targets. The stub is not meant
+//! to be loaded: all the functions share a single `ret` instruction.
+//!
+//! This module is only available with the `stub-elf` crate feature enabled.
+
+use std::io::{ErrorKind, Result};
+
+use crate::def::{DefExport, DefFile};
+use crate::error::ErrorCategory;
+
+/// `EM_X86_64`
+const EM_X86_64: u16 = 62;
+/// `EM_AARCH64`
+const EM_AARCH64: u16 = 183;
+
+/// `ET_DYN`
+const ET_DYN: u16 = 3;
+
+/// `SHT_PROGBITS`
+const SHT_PROGBITS: u32 = 1;
+/// `SHT_STRTAB`
+const SHT_STRTAB: u32 = 3;
+/// `SHT_HASH`
+const SHT_HASH: u32 = 5;
+/// `SHT_DYNAMIC`
+const SHT_DYNAMIC: u32 = 6;
+/// `SHT_NOBITS`
+const SHT_NOBITS: u32 = 8;
+/// `SHT_DYNSYM`
+const SHT_DYNSYM: u32 = 11;
+
+/// `SHF_WRITE`
+const SHF_WRITE: u64 = 0x1;
+/// `SHF_ALLOC`
+const SHF_ALLOC: u64 = 0x2;
+/// `SHF_EXECINSTR`
+const SHF_EXECINSTR: u64 = 0x4;
+
+/// `PT_LOAD`
+const PT_LOAD: u32 = 1;
+/// `PT_DYNAMIC`
+const PT_DYNAMIC: u32 = 2;
+/// `PF_X | PF_W | PF_R`
+const PF_RWX: u32 = 0x7;
+
+/// `DT_NULL`
+const DT_NULL: u64 = 0;
+/// `DT_HASH`
+const DT_HASH: u64 = 4;
+/// `DT_STRTAB`
+const DT_STRTAB: u64 = 5;
+/// `DT_SYMTAB`
+const DT_SYMTAB: u64 = 6;
+/// `DT_STRSZ`
+const DT_STRSZ: u64 = 10;
+/// `DT_SYMENT`
+const DT_SYMENT: u64 = 11;
+/// `DT_SONAME`
+const DT_SONAME: u64 = 14;
+
+/// `STB_GLOBAL`
+const STB_GLOBAL: u8 = 1;
+/// `STT_OBJECT`
+const STT_OBJECT: u8 = 1;
+/// `STT_FUNC`
+const STT_FUNC: u8 = 2;
+
+/// ELF file header size
+const EHDR_SIZE: u64 = 64;
+/// Program header size
+const PHDR_SIZE: u64 = 56;
+/// Section header size
+const SHDR_SIZE: u64 = 64;
+/// Symbol table entry size
+const SYM_SIZE: u64 = 24;
+/// Dynamic section entry size
+const DYN_SIZE: u64 = 16;
+
+/// `.dynsym` section header index
+const SECTION_DYNSYM: u16 = 2;
+/// `.dynstr` section header index
+const SECTION_DYNSTR: u16 = 3;
+/// `.text` section header index
+const SECTION_TEXT: u16 = 4;
+/// `.bss` section header index
+const SECTION_BSS: u16 = 5;
+/// `.shstrtab` section header index
+const SECTION_SHSTRTAB: u16 = 7;
+
+/// Section header string table contents
+const SHSTRTAB: &[u8] = b"\0.hash\0.dynsym\0.dynstr\0.text\0.bss\0.dynamic\0.shstrtab\0";
+
+/// Exported data symbol size, large enough for the static `PyTypeObject`
+/// instances copied into the executables by the copy relocations
+const DATA_SYMBOL_SIZE: u64 = 0x200;
+
+/// Stub library target machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Machine(u16);
+
+impl Machine {
+    /// Returns the ELF machine type for the `CARGO_CFG_TARGET_ARCH` name.
+    pub fn from_arch(arch: &str) -> Result<Self> {
+        match arch {
+            "x86_64" => Ok(Machine(EM_X86_64)),
+            "aarch64" => Ok(Machine(EM_AARCH64)),
+            _ => {
+                let msg = format!("Unsupported stub ELF library target arch '{}'", arch);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Returns the `ret` instruction encoding.
+    fn ret(self) -> &'static [u8] {
+        match self.0 {
+            EM_AARCH64 => &[0xc0, 0x03, 0x5f, 0xd6],
+            _ => &[0xc3, 0xcc, 0xcc, 0xcc],
+        }
+    }
+}
+
+/// Builds the stub shared library with the `soname` defining
+/// the symbols listed in `def`.
+pub(crate) fn write_stub_elf(def: &DefFile, machine: Machine, soname: &str) -> Vec<u8> {
+    let mut exports: Vec<&DefExport> = def.exports.iter().collect();
+    exports.sort_by(|a, b| a.name.cmp(&b.name));
+    exports.dedup_by(|a, b| a.name == b.name);
+
+    // Dynamic string table: the SONAME followed by the symbol names
+    let mut dynstr = vec![0];
+    let soname_offset = dynstr.len() as u64;
+    put_cstr(&mut dynstr, soname);
+    let name_offsets: Vec<_> = exports
+        .iter()
+        .map(|export| {
+            let offset = dynstr.len() as u32;
+            put_cstr(&mut dynstr, &export.name);
+            offset
+        })
+        .collect();
+
+    let symbols = exports.len() as u64 + 1;
+
+    // File layout: headers, .hash, .dynsym, .dynstr, .text, .dynamic,
+    // .shstrtab, section headers. The virtual addresses match the offsets.
+    let hash_offset = EHDR_SIZE + 2 * PHDR_SIZE;
+    let hash_size = 4 * (2 + 1 + symbols);
+    let dynsym_offset = align(hash_offset + hash_size, 8);
+    let dynstr_offset = dynsym_offset + SYM_SIZE * symbols;
+    let text_offset = align(dynstr_offset + dynstr.len() as u64, 16);
+    let text_size = machine.ret().len() as u64;
+    let dynamic_offset = align(text_offset + text_size, 8);
+    let dynamic_size = DYN_SIZE * 7;
+    let shstrtab_offset = dynamic_offset + dynamic_size;
+    let shdrs_offset = align(shstrtab_offset + SHSTRTAB.len() as u64, 8);
+    let file_size = shdrs_offset + SHDR_SIZE * 8;
+
+    let bss_address = align(file_size, 0x1000);
+    let data_symbols = exports.iter().filter(|e| e.data).count() as u64;
+    let bss_size = DATA_SYMBOL_SIZE * data_symbols;
+
+    let mut elf = Vec::with_capacity(file_size as usize);
+
+    // ELF file header
+    elf.extend_from_slice(b"\x7fELF\x02\x01\x01\0\0\0\0\0\0\0\0\0");
+    put_u16(&mut elf, ET_DYN);
+    put_u16(&mut elf, machine.0);
+    put_u32(&mut elf, 1);
+    put_u64(&mut elf, 0);
+    put_u64(&mut elf, EHDR_SIZE);
+    put_u64(&mut elf, shdrs_offset);
+    put_u32(&mut elf, 0);
+    put_u16(&mut elf, EHDR_SIZE as u16);
+    put_u16(&mut elf, PHDR_SIZE as u16);
+    put_u16(&mut elf, 2);
+    put_u16(&mut elf, SHDR_SIZE as u16);
+    put_u16(&mut elf, 8);
+    put_u16(&mut elf, SECTION_SHSTRTAB);
+
+    // Program headers: a single load segment and the dynamic section
+    put_phdr(
+        &mut elf,
+        PT_LOAD,
+        0,
+        file_size,
+        bss_address + bss_size,
+        0x1000,
+    );
+    put_phdr(
+        &mut elf,
+        PT_DYNAMIC,
+        dynamic_offset,
+        dynamic_size,
+        dynamic_size,
+        8,
+    );
+
+    // `.hash`: a single bucket chaining all the symbols
+    put_u32(&mut elf, 1);
+    put_u32(&mut elf, symbols as u32);
+    put_u32(&mut elf, (symbols - 1) as u32);
+    for index in 0..symbols as u32 {
+        put_u32(&mut elf, index.saturating_sub(1));
+    }
+
+    // `.dynsym`
+    pad_to(&mut elf, dynsym_offset);
+    elf.extend_from_slice(&[0; SYM_SIZE as usize]);
+    let mut data_index = 0;
+    for (export, name) in exports.iter().zip(name_offsets) {
+        let (kind, section, value, size) = if export.data {
+            let value = bss_address + DATA_SYMBOL_SIZE * data_index;
+            data_index += 1;
+            (STT_OBJECT, SECTION_BSS, value, DATA_SYMBOL_SIZE)
+        } else {
+            (STT_FUNC, SECTION_TEXT, text_offset, text_size)
+        };
+
+        put_u32(&mut elf, name);
+        elf.push((STB_GLOBAL << 4) | kind);
+        elf.push(0);
+        put_u16(&mut elf, section);
+        put_u64(&mut elf, value);
+        put_u64(&mut elf, size);
+    }
+
+    // `.dynstr`
+    elf.extend_from_slice(&dynstr);
+
+    // `.text`
+    pad_to(&mut elf, text_offset);
+    elf.extend_from_slice(machine.ret());
+
+    // `.dynamic`
+    pad_to(&mut elf, dynamic_offset);
+    for (tag, value) in [
+        (DT_SONAME, soname_offset),
+        (DT_HASH, hash_offset),
+        (DT_STRTAB, dynstr_offset),
+        (DT_SYMTAB, dynsym_offset),
+        (DT_STRSZ, dynstr.len() as u64),
+        (DT_SYMENT, SYM_SIZE),
+        (DT_NULL, 0),
+    ] {
+        put_u64(&mut elf, tag);
+        put_u64(&mut elf, value);
+    }
+
+    // `.shstrtab`
+    elf.extend_from_slice(SHSTRTAB);
+
+    // Section headers
+    pad_to(&mut elf, shdrs_offset);
+    elf.extend_from_slice(&[0; SHDR_SIZE as usize]);
+    let name = |section: &str| {
+        let needle = format!("\0{}\0", section);
+        SHSTRTAB
+            .windows(needle.len())
+            .position(|w| w == needle.as_bytes())
+            .map_or(0, |pos| pos as u32 + 1)
+    };
+    let headers = [
+        (
+            ".hash",
+            SHT_HASH,
+            SHF_ALLOC,
+            hash_offset,
+            hash_size,
+            SECTION_DYNSYM,
+            0,
+            4,
+            4,
+        ),
+        (
+            ".dynsym",
+            SHT_DYNSYM,
+            SHF_ALLOC,
+            dynsym_offset,
+            SYM_SIZE * symbols,
+            SECTION_DYNSTR,
+            1,
+            8,
+            SYM_SIZE,
+        ),
+        (
+            ".dynstr",
+            SHT_STRTAB,
+            SHF_ALLOC,
+            dynstr_offset,
+            dynstr.len() as u64,
+            0,
+            0,
+            1,
+            0,
+        ),
+        (
+            ".text",
+            SHT_PROGBITS,
+            SHF_ALLOC | SHF_EXECINSTR,
+            text_offset,
+            text_size,
+            0,
+            0,
+            16,
+            0,
+        ),
+        (
+            ".bss",
+            SHT_NOBITS,
+            SHF_ALLOC | SHF_WRITE,
+            bss_address,
+            bss_size,
+            0,
+            0,
+            16,
+            0,
+        ),
+        (
+            ".dynamic",
+            SHT_DYNAMIC,
+            SHF_ALLOC | SHF_WRITE,
+            dynamic_offset,
+            dynamic_size,
+            SECTION_DYNSTR,
+            0,
+            8,
+            DYN_SIZE,
+        ),
+        (
+            ".shstrtab",
+            SHT_STRTAB,
+            0,
+            shstrtab_offset,
+            SHSTRTAB.len() as u64,
+            0,
+            0,
+            1,
+            0,
+        ),
+    ];
+    for (section, kind, flags, offset, size, link, info, alignment, entsize) in headers {
+        put_u32(&mut elf, name(section));
+        put_u32(&mut elf, kind);
+        put_u64(&mut elf, flags);
+        put_u64(&mut elf, if flags & SHF_ALLOC != 0 { offset } else { 0 });
+        // The `.bss` section occupies no file space after the headers.
+        put_u64(
+            &mut elf,
+            if kind == SHT_NOBITS {
+                file_size
+            } else {
+                offset
+            },
+        );
+        put_u64(&mut elf, size);
+        put_u32(&mut elf, u32::from(link));
+        put_u32(&mut elf, info);
+        put_u64(&mut elf, alignment);
+        put_u64(&mut elf, entsize);
+    }
+
+    elf
+}
+
+/// Appends a 64-bit program header.
+fn put_phdr(elf: &mut Vec<u8>, kind: u32, offset: u64, file_size: u64, mem_size: u64, align: u64) {
+    put_u32(elf, kind);
+    put_u32(elf, PF_RWX);
+    put_u64(elf, offset);
+    put_u64(elf, offset);
+    put_u64(elf, offset);
+    put_u64(elf, file_size);
+    put_u64(elf, mem_size);
+    put_u64(elf, align);
+}
+
+/// Rounds `value` up to a multiple of `alignment` (a power of two).
+fn align(value: u64, alignment: u64) -> u64 {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// Pads `data` with zeroes up to `offset`.
+fn pad_to(data: &mut Vec<u8>, offset: u64) {
+    data.resize(offset as usize, 0);
+}
+
+/// Appends a little-endian `u16` value.
+fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a little-endian `u32` value.
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a little-endian `u64` value.
+fn put_u64(data: &mut Vec<u8>, value: u64) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a NUL-terminated string.
+fn put_cstr(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(s.as_bytes());
+    data.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stub_elf() {
+        let def = DefFile::parse(
+            "LIBRARY python3.dll\nEXPORTS\nPy_Main\nPyExc_TypeError DATA\nPy_Initialize\n",
+        )
+        .unwrap();
+
+        let elf = write_stub_elf(&def, Machine::from_arch("x86_64").unwrap(), "libpython3.so");
+
+        assert!(elf.starts_with(b"\x7fELF\x02\x01\x01"));
+        assert_eq!(u16::from_le_bytes([elf[18], elf[19]]), EM_X86_64);
+
+        let shdrs = u64::from_le_bytes(elf[40..48].try_into().unwrap()) as usize;
+        assert_eq!(elf.len(), shdrs + 8 * SHDR_SIZE as usize);
+
+        let contains = |s: &[u8]| elf.windows(s.len()).any(|w| w == s);
+        assert!(contains(
+            b"\0libpython3.so\0PyExc_TypeError\0Py_Initialize\0Py_Main\0"
+        ));
+
+        assert!(Machine::from_arch("x86").is_err());
+    }
+}
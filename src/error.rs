@@ -0,0 +1,110 @@
+//! Import library generation error categories
+
+use std::error;
+use std::fmt;
+use std::io::{Error, ErrorKind};
+
+/// Import library generation failure category
+///
+/// The errors returned by this crate are `std::io::Error` values,
+/// the category of an error is obtained with [`ErrorCategory::of()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// The `dlltool` executable was not found or not configured
+    ToolMissing,
+    /// The compile target architecture or environment ABI is not supported
+    UnsupportedTarget,
+    /// The Python version is not supported or its crate feature is disabled
+    UnsupportedVersion,
+    /// The `dlltool` executable failed or produced a malformed import library
+    ToolFailed,
+    /// The compile target OS is not Windows
+    NotAWindowsTarget,
+    /// Any other I/O or data error
+    Io,
+}
+
+impl ErrorCategory {
+    /// Returns the failure category of an error returned by this crate.
+    ///
+    /// The uncategorized errors are reported as [`ErrorCategory::Io`].
+    pub fn of(error: &Error) -> Self {
+        match error
+            .get_ref()
+            .and_then(|e| e.downcast_ref::<CategorizedError>())
+        {
+            Some(e) => e.category,
+            None => ErrorCategory::Io,
+        }
+    }
+
+    /// Returns the `python3-dll-a` exit status and the C API status code
+    /// for an error returned by this crate.
+    ///
+    /// The uncategorized [`ErrorKind::InvalidInput`] errors, such as
+    /// the argument parsing errors, are reported as 2, the other
+    /// uncategorized errors are reported as 1.
+    pub fn exit_code(error: &Error) -> u8 {
+        match Self::of(error) {
+            ErrorCategory::ToolMissing => 3,
+            ErrorCategory::UnsupportedTarget => 4,
+            ErrorCategory::UnsupportedVersion => 5,
+            ErrorCategory::ToolFailed => 6,
+            ErrorCategory::NotAWindowsTarget => 7,
+            _ if error.kind() == ErrorKind::InvalidInput => 2,
+            _ => 1,
+        }
+    }
+
+    /// Builds a new error of this category with the error `kind` and message.
+    pub fn error(self, kind: ErrorKind, msg: impl Into<String>) -> Error {
+        let msg = msg.into();
+        Error::new(
+            kind,
+            CategorizedError {
+                category: self,
+                msg,
+            },
+        )
+    }
+}
+
+/// Categorized error payload of `std::io::Error`
+#[derive(Debug)]
+struct CategorizedError {
+    /// Failure category
+    category: ErrorCategory,
+    /// Error message
+    msg: String,
+}
+
+impl fmt::Display for CategorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.msg)
+    }
+}
+
+impl error::Error for CategorizedError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn category() {
+        let err = ErrorCategory::ToolMissing.error(ErrorKind::NotFound, "no dlltool");
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+        assert_eq!(err.to_string(), "no dlltool");
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+
+        assert_eq!(ErrorCategory::exit_code(&err), 3);
+
+        let err = Error::other("other");
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::Io);
+        assert_eq!(ErrorCategory::exit_code(&err), 1);
+
+        let err = Error::new(ErrorKind::InvalidInput, "bad argument");
+        assert_eq!(ErrorCategory::exit_code(&err), 2);
+    }
+}
@@ -0,0 +1,665 @@
+//! Built-in Windows import library writer
+//!
+//! Produces the short import library archives in the same format
+//! as `llvm-dlltool` without running any external programs.
+//! The archives are deterministic: all timestamps are set to zero.
+//!
+//! This module is only available with the `builtin` crate feature enabled.
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::def::{DefExport, DefFile};
+use crate::error::ErrorCategory;
+
+/// `IMAGE_FILE_MACHINE_I386`
+const MACHINE_I386: u16 = 0x014c;
+/// `IMAGE_FILE_MACHINE_AMD64`
+const MACHINE_AMD64: u16 = 0x8664;
+/// `IMAGE_FILE_MACHINE_ARMNT`
+const MACHINE_ARMNT: u16 = 0x01c4;
+/// `IMAGE_FILE_MACHINE_ARM64`
+const MACHINE_ARM64: u16 = 0xaa64;
+
+/// `IMAGE_FILE_32BIT_MACHINE` COFF header flag
+const FILE_32BIT_MACHINE: u16 = 0x0100;
+
+/// `IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE`
+const SCN_DATA_RW: u32 = 0x0000_0040 | 0x4000_0000 | 0x8000_0000;
+/// `IMAGE_SCN_ALIGN_2BYTES`
+const SCN_ALIGN_2: u32 = 0x0020_0000;
+/// `IMAGE_SCN_ALIGN_4BYTES`
+const SCN_ALIGN_4: u32 = 0x0030_0000;
+/// `IMAGE_SCN_ALIGN_8BYTES`
+const SCN_ALIGN_8: u32 = 0x0040_0000;
+
+/// `IMAGE_SYM_CLASS_EXTERNAL`
+const SYM_CLASS_EXTERNAL: u8 = 2;
+/// `IMAGE_SYM_CLASS_STATIC`
+const SYM_CLASS_STATIC: u8 = 3;
+/// `IMAGE_SYM_CLASS_SECTION`
+const SYM_CLASS_SECTION: u8 = 104;
+
+/// `IMPORT_OBJECT_CODE`
+const IMPORT_CODE: u16 = 0;
+/// `IMPORT_OBJECT_DATA`
+const IMPORT_DATA: u16 = 1;
+
+/// `IMPORT_OBJECT_ORDINAL`
+const NAME_ORDINAL: u16 = 0;
+/// `IMPORT_OBJECT_NAME`
+const NAME_NAME: u16 = 1;
+/// `IMPORT_OBJECT_NAME_NO_PREFIX`
+const NAME_NOPREFIX: u16 = 2;
+/// `IMPORT_OBJECT_NAME_UNDECORATE`
+const NAME_UNDECORATE: u16 = 3;
+
+/// COFF file header size
+const FILE_HEADER_SIZE: u32 = 20;
+/// COFF section header size
+const SECTION_HEADER_SIZE: u32 = 40;
+/// COFF relocation entry size
+const RELOCATION_SIZE: u32 = 10;
+/// Import directory table entry size
+const IMPORT_DIRECTORY_SIZE: u32 = 20;
+
+/// Target machine type for the import library objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Machine(u16);
+
+impl Machine {
+    /// Returns the machine type for the `CARGO_CFG_TARGET_ARCH` name.
+    pub fn from_arch(arch: &str) -> Result<Self> {
+        match arch {
+            "x86_64" => Ok(Machine(MACHINE_AMD64)),
+            "x86" => Ok(Machine(MACHINE_I386)),
+            "aarch64" => Ok(Machine(MACHINE_ARM64)),
+            "arm" => Ok(Machine(MACHINE_ARMNT)),
+            _ => {
+                let msg = format!("Unsupported target arch '{}'", arch);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Checks for the 64-bit machine types.
+    fn is_64bit(self) -> bool {
+        self.0 == MACHINE_AMD64 || self.0 == MACHINE_ARM64
+    }
+
+    /// Returns the image-relative (RVA) relocation type.
+    fn rva_relocation(self) -> u16 {
+        match self.0 {
+            // IMAGE_REL_AMD64_ADDR32NB
+            MACHINE_AMD64 => 3,
+            // IMAGE_REL_I386_DIR32NB
+            MACHINE_I386 => 7,
+            // IMAGE_REL_ARM_ADDR32NB, IMAGE_REL_ARM64_ADDR32NB
+            _ => 2,
+        }
+    }
+
+    /// Returns the COFF header characteristics.
+    fn characteristics(self) -> u16 {
+        if self.is_64bit() {
+            0
+        } else {
+            FILE_32BIT_MACHINE
+        }
+    }
+}
+
+/// Archive member: object file name, contents and defined symbols
+struct Member {
+    /// Archive member name (the DLL name)
+    name: String,
+    /// Object file contents
+    data: Vec<u8>,
+    /// Symbols defined by this member
+    symbols: Vec<String>,
+}
+
+/// Builds the import library archive for the module definitions `def`.
+///
+/// The 32-bit x86 symbol names get the leading underscore prepended
+/// unless `no_leading_underscore` is set.
+pub(crate) fn write_import_library(
+    def: &DefFile,
+    machine: Machine,
+    no_leading_underscore: bool,
+) -> Result<Vec<u8>> {
+    let dll_name = def
+        .dll_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing LIBRARY statement in .def"))?;
+
+    // `python3.dll` -> `python3`
+    let library = match dll_name.rfind('.') {
+        Some(dot) => &dll_name[..dot],
+        None => &dll_name,
+    };
+
+    let import_descriptor = format!("__IMPORT_DESCRIPTOR_{}", library);
+    let null_import_descriptor = "__NULL_IMPORT_DESCRIPTOR".to_owned();
+    let null_thunk = format!("\x7f{}_NULL_THUNK_DATA", library);
+
+    let mut members = vec![
+        Member {
+            name: dll_name.clone(),
+            data: import_descriptor_object(
+                machine,
+                &dll_name,
+                &[&import_descriptor, &null_import_descriptor, &null_thunk],
+            ),
+            symbols: vec![import_descriptor.clone()],
+        },
+        Member {
+            name: dll_name.clone(),
+            data: null_import_descriptor_object(machine, &null_import_descriptor),
+            symbols: vec![null_import_descriptor.clone()],
+        },
+        Member {
+            name: dll_name.clone(),
+            data: null_thunk_object(machine, &null_thunk),
+            symbols: vec![null_thunk.clone()],
+        },
+    ];
+
+    let decorate = machine.0 == MACHINE_I386 && !no_leading_underscore;
+
+    for export in def.exports.iter().filter(|e| !e.private) {
+        let (symbol, name_type) = import_name_type(export, decorate)?;
+
+        let import_type = if export.data {
+            IMPORT_DATA
+        } else {
+            IMPORT_CODE
+        };
+
+        let mut symbols = vec![format!("__imp_{}", symbol)];
+        if import_type == IMPORT_CODE {
+            symbols.push(symbol.clone());
+        }
+
+        members.push(Member {
+            name: dll_name.clone(),
+            data: short_import(
+                machine,
+                &symbol,
+                &dll_name,
+                export.ordinal.unwrap_or_default(),
+                import_type,
+                name_type,
+            ),
+            symbols,
+        });
+    }
+
+    Ok(write_archive(&members))
+}
+
+/// Returns the link symbol name and the import name type for `export`.
+fn import_name_type(export: &DefExport, decorate: bool) -> Result<(String, u16)> {
+    if export.noname {
+        return Ok((export.name.clone(), NAME_ORDINAL));
+    }
+
+    let import_name = match &export.import_name {
+        // Plain cdecl names are decorated with the leading underscore,
+        // which is then stripped from the DLL import name.
+        None if decorate && !is_decorated(&export.name) => {
+            return Ok((format!("_{}", export.name), NAME_NOPREFIX));
+        }
+        None => return Ok((export.name.clone(), NAME_NAME)),
+        Some(import_name) => import_name,
+    };
+
+    // The `name == import_name` entries are written verbatim,
+    // the import name must be derivable from the symbol name.
+    let symbol = &export.name;
+
+    if symbol == import_name {
+        Ok((symbol.clone(), NAME_NAME))
+    } else if strip_prefix(symbol) == import_name {
+        Ok((symbol.clone(), NAME_NOPREFIX))
+    } else if undecorate(symbol) == import_name {
+        Ok((symbol.clone(), NAME_UNDECORATE))
+    } else {
+        let msg = format!(
+            "Unsupported import name '{}' for symbol '{}'",
+            import_name, symbol
+        );
+        Err(Error::new(ErrorKind::InvalidData, msg))
+    }
+}
+
+/// Checks if the 32-bit x86 symbol name is already decorated.
+fn is_decorated(name: &str) -> bool {
+    name.starts_with('@') || name.starts_with('?') || name.contains('@')
+}
+
+/// Strips the leading `?`, `@` or `_` symbol name prefix.
+fn strip_prefix(name: &str) -> &str {
+    name.strip_prefix(['?', '@', '_']).unwrap_or(name)
+}
+
+/// Strips the symbol name prefix and the `@<n>` suffix.
+fn undecorate(name: &str) -> &str {
+    let name = strip_prefix(name);
+
+    name.split('@').next().unwrap_or(name)
+}
+
+/// Builds the short import object (`IMPORT_OBJECT_HEADER` + names).
+fn short_import(
+    machine: Machine,
+    symbol: &str,
+    dll_name: &str,
+    ordinal_hint: u16,
+    import_type: u16,
+    name_type: u16,
+) -> Vec<u8> {
+    let size_of_data = symbol.len() + dll_name.len() + 2;
+
+    let mut data = Vec::with_capacity(20 + size_of_data);
+    put_u16(&mut data, 0); // Sig1
+    put_u16(&mut data, 0xffff); // Sig2
+    put_u16(&mut data, 0); // Version
+    put_u16(&mut data, machine.0);
+    put_u32(&mut data, 0); // TimeDateStamp
+    put_u32(&mut data, size_of_data as u32);
+    put_u16(&mut data, ordinal_hint);
+    put_u16(&mut data, (name_type << 2) | import_type);
+
+    put_cstr(&mut data, symbol);
+    put_cstr(&mut data, dll_name);
+
+    data
+}
+
+/// Builds the `__IMPORT_DESCRIPTOR_<library>` object.
+///
+/// `names` contains the import descriptor, the null import descriptor
+/// and the null thunk symbol names.
+fn import_descriptor_object(machine: Machine, dll_name: &str, names: &[&str; 3]) -> Vec<u8> {
+    const SECTIONS: u32 = 2;
+    const RELOCATIONS: u32 = 3;
+
+    let idata2_offset = FILE_HEADER_SIZE + SECTIONS * SECTION_HEADER_SIZE;
+    let relocations_offset = idata2_offset + IMPORT_DIRECTORY_SIZE;
+    let idata6_offset = relocations_offset + RELOCATIONS * RELOCATION_SIZE;
+    let idata6_size = dll_name.len() as u32 + 1;
+
+    let mut data = Vec::new();
+    put_file_header(&mut data, machine, SECTIONS, idata6_offset + idata6_size, 7);
+
+    put_section_header(
+        &mut data,
+        b".idata$2",
+        IMPORT_DIRECTORY_SIZE,
+        idata2_offset,
+        relocations_offset,
+        RELOCATIONS as u16,
+        SCN_ALIGN_4 | SCN_DATA_RW,
+    );
+    put_section_header(
+        &mut data,
+        b".idata$6",
+        idata6_size,
+        idata6_offset,
+        0,
+        0,
+        SCN_ALIGN_2 | SCN_DATA_RW,
+    );
+
+    // .idata$2: the import directory entry filled by the relocations
+    data.resize(data.len() + IMPORT_DIRECTORY_SIZE as usize, 0);
+
+    // NameRVA -> .idata$6, ImportLookupTableRVA -> .idata$4,
+    // ImportAddressTableRVA -> .idata$5
+    for (offset, symbol) in [(12, 2), (0, 3), (16, 4)] {
+        put_u32(&mut data, offset);
+        put_u32(&mut data, symbol);
+        put_u16(&mut data, machine.rva_relocation());
+    }
+
+    // .idata$6
+    put_cstr(&mut data, dll_name);
+
+    let import_descriptor_offset = 4;
+    let null_import_descriptor_offset = import_descriptor_offset + names[0].len() as u32 + 1;
+    let null_thunk_offset = null_import_descriptor_offset + names[1].len() as u32 + 1;
+
+    put_symbol(
+        &mut data,
+        SymbolName::Offset(import_descriptor_offset),
+        1,
+        SYM_CLASS_EXTERNAL,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Short(b".idata$2"),
+        1,
+        SYM_CLASS_SECTION,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Short(b".idata$6"),
+        2,
+        SYM_CLASS_STATIC,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Short(b".idata$4"),
+        0,
+        SYM_CLASS_SECTION,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Short(b".idata$5"),
+        0,
+        SYM_CLASS_SECTION,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Offset(null_import_descriptor_offset),
+        0,
+        SYM_CLASS_EXTERNAL,
+    );
+    put_symbol(
+        &mut data,
+        SymbolName::Offset(null_thunk_offset),
+        0,
+        SYM_CLASS_EXTERNAL,
+    );
+
+    put_string_table(&mut data, names);
+
+    data
+}
+
+/// Builds the `__NULL_IMPORT_DESCRIPTOR` object.
+fn null_import_descriptor_object(machine: Machine, name: &str) -> Vec<u8> {
+    const SECTIONS: u32 = 1;
+
+    let idata3_offset = FILE_HEADER_SIZE + SECTIONS * SECTION_HEADER_SIZE;
+
+    let mut data = Vec::new();
+    put_file_header(
+        &mut data,
+        machine,
+        SECTIONS,
+        idata3_offset + IMPORT_DIRECTORY_SIZE,
+        1,
+    );
+
+    put_section_header(
+        &mut data,
+        b".idata$3",
+        IMPORT_DIRECTORY_SIZE,
+        idata3_offset,
+        0,
+        0,
+        SCN_ALIGN_4 | SCN_DATA_RW,
+    );
+
+    // .idata$3: the terminating null import directory entry
+    data.resize(data.len() + IMPORT_DIRECTORY_SIZE as usize, 0);
+
+    put_symbol(&mut data, SymbolName::Offset(4), 1, SYM_CLASS_EXTERNAL);
+    put_string_table(&mut data, &[name]);
+
+    data
+}
+
+/// Builds the `\x7f<library>_NULL_THUNK_DATA` object.
+fn null_thunk_object(machine: Machine, name: &str) -> Vec<u8> {
+    const SECTIONS: u32 = 2;
+
+    let (va_size, align) = if machine.is_64bit() {
+        (8, SCN_ALIGN_8)
+    } else {
+        (4, SCN_ALIGN_4)
+    };
+
+    let idata5_offset = FILE_HEADER_SIZE + SECTIONS * SECTION_HEADER_SIZE;
+    let idata4_offset = idata5_offset + va_size;
+
+    let mut data = Vec::new();
+    put_file_header(&mut data, machine, SECTIONS, idata4_offset + va_size, 1);
+
+    put_section_header(
+        &mut data,
+        b".idata$5",
+        va_size,
+        idata5_offset,
+        0,
+        0,
+        align | SCN_DATA_RW,
+    );
+    put_section_header(
+        &mut data,
+        b".idata$4",
+        va_size,
+        idata4_offset,
+        0,
+        0,
+        align | SCN_DATA_RW,
+    );
+
+    // .idata$5 (IAT) and .idata$4 (ILT) null terminators
+    data.resize(data.len() + 2 * va_size as usize, 0);
+
+    put_symbol(&mut data, SymbolName::Offset(4), 1, SYM_CLASS_EXTERNAL);
+    put_string_table(&mut data, &[name]);
+
+    data
+}
+
+/// COFF symbol name representation
+enum SymbolName<'a> {
+    /// Name stored inline (up to 8 bytes)
+    Short(&'a [u8; 8]),
+    /// Name stored in the string table at the given offset
+    Offset(u32),
+}
+
+/// Appends the COFF file header.
+fn put_file_header(
+    data: &mut Vec<u8>,
+    machine: Machine,
+    sections: u32,
+    symbol_table_offset: u32,
+    symbols: u32,
+) {
+    put_u16(data, machine.0);
+    put_u16(data, sections as u16);
+    put_u32(data, 0); // TimeDateStamp
+    put_u32(data, symbol_table_offset);
+    put_u32(data, symbols);
+    put_u16(data, 0); // SizeOfOptionalHeader
+    put_u16(data, machine.characteristics());
+}
+
+/// Appends a COFF section header.
+fn put_section_header(
+    data: &mut Vec<u8>,
+    name: &[u8; 8],
+    size: u32,
+    offset: u32,
+    relocations_offset: u32,
+    relocations: u16,
+    characteristics: u32,
+) {
+    data.extend_from_slice(name);
+    put_u32(data, 0); // VirtualSize
+    put_u32(data, 0); // VirtualAddress
+    put_u32(data, size);
+    put_u32(data, offset);
+    put_u32(data, relocations_offset);
+    put_u32(data, 0); // PointerToLinenumbers
+    put_u16(data, relocations);
+    put_u16(data, 0); // NumberOfLinenumbers
+    put_u32(data, characteristics);
+}
+
+/// Appends a COFF symbol table entry.
+fn put_symbol(data: &mut Vec<u8>, name: SymbolName, section: u16, storage_class: u8) {
+    match name {
+        SymbolName::Short(name) => data.extend_from_slice(name),
+        SymbolName::Offset(offset) => {
+            put_u32(data, 0);
+            put_u32(data, offset);
+        }
+    }
+
+    put_u32(data, 0); // Value
+    put_u16(data, section);
+    put_u16(data, 0); // Type
+    data.push(storage_class);
+    data.push(0); // NumberOfAuxSymbols
+}
+
+/// Appends the COFF string table.
+fn put_string_table(data: &mut Vec<u8>, names: &[&str]) {
+    let size: usize = 4 + names.iter().map(|name| name.len() + 1).sum::<usize>();
+
+    put_u32(data, size as u32);
+
+    for name in names {
+        put_cstr(data, name);
+    }
+}
+
+/// Writes the GNU-style archive with the symbol lookup table.
+fn write_archive(members: &[Member]) -> Vec<u8> {
+    // Long member names are stored in the `//` member.
+    let mut long_names = String::new();
+    let mut header_names = Vec::with_capacity(members.len());
+
+    for member in members {
+        if member.name.len() < 16 {
+            header_names.push(format!("{}/", member.name));
+        } else {
+            let entry = format!("{}/\n", member.name);
+
+            let offset = match long_names.find(&entry) {
+                Some(offset) => offset,
+                None => {
+                    long_names.push_str(&entry);
+                    long_names.len() - entry.len()
+                }
+            };
+
+            header_names.push(format!("/{}", offset));
+        }
+    }
+
+    // The long names table size includes the padding.
+    if long_names.len() & 1 != 0 {
+        long_names.push('\n');
+    }
+
+    let symbol_count: usize = members.iter().map(|m| m.symbols.len()).sum();
+    let mut symbol_table_size = 4 + 4 * symbol_count;
+    symbol_table_size += members
+        .iter()
+        .flat_map(|m| &m.symbols)
+        .map(|symbol| symbol.len() + 1)
+        .sum::<usize>();
+    symbol_table_size += symbol_table_size % 2;
+
+    let mut offset = 8 + 60 + symbol_table_size;
+    if !long_names.is_empty() {
+        offset += 60 + long_names.len();
+    }
+
+    // Precompute the member header offsets for the symbol table.
+    let mut member_offsets = Vec::with_capacity(members.len());
+    for member in members {
+        member_offsets.push(offset as u32);
+        offset += 60 + member.data.len() + member.data.len() % 2;
+    }
+
+    let mut archive = Vec::with_capacity(offset);
+    archive.extend_from_slice(b"!<arch>\n");
+
+    put_member_header(&mut archive, "/", "0", symbol_table_size);
+    archive.extend_from_slice(&(symbol_count as u32).to_be_bytes());
+    for (member, offset) in members.iter().zip(&member_offsets) {
+        for _ in &member.symbols {
+            archive.extend_from_slice(&offset.to_be_bytes());
+        }
+    }
+    for symbol in members.iter().flat_map(|m| &m.symbols) {
+        put_cstr(&mut archive, symbol);
+    }
+    if archive.len() & 1 != 0 {
+        archive.push(0);
+    }
+
+    if !long_names.is_empty() {
+        archive.extend_from_slice(format!("{:<48}{:<10}`\n", "//", long_names.len()).as_bytes());
+        archive.extend_from_slice(long_names.as_bytes());
+    }
+
+    for (member, name) in members.iter().zip(&header_names) {
+        put_member_header(&mut archive, name, "644", member.data.len());
+        archive.extend_from_slice(&member.data);
+        if archive.len() & 1 != 0 {
+            archive.push(b'\n');
+        }
+    }
+
+    archive
+}
+
+/// Appends the deterministic archive member header.
+fn put_member_header(archive: &mut Vec<u8>, name: &str, mode: &str, size: usize) {
+    let header = format!(
+        "{:<16}{:<12}{:<6}{:<6}{:<8}{:<10}`\n",
+        name, "0", "0", "0", mode, size
+    );
+
+    archive.extend_from_slice(header.as_bytes());
+}
+
+/// Appends a little-endian `u16` value.
+fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a little-endian `u32` value.
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a NUL-terminated string.
+fn put_cstr(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(s.as_bytes());
+    data.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_names() {
+        let export = |name: &str, import_name: Option<&str>| DefExport {
+            name: name.to_owned(),
+            import_name: import_name.map(ToOwned::to_owned),
+            ..DefExport::default()
+        };
+
+        let (symbol, name_type) = import_name_type(&export("Py_Main", None), true).unwrap();
+        assert_eq!((symbol.as_str(), name_type), ("_Py_Main", NAME_NOPREFIX));
+
+        let (symbol, name_type) = import_name_type(&export("Py_Main", None), false).unwrap();
+        assert_eq!((symbol.as_str(), name_type), ("Py_Main", NAME_NAME));
+
+        let decorated = export("_Py_Main", Some("Py_Main"));
+        let (symbol, name_type) = import_name_type(&decorated, false).unwrap();
+        assert_eq!((symbol.as_str(), name_type), ("_Py_Main", NAME_NOPREFIX));
+
+        assert!(import_name_type(&export("Py_Main", Some("Py_Other")), false).is_err());
+    }
+}
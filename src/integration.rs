@@ -0,0 +1,388 @@
+//! Build system integration files for the generated import libraries
+
+use std::path::{Path, PathBuf};
+
+use crate::{DefFile, GenerationReport};
+
+/// Returns the CMake script defining an `IMPORTED` library target
+/// for the import library `implib`.
+///
+/// The target is named after the import library, e.g. `python3-dll-a::python3`.
+pub(crate) fn cmake_script(implib: &Path) -> String {
+    let target = format!("python3-dll-a::{}", implib_stem(implib));
+    let location = cmake_quote(&implib.to_string_lossy());
+
+    format!(
+        "# Generated by python3-dll-a {version}\n\
+         if(NOT TARGET {target})\n  \
+           add_library({target} SHARED IMPORTED)\n  \
+           set_target_properties({target} PROPERTIES\n    \
+             IMPORTED_IMPLIB \"{location}\"\n    \
+             IMPORTED_NO_SONAME TRUE)\n\
+         endif()\n",
+        version = env!("CARGO_PKG_VERSION"),
+        target = target,
+        location = location,
+    )
+}
+
+/// Returns the pkg-config file describing the import library `implib`
+/// for the Python `version`.
+///
+/// Meson (and other pkg-config aware build systems) find the import library
+/// as the `python3-dll-a-<stem>` dependency, e.g. `python3-dll-a-python3`.
+pub(crate) fn pkg_config_file(implib: &Path, version: &str) -> String {
+    let stem = implib_stem(implib);
+    let libdir = implib.parent().unwrap_or(Path::new(""));
+
+    format!(
+        "# Generated by python3-dll-a {generator}\n\
+         libdir={libdir}\n\
+         \n\
+         Name: {stem}\n\
+         Description: Python DLL import library\n\
+         Version: {version}\n\
+         Libs: -L${{libdir}} -l{stem}\n",
+        generator = env!("CARGO_PKG_VERSION"),
+        libdir = pkg_config_quote(&libdir.to_string_lossy()),
+        stem = stem,
+        version = version,
+    )
+}
+
+/// Returns the MSVC linker response file delay-loading the DLL `dll_name`.
+pub(crate) fn delay_load_response_file(dll_name: &str) -> String {
+    format!("/DELAYLOAD:{}\ndelayimp.lib\n", dll_name)
+}
+
+/// Returns the plain text list of the symbols exported by `def`,
+/// one name per line.
+pub(crate) fn symbols_file(def: &DefFile) -> String {
+    def.exports
+        .iter()
+        .map(|export| format!("{}\n", export.name))
+        .collect()
+}
+
+/// Returns the C header listing the symbols exported by `def`
+/// for the import library named `stem`.
+///
+/// The `<PREFIX>_EXPORTS(F, D)` X-macro expands to `F(name)` for every
+/// exported function and to `D(name)` for every exported data symbol.
+pub(crate) fn c_header(def: &DefFile, stem: &str) -> String {
+    let prefix: String = format!("python3-dll-a-{}", stem)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    let mut header = format!(
+        "/* Generated by python3-dll-a {version} */\n\
+         #ifndef {prefix}_EXPORTS_H\n\
+         #define {prefix}_EXPORTS_H\n\
+         \n\
+         #define {prefix}_DLL_NAME \"{dll_name}\"\n\
+         \n\
+         #define {prefix}_EXPORTS(F, D) \\\n",
+        version = env!("CARGO_PKG_VERSION"),
+        prefix = prefix,
+        dll_name = def.dll_name().unwrap_or_default(),
+    );
+
+    for export in &def.exports {
+        let macro_name = if export.data { 'D' } else { 'F' };
+        header.push_str(&format!("    {}({}) \\\n", macro_name, export.name));
+    }
+
+    header.push_str(&format!("\n#endif /* {}_EXPORTS_H */\n", prefix));
+
+    header
+}
+
+/// Returns the Make/Ninja depfile listing the `inputs` of the `target` file.
+///
+/// Every input also gets an empty rule, so that the removed inputs
+/// trigger a rebuild instead of a Make error.
+pub(crate) fn depfile(target: &Path, inputs: &[PathBuf]) -> String {
+    let mut depfile = depfile_quote(&target.to_string_lossy());
+    depfile.push(':');
+
+    for input in inputs {
+        depfile.push(' ');
+        depfile.push_str(&depfile_quote(&input.to_string_lossy()));
+    }
+    depfile.push('\n');
+
+    for input in inputs {
+        depfile.push_str(&format!("\n{}:\n", depfile_quote(&input.to_string_lossy())));
+    }
+
+    depfile
+}
+
+/// Returns the JSON provenance report for the generated import library.
+///
+/// The SHA-256 hashes of the import library and the `.def` file
+/// are passed in `implib_sha256` and `def_sha256`.
+pub(crate) fn report_json(
+    report: &GenerationReport,
+    implib_sha256: &str,
+    def_sha256: &str,
+) -> String {
+    let optional = |value: Option<&str>| value.map_or("null".to_owned(), json_quote);
+    let command: Vec<_> = report.command.iter().map(|arg| json_quote(arg)).collect();
+
+    format!(
+        "{{\n  \
+           \"generator\": {generator},\n  \
+           \"implib\": {implib},\n  \
+           \"implib_sha256\": {implib_sha256},\n  \
+           \"def\": {def},\n  \
+           \"def_sha256\": {def_sha256},\n  \
+           \"tool\": {tool},\n  \
+           \"tool_version\": {tool_version},\n  \
+           \"command\": [{command}]\n\
+         }}\n",
+        generator = json_quote(concat!("python3-dll-a ", env!("CARGO_PKG_VERSION"))),
+        implib = json_quote(&report.implib.to_string_lossy()),
+        implib_sha256 = json_quote(implib_sha256),
+        def = json_quote(&report.def.to_string_lossy()),
+        def_sha256 = json_quote(def_sha256),
+        tool = json_quote(&report.tool),
+        tool_version = optional(report.tool_version.as_deref()),
+        command = command.join(", "),
+    )
+}
+
+/// Returns the CycloneDX 1.5 JSON SBOM describing the generated import library.
+///
+/// The import library is the described component, which depends on the `.def`
+/// file component. The SHA-256 hashes of both are passed in `implib_sha256`
+/// and `def_sha256`, the generation environment is recorded in `properties`.
+pub(crate) fn cyclonedx_json(
+    report: &GenerationReport,
+    implib_sha256: &str,
+    def_sha256: &str,
+    properties: &[(&str, String)],
+) -> String {
+    let file_name =
+        |path: &Path| json_quote(&path.file_name().unwrap_or_default().to_string_lossy());
+    let tool_version = report
+        .tool_version
+        .as_deref()
+        .map_or(String::new(), |version| {
+            format!(", \"version\": {}", json_quote(version))
+        });
+    let properties: Vec<_> = properties
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{{\"name\": {}, \"value\": {}}}",
+                json_quote(name),
+                json_quote(value)
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\n  \
+           \"bomFormat\": \"CycloneDX\",\n  \
+           \"specVersion\": \"1.5\",\n  \
+           \"version\": 1,\n  \
+           \"metadata\": {{\n    \
+             \"tools\": {{\n      \
+               \"components\": [\n        \
+                 {{\"type\": \"library\", \"name\": \"python3-dll-a\", \"version\": {version}}},\n        \
+                 {{\"type\": \"application\", \"name\": {tool}{tool_version}}}\n      \
+               ]\n    \
+             }},\n    \
+             \"component\": {{\n      \
+               \"type\": \"file\",\n      \
+               \"bom-ref\": \"implib\",\n      \
+               \"name\": {implib},\n      \
+               \"hashes\": [{{\"alg\": \"SHA-256\", \"content\": {implib_sha256}}}]\n    \
+             }},\n    \
+             \"properties\": [\n      \
+               {properties}\n    \
+             ]\n  \
+           }},\n  \
+           \"components\": [\n    \
+             {{\n      \
+               \"type\": \"file\",\n      \
+               \"bom-ref\": \"def\",\n      \
+               \"name\": {def},\n      \
+               \"hashes\": [{{\"alg\": \"SHA-256\", \"content\": {def_sha256}}}]\n    \
+             }}\n  \
+           ],\n  \
+           \"dependencies\": [{{\"ref\": \"implib\", \"dependsOn\": [\"def\"]}}]\n\
+         }}\n",
+        version = json_quote(env!("CARGO_PKG_VERSION")),
+        tool = json_quote(&report.tool),
+        tool_version = tool_version,
+        implib = file_name(&report.implib),
+        implib_sha256 = json_quote(implib_sha256),
+        def = file_name(&report.def),
+        def_sha256 = json_quote(def_sha256),
+        properties = properties.join(",\n      "),
+    )
+}
+
+/// Returns the import library file name without the `.lib` or `.dll.a` extension.
+pub(crate) fn implib_stem(implib: &Path) -> String {
+    let name = implib.file_name().unwrap_or_default().to_string_lossy();
+
+    match name.strip_suffix(".dll.a") {
+        Some(stem) => stem.to_owned(),
+        None => name.strip_suffix(".lib").unwrap_or(&name).to_owned(),
+    }
+}
+
+/// Escapes the path for a CMake quoted argument.
+///
+/// The path separators are normalized to the forward slashes.
+fn cmake_quote(path: &str) -> String {
+    let mut quoted = String::with_capacity(path.len());
+
+    for c in path.chars() {
+        match c {
+            '\\' => quoted.push('/'),
+            '"' | '$' | ';' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted
+}
+
+/// Escapes the path for a pkg-config variable value.
+///
+/// The path separators are normalized to the forward slashes.
+fn pkg_config_quote(path: &str) -> String {
+    let mut quoted = String::with_capacity(path.len());
+
+    for c in path.chars() {
+        match c {
+            '\\' => quoted.push('/'),
+            ' ' | '$' | '#' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+
+    quoted
+}
+
+/// Escapes the path for a Make depfile rule.
+///
+/// The drive letter colons are understood by both Make and Ninja as is.
+fn depfile_quote(path: &str) -> String {
+    let mut quoted = String::with_capacity(path.len());
+
+    for c in path.chars() {
+        match c {
+            ' ' | '#' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '$' => quoted.push_str("$$"),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted
+}
+
+/// Escapes the string as a JSON string literal.
+fn json_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+
+    for c in s.chars() {
+        match c {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '\n' => quoted.push_str("\\n"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmake() {
+        let script = cmake_script(Path::new("C:/build/out/python312.lib"));
+
+        assert!(script.contains("if(NOT TARGET python3-dll-a::python312)\n"));
+        assert!(script.contains("add_library(python3-dll-a::python312 SHARED IMPORTED)\n"));
+        assert!(script.contains("IMPORTED_IMPLIB \"C:/build/out/python312.lib\"\n"));
+        assert!(script.ends_with("endif()\n"));
+
+        assert_eq!(implib_stem(Path::new("out/python3.dll.a")), "python3");
+        assert_eq!(cmake_quote(r"C:\out\a;b$c"), "C:/out/a\\;b\\$c");
+    }
+
+    #[test]
+    fn pkg_config() {
+        let file = pkg_config_file(Path::new("C:/Program Files/out/python313t.lib"), "3.13");
+
+        assert!(file.contains("\nlibdir=C:/Program\\ Files/out\n"));
+        assert!(file.contains("\nName: python313t\n"));
+        assert!(file.contains("\nVersion: 3.13\n"));
+        assert!(file.ends_with("\nLibs: -L${libdir} -lpython313t\n"));
+    }
+
+    #[test]
+    fn depfile_rules() {
+        let inputs = [
+            PathBuf::from("C:/Program Files/defs/python3.def"),
+            PathBuf::from("/usr/bin/llvm-dlltool"),
+        ];
+        let depfile = depfile(Path::new("out/$lib/python3.lib"), &inputs);
+
+        assert_eq!(
+            depfile,
+            "out/$$lib/python3.lib: C:/Program\\ Files/defs/python3.def /usr/bin/llvm-dlltool\n\
+             \nC:/Program\\ Files/defs/python3.def:\n\
+             \n/usr/bin/llvm-dlltool:\n"
+        );
+    }
+
+    #[test]
+    fn symbol_manifests() {
+        let def = DefFile::parse(
+            "LIBRARY \"libpypy3.10-c.dll\"\nEXPORTS\nPyPy_Main\nPyPyExc_TypeError DATA\n",
+        )
+        .unwrap();
+
+        assert_eq!(symbols_file(&def), "PyPy_Main\nPyPyExc_TypeError\n");
+
+        let header = c_header(&def, "libpypy3.10-c");
+        assert!(
+            header.contains("#define PYTHON3_DLL_A_LIBPYPY3_10_C_DLL_NAME \"libpypy3.10-c.dll\"\n")
+        );
+        assert!(header.contains(
+            "#define PYTHON3_DLL_A_LIBPYPY3_10_C_EXPORTS(F, D) \\\n    \
+             F(PyPy_Main) \\\n    D(PyPyExc_TypeError) \\\n\n"
+        ));
+        assert!(header.ends_with("#endif /* PYTHON3_DLL_A_LIBPYPY3_10_C_EXPORTS_H */\n"));
+    }
+}
@@ -0,0 +1,251 @@
+//! Minimal JSON parser for the structured input files
+//!
+//! Only the JSON subset used by the symbol lists and the rustc target
+//! specifications is supported: the numbers must be integers.
+
+use std::io::{Error, ErrorKind, Result};
+
+/// Parsed JSON document value
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Returns the `key` field of the object value.
+    pub(crate) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Returns the string value.
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the JSON document `input`.
+pub(crate) fn parse(input: &str) -> Result<Value> {
+    let mut parser = JsonParser { input, pos: 0 };
+
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != input.len() {
+        return Err(parser.error("trailing characters"));
+    }
+
+    Ok(value)
+}
+
+/// Minimal JSON parser
+struct JsonParser<'a> {
+    /// JSON document text
+    input: &'a str,
+    /// Current byte offset
+    pos: usize,
+}
+
+impl JsonParser<'_> {
+    /// Parses a JSON value.
+    fn value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+
+        match self.peek() {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(Value::String),
+            Some(b'-' | b'0'..=b'9') => self.integer(),
+            Some(_) if self.keyword("null") => Ok(Value::Null),
+            Some(_) if self.keyword("true") => Ok(Value::Bool(true)),
+            Some(_) if self.keyword("false") => Ok(Value::Bool(false)),
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Parses a JSON object.
+    fn object(&mut self) -> Result<Value> {
+        let mut fields = Vec::new();
+        self.pos += 1;
+
+        self.skip_whitespace();
+        if self.eat(b'}') {
+            return Ok(Value::Object(fields));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+
+            self.skip_whitespace();
+            if !self.eat(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            fields.push((key, self.value()?));
+
+            self.skip_whitespace();
+            if self.eat(b'}') {
+                return Ok(Value::Object(fields));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+        }
+    }
+
+    /// Parses a JSON array.
+    fn array(&mut self) -> Result<Value> {
+        let mut items = Vec::new();
+        self.pos += 1;
+
+        self.skip_whitespace();
+        if self.eat(b']') {
+            return Ok(Value::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+
+            self.skip_whitespace();
+            if self.eat(b']') {
+                return Ok(Value::Array(items));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or ']'"));
+            }
+        }
+    }
+
+    /// Parses a JSON string.
+    fn string(&mut self) -> Result<String> {
+        let mut s = String::new();
+        self.pos += 1;
+
+        loop {
+            let rest = &self.input[self.pos..];
+            let end = rest
+                .find(['"', '\\'])
+                .ok_or_else(|| self.error("unterminated string"))?;
+            s.push_str(&rest[..end]);
+            self.pos += end + 1;
+
+            if rest.as_bytes()[end] == b'"' {
+                return Ok(s);
+            }
+
+            let escape = self
+                .peek()
+                .ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            s.push(match escape {
+                b'"' => '"',
+                b'\\' => '\\',
+                b'/' => '/',
+                b'b' => '\u{8}',
+                b'f' => '\u{c}',
+                b'n' => '\n',
+                b'r' => '\r',
+                b't' => '\t',
+                b'u' => {
+                    let code = self
+                        .input
+                        .get(self.pos..self.pos + 4)
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| self.error("invalid unicode escape"))?;
+                    self.pos += 4;
+                    code
+                }
+                _ => return Err(self.error("invalid escape")),
+            });
+        }
+    }
+
+    /// Parses a JSON integer number.
+    fn integer(&mut self) -> Result<Value> {
+        let start = self.pos;
+        self.eat(b'-');
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+
+        self.input[start..self.pos]
+            .parse()
+            .map(Value::Integer)
+            .map_err(|_| self.error("invalid integer"))
+    }
+
+    /// Consumes the literal `keyword` if it is next.
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = self.input[self.pos..].starts_with(keyword);
+        if found {
+            self.pos += keyword.len();
+        }
+        found
+    }
+
+    /// Consumes the `byte` if it is next.
+    fn eat(&mut self, byte: u8) -> bool {
+        let found = self.peek() == Some(byte);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    /// Returns the next byte.
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    /// Skips the JSON whitespace.
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Builds the JSON syntax error at the current offset.
+    fn error(&self, msg: &str) -> Error {
+        let msg = format!("Invalid JSON at offset {}: {}", self.pos, msg);
+        Error::new(ErrorKind::InvalidData, msg)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let value = super::parse(r#" {"a": [1, -2, null], "b": "\u00e9\n", "c": true} "#).unwrap();
+
+        assert_eq!(
+            value.get("a"),
+            Some(&Value::Array(vec![
+                Value::Integer(1),
+                Value::Integer(-2),
+                Value::Null
+            ]))
+        );
+        assert_eq!(value.get("b").and_then(Value::as_str), Some("\u{e9}\n"));
+        assert_eq!(value.get("d"), None);
+
+        for invalid in ["", "{", r#"{"a" 1}"#, "[1,]", "1.5", r#""\x""#, "{} {}"] {
+            let err = super::parse(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! Installed Windows CPython discovery with the `py` launcher
+//!
+//! The native Windows builds may link against the genuine import libraries
+//! shipped in the `libs` directory of an installed CPython instead of
+//! the generated ones. The installations are listed by `py -0p` or,
+//! when the launcher is not installed, found in the PEP 514 registry keys.
+
+use std::env::consts::ARCH;
+use std::io::{ErrorKind, Result};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::{ErrorCategory, ImportLibraryGenerator};
+
+/// PEP 514 CPython registry keys and the default architectures
+/// of the installations registered under them
+const REGISTRY_KEYS: [(&str, Option<&str>); 3] = [
+    (r"HKCU\Software\Python\PythonCore", None),
+    (r"HKLM\SOFTWARE\Python\PythonCore", None),
+    (r"HKLM\SOFTWARE\WOW6432Node\Python\PythonCore", Some("x86")),
+];
+
+/// Installed Windows CPython found by [`installed_pythons()`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct InstalledPython {
+    /// Major and minor Python version
+    pub version: (u8, u8),
+    /// Free-threaded CPython build, e.g. `3.13t`
+    pub gil_disabled: bool,
+    /// Interpreter architecture (as in `CARGO_CFG_TARGET_ARCH`), e.g. `x86_64`
+    pub arch: String,
+    /// `python.exe` interpreter path
+    pub executable: PathBuf,
+}
+
+impl InstalledPython {
+    /// Returns the `libs` directory containing the installed import libraries,
+    /// e.g. `python3.lib` and `python312.lib`.
+    pub fn lib_dir(&self) -> PathBuf {
+        let prefix = self.executable.parent().unwrap_or(Path::new(""));
+
+        prefix.join("libs")
+    }
+
+    /// Creates a new import library generator for the installed
+    /// version-specific CPython DLL and the target environment ABI `env`.
+    #[must_use]
+    pub fn generator(&self, env: &str) -> ImportLibraryGenerator {
+        let mut generator = ImportLibraryGenerator::new(&self.arch, env);
+        generator.version(Some(self.version));
+        if self.gil_disabled {
+            generator.abiflags(Some("t"));
+        }
+
+        generator
+    }
+}
+
+/// Lists the CPython versions installed on this Windows host.
+///
+/// Runs `py -0p`, or reads the PEP 514 `PythonCore` registry keys
+/// with `reg query` if the `py` launcher is not installed.
+/// The installations of the other Python distributions are skipped.
+///
+/// Returns an empty list on the non-Windows hosts.
+pub fn installed_pythons() -> Result<Vec<InstalledPython>> {
+    if !cfg!(windows) {
+        return Ok(Vec::new());
+    }
+
+    match Command::new("py").arg("-0p").stdin(Stdio::null()).output() {
+        Ok(output) if output.status.success() => {
+            Ok(parse_py_list(&String::from_utf8_lossy(&output.stdout)))
+        }
+        // `py -0p` fails if no Python is installed.
+        Ok(_) => Ok(Vec::new()),
+        Err(e) if e.kind() == ErrorKind::NotFound => registry_pythons(),
+        Err(e) => {
+            let msg = format!("Failed to run py -0p: {}", e);
+            Err(ErrorCategory::ToolMissing.error(e.kind(), msg))
+        }
+    }
+}
+
+/// Reads the installed CPython versions from the PEP 514 registry keys.
+fn registry_pythons() -> Result<Vec<InstalledPython>> {
+    let mut pythons = Vec::new();
+
+    for (key, default_arch) in REGISTRY_KEYS {
+        let output = Command::new("reg")
+            .args(["query", key, "/s"])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                let msg = format!("Failed to run reg query: {}", e);
+                ErrorCategory::ToolMissing.error(e.kind(), msg)
+            })?;
+
+        // The missing keys are reported as failures.
+        if output.status.success() {
+            let query = String::from_utf8_lossy(&output.stdout);
+            pythons.extend(parse_registry(&query, default_arch));
+        }
+    }
+
+    Ok(pythons)
+}
+
+/// Parses the `py -0p` output `list`.
+///
+/// Both the `-V:3.12 *  <path>` (Python 3.11+ launcher) and
+/// the `-3.9-64  <path> *` (older launcher) formats are accepted.
+fn parse_py_list(list: &str) -> Vec<InstalledPython> {
+    list.lines()
+        .filter_map(|line| {
+            let (tag, path) = line.trim().split_once(char::is_whitespace)?;
+            let path = path.trim().trim_matches('*').trim();
+
+            let tag = tag.strip_prefix("-V:").or_else(|| tag.strip_prefix('-'))?;
+            let tag = tag.strip_prefix("PythonCore/").unwrap_or(tag);
+
+            installed_python(tag, path, None)
+        })
+        .collect()
+}
+
+/// Parses the `reg query <key> /s` output `query` listing the `PythonCore`
+/// subkeys, with the installations of the `default_arch` architecture
+/// unless the tags specify it.
+fn parse_registry(query: &str, default_arch: Option<&str>) -> Vec<InstalledPython> {
+    let mut pythons = Vec::new();
+    let mut tag = None;
+
+    for line in query.lines() {
+        if line.starts_with("HKEY_") {
+            // `...\PythonCore\<tag>\InstallPath` keys list the executables.
+            let mut components = line.trim().rsplit('\\');
+            tag = match (components.next(), components.next()) {
+                (Some("InstallPath"), Some(tag)) => Some(tag.to_owned()),
+                _ => None,
+            };
+            continue;
+        }
+
+        let mut fields = line.split_whitespace();
+        if let (Some(tag), Some("ExecutablePath"), Some("REG_SZ")) =
+            (&tag, fields.next(), fields.next())
+        {
+            let path = fields.collect::<Vec<_>>().join(" ");
+            pythons.extend(installed_python(tag, &path, default_arch));
+        }
+    }
+
+    pythons
+}
+
+/// Builds the installed CPython description from the PEP 514 `tag`
+/// like `3.12`, `3.13t` or `3.9-32` and the executable `path`.
+fn installed_python(tag: &str, path: &str, default_arch: Option<&str>) -> Option<InstalledPython> {
+    let (version, arch) = match tag.split_once('-') {
+        Some((version, "32")) => (version, "x86"),
+        Some((version, "64")) => (version, "x86_64"),
+        Some((version, "arm64")) => (version, "aarch64"),
+        Some(_) => return None,
+        None => (tag, default_arch.unwrap_or(ARCH)),
+    };
+
+    let (version, gil_disabled) = match version.strip_suffix('t') {
+        Some(version) => (version, true),
+        None => (version, false),
+    };
+
+    let (major, minor) = version.split_once('.')?;
+
+    Some(InstalledPython {
+        version: (major.parse().ok()?, minor.parse().ok()?),
+        gil_disabled,
+        arch: arch.to_owned(),
+        executable: PathBuf::from(path),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_py_list() {
+        let pythons = super::parse_py_list(
+            " -V:3.13t         C:\\Python313\\python3.13t.exe\n \
+             -V:3.12 *        C:\\Program Files\\Python312\\python.exe\n \
+             -V:3.11-32       C:\\Python311-32\\python.exe\n \
+             -V:ContinuumAnalytics/Anaconda39-64 C:\\Anaconda3\\python.exe\n",
+        );
+
+        assert_eq!(pythons.len(), 3);
+        assert_eq!(pythons[0].version, (3, 13));
+        assert!(pythons[0].gil_disabled);
+        assert_eq!(
+            pythons[1].executable,
+            Path::new("C:\\Program Files\\Python312\\python.exe")
+        );
+        assert_eq!(pythons[2].arch, "x86");
+
+        let pythons = super::parse_py_list(
+            "Installed Pythons found by py Launcher for Windows\n \
+             -3.9-64        C:/Python39/python.exe *\n",
+        );
+        assert_eq!(pythons.len(), 1);
+        assert_eq!(pythons[0].version, (3, 9));
+        assert_eq!(pythons[0].arch, "x86_64");
+        assert_eq!(pythons[0].lib_dir(), Path::new("C:/Python39/libs"));
+
+        let generator = pythons[0].generator("msvc");
+        assert_eq!(generator.import_name().unwrap(), "python39");
+    }
+
+    #[test]
+    fn parse_registry() {
+        let pythons = super::parse_registry(
+            "\r\nHKEY_LOCAL_MACHINE\\SOFTWARE\\WOW6432Node\\Python\\PythonCore\\3.10\r\n    \
+             DisplayName    REG_SZ    Python 3.10 (32-bit)\r\n\r\n\
+             HKEY_LOCAL_MACHINE\\SOFTWARE\\WOW6432Node\\Python\\PythonCore\\3.10\\InstallPath\r\n    \
+             (Default)    REG_SZ    C:\\Program Files (x86)\\Python310\\\r\n    \
+             ExecutablePath    REG_SZ    C:\\Program Files (x86)\\Python310\\python.exe\r\n",
+            Some("x86"),
+        );
+
+        assert_eq!(pythons.len(), 1);
+        assert_eq!(pythons[0].version, (3, 10));
+        assert_eq!(pythons[0].arch, "x86");
+        assert_eq!(
+            pythons[0].executable,
+            Path::new("C:\\Program Files (x86)\\Python310\\python.exe")
+        );
+    }
+}
@@ -12,6 +12,9 @@
 //! the Python DLL import libraries for MinGW-w64 targets.
 //! Setting `PYO3_MINGW_DLLTOOL` environment variable overrides
 //! the default `dlltool` command name for the target.
+//! If the MinGW-w64 binutils are not installed, `llvm-dlltool` (when found)
+//! writes the `.dll.a` import libraries for the MinGW targets instead.
+//! It is the only choice for the `aarch64-pc-windows-gnullvm` target.
 //!
 //! **Note:** MSVC cross-compile targets require either LLVM binutils
 //! or Zig to be available on the host system.
@@ -22,6 +25,9 @@
 //! or `"python -m ziglang"`, then `zig dlltool` will be used in place
 //! of `llvm-dlltool` (or MinGW binutils).
 //!
+//! [`ImportLibraryGenerator::require_tool()`] fails instead of falling back
+//! to the other tool flavors, and [`probe_tools()`] lists the tools found on the host.
+//!
 //! PyO3 integration
 //! ----------------
 //!
@@ -81,6 +87,18 @@
 //! or `python3.lib` will be automatically created in the directory
 //! pointed by the `PYO3_CROSS_LIB_DIR` environment variable.
 //!
+//! The [`generate!`] macro expands to the complete build script logic:
+//! it reads the target and the PyO3 cross-compilation environment variables,
+//! skips the non-Windows targets, generates the import library
+//! in `PYO3_CROSS_LIB_DIR` (or `OUT_DIR`) and prints the cargo directives
+//! linking it:
+//!
+//! ```no_run
+//! fn main() {
+//!     python3_dll_a::generate!();
+//! }
+//! ```
+//!
 //! ### Example `cargo build` invocation
 //!
 //! ```sh
@@ -94,16 +112,193 @@
 //! specific import libraries such as `python39.lib` or `python313t.lib`.
 //!
 //! See the [`ImportLibraryGenerator`] builder API description for details.
+//!
+//! Cross-compiling with PyO3
+//! -------------------------
+//!
+//! [`ImportLibraryGenerator::generate_cross_lib_dir()`] populates a directory
+//! suitable for `PYO3_CROSS_LIB_DIR` with both the version-specific
+//! and the Stable ABI import libraries, and [`ImportLibraryGenerator::pyo3_config()`]
+//! returns a ready-to-use `PYO3_CONFIG_FILE` interpreter configuration.
+//! [`ImportLibraryGenerator::from_pyo3_env()`] and [`ImportLibraryGenerator::from_pyo3_config()`]
+//! configure the generator from the PyO3 cross-compilation settings.
+//!
+//! Verifying the generated import libraries
+//! ----------------------------------------
+//!
+//! [`verify_implib()`] and [`ImportLibraryGenerator::verify_implib()`] check
+//! the imported symbols of an existing import library, [`diff_implibs()`]
+//! compares two import libraries and [`check_against_dll()`] compares
+//! the embedded definitions with an actual `pythonXY.dll`.
+//! [`ImportLibraryGenerator::checksum_manifest()`], [`ImportLibraryGenerator::report_file()`]
+//! and [`ImportLibraryGenerator::sbom()`] record the provenance of the generated files,
+//! [`symbol_history()`] and [`audit_symbols()`] track the symbol availability
+//! across the CPython versions.
+//!
+//! Build system integration
+//! ------------------------
+//!
+//! The generator writes the CMake scripts, pkg-config files, MSVC delay-load
+//! response files, symbol lists, C headers and Make/Ninja depfiles next to
+//! the import libraries, see [`ImportLibraryGenerator::cmake_script()`] and
+//! the neighbouring methods. The optional `capi` feature exposes
+//! the generator as a C library for the non-Rust build systems.
+//!
+//! [`ImportLibraryGenerator::hermetic()`] disables all the `PATH`, registry
+//! and environment based discovery for the sandboxed build systems like Bazel.
+//!
+//! Environment variables
+//! ---------------------
+//!
+//! The CI systems can tune the import library generation without
+//! changing the build scripts with these environment variables:
+//!
+//! | Variable                  | Generator setting                              |
+//! |---------------------------|------------------------------------------------|
+//! | `PYTHON3_DLL_A_DLLTOOL`   | [`ImportLibraryGenerator::dlltool()`]          |
+//! | `PYTHON3_DLL_A_LIB_EXE`   | [`ImportLibraryGenerator::lib_exe()`]          |
+//! | `PYTHON3_DLL_A_DEF_DIR`   | [`ImportLibraryGenerator::def_dir()`]          |
+//! | `PYTHON3_DLL_A_CACHE_DIR` | [`ImportLibraryGenerator::cache_dir()`]        |
+//! | `PYTHON3_DLL_A_BACKEND`   | [`ImportLibraryGenerator::backend()`]          |
+//! | `PYTHON3_DLL_A_VERBOSITY` | [`ImportLibraryGenerator::verbosity()`]        |
+//!
+//! The settings made explicitly with the generator methods take precedence
+//! over the environment variables, and the hermetic mode ignores them.
+//!
+//! Cargo features
+//! --------------
+//!
+//! Only the Stable ABI `python3.def` definitions are embedded by default.
+//! The version-specific Python ABI definitions are enabled by the individual
+//! version features (`py37` to `py313`, `py313t`, `pypy37` to `pypy310`),
+//! or all at once by the `all-versions` feature:
+//!
+//! ```toml
+//! [build-dependencies]
+//! python3-dll-a = { version = "0.2", features = ["py311"] }
+//! ```
+//!
+//! The other optional features:
+//!
+//! | Feature            | Adds                                                        |
+//! |--------------------|-------------------------------------------------------------|
+//! | `msvc-discovery`   | the Visual Studio `lib.exe` registry lookup (default)       |
+//! | `builtin`          | the built-in import library writer, `Backend::Builtin`      |
+//! | `download`         | the pinned `llvm-dlltool` download, the `download` module   |
+//! | `compress`         | the deflate-compressed embedded definitions                 |
+//! | `config`           | the `python3-dll-a.toml` config file loader                 |
+//! | `serde`            | `Serialize` and `Deserialize` for the generator             |
+//! | `toml-symbol-list` | the TOML symbol lists in place of the `.def` files          |
+//! | `defgen`           | the `defgen` module regenerating the embedded `python3.def` |
+//! | `capi`             | the `capi` C API module                                     |
+//! | `test-support`     | the `mock` module and `Backend::Mock`                       |
+//! | `stub-dll`         | the stub `python3.dll` writer                               |
+//! | `stub-elf`         | the stub `libpython3.so` writer for Linux and Android       |
+//! | `stub-tbd`         | the stub `Python.tbd` writer for macOS                      |
+//! | `cli`              | the `python3-dll-a` and `cargo-python3-dll` executables     |
+//!
+//! The `cli` and `capi` features enable `all-versions`.
+//!
+//! Command line utility
+//! --------------------
+//!
+//! The optional `cli` crate feature builds the `python3-dll-a` executable,
+//! see `python3-dll-a --help` for its commands:
+//!
+//! ```sh
+//! python3-dll-a --all-versions --targets x86_64-pc-windows-msvc,aarch64-pc-windows-msvc --out-dir dist/
+//! ```
+//!
+//! The `python3-dll-a` exit status tells the failure category apart:
+//! 1 for the I/O errors, 2 for the invalid arguments, 3 for a missing `dlltool`,
+//! 4 for an unsupported target, 5 for an unsupported Python version,
+//! 6 for a failed `dlltool` run and 7 for a non-Windows target. The library users can get the same
+//! categories from the returned errors with [`ErrorCategory::of()`].
 
 #![deny(missing_docs)]
 #![allow(clippy::needless_doctest_main)]
 #![allow(clippy::uninlined_format_args)]
 
+mod build_script;
+#[cfg(feature = "capi")]
+pub mod capi;
+#[cfg(feature = "config")]
+mod config;
+mod def;
+#[cfg(feature = "defgen")]
+pub mod defgen;
+mod discovery;
+#[cfg(feature = "download")]
+pub mod download;
+#[cfg(feature = "stub-elf")]
+mod elfstub;
+mod error;
+#[cfg(feature = "builtin")]
+mod implib;
+mod integration;
+mod json;
+mod launcher;
+#[cfg(feature = "test-support")]
+pub mod mock;
+mod pe;
+mod pyo3;
+#[cfg(feature = "serde")]
+mod serialize;
+mod settings;
+mod sha256;
+mod stats;
+#[cfg(feature = "stub-dll")]
+mod stubdll;
+#[cfg(any(feature = "stub-dll", feature = "stub-elf", feature = "stub-tbd"))]
+mod stubs;
+mod symbols;
+mod symlist;
+mod target;
+#[cfg(feature = "stub-tbd")]
+mod tbd;
+mod verify;
+
+pub use build_script::run_build_script;
+#[cfg(feature = "config")]
+pub use config::{GenerateConfig, GenerateMatrix};
+pub use def::{DefDialect, DefExport, DefFile, DefLint, DefLintKind, DefMetadata};
+pub use discovery::probe_tools;
+pub use error::ErrorCategory;
+pub use launcher::{installed_pythons, InstalledPython};
+pub use pe::{read_dll_exports, DllExport, DllExports, DllReport};
+pub use stats::ExportStats;
+pub use symbols::{
+    audit_symbols, check_def_consistency, symbol_history, AuditFinding, AuditIssue,
+    MissingStableAbiSymbol, Symbol, SymbolHistory, SymbolKind, STABLE_ABI_SYMBOLS,
+};
+pub use target::TargetInfo;
+pub use verify::{
+    diff_implibs, read_implib, verify_implib, ImplibDiff, ImplibReport, ImportedSymbol,
+};
+
+use std::borrow::Cow;
 use std::env;
-use std::fs::{create_dir_all, write};
+use std::ffi::OsString;
+use std::fmt;
+#[cfg(not(target_os = "wasi"))]
+use std::fs::copy;
+use std::fs::{
+    create_dir_all, metadata, read, read_dir, read_to_string, remove_dir_all, remove_file, rename,
+    set_permissions, write,
+};
 use std::io::{Error, ErrorKind, Result};
 use std::path::{Path, PathBuf};
+#[cfg(not(target_os = "wasi"))]
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(not(target_os = "wasi"))]
+use std::time::UNIX_EPOCH;
+use std::time::{Duration, Instant};
+
+#[cfg(not(target_os = "wasi"))]
+use discovery::DllToolCommand;
+use discovery::{find_in_path, install_hint};
+use settings::EnvSettings;
 
 /// Import library file extension for the GNU environment ABI (MinGW-w64)
 const IMPLIB_EXT_GNU: &str = ".dll.a";
@@ -111,21 +306,54 @@ const IMPLIB_EXT_GNU: &str = ".dll.a";
 /// Import library file extension for the MSVC environment ABI
 const IMPLIB_EXT_MSVC: &str = ".lib";
 
-/// Canonical MinGW-w64 `dlltool` program name
-const DLLTOOL_GNU: &str = "x86_64-w64-mingw32-dlltool";
+/// `GenerationReport::tool` value for the reused existing import libraries
+const REUSED_TOOL: &str = "existing";
+
+/// Maximum number of the replacement definitions problems in the error message
+const MAX_REPORTED_LINTS: usize = 10;
 
-/// Canonical MinGW-w64 `dlltool` program name (32-bit version)
-const DLLTOOL_GNU_32: &str = "i686-w64-mingw32-dlltool";
+/// Python version and ABI flags of an embedded definitions file
+type EmbeddedVersion = (Option<(u8, u8)>, Option<&'static str>);
 
-/// Canonical `dlltool` program name for the MSVC environment ABI (LLVM dlltool)
-const DLLTOOL_MSVC: &str = "llvm-dlltool";
+/// Embedded CPython versions and ABI flags (`None` is the Stable ABI)
+const CPYTHON_VERSIONS: [EmbeddedVersion; 9] = [
+    (None, None),
+    (Some((3, 7)), None),
+    (Some((3, 8)), None),
+    (Some((3, 9)), None),
+    (Some((3, 10)), None),
+    (Some((3, 11)), None),
+    (Some((3, 12)), None),
+    (Some((3, 13)), None),
+    (Some((3, 13)), Some("t")),
+];
+
+/// Embedded PyPy versions
+const PYPY_VERSIONS: [EmbeddedVersion; 4] = [
+    (Some((3, 7)), None),
+    (Some((3, 8)), None),
+    (Some((3, 9)), None),
+    (Some((3, 10)), None),
+];
+
+/// Embeds the named Module-Definition file from the `src` directory.
+#[cfg(not(feature = "compress"))]
+macro_rules! def_file {
+    ($name:literal) => {
+        include_str!($name)
+    };
+}
 
-/// Canonical `lib` program name for the MSVC environment ABI (MSVC lib.exe)
-#[cfg(windows)]
-const LIB_MSVC: &str = "lib.exe";
+/// Embeds the named Module-Definition file compressed by the build script.
+#[cfg(feature = "compress")]
+macro_rules! def_file {
+    ($name:literal) => {
+        include_bytes!(concat!(env!("OUT_DIR"), "/", $name, ".deflate")).as_slice()
+    };
+}
 
 /// Python interpreter implementations
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PythonImplementation {
     /// CPython
     CPython,
@@ -133,6 +361,264 @@ pub enum PythonImplementation {
     PyPy,
 }
 
+/// Import library generation backends
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// Run the external `dlltool` or `lib.exe` program (default)
+    #[cfg_attr(not(all(target_os = "wasi", feature = "builtin")), default)]
+    DllTool,
+    /// Write the import library directly without any external programs
+    ///
+    /// This is the default on the WASI hosts, which can not run programs.
+    ///
+    /// Requires the `builtin` crate feature.
+    #[cfg(feature = "builtin")]
+    #[cfg_attr(target_os = "wasi", default)]
+    Builtin,
+    /// Write a placeholder import library and record the invocation
+    /// in [`mock::invocations()`]
+    ///
+    /// Requires the `test-support` crate feature.
+    #[cfg(feature = "test-support")]
+    Mock,
+}
+
+/// Python DLL ABI selection, see [`ImportLibraryGenerator::abi()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Abi {
+    /// Stable ABI `python3.dll`
+    Abi3 {
+        /// Oldest CPython version the extension supports
+        ///
+        /// Limits the exports to the Stable ABI symbols available in it,
+        /// all of them are exported if `None`.
+        minimum: Option<(u8, u8)>,
+    },
+    /// Version-specific `pythonXY.dll`
+    Versioned {
+        /// Major and minor Python version
+        version: (u8, u8),
+        /// CPython debug build `pythonXY_d.dll`
+        debug: bool,
+        /// Free-threaded CPython build `pythonXYt.dll`
+        gil_disabled: bool,
+    },
+}
+
+/// Handling of the Python versions without the embedded definitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum UnknownVersionPolicy {
+    /// Fail with [`ErrorCategory::UnsupportedVersion`] (default)
+    #[default]
+    Error,
+    /// Warn and use the latest embedded definitions of the same Python
+    /// implementation and ABI flags with the requested DLL name
+    UseLatest,
+    /// Warn and generate the Stable ABI `python3.dll` import library instead
+    /// (CPython only)
+    StableAbi,
+}
+
+/// Handling of the import library already present at the output path
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum OverwritePolicy {
+    /// Replace the existing import library (default)
+    #[default]
+    Overwrite,
+    /// Keep the existing import library and skip generating it
+    SkipIfExists,
+    /// Fail with [`std::io::ErrorKind::AlreadyExists`]
+    ErrorIfExists,
+}
+
+/// Line terminators of the written `.def` files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum LineEnding {
+    /// `CRLF` for `lib.exe`, `LF` for the other tools (default)
+    #[default]
+    Auto,
+    /// Unix `LF`
+    Lf,
+    /// Windows `CRLF`
+    CrLf,
+}
+
+/// Import library generation tool flavors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ToolFlavor {
+    /// MinGW `dlltool` (`gnu` targets only)
+    Mingw,
+    /// LLVM `llvm-dlltool`
+    Llvm,
+    /// MSVC `lib.exe` or LLVM `llvm-lib` (`msvc` targets only)
+    LibExe,
+    /// `zig dlltool` found via `ZIG_COMMAND`
+    Zig,
+}
+
+impl ToolFlavor {
+    /// Returns the tool flavor name for the diagnostics.
+    fn name(self) -> &'static str {
+        match self {
+            ToolFlavor::Mingw => "MinGW dlltool",
+            ToolFlavor::Llvm => "llvm-dlltool",
+            ToolFlavor::LibExe => "lib.exe",
+            ToolFlavor::Zig => "zig dlltool",
+        }
+    }
+}
+
+/// Generated artifacts directory layout
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OutputLayout {
+    /// Directly into `out_dir` (default)
+    #[default]
+    Flat,
+    /// Into `out_dir/<target-triple>/`, the PyPy import libraries
+    /// into `out_dir/<target-triple>/pypy/`
+    PerTarget,
+    /// Into `out_dir/<target-triple>/<python-version>/`,
+    /// e.g. `out_dir/x86_64-pc-windows-msvc/3.13t/`
+    PerTargetVersion,
+    /// Into `out_dir/<hash>/`, where `<hash>` is the first 16 hex digits
+    /// of the SHA-256 hash of the generator configuration
+    Hashed,
+}
+
+/// Import library generation report
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GenerationReport {
+    /// Generated import library file path
+    pub implib: PathBuf,
+    /// Module-Definition file path the import library was generated from
+    pub def: PathBuf,
+    /// Import library generation tool name,
+    /// e.g. `llvm-dlltool`, `lib.exe` or `builtin`
+    pub tool: String,
+    /// Warning messages printed by the import library generation tool,
+    /// preceded by the `unknown_version()` policy warning, if any
+    pub warnings: Vec<String>,
+    /// Retained `lib.exe` export file path, e.g. `python3.exp`
+    pub export_file: Option<PathBuf>,
+    /// Executed tool command line, empty if no external tool was run
+    pub command: Vec<String>,
+    /// Tool version string, if requested with
+    /// [`ImportLibraryGenerator::report_file()`] and known
+    pub tool_version: Option<String>,
+    /// Import library generation phase timings
+    pub timings: GenerationTimings,
+}
+
+impl GenerationReport {
+    /// Returns the SHA-256 hex digest of the generated import library.
+    pub fn implib_sha256(&self) -> Result<String> {
+        Ok(sha256::sha256_hex(&read(&self.implib)?))
+    }
+
+    /// Returns the SHA-256 hex digest of the Module-Definition file.
+    pub fn def_sha256(&self) -> Result<String> {
+        Ok(sha256::sha256_hex(&read(&self.def)?))
+    }
+
+    /// Returns the report with the artifact paths in `from` moved into `to`.
+    ///
+    /// The executed tool command line is kept as is.
+    fn relocated(&self, from: &Path, to: &Path) -> Self {
+        let relocate = |path: &PathBuf| match path.strip_prefix(from) {
+            Ok(relative) => to.join(relative),
+            Err(_) => path.clone(),
+        };
+
+        GenerationReport {
+            implib: relocate(&self.implib),
+            def: relocate(&self.def),
+            export_file: self.export_file.as_ref().map(relocate),
+            ..self.clone()
+        }
+    }
+}
+
+/// Generator decisions for its configuration,
+/// see [`ImportLibraryGenerator::resolve()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResolvedConfig {
+    /// Compile target `<arch>-<env>`, e.g. `x86_64-msvc`
+    pub target: String,
+    /// Python version, e.g. `abi3`, `3.13t` or `pypy3.10`
+    pub python: String,
+    /// Python DLL name the import library binds to, e.g. `python3.dll`
+    pub dll_name: String,
+    /// Definitions source, `embedded` or `override`
+    pub def_source: String,
+    /// Import library generation backend
+    pub backend: Backend,
+    /// Selected tool command, e.g. `llvm-dlltool` or `zig dlltool`,
+    /// `None` for the backends running no tool
+    pub tool: Option<String>,
+    /// How the tool was selected: the builder method (e.g. `dlltool()`)
+    /// or the environment variable name, `discovered` or `downloaded`
+    pub tool_source: Option<String>,
+    /// Import library output path
+    pub implib: PathBuf,
+}
+
+/// Import library generation tool found by [`probe_tools()`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ToolProbe {
+    /// Tool flavor
+    pub flavor: ToolFlavor,
+    /// Tool command, e.g. `x86_64-w64-mingw32-dlltool` or `zig dlltool`
+    pub command: String,
+    /// Executable path, `None` if the tool is not installed
+    pub path: Option<PathBuf>,
+    /// Tool version string, if the tool reports it
+    pub version: Option<String>,
+    /// Whether the tool flavor supports the probed target
+    pub supports_target: bool,
+}
+
+impl ToolProbe {
+    /// Returns whether the tool is installed and supports the probed target.
+    pub fn is_usable(&self) -> bool {
+        self.path.is_some() && self.supports_target
+    }
+
+    /// Returns the installation hint for the tool, e.g. the package name,
+    /// selected by the host OS.
+    pub fn install_hint(&self, arch: &str) -> String {
+        install_hint(self.flavor, &normalize_arch(arch))
+    }
+}
+
+/// Import library generation phase timings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GenerationTimings {
+    /// `dlltool` executable discovery time
+    pub discovery: Duration,
+    /// Module-Definition file write time
+    pub def_write: Duration,
+    /// Import library generation tool execution time
+    /// (or the cached import library copying time)
+    pub tool: Duration,
+}
+
+impl GenerationTimings {
+    /// Returns the total time spent in all the phases.
+    pub fn total(&self) -> Duration {
+        self.discovery + self.def_write + self.tool
+    }
+}
+
 /// Windows import library generator for Python
 ///
 /// Generates `python3.dll` or `pythonXY.dll` import library directly from the
@@ -176,14 +662,22 @@ pub enum PythonImplementation {
 ///     .generate(Path::new("target/python3-lib"))
 ///     .unwrap();
 /// ```
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ImportLibraryGenerator {
     /// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
     arch: String,
     // The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
     env: String,
+    /// The `gnullvm` target (`CARGO_CFG_TARGET_ABI` is `llvm`) of the `gnu` env
+    gnullvm: bool,
+    /// The compile target OS name (as in `CARGO_CFG_TARGET_OS`)
+    target_os: Option<String>,
+    /// Skip the generation for the non-Windows targets
+    noop_on_non_windows: bool,
     /// Major and minor Python version (for `pythonXY.dll` only)
     version: Option<(u8, u8)>,
+    /// Oldest supported CPython version (for `python3.dll` only)
+    abi3_minimum: Option<(u8, u8)>,
     /// Python interpreter implementation
     implementation: PythonImplementation,
     /// Optional Python ABI flags
@@ -191,6 +685,109 @@ pub struct ImportLibraryGenerator {
     /// For example, `"t"` stands for the free-threaded CPython v3.13 build
     /// aka CPython `3.13t`.
     abiflags: Option<String>,
+    /// Handling of the Python versions without the embedded definitions
+    unknown_version: UnknownVersionPolicy,
+    /// Existing import library handling
+    overwrite: OverwritePolicy,
+    /// Written `.def` file line terminators
+    line_ending: LineEnding,
+    /// Link against the CPython debug build `pythonXY_d.dll`
+    debug: bool,
+    /// Extra `dlltool` symbol handling flags
+    options: DllToolOptions,
+    /// Write the explicitly decorated 32-bit x86 symbol names
+    decorate_x86_names: bool,
+    /// Omit the private leading-underscore symbols
+    exclude_private: bool,
+    /// Replacement `LIBRARY` statement DLL name
+    library_name: Option<String>,
+    /// Write the `LIBRARY` statement DLL name with the `.dll` extension
+    library_extension: bool,
+    /// `LIBRARY` statement `BASE` image base address
+    library_base: Option<u64>,
+    /// Directory containing the replacement `.def` files
+    def_dir: Option<PathBuf>,
+    /// Import library generation backend
+    backend: Option<Backend>,
+    /// Required import library generation tool flavor
+    require_tool: Option<ToolFlavor>,
+    /// Write the `.sha256` checksum manifest next to the import library
+    checksum_manifest: bool,
+    /// Write the CMake script importing the import library
+    cmake_script: bool,
+    /// Write the pkg-config file describing the import library
+    pkg_config_file: bool,
+    /// Write the PyO3 interpreter config file for the import library
+    pyo3_config_file: bool,
+    /// Write the linker response file delay-loading the Python DLL
+    delay_load: bool,
+    /// Retain the `.exp` export file written by `lib.exe`
+    export_file: bool,
+    /// Write the plain text symbol list next to the import library
+    symbols_file: bool,
+    /// Write the Make/Ninja depfile listing the import library inputs
+    depfile: bool,
+    /// Reuse the genuine import library found in the output directory
+    reuse_existing: bool,
+    /// Write the C header listing the exports next to the import library
+    c_header: bool,
+    /// Also generate the Stable ABI `python3.dll` import library
+    also_stable_abi: bool,
+    /// Mark the generated artifacts read-only
+    read_only: bool,
+    /// Write the JSON provenance report next to the import library
+    report_file: bool,
+    /// Fail the generation when the tool reports warnings
+    deny_tool_warnings: bool,
+    /// Generated artifacts directory layout
+    layout: OutputLayout,
+    /// Explicit `dlltool` or `lib.exe` executable path
+    dlltool: Option<PathBuf>,
+    /// Explicit Visual Studio `lib.exe` executable path
+    lib_exe: Option<PathBuf>,
+    /// Write the MSVC-format `.lib` import library for the `gnu` targets
+    msvc_lib: bool,
+    /// Pinned `llvm-dlltool` download used when no tool is installed
+    #[cfg(feature = "download")]
+    download_dlltool: Option<download::PinnedTool>,
+    /// Disable the tool discovery and the environment variable overrides
+    hermetic: bool,
+    /// Generated import library cache directory
+    cache_dir: Option<PathBuf>,
+    /// Diagnostic output verbosity
+    verbosity: Option<u8>,
+}
+
+/// Extra `dlltool` command line options
+///
+/// These mostly matter for the 32-bit x86 targets,
+/// where the C symbol names are decorated with a leading underscore.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct DllToolOptions {
+    /// Strip the `@<n>` stdcall suffixes from the exported names (`-k`)
+    kill_at: bool,
+    /// Do not prepend the leading underscore to the symbol names
+    no_leading_underscore: bool,
+    /// MinGW `dlltool` intermediate file name prefix (`--temp-prefix`)
+    temp_prefix: Option<PathBuf>,
+    /// MinGW `dlltool` assembler executable (`--as`)
+    assembler: Option<PathBuf>,
+    /// MinGW `dlltool` assembler options (`--as-flags`)
+    assembler_flags: Option<String>,
+}
+
+impl DllToolOptions {
+    /// Appends the enabled options to an `llvm-dlltool` compatible command.
+    #[cfg(not(target_os = "wasi"))]
+    fn add_llvm_args(&self, command: &mut Command) {
+        if self.kill_at {
+            command.arg("-k");
+        }
+
+        if self.no_leading_underscore {
+            command.arg("--no-leading-underscore");
+        }
+    }
 }
 
 impl ImportLibraryGenerator {
@@ -201,17 +798,125 @@ impl ImportLibraryGenerator {
     ///
     /// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
     /// is passed in `env`.
+    ///
+    /// The common synonyms used by the other build systems are accepted
+    /// case-insensitively and normalized to the Cargo names, e.g. `amd64`
+    /// and `x64` to `x86_64`, `i686` and `win32` to `x86`, `arm64` to `aarch64`,
+    /// `MSVC` to `msvc`, and `mingw` to `gnu`.
+    ///
+    /// The `gnullvm` env selects the LLVM flavor MinGW targets, e.g.
+    /// `x86_64-pc-windows-gnullvm`, which are handled like the `gnu` ones,
+    /// except that only `llvm-dlltool` is discovered for them.
     #[must_use]
     pub fn new(arch: &str, env: &str) -> Self {
         ImportLibraryGenerator {
-            arch: arch.to_string(),
-            env: env.to_string(),
+            arch: normalize_arch(arch),
+            env: normalize_env(env),
+            gnullvm: env.eq_ignore_ascii_case("gnullvm"),
+            target_os: None,
+            noop_on_non_windows: false,
             version: None,
+            abi3_minimum: None,
             implementation: PythonImplementation::CPython,
             abiflags: None,
+            unknown_version: UnknownVersionPolicy::Error,
+            overwrite: OverwritePolicy::Overwrite,
+            line_ending: LineEnding::Auto,
+            debug: false,
+            options: DllToolOptions::default(),
+            decorate_x86_names: false,
+            exclude_private: false,
+            library_name: None,
+            library_extension: true,
+            library_base: None,
+            def_dir: None,
+            backend: None,
+            require_tool: None,
+            checksum_manifest: false,
+            cmake_script: false,
+            pkg_config_file: false,
+            pyo3_config_file: false,
+            delay_load: false,
+            export_file: false,
+            symbols_file: false,
+            depfile: false,
+            reuse_existing: false,
+            c_header: false,
+            also_stable_abi: false,
+            read_only: false,
+            report_file: false,
+            deny_tool_warnings: false,
+            layout: OutputLayout::Flat,
+            dlltool: None,
+            lib_exe: None,
+            msvc_lib: false,
+            #[cfg(feature = "download")]
+            download_dlltool: None,
+            hermetic: false,
+            cache_dir: None,
+            verbosity: None,
         }
     }
 
+    /// Creates a new import library generator for the specified compile target
+    /// configured from the PyO3 cross-compilation environment variables.
+    ///
+    /// `PYO3_CROSS_PYTHON_VERSION` (e.g. `3.12` or `3.13t`) selects
+    /// the version-specific `pythonXY.dll` import library,
+    /// the Stable ABI `python3.dll` is generated if it is not set.
+    /// `PYO3_CROSS_PYTHON_IMPLEMENTATION` (`CPython` or `PyPy`) selects
+    /// the Python implementation, CPython by default.
+    ///
+    /// Fails if the variables are set to invalid or unsupported values.
+    pub fn from_pyo3_env(arch: &str, env: &str) -> Result<Self> {
+        let mut generator = Self::new(arch, env);
+
+        generator.apply_pyo3_env(|name| env::var(name).ok())?;
+
+        Ok(generator)
+    }
+
+    /// Applies the PyO3 cross-compilation environment variable values
+    /// returned by `var`.
+    fn apply_pyo3_env(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<()> {
+        const VERSION_ENV: &str = "PYO3_CROSS_PYTHON_VERSION";
+        const IMPLEMENTATION_ENV: &str = "PYO3_CROSS_PYTHON_IMPLEMENTATION";
+
+        let implementation = var(IMPLEMENTATION_ENV).filter(|v| !v.is_empty());
+        self.implementation = match implementation.as_deref() {
+            None => PythonImplementation::CPython,
+            Some(v) if v.eq_ignore_ascii_case("cpython") => PythonImplementation::CPython,
+            Some(v) if v.eq_ignore_ascii_case("pypy") => PythonImplementation::PyPy,
+            Some(v) => {
+                let msg = format!("Unsupported {} value '{}'", IMPLEMENTATION_ENV, v);
+                return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::InvalidInput, msg));
+            }
+        };
+
+        let version = match var(VERSION_ENV).filter(|v| !v.is_empty()) {
+            Some(version) => version,
+            None => return Ok(()),
+        };
+
+        let invalid = || {
+            let msg = format!("Invalid {} value '{}'", VERSION_ENV, version);
+            Error::new(ErrorKind::InvalidInput, msg)
+        };
+
+        let (number, abiflags) = match version.strip_suffix('t') {
+            Some(number) => (number, Some("t")),
+            None => (version.as_str(), None),
+        };
+
+        let (major, minor) = number.split_once('.').ok_or_else(invalid)?;
+        let major = major.parse().map_err(|_| invalid())?;
+        let minor = minor.parse().map_err(|_| invalid())?;
+
+        self.version(Some((major, minor))).abiflags(abiflags);
+
+        Ok(())
+    }
+
     /// Sets major and minor version for the `pythonXY.dll` import library.
     ///
     /// The version-agnostic `python3.dll` is generated by default.
@@ -220,6 +925,52 @@ impl ImportLibraryGenerator {
         self
     }
 
+    /// Selects the Python DLL ABI explicitly.
+    ///
+    /// Sets the `version()`, `abiflags()` and `debug()` options at once:
+    ///
+    /// ```no_run
+    /// # use std::path::Path;
+    /// use python3_dll_a::{Abi, ImportLibraryGenerator};
+    ///
+    /// // Generate `python313t_d.lib` in "target/python3-lib"
+    /// ImportLibraryGenerator::new("x86_64", "msvc")
+    ///     .abi(Abi::Versioned {
+    ///         version: (3, 13),
+    ///         debug: true,
+    ///         gil_disabled: true,
+    ///     })
+    ///     .generate(Path::new("target/python3-lib"))
+    ///     .unwrap();
+    /// ```
+    ///
+    /// `Abi::Abi3` with the `minimum` version limits the Stable ABI
+    /// `python3.dll` import library exports to the symbols available
+    /// in that CPython version, so that linking an `abi3-pyXY` extension
+    /// using the newer API fails early. The symbols exported by CPython 3.7,
+    /// the oldest embedded version, are always included.
+    pub fn abi(&mut self, abi: Abi) -> &mut Self {
+        match abi {
+            Abi::Abi3 { minimum } => {
+                self.version = None;
+                self.abi3_minimum = minimum;
+                self.abiflags = None;
+                self.debug = false;
+            }
+            Abi::Versioned {
+                version,
+                debug,
+                gil_disabled,
+            } => {
+                self.version = Some(version);
+                self.abi3_minimum = None;
+                self.abiflags = gil_disabled.then(|| "t".to_owned());
+                self.debug = debug;
+            }
+        }
+        self
+    }
+
     /// Sets the ABI flags for the `pythonXY<abi>.dll` import library.
     ///
     /// For example, `"t"` stands for the free-threaded CPython v3.13 build
@@ -233,368 +984,4104 @@ impl ImportLibraryGenerator {
         self
     }
 
-    /// Sets Python interpreter implementation
-    pub fn implementation(&mut self, implementation: PythonImplementation) -> &mut Self {
-        self.implementation = implementation;
+    /// Selects the CPython debug build DLL, e.g. `python312_d.dll`.
+    ///
+    /// The debug builds export the same symbols as the release ones
+    /// under the DLL and import library names with the `_d` suffix,
+    /// e.g. `python312_d.lib`. PyPy has no debug DLLs.
+    pub fn debug(&mut self, enable: bool) -> &mut Self {
+        self.debug = enable;
         self
     }
 
-    /// Generates the Python DLL import library in `out_dir`.
+    /// Creates a new import library generator for the specified compile target
+    /// configured from the interpreter configuration produced by `pyo3-build-config`.
     ///
-    /// The version-agnostic `python3.dll` import library is generated
-    /// by default unless the version-specific `pythonXY.dll` import
-    /// was requested via `version()`.
-    pub fn generate(&self, out_dir: &Path) -> Result<()> {
-        create_dir_all(out_dir)?;
-
-        let defpath = self.write_def_file(out_dir)?;
-
-        // Try to guess the `dlltool` executable name from the target triple.
-        let dlltool_command = DllToolCommand::find_for_target(&self.arch, &self.env)?;
+    /// `config` is the serialized `InterpreterConfig` text
+    /// (as in `PYO3_CONFIG_FILE`), made of the `key=value` lines.
+    /// The `implementation`, `version`, `abi3` and `build_flags` values select
+    /// the Python implementation, the version-specific or the Stable ABI DLL,
+    /// the CPython debug build (`Py_DEBUG`) and the free-threaded build
+    /// (`Py_GIL_DISABLED`). The other keys are ignored.
+    ///
+    /// With `abi3=true` the `version` is the minimum Stable ABI version,
+    /// as in [`Abi::Abi3`].
+    pub fn from_pyo3_config(arch: &str, env: &str, config: &str) -> Result<Self> {
+        let mut generator = Self::new(arch, env);
 
-        // Get the import library file extension from the used `dlltool` flavor.
-        let implib_ext = dlltool_command.implib_file_ext();
+        let invalid = |line: &str| {
+            let msg = format!("Invalid PyO3 interpreter config line '{}'", line);
+            Error::new(ErrorKind::InvalidData, msg)
+        };
 
-        let implib_file = self.implib_file_path(out_dir, implib_ext);
+        let mut version = None;
+        let mut abi3 = false;
 
-        // Build the complete `dlltool` command with all required arguments.
-        let mut command = dlltool_command.build(&defpath, &implib_file);
+        for line in config.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            let (key, value) = line.split_once('=').ok_or_else(|| invalid(line))?;
 
-        // Run the selected `dlltool` executable to generate the import library.
-        let status = command.status().map_err(|e| {
-            let msg = format!("{:?} failed with {}", command, e);
-            Error::new(e.kind(), msg)
-        })?;
+            match key {
+                "implementation" => {
+                    generator.implementation = match value {
+                        "CPython" => PythonImplementation::CPython,
+                        "PyPy" => PythonImplementation::PyPy,
+                        _ => {
+                            let msg = format!("Unsupported Python implementation '{}'", value);
+                            return Err(
+                                ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg)
+                            );
+                        }
+                    }
+                }
+                "version" => {
+                    let mut parts = value.split('.').map(str::parse);
+                    version = match (parts.next(), parts.next()) {
+                        (Some(Ok(major)), Some(Ok(minor))) => Some((major, minor)),
+                        _ => return Err(invalid(line)),
+                    };
+                }
+                "abi3" => abi3 = value == "true",
+                "build_flags" => {
+                    for flag in value.split(',') {
+                        match flag {
+                            "Py_DEBUG" => generator.debug = true,
+                            "Py_GIL_DISABLED" => generator.abiflags = Some("t".to_owned()),
+                            _ => (),
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
 
-        if status.success() {
-            Ok(())
+        if abi3 {
+            // The `abi3-pyXY` version is the minimum Stable ABI version.
+            let debug = generator.debug;
+            generator.abi(Abi::Abi3 { minimum: version }).debug(debug);
         } else {
-            let msg = format!("{:?} failed with {}", command, status);
-            Err(Error::new(ErrorKind::Other, msg))
+            generator.version(version);
         }
+
+        Ok(generator)
     }
 
-    /// Writes out the embedded Python library definitions file to `out_dir`.
+    /// Enables generating the Stable ABI `python3.dll` import library
+    /// along with the version-specific `pythonXY.dll` one.
     ///
-    /// Returns the newly created `python3.def` or `pythonXY.def` file path.
-    fn write_def_file(&self, out_dir: &Path) -> Result<PathBuf> {
-        let (def_file, def_file_content) = match self.implementation {
-            PythonImplementation::CPython => match self.version {
-                None => ("python3.def", include_str!("python3.def")),
-                Some((3, 7)) => ("python37.def", include_str!("python37.def")),
-                Some((3, 8)) => ("python38.def", include_str!("python38.def")),
-                Some((3, 9)) => ("python39.def", include_str!("python39.def")),
-                Some((3, 10)) => ("python310.def", include_str!("python310.def")),
-                Some((3, 11)) => ("python311.def", include_str!("python311.def")),
-                Some((3, 12)) => ("python312.def", include_str!("python312.def")),
-                Some((3, 13)) => match self.abiflags.as_deref() {
-                    Some("t") => ("python313t.def", include_str!("python313t.def")),
-                    None => ("python313.def", include_str!("python313.def")),
-                    _ => return Err(Error::new(ErrorKind::Other, "Unsupported Python ABI flags")),
-                },
-                _ => return Err(Error::new(ErrorKind::Other, "Unsupported Python version")),
-            },
-            PythonImplementation::PyPy => match self.version {
-                Some((3, 7)) | Some((3, 8)) => ("libpypy3-c.def", include_str!("libpypy3-c.def")),
-                Some((3, 9)) => ("libpypy3.9-c.def", include_str!("libpypy3.9-c.def")),
-                Some((3, 10)) => ("libpypy3.10-c.def", include_str!("libpypy3.10-c.def")),
-                _ => return Err(Error::new(ErrorKind::Other, "Unsupported PyPy version")),
-            },
-        };
-
-        let mut defpath = out_dir.to_owned();
-        defpath.push(def_file);
-
-        write(&defpath, def_file_content)?;
-
-        Ok(defpath)
+    /// Both import libraries, e.g. `python312.lib` and `python3.lib`,
+    /// are written into the same `out_dir`, which suits the projects building
+    /// both the `abi3` and the version-specific extension variants.
+    ///
+    /// This option has no effect for the Stable ABI and the PyPy targets.
+    pub fn also_stable_abi(&mut self, enable: bool) -> &mut Self {
+        self.also_stable_abi = enable;
+        self
     }
 
-    /// Builds the generated import library file name.
+    /// Sets the generated artifacts directory layout.
     ///
-    /// The output file extension is passed in `libext`.
+    /// With [`OutputLayout::PerTarget`] and [`OutputLayout::PerTargetVersion`]
+    /// the artifacts are written into the subdirectories of `out_dir` named
+    /// after the canonical target triple, e.g. `i686-pc-windows-gnu`,
+    /// and the Python version (`abi3`, `3.12`, `3.13t`, `pypy3.10`,
+    /// `3.12_d` for the debug builds), preventing the collisions
+    /// when one build tree generates for multiple targets.
     ///
-    /// Returns the full import library file path under `out_dir`.
-    fn implib_file_path(&self, out_dir: &Path, libext: &str) -> PathBuf {
-        let abiflags = self.abiflags.as_deref().unwrap_or_default();
-        let libname = match self.version {
-            Some((major, minor)) => {
-                format!("python{}{}{}{}", major, minor, abiflags, libext)
-            }
-            None => format!("python3{}", libext),
-        };
-
-        let mut libpath = out_dir.to_owned();
-        libpath.push(libname);
-
-        libpath
+    /// [`OutputLayout::Hashed`] places the artifacts (and the `dlltool`
+    /// temporary files) into a subdirectory named after the hash of all the
+    /// generator settings affecting the artifacts, so that the crates sharing `out_dir` never
+    /// overwrite each other's files even when building concurrently.
+    /// The artifacts are generated in a temporary sibling directory first and
+    /// renamed into place, so the concurrent builds of the same configuration
+    /// never see the partially written files either. The report command lines
+    /// refer to the temporary paths then.
+    /// Use [`output_dir()`](Self::output_dir) or the returned report paths
+    /// to find the generated files.
+    ///
+    /// The artifacts are written directly into `out_dir` by default.
+    /// The Stable ABI import library generated with
+    /// [`also_stable_abi()`](Self::also_stable_abi) is placed next to
+    /// the version-specific one.
+    pub fn layout(&mut self, layout: OutputLayout) -> &mut Self {
+        self.layout = layout;
+        self
     }
-}
-
-/// Generates `python3.dll` import library directly from the embedded
-/// Python Stable ABI definitions data for the specified compile target.
-///
-/// The import library file named `python3.dll.a` or `python3.lib` is created
-/// in directory `out_dir`.
-///
-/// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
-/// is passed in `arch`.
-///
-/// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
-/// is passed in `env`.
-pub fn generate_implib_for_target(out_dir: &Path, arch: &str, env: &str) -> Result<()> {
-    ImportLibraryGenerator::new(arch, env).generate(out_dir)
-}
 
-/// `dlltool` utility command builder
-///
-/// Supports Visual Studio `lib.exe`, MinGW, LLVM and Zig `dlltool` flavors.
-#[derive(Debug)]
-enum DllToolCommand {
-    /// MinGW `dlltool` program (with prefix)
-    Mingw { command: Command },
-    /// LLVM `llvm-dlltool` program (no prefix)
-    Llvm { command: Command, machine: String },
-    /// MSVC `lib.exe` program (no prefix)
-    LibExe { command: Command, machine: String },
-    /// `zig dlltool` wrapper (no prefix)
-    Zig { command: Command, machine: String },
-}
-
-impl DllToolCommand {
-    /// Attempts to find the best matching `dlltool` flavor for the target.
-    fn find_for_target(arch: &str, env: &str) -> Result<DllToolCommand> {
-        // LLVM tools use their own target architecture names...
-        let machine = match arch {
-            "x86_64" => "i386:x86-64",
-            "x86" => "i386",
-            "aarch64" => "arm64",
-            arch => arch,
+    /// Returns the directory within `out_dir` the artifacts are written into
+    /// with the configured [`layout()`](Self::layout).
+    pub fn output_dir(&self, out_dir: &Path) -> PathBuf {
+        match self.layout {
+            OutputLayout::Flat => out_dir.to_owned(),
+            OutputLayout::PerTarget => {
+                let dir = out_dir.join(self.target_triple());
+                match self.implementation {
+                    PythonImplementation::CPython => dir,
+                    PythonImplementation::PyPy => dir.join("pypy"),
+                }
+            }
+            OutputLayout::PerTargetVersion => out_dir
+                .join(self.target_triple())
+                .join(self.version_label()),
+            OutputLayout::Hashed => out_dir.join(self.config_hash()),
         }
-        .to_owned();
+    }
 
-        // If `zig cc` is used as the linker, `zig dlltool` is the best choice.
-        if let Some(command) = find_zig() {
-            return Ok(DllToolCommand::Zig { command, machine });
+    /// Returns the first 16 hex digits of the generator configuration hash.
+    ///
+    /// Only the settings affecting the written artifacts are hashed,
+    /// the diagnostic and the overwrite handling settings are excluded.
+    fn config_hash(&self) -> String {
+        let mut key = format!("python3-dll-a {}\n", env!("CARGO_PKG_VERSION"));
+        for (name, value) in self.hashed_settings() {
+            key.push_str(&format!("{} {:?}\n", name, value));
         }
 
-        match env {
-            // 64-bit and 32-bit MinGW-w64 (aka `{x86_64,i686}-pc-windows-gnu`)
-            "gnu" => Ok(DllToolCommand::Mingw {
-                command: get_mingw_dlltool(arch)?,
-            }),
+        let mut hash = sha256::sha256_hex(key.as_bytes());
+        hash.truncate(16);
 
-            // MSVC ABI (multiarch)
-            "msvc" => {
-                if let Some(command) = find_lib_exe(arch) {
-                    // MSVC tools use their own target architecture names...
-                    let machine = match arch {
-                        "x86_64" => "X64",
-                        "x86" => "X86",
-                        "aarch64" => "ARM64",
-                        arch => arch,
-                    }
-                    .to_owned();
+        hash
+    }
 
-                    Ok(DllToolCommand::LibExe { command, machine })
-                } else {
-                    let command = Command::new(DLLTOOL_MSVC);
+    /// Returns the names and the values of the settings affecting
+    /// the written artifacts.
+    fn hashed_settings(&self) -> Vec<(&'static str, &dyn fmt::Debug)> {
+        vec![
+            ("arch", &self.arch),
+            ("env", &self.env),
+            ("gnullvm", &self.gnullvm),
+            ("target_os", &self.target_os),
+            ("version", &self.version),
+            ("abi3_minimum", &self.abi3_minimum),
+            ("implementation", &self.implementation),
+            ("abiflags", &self.abiflags),
+            ("unknown_version", &self.unknown_version),
+            ("line_ending", &self.line_ending),
+            ("debug", &self.debug),
+            ("options", &self.options),
+            ("decorate_x86_names", &self.decorate_x86_names),
+            ("exclude_private", &self.exclude_private),
+            ("library_name", &self.library_name),
+            ("library_extension", &self.library_extension),
+            ("library_base", &self.library_base),
+            ("def_dir", &self.def_dir),
+            ("backend", &self.backend),
+            ("require_tool", &self.require_tool),
+            ("checksum_manifest", &self.checksum_manifest),
+            ("cmake_script", &self.cmake_script),
+            ("pkg_config_file", &self.pkg_config_file),
+            ("pyo3_config_file", &self.pyo3_config_file),
+            ("delay_load", &self.delay_load),
+            ("export_file", &self.export_file),
+            ("symbols_file", &self.symbols_file),
+            ("depfile", &self.depfile),
+            ("c_header", &self.c_header),
+            ("also_stable_abi", &self.also_stable_abi),
+            ("read_only", &self.read_only),
+            ("report_file", &self.report_file),
+            ("dlltool", &self.dlltool),
+            ("lib_exe", &self.lib_exe),
+            ("msvc_lib", &self.msvc_lib),
+            ("hermetic", &self.hermetic),
+            #[cfg(feature = "download")]
+            ("download_dlltool", &self.download_dlltool),
+        ]
+    }
 
-                    Ok(DllToolCommand::Llvm { command, machine })
-                }
-            }
-            _ => {
-                let msg = format!("Unsupported target env ABI '{}'", env);
-                Err(Error::new(ErrorKind::Other, msg))
-            }
-        }
+    /// Returns the canonical Windows target triple for the configured
+    /// target architecture and environment ABI.
+    fn target_triple(&self) -> String {
+        let arch = match self.arch.as_str() {
+            "x86" => "i686",
+            "arm" => "thumbv7a",
+            arch => arch,
+        };
+
+        // There is no AArch64 MinGW target other than the LLVM one.
+        let env = match (self.arch.as_str(), self.env_name()) {
+            ("aarch64", "gnu") => "gnullvm",
+            (_, env) => env,
+        };
+
+        format!("{}-pc-windows-{}", arch, env)
     }
 
-    /// Returns the import library file extension used by
-    /// this `dlltool` flavor.
-    fn implib_file_ext(&self) -> &'static str {
-        if let DllToolCommand::Mingw { .. } = self {
-            IMPLIB_EXT_GNU
+    /// Returns the target environment ABI name, `gnullvm` for the LLVM
+    /// flavor MinGW targets.
+    fn env_name(&self) -> &str {
+        if self.gnullvm && self.env == "gnu" {
+            "gnullvm"
         } else {
-            IMPLIB_EXT_MSVC
+            &self.env
         }
     }
 
-    /// Generates the complete `dlltool` executable invocation command.
-    fn build(self, defpath: &Path, libpath: &Path) -> Command {
-        match self {
-            Self::Mingw { mut command } => {
-                command
-                    .arg("--input-def")
-                    .arg(defpath)
-                    .arg("--output-lib")
-                    .arg(libpath);
-
-                command
-            }
-            Self::Llvm {
-                mut command,
-                machine,
-            } => {
-                command
-                    .arg("-m")
-                    .arg(machine)
-                    .arg("-d")
-                    .arg(defpath)
-                    .arg("-l")
-                    .arg(libpath);
-
-                command
-            }
-            Self::LibExe {
-                mut command,
-                machine,
-            } => {
-                command
-                    .arg(format!("/MACHINE:{}", machine))
-                    .arg(format!("/DEF:{}", defpath.display()))
-                    .arg(format!("/OUT:{}", libpath.display()));
-
-                command
+    /// Returns the Python version directory name, e.g. `abi3`, `3.13t` or `pypy3.10`.
+    fn version_label(&self) -> String {
+        let mut label = match (self.implementation, self.version) {
+            (PythonImplementation::CPython, None) => "abi3".to_owned(),
+            (PythonImplementation::CPython, Some((major, minor))) => format!(
+                "{}.{}{}",
+                major,
+                minor,
+                self.abiflags.as_deref().unwrap_or_default()
+            ),
+            (PythonImplementation::PyPy, Some((major, minor))) => {
+                format!("pypy{}.{}", major, minor)
             }
-            Self::Zig {
-                mut command,
-                machine,
-            } => {
-                // Same as `llvm-dlltool`, but invoked as `zig dlltool`.
-                command
-                    .arg("dlltool")
-                    .arg("-m")
-                    .arg(machine)
-                    .arg("-d")
-                    .arg(defpath)
-                    .arg("-l")
-                    .arg(libpath);
+            (PythonImplementation::PyPy, None) => "pypy".to_owned(),
+        };
 
-                command
-            }
+        if self.debug {
+            label.push_str("_d");
         }
+
+        label
     }
-}
 
-/// Chooses the appropriate MinGW-w64 `dlltool` executable
-/// for the target architecture.
-///
-/// Examines the user-provided `PYO3_MINGW_DLLTOOL` environment variable first
-/// and falls back to the default MinGW-w64 arch prefixes.
-fn get_mingw_dlltool(arch: &str) -> Result<Command> {
-    if let Ok(user_dlltool) = env::var("PYO3_MINGW_DLLTOOL") {
-        Ok(Command::new(user_dlltool))
-    } else {
-        let prefix_dlltool = match arch {
-            // 64-bit MinGW-w64 (aka `x86_64-pc-windows-gnu`)
-            "x86_64" => Ok(DLLTOOL_GNU),
-            // 32-bit MinGW-w64 (aka `i686-pc-windows-gnu`)
-            "x86" => Ok(DLLTOOL_GNU_32),
-            // AArch64?
-            _ => {
-                let msg = format!("Unsupported MinGW target arch '{}'", arch);
-                Err(Error::new(ErrorKind::Other, msg))
-            }
-        }?;
+    /// Sets the compile target OS name (as in `CARGO_CFG_TARGET_OS`).
+    ///
+    /// `generate()` fails with [`ErrorCategory::NotAWindowsTarget`]
+    /// for the targets other than `windows`. If not set, the `CARGO_CFG_TARGET_OS`
+    /// environment variable value is checked (outside of the hermetic mode),
+    /// so the build scripts get the dedicated error instead of a `dlltool`
+    /// failure. Set it to `Some("windows")` explicitly to generate
+    /// the Windows import libraries from a non-Windows target build script.
+    pub fn target_os(&mut self, os: Option<&str>) -> &mut Self {
+        self.target_os = os.map(ToOwned::to_owned);
+        self
+    }
 
-        Ok(Command::new(prefix_dlltool))
+    /// Makes `generate()` a no-op for the non-Windows compile targets.
+    ///
+    /// The build scripts can then call the generator unconditionally.
+    /// The target OS is checked as described in [`target_os()`](Self::target_os).
+    /// [`generate_all()`](Self::generate_all) returns no reports then, and
+    /// [`generate_with_report()`](Self::generate_with_report) still fails
+    /// with [`ErrorCategory::NotAWindowsTarget`], as there is no report to return.
+    pub fn noop_on_non_windows(&mut self, enable: bool) -> &mut Self {
+        self.noop_on_non_windows = enable;
+        self
     }
-}
 
-/// Finds the `zig` executable (when built by `maturin --zig`).
-///
-/// Examines the `ZIG_COMMAND` environment variable
-/// to find out if `zig cc` is being used as the linker.
-fn find_zig() -> Option<Command> {
-    // `ZIG_COMMAND` may contain simply `zig` or `/usr/bin/zig`,
-    // or a more complex construct like `python3 -m ziglang`.
-    let zig_command = env::var("ZIG_COMMAND").ok()?;
+    /// Sets the handling of the Python versions without the embedded definitions.
+    ///
+    /// A new CPython release appearing before the crate update fails
+    /// the generation with [`ErrorCategory::UnsupportedVersion`] by default.
+    /// `UnknownVersionPolicy::UseLatest` generates the requested `pythonXY.dll`
+    /// import library from the latest embedded definitions instead, missing
+    /// the symbols added in the new release. `UnknownVersionPolicy::StableAbi`
+    /// makes `generate()` write the Stable ABI `python3.dll` import library
+    /// instead. Both add a warning to [`GenerationReport::warnings`],
+    /// which `run_build_script()` prints as a `cargo:warning=` line.
+    ///
+    /// The versions known to the crate, but disabled by the crate features,
+    /// and the versions with the `def_dir()` replacement definitions
+    /// are not affected.
+    pub fn unknown_version(&mut self, policy: UnknownVersionPolicy) -> &mut Self {
+        self.unknown_version = policy;
+        self
+    }
 
-    // Try to emulate `sh -c ${ZIG_COMMAND}`.
-    let mut zig_cmdlet = zig_command.split_ascii_whitespace();
+    /// Sets the handling of the import library already present at the output path.
+    ///
+    /// The existing import library file is replaced by default.
+    /// `OverwritePolicy::SkipIfExists` keeps it and reports it with
+    /// the `existing` tool name, still writing the `.def` file and the other
+    /// requested artifacts next to it. `OverwritePolicy::ErrorIfExists` fails
+    /// the generation with [`std::io::ErrorKind::AlreadyExists`] instead,
+    /// so that a hand-placed or vendor-provided import library is never
+    /// clobbered silently.
+    ///
+    /// The policy applies to the import libraries written by the previous
+    /// runs as well, see [`ImportLibraryGenerator::reuse_existing()`]
+    /// for keeping only the genuine ones.
+    pub fn overwrite(&mut self, policy: OverwritePolicy) -> &mut Self {
+        self.overwrite = policy;
+        self
+    }
 
-    // Extract the main program component (e.g. `zig` or `python3`).
-    let mut zig = Command::new(zig_cmdlet.next()?);
+    /// Sets the line terminators of the written `.def` files.
+    ///
+    /// `LineEnding::Auto` (default) writes the `CRLF` line endings for
+    /// `lib.exe`, as some older Windows tools misparse the `LF`-only files,
+    /// and the `LF` line endings for the MinGW and LLVM tools and the `.def`
+    /// files written for the direct linking.
+    pub fn line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+        self.line_ending = line_ending;
+        self
+    }
 
-    // Append the rest of the commandlet.
-    zig.args(zig_cmdlet);
+    /// Sets Python interpreter implementation
+    pub fn implementation(&mut self, implementation: PythonImplementation) -> &mut Self {
+        self.implementation = implementation;
+        self
+    }
+
+    /// Enables the `dlltool` `--kill-at` (`-k`) option.
+    ///
+    /// Strips the stdcall `@<n>` suffixes from the symbol names
+    /// exported by the generated import library.
+    ///
+    /// This option is passed to the MinGW, LLVM and Zig `dlltool` flavors
+    /// and is ignored by `lib.exe`.
+    pub fn kill_at(&mut self, enable: bool) -> &mut Self {
+        self.options.kill_at = enable;
+        self
+    }
+
+    /// Enables the `dlltool` `--no-leading-underscore` option.
+    ///
+    /// Prevents `dlltool` from prepending the underscore to the symbol names
+    /// for the 32-bit x86 targets, which is useful when the object files
+    /// being linked reference the undecorated Python API names.
+    ///
+    /// This option is passed to the MinGW, LLVM and Zig `dlltool` flavors
+    /// and is ignored by `lib.exe`.
+    /// Note that `llvm-dlltool` supports it since LLVM 16.
+    pub fn no_leading_underscore(&mut self, enable: bool) -> &mut Self {
+        self.options.no_leading_underscore = enable;
+        self
+    }
+
+    /// Sets the MinGW `dlltool` `--temp-prefix` option value.
+    ///
+    /// The MinGW `dlltool` writes and then removes the intermediate assembly
+    /// and object files named `<prefix>_*`, which land in the current
+    /// working directory by default and fail the generation when that
+    /// directory is not writable, e.g. in the sandboxed builds.
+    ///
+    /// If not set, the prefix points into the output directory,
+    /// e.g. `<out_dir>/python3-dlltool`. `llvm-dlltool`, `zig dlltool`
+    /// and `lib.exe` do not write the intermediate files.
+    pub fn temp_prefix(&mut self, prefix: Option<&Path>) -> &mut Self {
+        self.options.temp_prefix = prefix.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Sets the assembler executable used by the MinGW `dlltool` (`--as`).
+    ///
+    /// The MinGW `dlltool` runs `as` from `PATH` to assemble the import
+    /// library members, which is not necessarily the right cross-assembler
+    /// on the hosts with several toolchains installed,
+    /// e.g. `/usr/bin/x86_64-w64-mingw32-as` can be pinned instead.
+    ///
+    /// The other `dlltool` flavors and `lib.exe` do not run an assembler,
+    /// so this option is ignored for them.
+    pub fn assembler(&mut self, assembler: Option<&Path>) -> &mut Self {
+        self.options.assembler = assembler.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Sets the extra options passed to the MinGW `dlltool` assembler
+    /// (`--as-flags`), e.g. `--32`.
+    ///
+    /// This option is ignored by the other `dlltool` flavors and `lib.exe`.
+    pub fn assembler_flags(&mut self, flags: Option<&str>) -> &mut Self {
+        self.options.assembler_flags = flags.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Enables the explicit 32-bit x86 symbol name decoration mode.
+    ///
+    /// The C symbol names on the 32-bit x86 targets are decorated
+    /// with a leading underscore, which is normally added by `dlltool`.
+    /// Unfortunately, the binutils `dlltool` default depends on the target
+    /// it was configured for, so e.g. a multi-target or an x86-64 `dlltool`
+    /// may produce undecorated names for the `x86` architecture.
+    ///
+    /// When enabled for the `x86` target architecture, the MinGW `dlltool`
+    /// flavor receives a module definition file with the pre-decorated
+    /// `_name == name` exports and the `--no-leading-underscore` option.
+    /// The LLVM, Zig and `lib.exe` flavors always decorate the 32-bit x86
+    /// symbol names themselves, so the definitions are passed to them as is.
+    ///
+    /// This option has no effect for the other target architectures.
+    pub fn decorate_x86_names(&mut self, enable: bool) -> &mut Self {
+        self.decorate_x86_names = enable;
+        self
+    }
+
+    /// Enables omitting the private symbols from the generated import library.
+    ///
+    /// The exported names with a leading underscore (e.g. `_PyObject_GC_New`
+    /// or `_Py_Dealloc`) are internal CPython and PyPy implementation details
+    /// not covered by the documented API, so linking fails if the extension
+    /// uses them.
+    ///
+    /// Note that some of the limited API macros expand to the ABI-only `_Py*`
+    /// symbols listed in `python3.def`, e.g. `Py_DECREF()` to `_Py_Dealloc()`
+    /// in the older limited API versions, so the extensions using them
+    /// fail to link as well.
+    pub fn exclude_private(&mut self, enable: bool) -> &mut Self {
+        self.exclude_private = enable;
+        self
+    }
+
+    /// Sets the DLL name written into the `LIBRARY` statement of the `.def`
+    /// file, e.g. for the custom Python distributions with renamed DLLs.
+    ///
+    /// The generated import library binds to this DLL name. The file names
+    /// of the generated artifacts are not affected. The Stable ABI library
+    /// added by [`also_stable_abi()`](Self::also_stable_abi) keeps its name.
+    pub fn library_name(&mut self, name: Option<&str>) -> &mut Self {
+        self.library_name = name.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Controls the `.dll` extension of the `LIBRARY` statement DLL name.
+    ///
+    /// The `.dll` extension is written by default. Some toolchains expect
+    /// the bare DLL name, which the linkers complete with `.dll` anyway.
+    pub fn library_extension(&mut self, enable: bool) -> &mut Self {
+        self.library_extension = enable;
+        self
+    }
+
+    /// Sets the `BASE` image base address attribute of the `LIBRARY`
+    /// statement, e.g. `LIBRARY "python3.dll" BASE=503316480`.
+    ///
+    /// Not written by default.
+    pub fn library_base(&mut self, base: Option<u64>) -> &mut Self {
+        self.library_base = base;
+        self
+    }
+
+    /// Sets the directory containing the replacement `.def` files.
+    ///
+    /// The Module-Definition files found in this directory take precedence
+    /// over the embedded Python ABI definitions data. The files must be named
+    /// like the embedded ones, e.g. `python3.def`, `python313t.def`
+    /// or `libpypy3.10-c.def`. The embedded data is used as a fallback
+    /// when the directory does not contain the required file.
+    ///
+    /// The JSON or TOML symbol lists named like the `.def` files, e.g.
+    /// `python3.json`, are used if there is no `.def` file,
+    /// see [`DefFile::from_path()`] for the format.
+    ///
+    /// This also enables generating import libraries for the Python versions
+    /// not yet supported by the embedded data.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_DEF_DIR` environment variable value
+    /// is used instead.
+    pub fn def_dir(&mut self, def_dir: Option<&Path>) -> &mut Self {
+        self.def_dir = def_dir.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Sets the import library generation backend.
+    ///
+    /// The external `dlltool` program is used by default.
+    ///
+    /// `Backend::Builtin` (requires the `builtin` crate feature)
+    /// writes the import library without running any external programs,
+    /// which is useful in the sandboxed build environments.
+    ///
+    /// `Backend::Mock` (requires the `test-support` crate feature)
+    /// writes a placeholder import library for the downstream test suites.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_BACKEND` environment variable value
+    /// (`dlltool`, `builtin` or `mock`) is used instead.
+    pub fn backend(&mut self, backend: Backend) -> &mut Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Enables the strict tool selection mode requiring the `flavor` tool.
+    ///
+    /// The tool discovery looks for the required tool flavor only
+    /// instead of falling back to the other ones, e.g. `zig dlltool`
+    /// is not used in place of `lib.exe` when `ZIG_COMMAND` is set, and
+    /// the generation fails with [`ErrorCategory::ToolMissing`] if the tool
+    /// is not found. The explicitly set [`dlltool()`](Self::dlltool) must be
+    /// of the required flavor, and `Backend::Builtin` can not be used.
+    ///
+    /// The tool is discovered automatically by default.
+    pub fn require_tool(&mut self, flavor: Option<ToolFlavor>) -> &mut Self {
+        self.require_tool = flavor;
+        self
+    }
+
+    /// Sets the explicit `dlltool` executable path, skipping the tool discovery.
+    ///
+    /// For the `gnu` target environment the tool is run as the MinGW `dlltool`,
+    /// or as `llvm-dlltool` if named so.
+    /// For the `msvc` target environment the tool is run as `lib.exe`
+    /// if named `lib` or `llvm-lib`, and as `llvm-dlltool` otherwise.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_DLLTOOL` environment variable value
+    /// is used instead.
+    pub fn dlltool(&mut self, dlltool: Option<&Path>) -> &mut Self {
+        self.dlltool = dlltool.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Sets the exact Visual Studio `lib.exe` executable path to use
+    /// for the `msvc` targets instead of the registry and `PATH` lookup.
+    ///
+    /// This is useful with the custom Visual Studio layouts, the toolchains
+    /// copied into containers, or when the registry lookup picks the wrong
+    /// one of several installed Visual Studio versions.
+    /// The tool is always run as `lib.exe`, whatever its name is.
+    /// [`dlltool()`](Self::dlltool) takes precedence over this setting.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_LIB_EXE` environment variable value
+    /// is used instead.
+    pub fn lib_exe(&mut self, lib_exe: Option<&Path>) -> &mut Self {
+        self.lib_exe = lib_exe.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Enables writing the MSVC-format `.lib` import library
+    /// for the `gnu` target environment.
+    ///
+    /// `rust-lld` and the other GNU-compatible LLVM linkers accept
+    /// the short import `.lib` archives, so the MinGW binutils are not needed:
+    /// the import library is generated with `lib.exe` or `llvm-dlltool`
+    /// as for the `msvc` targets and named e.g. `python3.lib`.
+    ///
+    /// Has no effect for the `msvc` target environment.
+    pub fn msvc_lib(&mut self, enable: bool) -> &mut Self {
+        self.msvc_lib = enable;
+        self
+    }
+
+    /// Sets the pinned `llvm-dlltool` download used when the tool discovery
+    /// finds neither `llvm-dlltool` nor the MinGW `dlltool` installed.
+    ///
+    /// The downloaded executable is verified and cached in the `tools`
+    /// subdirectory of the [`cache_dir()`](Self::cache_dir) or of the user
+    /// cache directory. The hermetic mode never downloads the tool.
+    ///
+    /// Requires the `download` crate feature.
+    #[cfg(feature = "download")]
+    pub fn download_dlltool(&mut self, tool: Option<download::PinnedTool>) -> &mut Self {
+        self.download_dlltool = tool;
+        self
+    }
+
+    /// Enables the hermetic mode for the sandboxed build systems
+    /// like Bazel and Buck2.
+    ///
+    /// In the hermetic mode no `PATH`, registry or `ZIG_COMMAND` probing is done.
+    /// The `dlltool` executable must be set to an absolute path
+    /// with [`dlltool()`](Self::dlltool) (or `PYO3_MINGW_DLLTOOL`
+    /// for the `gnu` targets, or [`lib_exe()`](Self::lib_exe) for the `msvc`
+    /// targets), unless `Backend::Builtin` is used.
+    ///
+    /// The `PYTHON3_DLL_A_*` environment variables are ignored,
+    /// so all the inputs are declared via the generator methods,
+    /// and the outputs are written only to the `out_dir` passed to `generate()`.
+    pub fn hermetic(&mut self, enable: bool) -> &mut Self {
+        self.hermetic = enable;
+        self
+    }
+
+    /// Sets the generated import library cache directory.
+    ///
+    /// The import libraries produced by `dlltool` are copied into a cache
+    /// subdirectory keyed by the SHA-256 hash of the tool name,
+    /// the target and the Module-Definition file contents, and are reused
+    /// from there instead of running the tool again.
+    /// The cache is not used when the export file is retained.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_CACHE_DIR` environment variable value
+    /// is used instead.
+    pub fn cache_dir(&mut self, cache_dir: Option<&Path>) -> &mut Self {
+        self.cache_dir = cache_dir.map(ToOwned::to_owned);
+        self
+    }
+
+    /// Sets the diagnostic output verbosity level.
+    ///
+    /// - `0` suppresses the `dlltool` messages,
+    /// - `1` passes the `dlltool` messages through to stderr (default),
+    /// - `2` also prints the executed commands and the cache hits.
+    ///
+    /// If not set, the `PYTHON3_DLL_A_VERBOSITY` environment variable value
+    /// is used instead.
+    pub fn verbosity(&mut self, level: u8) -> &mut Self {
+        self.verbosity = Some(level);
+        self
+    }
+
+    /// Enables writing the SHA-256 checksum manifest for the import library.
+    ///
+    /// The manifest file is named after the generated import library
+    /// with the `.sha256` extension appended, e.g. `python3.lib.sha256`,
+    /// and uses the `sha256sum` output format.
+    ///
+    /// The import library can be checked against the manifest later
+    /// with [`verify_checksum()`].
+    pub fn checksum_manifest(&mut self, enable: bool) -> &mut Self {
+        self.checksum_manifest = enable;
+        self
+    }
+
+    /// Enables writing the CMake script defining an `IMPORTED` library target
+    /// for the generated import library.
+    ///
+    /// The script is named after the import library with the `.cmake` extension,
+    /// e.g. `python3.cmake`, and defines the `python3-dll-a::python3` target,
+    /// which C/C++ embedders can link to after an `include()` of the script.
+    pub fn cmake_script(&mut self, enable: bool) -> &mut Self {
+        self.cmake_script = enable;
+        self
+    }
+
+    /// Enables writing the pkg-config file describing the generated import library.
+    ///
+    /// The file is named `python3-dll-a-<name>.pc` after the import library,
+    /// e.g. `python3-dll-a-python3.pc`, so that Meson can find the import library
+    /// with `dependency('python3-dll-a-python3')` when `out_dir` is listed
+    /// in the `pkg_config_path` cross file property or in `PKG_CONFIG_PATH`.
+    pub fn pkg_config_file(&mut self, enable: bool) -> &mut Self {
+        self.pkg_config_file = enable;
+        self
+    }
+
+    /// Enables writing the PyO3 interpreter config file for the generated
+    /// import library, see [`pyo3_config()`](Self::pyo3_config).
+    ///
+    /// The file is named after the import library with the `.pyo3-config.txt`
+    /// extension, e.g. `python312.pyo3-config.txt`, and is used as
+    /// the `PYO3_CONFIG_FILE` value for the PyO3 cross builds.
+    pub fn pyo3_config_file(&mut self, enable: bool) -> &mut Self {
+        self.pyo3_config_file = enable;
+        self
+    }
+
+    /// Enables writing the MSVC linker response file delay-loading the Python DLL.
+    ///
+    /// The MSVC import libraries work for the delay-loaded DLLs as is,
+    /// but the linker needs the `/DELAYLOAD:<dll>` option and the `delayimp.lib`
+    /// helper library. The response file passing both is named after
+    /// the import library with the `.delayload.rsp` extension,
+    /// e.g. `python3.delayload.rsp`, and is used as `link.exe @python3.delayload.rsp`
+    /// or `cargo:rustc-link-arg=@<path>` in the build scripts.
+    ///
+    /// Only the `msvc` target environment supports delay loading.
+    pub fn delay_load(&mut self, enable: bool) -> &mut Self {
+        self.delay_load = enable;
+        self
+    }
+
+    /// Enables retaining the `.exp` export file written by `lib.exe`.
+    ///
+    /// `lib.exe` writes the export file next to the import library
+    /// with the `.exp` extension, e.g. `python3.exp`. It is removed
+    /// after the generation by default.
+    ///
+    /// Only `lib.exe` writes the export files, so the other `dlltool`
+    /// flavors and `Backend::Builtin` fail with [`ErrorCategory::ToolMissing`]
+    /// when this option is enabled.
+    pub fn export_file(&mut self, enable: bool) -> &mut Self {
+        self.export_file = enable;
+        self
+    }
+
+    /// Enables writing the plain text list of the exported symbols.
+    ///
+    /// The file is named after the import library with the `.symbols.txt`
+    /// extension, e.g. `python3.symbols.txt`, and lists one symbol name
+    /// per line for the custom linker scripts and the non-Rust link tooling.
+    pub fn symbols_file(&mut self, enable: bool) -> &mut Self {
+        self.symbols_file = enable;
+        self
+    }
+
+    /// Enables writing the Make/Ninja depfile listing the import library inputs.
+    ///
+    /// The depfile is named after the import library with the `.d` extension
+    /// appended, e.g. `python3.lib.d`, and lists the replacement `.def` file
+    /// (see [`def_dir()`](Self::def_dir)) and the `dlltool` executable
+    /// the import library was generated with, so that the non-cargo build
+    /// systems rebuild it when any of them changes.
+    pub fn depfile(&mut self, enable: bool) -> &mut Self {
+        self.depfile = enable;
+        self
+    }
+
+    /// Enables writing the C header listing the exported symbols.
+    ///
+    /// The header is named after the import library with the `.exports.h`
+    /// extension, e.g. `python3.exports.h`. It defines the DLL name string
+    /// and an X-macro invoking `F(name)` for every exported function
+    /// and `D(name)` for every exported data symbol, e.g.
+    /// `PYTHON3_DLL_A_PYTHON3_EXPORTS(F, D)`.
+    pub fn c_header(&mut self, enable: bool) -> &mut Self {
+        self.c_header = enable;
+        self
+    }
+
+    /// Enables marking the generated artifacts read-only.
+    ///
+    /// The import library, the `.def` file and the other enabled output
+    /// files are write-protected after the generation, so that the later
+    /// build steps can not accidentally overwrite the cached artifacts.
+    ///
+    /// The read-only flag of the previously generated artifacts is always
+    /// cleared before the regeneration.
+    pub fn read_only(&mut self, enable: bool) -> &mut Self {
+        self.read_only = enable;
+        self
+    }
+
+    /// Enables reusing a genuine import library already present in `out_dir`.
+    ///
+    /// If the output directory, e.g. `PYO3_CROSS_LIB_DIR`, already contains
+    /// the `python3.lib` or `pythonXY.lib` import library copied from
+    /// a Windows Python installation, it is reused as is instead of generating
+    /// a new one, avoiding the mismatches with the actual interpreter.
+    /// The import libraries written by this crate (with the `.stamp` marker
+    /// file next to them) are regenerated as usual.
+    ///
+    /// The existing import library must be for the target architecture
+    /// and import all the expected symbols from the expected DLL,
+    /// otherwise the generation fails instead of overwriting it.
+    /// The reused import library is reported with the `existing` tool name.
+    pub fn reuse_existing(&mut self, enable: bool) -> &mut Self {
+        self.reuse_existing = enable;
+        self
+    }
+
+    /// Enables writing the JSON provenance report for the import library.
+    ///
+    /// The report is named after the import library with the `.report.json`
+    /// extension appended, e.g. `python3.lib.report.json`, and records
+    /// the tool command line and version, and the SHA-256 hashes of the `.def`
+    /// file and the import library, so that the packaging steps can archive
+    /// the provenance alongside the wheel.
+    pub fn report_file(&mut self, enable: bool) -> &mut Self {
+        self.report_file = enable;
+        self
+    }
+
+    /// Enables escalating the import library generation tool warnings to errors.
+    ///
+    /// The warnings, e.g. the `lib.exe` `LNK4272` machine type mismatch,
+    /// are always collected in [`GenerationReport::warnings`]. With this
+    /// option enabled, `generate()` fails with [`ErrorCategory::ToolFailed`]
+    /// listing the warnings instead.
+    pub fn deny_tool_warnings(&mut self, enable: bool) -> &mut Self {
+        self.deny_tool_warnings = enable;
+        self
+    }
+
+    /// Generates the Python DLL import library in `out_dir`.
+    ///
+    /// The version-agnostic `python3.dll` import library is generated
+    /// by default unless the version-specific `pythonXY.dll` import
+    /// was requested via `version()`.
+    ///
+    /// The `dlltool` output is checked to be a well-formed import library
+    /// for the target architecture containing all the expected symbols.
+    pub fn generate(&self, out_dir: &Path) -> Result<()> {
+        self.generate_all(out_dir).map(|_| ())
+    }
+
+    /// Generates the Python DLL import library in `out_dir`
+    /// like `generate()` does.
+    ///
+    /// Returns the generation report describing the produced artifacts.
+    /// The report of the Stable ABI import library generated with
+    /// [`also_stable_abi()`](Self::also_stable_abi) is returned by
+    /// [`generate_all()`](Self::generate_all) only.
+    pub fn generate_with_report(&self, out_dir: &Path) -> Result<GenerationReport> {
+        let mut reports = self.generate_all(out_dir)?;
+
+        if reports.is_empty() {
+            // Skipped by `noop_on_non_windows()`.
+            let os = self.resolved_target_os().unwrap_or_default();
+            return Err(not_a_windows_target(&os));
+        }
+
+        Ok(reports.remove(0))
+    }
+
+    /// Generates the Python DLL import library in `out_dir`, followed
+    /// by the Stable ABI import library if [`also_stable_abi()`](Self::also_stable_abi)
+    /// is enabled.
+    ///
+    /// Returns the generation reports for all the import libraries.
+    pub fn generate_all(&self, out_dir: &Path) -> Result<Vec<GenerationReport>> {
+        match self.resolved_target_os() {
+            Some(os) if os != "windows" && self.noop_on_non_windows => return Ok(Vec::new()),
+            Some(os) if os != "windows" => return Err(not_a_windows_target(&os)),
+            _ => {}
+        }
+
+        if let Some(stable_abi) = self.unknown_version_stable_abi()? {
+            let (major, minor) = self.version.unwrap_or_default();
+            let warning = format!(
+                "Unsupported Python version {}.{}, \
+                 generating the Stable ABI python3.dll import library instead",
+                major, minor
+            );
+
+            let mut reports = stable_abi.generate_all(out_dir)?;
+            if let Some(report) = reports.first_mut() {
+                report.warnings.insert(0, warning);
+            }
+
+            return Ok(reports);
+        }
+
+        let out_dir = self.output_dir(out_dir);
+
+        // The kept artifacts of the existing configuration directory
+        // are looked up in place.
+        let keep_existing = self.reuse_existing || self.overwrite != OverwritePolicy::Overwrite;
+        if self.layout != OutputLayout::Hashed || (keep_existing && out_dir.is_dir()) {
+            return self.generate_reports(&out_dir, &out_dir);
+        }
+
+        // The identical concurrent configurations must not write
+        // into the same files, stage them in a unique sibling directory.
+        let staging = staging_path(&out_dir);
+        let result = self
+            .generate_reports(&staging, &out_dir)
+            .and_then(|reports| install_staged(&staging, &out_dir).map(|()| reports));
+        let _ = remove_dir_all(&staging);
+
+        result
+    }
+
+    /// Generates the import libraries in `out_dir` for installing them into `dest`.
+    ///
+    /// The returned reports and the written artifacts refer to the `dest` paths.
+    fn generate_reports(&self, out_dir: &Path, dest: &Path) -> Result<Vec<GenerationReport>> {
+        let mut reports = vec![self.generate_one(out_dir, dest)?];
+
+        if let Some(stable_abi) = self.stable_abi_generator() {
+            reports.push(stable_abi.generate_one(out_dir, dest)?);
+        }
+
+        Ok(reports)
+    }
+
+    /// Writes only the `.def` file into `out_dir` for linking against it directly
+    /// instead of an import library, as a faster path for the MinGW builds
+    /// with the GNU `ld` configurations that accept `.def` input files.
+    ///
+    /// No `dlltool` is run. Returns the linker arguments to pass with
+    /// `cargo:rustc-link-arg=`, i.e. the absolute `.def` file path.
+    ///
+    /// Only the `gnu` target environment is supported.
+    pub fn generate_def_link_args(&self, out_dir: &Path) -> Result<Vec<String>> {
+        match self.resolved_target_os() {
+            Some(os) if os != "windows" => return Err(not_a_windows_target(&os)),
+            _ => {}
+        }
+
+        if self.env != "gnu" {
+            let msg = format!(
+                "Direct .def linking is not supported for the '{}' env",
+                self.env
+            );
+            return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg));
+        }
+
+        let out_dir = self.output_dir(out_dir);
+        create_dir_all(&out_dir)?;
+
+        // GNU `ld` expects the decorated 32-bit x86 symbol names.
+        let decorate = self.decorate_x86_names && self.arch == "x86";
+        let defpath =
+            absolute_path(&self.write_def_file(&out_dir, DefDialect::Mingw, decorate)?)?;
+
+        Ok(vec![defpath.to_string_lossy().into_owned()])
+    }
+
+    /// Removes the artifacts `generate_all()` would have written into `out_dir`
+    /// for this configuration.
+    ///
+    /// Only the `.def` files, the import libraries (with either file extension)
+    /// and the checksum manifests, metadata markers, CMake scripts, pkg-config,
+    /// PyO3 interpreter config, linker response and export files named after them
+    /// are removed, the other files in `out_dir` and the directory itself are left intact.
+    /// The read-only artifacts are removed as well.
+    ///
+    /// Returns the paths of the removed files.
+    pub fn clean(&self, out_dir: &Path) -> Result<Vec<PathBuf>> {
+        let out_dir = self.output_dir(out_dir);
+
+        let mut paths = self.artifact_paths(&out_dir)?;
+        if let Some(stable_abi) = self.stable_abi_generator() {
+            paths.extend(stable_abi.artifact_paths(&out_dir)?);
+        }
+
+        let mut removed = Vec::new();
+
+        for path in paths {
+            if path.is_file() {
+                clear_read_only(&path)?;
+                remove_file(&path)?;
+                removed.push(path);
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// Resolves the target, the Python version, the definitions,
+    /// the backend, the tool and the output path the same way as
+    /// [`generate()`](Self::generate) does, without generating anything.
+    ///
+    /// Runs the tool discovery, but no tools. Meant for debugging
+    /// the unexpected tool selections.
+    pub fn resolve(&self, out_dir: &Path) -> Result<ResolvedConfig> {
+        match self.resolved_target_os() {
+            Some(os) if os != "windows" => return Err(not_a_windows_target(&os)),
+            _ => {}
+        }
+
+        if let Some(stable_abi) = self.unknown_version_stable_abi()? {
+            return stable_abi.resolve(out_dir);
+        }
+
+        let out_dir = self.output_dir(out_dir);
+        let settings = EnvSettings::read(self.hermetic)?;
+        let backend = self.backend.or(settings.backend).unwrap_or_default();
+
+        let mut implib_ext = match self.lib_env() {
+            "msvc" => IMPLIB_EXT_MSVC,
+            _ => IMPLIB_EXT_GNU,
+        };
+
+        let (mut tool, mut tool_source) = (None, None);
+        if backend == Backend::DllTool {
+            let (command, source) = self.select_dlltool(&settings)?;
+            implib_ext = command.implib_file_ext(self.lib_env());
+            tool = Some(command.name());
+            tool_source = Some(source);
+        }
+
+        let def_source = match self.read_def_override(&self.def_file_name()?)? {
+            Some(_) => "override",
+            None => "embedded",
+        };
+
+        Ok(ResolvedConfig {
+            target: format!("{}-{}", self.arch, self.env),
+            python: self.version_label(),
+            dll_name: self.dll_name()?,
+            def_source: def_source.to_owned(),
+            backend,
+            tool,
+            tool_source,
+            implib: self.implib_file_path(&out_dir, implib_ext),
+        })
+    }
+
+    /// Checks whether the import libraries in `out_dir` need to be regenerated.
+    ///
+    /// `generate()` writes a metadata marker file next to the import library,
+    /// e.g. `python3.lib.stamp`, recording the crate version and the SHA-256
+    /// hash of the used Python library definitions. The import library
+    /// is stale if it is missing, has no marker or the marker does not
+    /// match this crate release and configuration, e.g. after an upgrade
+    /// bringing the updated symbol data.
+    pub fn is_stale(&self, out_dir: &Path) -> bool {
+        let out_dir = self.output_dir(out_dir);
+
+        self.implib_is_stale(&out_dir)
+            || self
+                .stable_abi_generator()
+                .is_some_and(|stable_abi| stable_abi.implib_is_stale(&out_dir))
+    }
+
+    /// Checks the import library marker file for the configured version.
+    fn implib_is_stale(&self, out_dir: &Path) -> bool {
+        let expected = match self.stamp() {
+            Ok(stamp) => stamp,
+            Err(_) => return true,
+        };
+
+        // The import library file extension depends on the used tool.
+        ![IMPLIB_EXT_MSVC, IMPLIB_EXT_GNU].iter().any(|libext| {
+            let implib = self.implib_file_path(out_dir, libext);
+
+            implib.is_file() && read_to_string(stamp_path(&implib)).is_ok_and(|s| s == expected)
+        })
+    }
+
+    /// Builds the import library metadata marker file contents.
+    fn stamp(&self) -> Result<String> {
+        let def_sha256 = sha256::sha256_hex(self.def_file_content()?.as_bytes());
+
+        Ok(format!(
+            "python3-dll-a {}\ndef-sha256 {}\n",
+            env!("CARGO_PKG_VERSION"),
+            def_sha256
+        ))
+    }
+
+    /// Builds the CycloneDX 1.5 JSON SBOM document describing the import library
+    /// generated with [`generate_with_report()`](Self::generate_with_report).
+    ///
+    /// The document lists the import library and the `.def` file it was
+    /// generated from with their SHA-256 hashes, the `python3-dll-a`
+    /// and the import library generation tool versions (the latter is only
+    /// queried with [`report_file()`](Self::report_file) enabled), and records
+    /// the target, the Python version, the `.def` file source (`embedded`
+    /// or `override`) and the host platform as the `python3-dll-a:*`
+    /// metadata properties, ready to be merged into the project SBOM.
+    pub fn sbom(&self, report: &GenerationReport) -> Result<String> {
+        let def_source = match self.read_def_override(&self.def_file_name()?)? {
+            Some(_) => "override",
+            None => "embedded",
+        };
+
+        let properties = [
+            ("python3-dll-a:target-arch", self.arch.clone()),
+            ("python3-dll-a:target-env", self.env.clone()),
+            ("python3-dll-a:python", self.version_label()),
+            ("python3-dll-a:def-source", def_source.to_owned()),
+            (
+                "python3-dll-a:host",
+                format!("{}-{}", env::consts::ARCH, env::consts::OS),
+            ),
+        ];
+
+        Ok(integration::cyclonedx_json(
+            report,
+            &report.implib_sha256()?,
+            &report.def_sha256()?,
+            &properties,
+        ))
+    }
+
+    /// Returns the Stable ABI import library generator replacing
+    /// the unknown Python version generator if `UnknownVersionPolicy::StableAbi`
+    /// applies.
+    fn unknown_version_stable_abi(&self) -> Result<Option<Self>> {
+        if self.unknown_version != UnknownVersionPolicy::StableAbi
+            || !self.is_unknown_version()
+            || self.read_def_override(&self.def_file_name()?)?.is_some()
+        {
+            return Ok(None);
+        }
+
+        if let PythonImplementation::PyPy = self.implementation {
+            let (major, minor) = self.version.unwrap_or_default();
+            let msg = format!("Unsupported PyPy version {}.{}", major, minor);
+            return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg));
+        }
+
+        let mut stable_abi = self.clone();
+        stable_abi
+            .version(None)
+            .abiflags(None)
+            .library_name(None)
+            .also_stable_abi(false)
+            .unknown_version(UnknownVersionPolicy::Error);
+
+        Ok(Some(stable_abi))
+    }
+
+    /// Returns the explicitly set or the `CARGO_CFG_TARGET_OS` compile target OS.
+    fn resolved_target_os(&self) -> Option<String> {
+        if self.target_os.is_some() || self.hermetic {
+            return self.target_os.clone();
+        }
+
+        env::var("CARGO_CFG_TARGET_OS")
+            .ok()
+            .filter(|os| !os.is_empty())
+    }
+
+    /// Checks whether the configured Python version is unknown to this crate
+    /// release (as opposed to known but disabled by the crate features).
+    fn is_unknown_version(&self) -> bool {
+        self.version.is_some() && self.version_feature().is_none()
+    }
+
+    /// Returns the Stable ABI import library generator
+    /// if [`also_stable_abi()`](Self::also_stable_abi) applies.
+    fn stable_abi_generator(&self) -> Option<Self> {
+        if self.also_stable_abi
+            && matches!(self.implementation, PythonImplementation::CPython)
+            && self.version.is_some()
+        {
+            let mut stable_abi = self.clone();
+            stable_abi.version(None).abiflags(None).library_name(None);
+
+            Some(stable_abi)
+        } else {
+            None
+        }
+    }
+
+    /// Generates the configured Python DLL import library
+    /// and the extra output files in `out_dir`.
+    fn generate_one(&self, out_dir: &Path, dest: &Path) -> Result<GenerationReport> {
+        if self.delay_load && self.env != "msvc" {
+            let msg = format!("Delay loading is not supported for the '{}' env", self.env);
+            return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg));
+        }
+
+        create_dir_all(out_dir)?;
+
+        let implib = match self.lib_env() {
+            "msvc" => self.implib_file_path(out_dir, IMPLIB_EXT_MSVC),
+            _ => self.implib_file_path(out_dir, IMPLIB_EXT_GNU),
+        };
+
+        if self.overwrite == OverwritePolicy::ErrorIfExists && implib.exists() {
+            let msg = format!("Import library {} already exists", implib.display());
+            return Err(Error::new(ErrorKind::AlreadyExists, msg));
+        }
+
+        // Allow overwriting the read-only artifacts of the previous run.
+        for path in self.artifact_paths(out_dir)? {
+            clear_read_only(&path)?;
+        }
+
+        let settings = EnvSettings::read(self.hermetic)?;
+
+        let mut existing = match self.reuse_existing {
+            true => self.reuse_existing_implib(out_dir)?,
+            false => None,
+        };
+
+        if existing.is_none() && self.overwrite == OverwritePolicy::SkipIfExists && implib.is_file()
+        {
+            existing = Some(self.existing_implib_report(implib, out_dir)?);
+        }
+
+        let mut report = match existing {
+            Some(report) => report,
+            None => match self.backend.or(settings.backend).unwrap_or_default() {
+                Backend::DllTool => self.run_dlltool(out_dir, &settings)?,
+                #[cfg(feature = "builtin")]
+                Backend::Builtin => self.generate_builtin(out_dir)?,
+                #[cfg(feature = "test-support")]
+                Backend::Mock => self.generate_mock(out_dir)?,
+            },
+        };
+
+        if let Some(warning) = self.use_latest_warning()? {
+            report.warnings.insert(0, warning);
+        }
+
+        let stem = integration::implib_stem(&report.implib);
+
+        // The artifacts embedding the paths refer to the installed files.
+        let installed = report.relocated(out_dir, dest);
+
+        let mut outputs = vec![report.implib.clone(), report.def.clone()];
+
+        // The marker would make the next run regenerate the kept import library.
+        if report.tool != REUSED_TOOL {
+            let stamp_file = stamp_path(&report.implib);
+            write(&stamp_file, self.stamp()?)?;
+            outputs.push(stamp_file);
+        }
+        outputs.extend(report.export_file.clone());
+
+        if self.checksum_manifest {
+            write_checksum_manifest(&report.implib)?;
+            outputs.push(checksum_manifest_path(&report.implib));
+        }
+
+        if self.cmake_script {
+            write_cmake_script(&report.implib, &installed.implib)?;
+            outputs.push(report.implib.with_file_name(format!("{}.cmake", stem)));
+        }
+
+        if self.pkg_config_file {
+            let version = match self.version {
+                Some((major, minor)) => format!("{}.{}", major, minor),
+                None => "3".to_owned(),
+            };
+
+            let implib = absolute_path(&installed.implib)?;
+            let pc_file = report
+                .implib
+                .with_file_name(format!("python3-dll-a-{}.pc", stem));
+
+            write(&pc_file, integration::pkg_config_file(&implib, &version))?;
+            outputs.push(pc_file);
+        }
+
+        if self.pyo3_config_file {
+            let lib_dir = absolute_path(installed.implib.parent().unwrap_or(dest))?;
+            let config_file = report
+                .implib
+                .with_file_name(format!("{}.pyo3-config.txt", stem));
+
+            write(&config_file, self.pyo3_config_for(&lib_dir, &stem))?;
+            outputs.push(config_file);
+        }
+
+        if self.delay_load {
+            let def = DefFile::from_path(&report.def)?;
+            let dll_name = def.dll_name().unwrap_or_default();
+            let rsp_file = report
+                .implib
+                .with_file_name(format!("{}.delayload.rsp", stem));
+
+            write(&rsp_file, integration::delay_load_response_file(&dll_name))?;
+            outputs.push(rsp_file);
+        }
+
+        if self.symbols_file || self.c_header {
+            let def = DefFile::from_path(&report.def)?;
+
+            if self.symbols_file {
+                let symbols_file = report
+                    .implib
+                    .with_file_name(format!("{}.symbols.txt", stem));
+
+                write(&symbols_file, integration::symbols_file(&def))?;
+                outputs.push(symbols_file);
+            }
+
+            if self.c_header {
+                let header = report.implib.with_file_name(format!("{}.exports.h", stem));
+
+                write(&header, integration::c_header(&def, &stem))?;
+                outputs.push(header);
+            }
+        }
+
+        if self.depfile {
+            let mut inputs = Vec::new();
+            if let Some(def) = self.def_override_path(&self.def_file_name()?)? {
+                inputs.push(absolute_path(&def)?);
+            }
+            inputs.extend(
+                report
+                    .command
+                    .first()
+                    .and_then(|program| tool_path(program)),
+            );
+
+            let depfile = depfile_path(&report.implib);
+            write(&depfile, integration::depfile(&installed.implib, &inputs))?;
+            outputs.push(depfile);
+        }
+
+        if self.report_file {
+            let report_file = report_file_path(&report.implib);
+            let json = integration::report_json(
+                &installed,
+                &report.implib_sha256()?,
+                &report.def_sha256()?,
+            );
+
+            write(&report_file, json)?;
+            outputs.push(report_file);
+        }
+
+        if self.read_only {
+            for path in &outputs {
+                set_read_only(path)?;
+            }
+        }
+
+        Ok(installed)
+    }
+
+    /// Returns the report for the genuine `.lib` import library in `out_dir`
+    /// if there is one, after checking it against the expected definitions.
+    fn reuse_existing_implib(&self, out_dir: &Path) -> Result<Option<GenerationReport>> {
+        let implib = self.implib_file_path(out_dir, IMPLIB_EXT_MSVC);
+
+        if !implib.is_file() || stamp_path(&implib).is_file() {
+            return Ok(None);
+        }
+
+        let existing = self.existing_implib_report(implib, out_dir)?;
+        let (implib, def) = (&existing.implib, DefFile::from_path(&existing.def)?);
+        verify::validate_implib(implib, &self.arch, &def)?;
+
+        let report = verify_implib(implib, &def)?;
+        if !report.missing.is_empty() || !report.wrong_dll.is_empty() {
+            let msg = format!(
+                "Existing import library {} does not match the Python library definitions: \
+                 {} missing symbols, {} symbols imported from another DLL",
+                implib.display(),
+                report.missing.len(),
+                report.wrong_dll.len()
+            );
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+
+        Ok(Some(existing))
+    }
+
+    /// Writes the `.def` file for the existing `implib` import library
+    /// kept in `out_dir` and returns its report.
+    fn existing_implib_report(&self, implib: PathBuf, out_dir: &Path) -> Result<GenerationReport> {
+        let mut timings = GenerationTimings::default();
+
+        let start = Instant::now();
+        let def = self.write_def_file(out_dir, DefDialect::Mingw, false)?;
+        timings.def_write = start.elapsed();
+
+        Ok(GenerationReport {
+            implib,
+            def,
+            tool: REUSED_TOOL.to_owned(),
+            warnings: Vec::new(),
+            export_file: None,
+            command: Vec::new(),
+            tool_version: None,
+            timings,
+        })
+    }
+
+    /// Fails on the WASI hosts, which can not run `dlltool`.
+    ///
+    /// `std::process` is not available to the WASI modules.
+    #[cfg(target_os = "wasi")]
+    fn run_dlltool(&self, _out_dir: &Path, _settings: &EnvSettings) -> Result<GenerationReport> {
+        let msg = "The WASI hosts can not run dlltool, use the builtin backend instead";
+        Err(ErrorCategory::ToolMissing.error(ErrorKind::Unsupported, msg))
+    }
+
+    /// Generates the Python DLL import library in `out_dir`
+    /// by running the best matching `dlltool` flavor.
+    ///
+    /// Returns the generation report.
+    #[cfg(not(target_os = "wasi"))]
+    fn run_dlltool(&self, out_dir: &Path, settings: &EnvSettings) -> Result<GenerationReport> {
+        let mut timings = GenerationTimings::default();
+
+        let start = Instant::now();
+        let (dlltool_command, _) = self.select_dlltool(settings)?;
+        timings.discovery = start.elapsed();
+
+        let lib_exe = matches!(dlltool_command, DllToolCommand::LibExe { .. });
+        if self.export_file && !lib_exe {
+            let msg = format!(
+                "Export file generation requires lib.exe, found {}",
+                dlltool_command.name()
+            );
+            return Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg));
+        }
+
+        // Only the MinGW `dlltool` needs the pre-decorated x86 symbol names.
+        let decorate = self.decorate_x86_names
+            && self.arch == "x86"
+            && matches!(dlltool_command, DllToolCommand::Mingw { .. });
+
+        let dialect = if lib_exe {
+            DefDialect::Msvc
+        } else {
+            DefDialect::Mingw
+        };
+
+        let start = Instant::now();
+        let defpath = self.write_def_file(out_dir, dialect, decorate)?;
+        timings.def_write = start.elapsed();
+
+        let mut options = self.options.clone();
+        options.no_leading_underscore |= decorate;
+
+        // Get the import library file extension from the used `dlltool` flavor.
+        let implib_ext = dlltool_command.implib_file_ext(self.lib_env());
+
+        let implib_file = self.implib_file_path(out_dir, implib_ext);
+
+        if options.temp_prefix.is_none() {
+            let stem = integration::implib_stem(&implib_file);
+            options.temp_prefix = Some(absolute_path(&out_dir.join(format!("{}-dlltool", stem)))?);
+        }
+
+        let tool = dlltool_command.name();
+
+        let tool_version = if self.report_file {
+            dlltool_command.version()
+        } else {
+            None
+        };
+
+        let verbosity = self
+            .verbosity
+            .or(settings.verbosity)
+            .unwrap_or(settings::DEFAULT_VERBOSITY);
+
+        // The export files are not cached.
+        let cache_entry = match self.cache_dir.as_ref().or(settings.cache_dir.as_ref()) {
+            Some(cache_dir) if !self.export_file => {
+                let key = self.cache_key(&dlltool_command, &defpath, &options)?;
+                Some(
+                    cache_dir
+                        .join(key)
+                        .join(implib_file.file_name().unwrap_or_default()),
+                )
+            }
+            _ => None,
+        };
+
+        if let Some(cache_entry) = cache_entry.as_ref().filter(|entry| entry.is_file()) {
+            if verbosity >= 2 {
+                eprintln!("python3-dll-a: using cached {}", cache_entry.display());
+            }
+
+            let start = Instant::now();
+            copy(cache_entry, &implib_file)?;
+            timings.tool = start.elapsed();
+
+            let def = DefFile::from_path(&defpath)?;
+            verify::validate_implib(&implib_file, &self.arch, &def)?;
+
+            return Ok(GenerationReport {
+                implib: implib_file,
+                def: defpath,
+                tool,
+                warnings: Vec::new(),
+                export_file: None,
+                command: Vec::new(),
+                tool_version,
+                timings,
+            });
+        }
+
+        // Build the complete `dlltool` command with all required arguments.
+        // Pass the absolute paths, so that the wrapper scripts changing
+        // the working directory and the response files work as expected.
+        let def_arg = absolute_path(&defpath)?;
+        let implib_arg = absolute_path(&implib_file)?;
+
+        let flavor = dlltool_command.flavor();
+        let mut command = dlltool_command.build(&def_arg, &implib_arg, &options);
+        let command_line = command_line(&command);
+
+        // `lib.exe` decodes the command line using the ANSI code page,
+        // which mangles the non-ASCII paths, but reads the UTF-16 response
+        // files losslessly.
+        let ascii = |path: &Path| path.to_str().is_some_and(|path| path.is_ascii());
+
+        let response_file = if lib_exe && !(ascii(&def_arg) && ascii(&implib_arg)) {
+            let rsp = implib_arg.with_extension("rsp");
+            command = response_file_command(&command, &rsp)?;
+            Some(rsp)
+        } else {
+            None
+        };
+
+        if verbosity >= 2 {
+            eprintln!("python3-dll-a: running {:?}", command);
+        }
+
+        // Run the selected `dlltool` executable to generate the import library.
+        let start = Instant::now();
+        let output = command.output().map_err(|e| match e.kind() {
+            ErrorKind::NotFound => {
+                let msg = format!(
+                    "{:?} failed with {}; {}",
+                    command,
+                    e,
+                    install_hint(flavor, &self.arch)
+                );
+                ErrorCategory::ToolMissing.error(e.kind(), msg)
+            }
+            _ => {
+                let msg = format!("{:?} failed with {}", command, e);
+                ErrorCategory::ToolFailed.error(e.kind(), msg)
+            }
+        })?;
+        timings.tool = start.elapsed();
+
+        if let Some(rsp) = response_file {
+            remove_file(rsp)?;
+        }
+
+        // Pass the tool messages through, but keep our stdout clean.
+        let messages = String::from_utf8_lossy(&output.stdout).into_owned()
+            + &String::from_utf8_lossy(&output.stderr);
+        if verbosity >= 1 {
+            eprint!("{}", messages);
+        }
+
+        if !output.status.success() {
+            let msg = format!("{:?} failed with {}", command, output.status);
+            return Err(ErrorCategory::ToolFailed.error(ErrorKind::Other, msg));
+        }
+
+        let warnings = tool_warnings(&messages);
+
+        if self.deny_tool_warnings && !warnings.is_empty() {
+            let msg = format!("{} reported warnings: {}", tool, warnings.join("; "));
+            return Err(ErrorCategory::ToolFailed.error(ErrorKind::Other, msg));
+        }
+
+        // Catch the empty or malformed output archives.
+        let def = DefFile::from_path(&defpath)?;
+        verify::validate_implib(&implib_file, &self.arch, &def)
+            .map_err(|e| ErrorCategory::ToolFailed.error(e.kind(), e.to_string()))?;
+
+        // `lib.exe` names the export file after the `/OUT` import library.
+        let exp_file = implib_file.with_extension("exp");
+        let export_file = if self.export_file {
+            if !exp_file.is_file() {
+                let msg = format!("{} did not write {}", tool, exp_file.display());
+                return Err(ErrorCategory::ToolFailed.error(ErrorKind::NotFound, msg));
+            }
+            Some(exp_file)
+        } else {
+            if lib_exe && exp_file.is_file() {
+                remove_file(&exp_file)?;
+            }
+            None
+        };
+
+        if let Some(cache_entry) = cache_entry {
+            store_cache_entry(&implib_file, &cache_entry)?;
+        }
+
+        Ok(GenerationReport {
+            implib: implib_file,
+            def: defpath,
+            tool,
+            warnings,
+            export_file,
+            command: command_line,
+            tool_version,
+            timings,
+        })
+    }
+
+    /// Returns the import library cache key for the `dlltool` invocation.
+    #[cfg(not(target_os = "wasi"))]
+    fn cache_key(
+        &self,
+        command: &DllToolCommand,
+        defpath: &Path,
+        options: &DllToolOptions,
+    ) -> Result<String> {
+        // The intermediate file location does not affect the import library.
+        let options = DllToolOptions {
+            temp_prefix: None,
+            ..options.clone()
+        };
+
+        // A different tool installed under the same name must not
+        // reuse the import libraries cached for the replaced one.
+        let program = tool_path(&command.program().to_string_lossy())
+            .and_then(|path| path.canonicalize().ok());
+        let identity = match program {
+            Some(path) => {
+                let metadata = metadata(&path)?;
+                let modified = metadata.modified().ok();
+                let mtime = modified.and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+                format!("{} {} {:?}", path.display(), metadata.len(), mtime)
+            }
+            None => String::new(),
+        };
+
+        let mut key = format!(
+            "python3-dll-a {}\n{}\n{}\n{}-{}\n{:?}\n",
+            env!("CARGO_PKG_VERSION"),
+            command.name(),
+            identity,
+            self.arch,
+            self.env,
+            options
+        )
+        .into_bytes();
+        key.extend(read(defpath)?);
+
+        Ok(sha256::sha256_hex(&key))
+    }
+
+    /// Returns the target environment ABI determining the import library format.
+    ///
+    /// This is `msvc` for the `gnu` targets with [`msvc_lib()`](Self::msvc_lib) enabled.
+    fn lib_env(&self) -> &str {
+        if self.msvc_lib && self.env == "gnu" {
+            "msvc"
+        } else {
+            &self.env
+        }
+    }
+
+    /// Checks the existing import library at `path` against the Python
+    /// library definitions for the configured Python version
+    /// (see [`def_file()`](Self::def_file)) and the target machine type.
+    ///
+    /// Returns the structured report listing the mismatching symbols,
+    /// see [`verify_implib()`].
+    pub fn verify_implib(&self, path: &Path) -> Result<ImplibReport> {
+        verify::verify_implib_for_target(path, &self.arch, &self.def_file()?)
+    }
+
+    /// Returns the parsed Python library definitions for the configured
+    /// Python version, as used for the import library generation.
+    ///
+    /// The replacement file from the `def_dir()` directory is used
+    /// in place of the embedded definitions when present.
+    pub fn def_file(&self) -> Result<DefFile> {
+        DefFile::parse(&self.def_file_content()?)
+    }
+
+    /// Returns the Python library definitions file contents
+    /// with the debug DLL name substituted when required.
+    fn def_file_content(&self) -> Result<Cow<'static, str>> {
+        let def_file = self.def_file_name()?;
+
+        let mut content = match self.read_def_override(&def_file)? {
+            Some(content) => Cow::Owned(content),
+            None => self.embedded_def_file()?,
+        };
+
+        if self.exclude_private {
+            content = Cow::Owned(exclude_private_exports(&content));
+        }
+
+        if let (None, Some(minimum)) = (self.version, self.abi3_minimum) {
+            if let PythonImplementation::CPython = self.implementation {
+                content = Cow::Owned(limit_stable_abi_exports(&content, minimum));
+            }
+        }
+
+        if self.debug {
+            if let PythonImplementation::PyPy = self.implementation {
+                let msg = "PyPy has no debug build DLLs";
+                return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg));
+            }
+
+            content = Cow::Owned(debug_library_name(&content));
+        }
+
+        if self.library_name.is_some() || !self.library_extension || self.library_base.is_some() {
+            content = Cow::Owned(self.library_statement(&content)?);
+        }
+
+        Ok(content)
+    }
+
+    /// Rewrites the `LIBRARY` statement of the definitions file contents
+    /// `def` according to the `library_*()` settings.
+    fn library_statement(&self, def: &str) -> Result<String> {
+        let dll_name = match &self.library_name {
+            Some(name) => name.clone(),
+            None => DefFile::parse(def)?.dll_name().unwrap_or_default(),
+        };
+
+        let stem = match dll_name.len().checked_sub(4) {
+            Some(len) if dll_name[len..].eq_ignore_ascii_case(".dll") => &dll_name[..len],
+            _ => &dll_name,
+        };
+
+        let name = if self.library_extension {
+            format!("{}.dll", stem)
+        } else {
+            stem.to_owned()
+        };
+
+        let mut statement = format!("LIBRARY \"{}\"", name);
+        if let Some(base) = self.library_base {
+            // `llvm-dlltool` only accepts the decimal numbers.
+            statement.push_str(&format!(" BASE={}", base));
+        }
+
+        let mut rewritten = String::with_capacity(def.len());
+
+        for line in def.lines() {
+            if line.starts_with("LIBRARY ") {
+                rewritten.push_str(&statement);
+            } else {
+                rewritten.push_str(line);
+            }
+            rewritten.push('\n');
+        }
+
+        Ok(rewritten)
+    }
+
+    /// Returns the exact Python DLL name the generated import library binds to,
+    /// e.g. `python3.dll`, `python313t.dll`, `python311_d.dll`
+    /// or `libpypy3.10-c.dll`.
+    ///
+    /// The name is taken from the `LIBRARY` statement of the used
+    /// Python library definitions, including the `def_dir()` replacements.
+    pub fn dll_name(&self) -> Result<String> {
+        if let Some(stable_abi) = self.unknown_version_stable_abi()? {
+            return stable_abi.dll_name();
+        }
+
+        self.def_file()?.dll_name().ok_or_else(|| {
+            let msg = "The Python library definitions have no LIBRARY statement";
+            Error::new(ErrorKind::InvalidData, msg)
+        })
+    }
+
+    /// Returns the generated import library name for linking, e.g. `python3`,
+    /// `python313t` or `python311_d`, as passed to `cargo:rustc-link-lib=`
+    /// or the linker `-l` option.
+    ///
+    /// This is the import library file name without the `.lib`
+    /// or `.dll.a` extension.
+    pub fn import_name(&self) -> Result<String> {
+        if let Some(stable_abi) = self.unknown_version_stable_abi()? {
+            return stable_abi.import_name();
+        }
+
+        let implib = self.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC);
+
+        Ok(integration::implib_stem(&implib))
+    }
+
+    /// Finds the genuine import library of a matching installed CPython
+    /// for the native Windows builds, see [`installed_pythons()`].
+    ///
+    /// Returns the `python3.lib` or `pythonXY.lib` path in the `libs`
+    /// directory of the installed CPython of the configured version,
+    /// ABI flags and architecture, if there is one, so that the build
+    /// script can link against it instead of generating an import library.
+    /// Any installed CPython matches the Stable ABI configuration.
+    ///
+    /// Returns `None` for PyPy and on the non-Windows hosts.
+    pub fn installed_import_library(&self) -> Result<Option<PathBuf>> {
+        if self.implementation != PythonImplementation::CPython {
+            return Ok(None);
+        }
+
+        let implib = format!("{}{}", self.import_name()?, IMPLIB_EXT_MSVC);
+        let gil_disabled = self.abiflags.as_deref() == Some("t");
+
+        let found = installed_pythons()?
+            .into_iter()
+            .filter(|python| python.arch == self.arch)
+            .filter(|python| match self.version {
+                Some(version) => python.version == version && python.gil_disabled == gil_disabled,
+                None => true,
+            })
+            .map(|python| python.lib_dir().join(&implib))
+            .find(|path| path.is_file());
+
+        Ok(found)
+    }
+
+    /// Returns the exported symbol statistics for the configured
+    /// Python version from the embedded Python ABI definitions data.
+    ///
+    /// The unique symbols are the ones not exported by any other
+    /// embedded Python version (of the same implementation) enabled
+    /// by the crate features. Different ABI flags of the same version,
+    /// e.g. `3.13` and `3.13t`, are not compared with each other.
+    pub fn export_stats(&self) -> Result<ExportStats> {
+        let def = DefFile::parse(&self.embedded_def_file()?)?;
+
+        let versions = match self.implementation {
+            PythonImplementation::CPython => &CPYTHON_VERSIONS[..],
+            PythonImplementation::PyPy => &PYPY_VERSIONS[..],
+        };
+
+        let mut others = Vec::new();
+        for (version, abiflags) in versions {
+            if *version == self.version {
+                continue;
+            }
+
+            let mut other = self.clone();
+            other.version(*version).abiflags(*abiflags);
+
+            // Skip the versions disabled by the crate features.
+            if let Ok(other_def) = other.embedded_def_file() {
+                others.push(DefFile::parse(&other_def)?);
+            }
+        }
+
+        Ok(ExportStats::new(&def, &others))
+    }
+
+    /// Writes the Python DLL import library to `out_dir`
+    /// using the built-in import library writer.
+    ///
+    /// The generated `.def` file is written to `out_dir` as well.
+    ///
+    /// Returns the generation report.
+    #[cfg(feature = "builtin")]
+    fn generate_builtin(&self, out_dir: &Path) -> Result<GenerationReport> {
+        if let Some(flavor) = self.require_tool {
+            let msg = format!(
+                "Strict tool selection requires {}, found the builtin backend",
+                flavor.name()
+            );
+            return Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg));
+        }
+
+        if self.export_file {
+            let msg = "Export file generation requires lib.exe";
+            return Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg));
+        }
+
+        let machine = implib::Machine::from_arch(&self.arch)?;
+
+        let implib_ext = match self.lib_env() {
+            "gnu" => IMPLIB_EXT_GNU,
+            "msvc" => IMPLIB_EXT_MSVC,
+            env => {
+                let msg = format!("Unsupported target env ABI '{}'", env);
+                return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg));
+            }
+        };
+
+        let mut timings = GenerationTimings::default();
+
+        let start = Instant::now();
+        let defpath = self.write_def_file(out_dir, DefDialect::Mingw, false)?;
+        timings.def_write = start.elapsed();
+
+        let start = Instant::now();
+        let def = DefFile::from_path(&defpath)?;
+
+        let implib =
+            implib::write_import_library(&def, machine, self.options.no_leading_underscore)?;
+
+        let implib_file = self.implib_file_path(out_dir, implib_ext);
+        write(&implib_file, implib)?;
+        timings.tool = start.elapsed();
+
+        Ok(GenerationReport {
+            implib: implib_file,
+            def: defpath,
+            tool: "builtin".to_owned(),
+            warnings: Vec::new(),
+            export_file: None,
+            command: Vec::new(),
+            tool_version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            timings,
+        })
+    }
+
+    /// Writes the `.def` file and a placeholder import library in `out_dir`
+    /// and records the invocation for the downstream tests.
+    ///
+    /// The tool selection settings are ignored.
+    #[cfg(feature = "test-support")]
+    fn generate_mock(&self, out_dir: &Path) -> Result<GenerationReport> {
+        if self.export_file {
+            let msg = "Export file generation requires lib.exe";
+            return Err(ErrorCategory::ToolMissing.error(ErrorKind::NotFound, msg));
+        }
+
+        let implib_ext = match self.lib_env() {
+            "gnu" => IMPLIB_EXT_GNU,
+            "msvc" => IMPLIB_EXT_MSVC,
+            env => {
+                let msg = format!("Unsupported target env ABI '{}'", env);
+                return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg));
+            }
+        };
+
+        let mut timings = GenerationTimings::default();
+
+        let start = Instant::now();
+        let defpath = self.write_def_file(out_dir, DefDialect::Mingw, false)?;
+        timings.def_write = start.elapsed();
+
+        let start = Instant::now();
+        let implib_file = self.implib_file_path(out_dir, implib_ext);
+        write(&implib_file, mock::PLACEHOLDER_ARCHIVE)?;
+        timings.tool = start.elapsed();
+
+        mock::record(mock::MockInvocation {
+            arch: self.arch.clone(),
+            env: self.env.clone(),
+            dll_name: self.dll_name()?,
+            implib: implib_file.clone(),
+            def: defpath.clone(),
+        });
+
+        Ok(GenerationReport {
+            implib: implib_file,
+            def: defpath,
+            tool: "mock".to_owned(),
+            warnings: Vec::new(),
+            export_file: None,
+            command: Vec::new(),
+            tool_version: Some(env!("CARGO_PKG_VERSION").to_owned()),
+            timings,
+        })
+    }
+
+    /// Writes out the Python library definitions file to `out_dir`.
+    ///
+    /// The replacement file from the `def_dir()` directory is used
+    /// in place of the embedded definitions when present.
+    ///
+    /// The definitions are normalized for the tool `dialect`, and
+    /// the exported symbol names are pre-decorated for the 32-bit x86
+    /// targets when `decorate` is set.
+    ///
+    /// Returns the newly created `python3.def` or `pythonXY.def` file path.
+    fn write_def_file(
+        &self,
+        out_dir: &Path,
+        dialect: DefDialect,
+        decorate: bool,
+    ) -> Result<PathBuf> {
+        let mut def_file = self.def_file_name()?;
+
+        let def_file_content = dialect.normalize(&self.def_file_content()?)?;
+
+        if self.debug {
+            def_file = def_file.replace(".def", "_d.def");
+        }
+
+        let mut defpath = out_dir.to_owned();
+        defpath.push(def_file);
+
+        let mut content = self.def_metadata(&def_file_content, decorate)?.header();
+
+        if decorate {
+            content.push_str(&decorate_x86_exports(&def_file_content));
+        } else {
+            content.push_str(&def_file_content);
+        }
+
+        let crlf = match self.line_ending {
+            LineEnding::Auto => dialect == DefDialect::Msvc,
+            LineEnding::Lf => false,
+            LineEnding::CrLf => true,
+        };
+
+        // All the lines are `LF`-terminated after the normalization.
+        if crlf {
+            content = content.replace('\n', "\r\n");
+        }
+
+        write(&defpath, content)?;
+
+        Ok(defpath)
+    }
+
+    /// Returns the generation metadata header for the written
+    /// definitions file contents `def`.
+    fn def_metadata(&self, def: &str, decorate: bool) -> Result<DefMetadata> {
+        let source = match self.read_def_override(&self.def_file_name()?)? {
+            Some(_) => "override",
+            None => "embedded",
+        };
+
+        let options = [
+            (self.debug, "debug"),
+            (decorate, "decorate-x86-names"),
+            (self.exclude_private, "exclude-private"),
+            (self.options.kill_at, "kill-at"),
+            (self.options.no_leading_underscore, "no-leading-underscore"),
+        ];
+
+        Ok(DefMetadata {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            source: source.to_owned(),
+            source_sha256: sha256::sha256_hex(def.as_bytes()),
+            target: format!("{}-{}", self.arch, self.env),
+            python: self.version_label(),
+            options: options
+                .iter()
+                .filter(|(enabled, _)| *enabled)
+                .map(|(_, name)| (*name).to_owned())
+                .collect(),
+        })
+    }
+
+    /// Returns the embedded Python library definitions file contents.
+    fn embedded_def_file(&self) -> Result<Cow<'static, str>> {
+        let def_file_content = match self.implementation {
+            PythonImplementation::CPython => match (self.version, self.abiflags.as_deref()) {
+                (None, _) => def_file!("python3.def"),
+                #[cfg(feature = "py37")]
+                (Some((3, 7)), _) => def_file!("python37.def"),
+                #[cfg(feature = "py38")]
+                (Some((3, 8)), _) => def_file!("python38.def"),
+                #[cfg(feature = "py39")]
+                (Some((3, 9)), _) => def_file!("python39.def"),
+                #[cfg(feature = "py310")]
+                (Some((3, 10)), _) => def_file!("python310.def"),
+                #[cfg(feature = "py311")]
+                (Some((3, 11)), _) => def_file!("python311.def"),
+                #[cfg(feature = "py312")]
+                (Some((3, 12)), _) => def_file!("python312.def"),
+                #[cfg(feature = "py313t")]
+                (Some((3, 13)), Some("t")) => def_file!("python313t.def"),
+                #[cfg(feature = "py313")]
+                (Some((3, 13)), None) => def_file!("python313.def"),
+                (Some((3, 13)), Some(flags)) if flags != "t" => {
+                    let msg = "Unsupported Python ABI flags";
+                    return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg));
+                }
+                _ => return self.unknown_version_def_file("Unsupported Python version"),
+            },
+            PythonImplementation::PyPy => match self.version {
+                #[cfg(any(feature = "pypy37", feature = "pypy38"))]
+                Some((3, 7)) | Some((3, 8)) => def_file!("libpypy3-c.def"),
+                #[cfg(feature = "pypy39")]
+                Some((3, 9)) => def_file!("libpypy3.9-c.def"),
+                #[cfg(feature = "pypy310")]
+                Some((3, 10)) => def_file!("libpypy3.10-c.def"),
+                _ => return self.unknown_version_def_file("Unsupported PyPy version"),
+            },
+        };
+
+        decode_def_file(def_file_content)
+    }
+
+    /// Returns the latest embedded definitions renamed to the configured
+    /// Python DLL name if `UnknownVersionPolicy::UseLatest` applies.
+    ///
+    /// Fails with the unsupported version error `msg` otherwise.
+    fn unknown_version_def_file(&self, msg: &str) -> Result<Cow<'static, str>> {
+        if self.unknown_version != UnknownVersionPolicy::UseLatest || !self.is_unknown_version() {
+            return Err(self.unsupported_version(msg));
+        }
+
+        let def = match self.latest_embedded_def_file() {
+            Some((_, def)) => def,
+            None => return Err(self.unsupported_version(msg)),
+        };
+
+        let dll_name = self.def_file_name()?.replace(".def", ".dll");
+
+        Ok(Cow::Owned(rename_library(&def, &dll_name)))
+    }
+
+    /// Returns the latest embedded definitions of the configured Python
+    /// implementation and ABI flags along with their Python version.
+    fn latest_embedded_def_file(&self) -> Option<((u8, u8), Cow<'static, str>)> {
+        let versions = match self.implementation {
+            PythonImplementation::CPython => &CPYTHON_VERSIONS[..],
+            PythonImplementation::PyPy => &PYPY_VERSIONS[..],
+        };
+
+        // Skip the versions disabled by the crate features.
+        versions
+            .iter()
+            .rev()
+            .filter(|(version, abiflags)| {
+                version.is_some() && *abiflags == self.abiflags.as_deref()
+            })
+            .find_map(|(version, abiflags)| {
+                let mut other = self.clone();
+                other
+                    .version(*version)
+                    .abiflags(*abiflags)
+                    .unknown_version(UnknownVersionPolicy::Error);
+
+                Some(((*version)?, other.embedded_def_file().ok()?))
+            })
+    }
+
+    /// Returns the warning about using the latest embedded definitions
+    /// for the unknown Python version if `UnknownVersionPolicy::UseLatest` applies.
+    fn use_latest_warning(&self) -> Result<Option<String>> {
+        if self.unknown_version != UnknownVersionPolicy::UseLatest
+            || !self.is_unknown_version()
+            || self.def_override_path(&self.def_file_name()?)?.is_some()
+        {
+            return Ok(None);
+        }
+
+        let (latest_major, latest_minor) = match self.latest_embedded_def_file() {
+            Some((latest, _)) => latest,
+            None => return Ok(None),
+        };
+
+        let implementation = match self.implementation {
+            PythonImplementation::CPython => "Python",
+            PythonImplementation::PyPy => "PyPy",
+        };
+
+        let (major, minor) = self.version.unwrap_or_default();
+        Ok(Some(format!(
+            "Unsupported {} version {}.{}, using the Python {}.{} definitions",
+            implementation, major, minor, latest_major, latest_minor
+        )))
+    }
+
+    /// Returns the paths of all the artifacts `generate()` can write
+    /// into `out_dir` for the configured Python version.
+    ///
+    /// Both the import library file extensions are included.
+    fn artifact_paths(&self, out_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut def_file = self.def_file_name()?;
+        if self.debug {
+            def_file = def_file.replace(".def", "_d.def");
+        }
+
+        let mut paths = vec![out_dir.join(def_file)];
+
+        for libext in [IMPLIB_EXT_MSVC, IMPLIB_EXT_GNU] {
+            let implib = self.implib_file_path(out_dir, libext);
+            let stem = integration::implib_stem(&implib);
+
+            paths.push(checksum_manifest_path(&implib));
+            paths.push(stamp_path(&implib));
+            paths.push(report_file_path(&implib));
+            paths.push(depfile_path(&implib));
+            for name in [
+                format!("{}.cmake", stem),
+                format!("python3-dll-a-{}.pc", stem),
+                format!("{}.pyo3-config.txt", stem),
+                format!("{}.delayload.rsp", stem),
+                format!("{}.exp", stem),
+                format!("{}.symbols.txt", stem),
+                format!("{}.exports.h", stem),
+            ] {
+                paths.push(implib.with_file_name(name));
+            }
+            paths.push(implib);
+        }
+
+        paths.sort();
+        paths.dedup();
+
+        Ok(paths)
+    }
+
+    /// Returns the Python library definitions file name,
+    /// e.g. `python3.def`, `python313t.def` or `libpypy3.9-c.def`.
+    fn def_file_name(&self) -> Result<String> {
+        let abiflags = self.abiflags.as_deref().unwrap_or_default();
+
+        let name = match (self.implementation, self.version) {
+            (PythonImplementation::CPython, None) => "python3.def".to_owned(),
+            (PythonImplementation::CPython, Some((major, minor))) => {
+                format!("python{}{}{}.def", major, minor, abiflags)
+            }
+            (PythonImplementation::PyPy, Some((3, 7)))
+            | (PythonImplementation::PyPy, Some((3, 8))) => "libpypy3-c.def".to_owned(),
+            (PythonImplementation::PyPy, Some((major, minor))) => {
+                format!("libpypy{}.{}-c.def", major, minor)
+            }
+            (PythonImplementation::PyPy, None) => {
+                let msg = "Unsupported PyPy version";
+                return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg));
+            }
+        };
+
+        Ok(name)
+    }
+
+    /// Reads the replacement Python library definitions file `def_file`
+    /// from the `def_dir()` or `PYTHON3_DLL_A_DEF_DIR` directory.
+    ///
+    /// Returns `None` if no replacement file exists.
+    ///
+    /// Fails if [`DefFile::lint()`] finds any problems in the replacement
+    /// definitions, before the import library tools could.
+    fn read_def_override(&self, def_file: &str) -> Result<Option<String>> {
+        let (path, def, mut lints) = match self.def_override_path(def_file)? {
+            Some(defpath) if defpath.extension().is_some_and(|ext| ext == "def") => {
+                let def = read_to_string(&defpath)?;
+                let lints = DefFile::lint(&def);
+                (defpath, def, lints)
+            }
+            Some(listpath) => {
+                let def = DefFile::from_path(&listpath)?.to_def();
+                let mut lints = DefFile::lint(&def);
+                // The line numbers of the written definitions are meaningless.
+                for lint in &mut lints {
+                    lint.line = None;
+                }
+                (listpath, def, lints)
+            }
+            None => return Ok(None),
+        };
+
+        if !lints.is_empty() {
+            lints.truncate(MAX_REPORTED_LINTS);
+            let lints: Vec<_> = lints.iter().map(DefLint::to_string).collect();
+            let msg = format!(
+                "Invalid Python library definitions in {}: {}",
+                path.display(),
+                lints.join("; ")
+            );
+            return Err(Error::new(ErrorKind::InvalidData, msg));
+        }
+
+        Ok(Some(def))
+    }
+
+    /// Returns the replacement `def_file` path in the `.def` file directory,
+    /// or the replacement JSON or TOML symbol list path, if it exists.
+    fn def_override_path(&self, def_file: &str) -> Result<Option<PathBuf>> {
+        let settings = EnvSettings::read(self.hermetic)?;
+
+        let def_dir = match self.def_dir.as_ref().or(settings.def_dir.as_ref()) {
+            Some(def_dir) => def_dir,
+            None => return Ok(None),
+        };
+
+        let defpath = def_dir.join(def_file);
+
+        Ok(["def", "json", "toml"]
+            .iter()
+            .map(|ext| defpath.with_extension(ext))
+            .find(|path| path.is_file()))
+    }
+
+    /// Returns the crate feature name enabling the embedded definitions
+    /// for the configured Python version, if there is one.
+    fn version_feature(&self) -> Option<&'static str> {
+        let feature = match self.implementation {
+            PythonImplementation::CPython => match (self.version?, self.abiflags.as_deref()) {
+                ((3, 7), _) => "py37",
+                ((3, 8), _) => "py38",
+                ((3, 9), _) => "py39",
+                ((3, 10), _) => "py310",
+                ((3, 11), _) => "py311",
+                ((3, 12), _) => "py312",
+                ((3, 13), Some("t")) => "py313t",
+                ((3, 13), _) => "py313",
+                _ => return None,
+            },
+            PythonImplementation::PyPy => match self.version? {
+                (3, 7) => "pypy37",
+                (3, 8) => "pypy38",
+                (3, 9) => "pypy39",
+                (3, 10) => "pypy310",
+                _ => return None,
+            },
+        };
+
+        Some(feature)
+    }
+
+    /// Builds the unsupported Python version error.
+    ///
+    /// Mentions the disabled crate feature when the version is known.
+    fn unsupported_version(&self, msg: &str) -> Error {
+        match self.version_feature() {
+            Some(feature) => {
+                let msg = format!("{}: the '{}' crate feature is disabled", msg, feature);
+                ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg)
+            }
+            None => ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg),
+        }
+    }
+
+    /// Builds the generated import library file name.
+    ///
+    /// The output file extension is passed in `libext`.
+    ///
+    /// Returns the full import library file path under `out_dir`.
+    fn implib_file_path(&self, out_dir: &Path, libext: &str) -> PathBuf {
+        let abiflags = self.abiflags.as_deref().unwrap_or_default();
+        let debug = if self.debug { "_d" } else { "" };
+        let libname = match self.version {
+            Some((major, minor)) => {
+                format!("python{}{}{}{}{}", major, minor, abiflags, debug, libext)
+            }
+            None => format!("python3{}{}", debug, libext),
+        };
+
+        let mut libpath = out_dir.to_owned();
+        libpath.push(libname);
+
+        libpath
+    }
+}
+
+/// Replaces the `LIBRARY` statement DLL name with `dll_name`.
+fn rename_library(def: &str, dll_name: &str) -> String {
+    let mut renamed = String::with_capacity(def.len());
+
+    for line in def.lines() {
+        if line.starts_with("LIBRARY ") {
+            renamed.push_str("LIBRARY \"");
+            renamed.push_str(dll_name);
+            renamed.push('"');
+        } else {
+            renamed.push_str(line);
+        }
+        renamed.push('\n');
+    }
+
+    renamed
+}
+
+/// Appends the `_d` debug build suffix to the `LIBRARY` statement DLL name.
+fn debug_library_name(def: &str) -> String {
+    let mut renamed = String::with_capacity(def.len() + 2);
+
+    for line in def.lines() {
+        match line.strip_prefix("LIBRARY ") {
+            Some(name) => {
+                // The gendef output has the DLL name quoted.
+                let name = name.trim().trim_matches('"');
+                let stem = name.strip_suffix(".dll").unwrap_or(name);
+                renamed.push_str("LIBRARY \"");
+                renamed.push_str(stem);
+                renamed.push_str("_d.dll\"");
+            }
+            None => renamed.push_str(line),
+        }
+        renamed.push('\n');
+    }
+
+    renamed
+}
+
+/// Generates `python3.dll` import library directly from the embedded
+/// Python Stable ABI definitions data for the specified compile target.
+///
+/// The import library file named `python3.dll.a` or `python3.lib` is created
+/// in directory `out_dir`.
+///
+/// The compile target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
+/// is passed in `arch`.
+///
+/// The compile target environment ABI name (as in `CARGO_CFG_TARGET_ENV`)
+/// is passed in `env`.
+///
+/// The `arch` and `env` values of a custom compile target are read from
+/// its target specification JSON file with [`TargetInfo::from_target_spec()`].
+pub fn generate_implib_for_target(out_dir: &Path, arch: &str, env: &str) -> Result<()> {
+    ImportLibraryGenerator::new(arch, env).generate(out_dir)
+}
+
+/// Verifies the import library file `implib` against its SHA-256
+/// checksum manifest written by [`ImportLibraryGenerator::checksum_manifest()`].
+///
+/// Fails with [`ErrorKind::InvalidData`] if the file contents do not match
+/// the manifest, and with [`ErrorKind::NotFound`] if there is no manifest.
+pub fn verify_checksum(implib: &Path) -> Result<()> {
+    let manifest = read_to_string(checksum_manifest_path(implib))?;
+
+    let mut fields = manifest.split_ascii_whitespace();
+    let expected = fields.next().unwrap_or_default();
+    let file_name = fields.next().unwrap_or_default();
+
+    // `sha256sum` marks the binary mode file names with `*`.
+    let file_name = file_name.strip_prefix('*').unwrap_or(file_name);
+
+    if implib.file_name() != Some(file_name.as_ref()) {
+        let msg = format!("Checksum manifest does not describe '{}'", implib.display());
+        return Err(Error::new(ErrorKind::InvalidData, msg));
+    }
+
+    let actual = sha256::sha256_hex(&read(implib)?);
+
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        let msg = format!("Checksum mismatch for '{}'", implib.display());
+        Err(Error::new(ErrorKind::InvalidData, msg))
+    }
+}
+
+/// Compares the embedded Python ABI definitions for `version`
+/// with the export table of an actual Python DLL at `dll_path`.
+///
+/// The Stable ABI `python3.dll` definitions are used when `version` is `None`.
+/// The replacement `.def` files from the `PYTHON3_DLL_A_DEF_DIR` directory
+/// take precedence over the embedded data, as usual.
+///
+/// This helps validating the definitions for the new Python releases and
+/// the custom Python distributions. Returns the report listing
+/// the discrepancies, fails if the DLL or the definitions can not be read.
+pub fn check_against_dll(version: Option<(u8, u8)>, dll_path: &Path) -> Result<DllReport> {
+    let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+    generator.version(version);
+
+    let def = generator.def_file()?;
+
+    let exports = pe::parse_exports(&read(dll_path)?).map_err(|e| {
+        let msg = format!("{}: {}", dll_path.display(), e);
+        Error::new(e.kind(), msg)
+    })?;
+
+    Ok(pe::compare_exports(&def, &exports.exports))
+}
+
+/// Writes the SHA-256 checksum manifest for the import library `implib`.
+fn write_checksum_manifest(implib: &Path) -> Result<()> {
+    let digest = sha256::sha256_hex(&read(implib)?);
+    let file_name = implib.file_name().unwrap_or_default().to_string_lossy();
+
+    write(
+        checksum_manifest_path(implib),
+        format!("{}  {}\n", digest, file_name),
+    )
+}
+
+/// Copies the generated import library into the cache.
+///
+/// The file is renamed into place, so that the concurrent builds
+/// never see a partially written cache entry.
+#[cfg(not(target_os = "wasi"))]
+fn store_cache_entry(implib: &Path, cache_entry: &Path) -> Result<()> {
+    let entry_dir = cache_entry.parent().unwrap_or(Path::new(""));
+    create_dir_all(entry_dir)?;
+
+    let temp = entry_dir.join(format!(".tmp-{}", std::process::id()));
+    copy(implib, &temp)?;
+    rename(&temp, cache_entry)
+}
+
+/// Returns a unique temporary sibling path for staging the `dir` contents.
+fn staging_path(dir: &Path) -> PathBuf {
+    static STAGING_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let count = STAGING_COUNTER.fetch_add(1, Ordering::Relaxed);
+    // The WASI hosts have no process IDs.
+    #[cfg(not(target_os = "wasi"))]
+    let id = format!("{}-{}", std::process::id(), count);
+    #[cfg(target_os = "wasi")]
+    let id = count.to_string();
+
+    let mut name = OsString::from(".");
+    name.push(dir.file_name().unwrap_or_default());
+    name.push(format!(".tmp-{}", id));
+
+    dir.with_file_name(name)
+}
+
+/// Moves the staged artifacts directory `staging` into place as `dir`.
+///
+/// The directory is renamed as a whole if `dir` does not exist yet,
+/// otherwise the staged files replace the existing ones one by one,
+/// so that the concurrent builds never see a partially written artifact.
+fn install_staged(staging: &Path, dir: &Path) -> Result<()> {
+    if rename(staging, dir).is_ok() {
+        return Ok(());
+    }
+
+    for entry in read_dir(staging)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            let path = dir.join(entry.file_name());
+            clear_read_only(&path)?;
+            rename(entry.path(), path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the CMake script next to the import library `implib`,
+/// importing it from the `installed` path.
+fn write_cmake_script(implib: &Path, installed: &Path) -> Result<()> {
+    let path = implib.with_file_name(format!("{}.cmake", integration::implib_stem(implib)));
+
+    // `IMPORTED_IMPLIB` must be an absolute path.
+    write(path, integration::cmake_script(&absolute_path(installed)?))
+}
+
+/// Extracts the warning messages from the import library generation
+/// tool output `messages`.
+///
+/// `lib.exe` reports the warnings as `<origin> : warning LNK<n>: <text>`,
+/// the `dlltool` flavors as `<tool>: warning: <text>`.
+#[cfg(not(target_os = "wasi"))]
+fn tool_warnings(messages: &str) -> Vec<String> {
+    messages
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.to_ascii_lowercase().contains("warning"))
+        .map(|line| match line.find(" : warning LNK") {
+            // Drop the `LIB` or `LINK` origin prefix.
+            Some(index) if matches!(&line[..index], "LIB" | "LINK") => line[index + 3..].to_owned(),
+            _ => line.to_owned(),
+        })
+        .collect()
+}
+
+/// Write-protects the generated artifact `path`.
+fn set_read_only(path: &Path) -> Result<()> {
+    let mut permissions = metadata(path)?.permissions();
+    permissions.set_readonly(true);
+
+    set_permissions(path, permissions)
+}
+
+/// Clears the read-only flag of the previously generated artifact `path`,
+/// if it exists.
+fn clear_read_only(path: &Path) -> Result<()> {
+    let mut permissions = match metadata(path) {
+        Ok(metadata) => metadata.permissions(),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if !permissions.readonly() {
+        return Ok(());
+    }
+
+    // Only restore the owner write permission on Unix.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        permissions.set_mode(permissions.mode() | 0o200);
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(false);
+
+    set_permissions(path, permissions)
+}
+
+/// Makes the relative `path` absolute by joining it to the current directory.
+fn absolute_path(path: &Path) -> Result<PathBuf> {
+    if path.is_absolute() {
+        Ok(path.to_owned())
+    } else {
+        Ok(env::current_dir()?.join(path))
+    }
+}
+
+/// Returns the checksum manifest file path for the import library `implib`.
+fn checksum_manifest_path(implib: &Path) -> PathBuf {
+    let mut path = implib.as_os_str().to_owned();
+    path.push(".sha256");
+
+    PathBuf::from(path)
+}
+
+/// Returns the JSON provenance report file path for the import library `implib`.
+fn report_file_path(implib: &Path) -> PathBuf {
+    let mut path = implib.as_os_str().to_owned();
+    path.push(".report.json");
+
+    PathBuf::from(path)
+}
+
+/// Returns the Make/Ninja depfile path for the import library `implib`.
+fn depfile_path(implib: &Path) -> PathBuf {
+    let mut path = implib.as_os_str().to_owned();
+    path.push(".d");
+
+    PathBuf::from(path)
+}
+
+/// Resolves the executed tool `program` name to the executable path.
+///
+/// The bare program names are looked up in `PATH`.
+fn tool_path(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+
+    if path.components().count() > 1 {
+        absolute_path(path).ok()
+    } else {
+        find_in_path(program)
+    }
+}
+
+/// Returns the program name and the arguments of `command`.
+#[cfg(not(target_os = "wasi"))]
+fn command_line(command: &Command) -> Vec<String> {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Moves the `command` arguments into the UTF-16 response file `rsp`.
+///
+/// Returns the same program invocation reading the arguments from `@<name>`
+/// in the response file directory, so that the response file path itself
+/// is passed as ASCII. The moved arguments must not be relative paths.
+#[cfg(not(target_os = "wasi"))]
+fn response_file_command(command: &Command, rsp: &Path) -> Result<Command> {
+    // The response file is UTF-16LE encoded with a BOM, one quoted argument per line.
+    let mut content = String::from('\u{feff}');
+    for arg in command.get_args() {
+        content.push_str(&format!("\"{}\"\r\n", arg.to_string_lossy()));
+    }
+
+    let bytes: Vec<u8> = content.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    write(rsp, bytes)?;
+
+    let mut rsp_arg = std::ffi::OsString::from("@");
+    rsp_arg.push(rsp.file_name().unwrap_or_default());
+
+    let mut rsp_command = Command::new(command.get_program());
+    rsp_command.arg(rsp_arg);
+
+    if let Some(dir) = rsp.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        rsp_command.current_dir(dir);
+    }
+
+    for (key, value) in command.get_envs() {
+        match value {
+            Some(value) => rsp_command.env(key, value),
+            None => rsp_command.env_remove(key),
+        };
+    }
+
+    Ok(rsp_command)
+}
+
+/// Returns the metadata marker file path for the import library `implib`.
+fn stamp_path(implib: &Path) -> PathBuf {
+    let mut path = implib.as_os_str().to_owned();
+    path.push(".stamp");
+
+    PathBuf::from(path)
+}
+
+/// Returns the embedded Module-Definition file contents.
+#[cfg(not(feature = "compress"))]
+fn decode_def_file(def: &'static str) -> Result<Cow<'static, str>> {
+    Ok(Cow::Borrowed(def))
+}
+
+/// Decompresses the embedded Module-Definition file contents.
+#[cfg(feature = "compress")]
+fn decode_def_file(def: &'static [u8]) -> Result<Cow<'static, str>> {
+    let data = miniz_oxide::inflate::decompress_to_vec(def).map_err(|e| {
+        let msg = format!("Failed to decompress the embedded definitions: {}", e);
+        Error::new(ErrorKind::InvalidData, msg)
+    })?;
+
+    String::from_utf8(data)
+        .map(Cow::Owned)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, e))
+}
+
+/// Rewrites the `EXPORTS` section of the module definition file `def`
+/// using the explicitly decorated 32-bit x86 symbol names.
+///
+/// Every `name` export is replaced with `_name == name`, so that
+/// the import library provides the `_name` and `__imp__name` symbols,
+/// while the DLL import name stays undecorated.
+fn decorate_x86_exports(def: &str) -> String {
+    let mut decorated = String::with_capacity(def.len() * 2);
+    let mut exports = false;
+
+    for line in def.lines() {
+        let trimmed = line.trim();
+
+        if exports && !trimmed.is_empty() && !trimmed.starts_with(';') {
+            let mut fields = trimmed.split_ascii_whitespace();
+
+            if let Some(name) = fields.next() {
+                decorated.push('_');
+                decorated.push_str(name);
+                decorated.push_str(" == ");
+                decorated.push_str(name);
+            }
+
+            // Keep the `DATA` keyword and other export attributes.
+            for field in fields {
+                decorated.push(' ');
+                decorated.push_str(field);
+            }
+        } else {
+            decorated.push_str(line);
+        }
+
+        decorated.push('\n');
+
+        if trimmed == "EXPORTS" {
+            exports = true;
+        }
+    }
+
+    decorated
+}
+
+/// Removes the private leading-underscore symbols from the `EXPORTS` section.
+fn exclude_private_exports(def: &str) -> String {
+    let mut filtered = String::with_capacity(def.len());
+    let mut exports = false;
+
+    for line in def.lines() {
+        let trimmed = line.trim();
+
+        if exports && trimmed.starts_with('_') {
+            continue;
+        }
+
+        filtered.push_str(line);
+        filtered.push('\n');
+
+        if trimmed == "EXPORTS" {
+            exports = true;
+        }
+    }
+
+    filtered
+}
+
+/// Removes the Stable ABI symbols added after the CPython `minimum` version
+/// from the `EXPORTS` section.
+///
+/// The symbols missing from the Stable ABI symbol table are kept.
+fn limit_stable_abi_exports(def: &str, minimum: (u8, u8)) -> String {
+    // The oldest embedded version symbols may have been added earlier.
+    let minimum = minimum.max((3, 7));
+
+    let mut filtered = String::with_capacity(def.len());
+    let mut exports = false;
+
+    for line in def.lines() {
+        let name = line.split_whitespace().next().unwrap_or_default();
+
+        let symbol = STABLE_ABI_SYMBOLS
+            .binary_search_by(|symbol| symbol.name.cmp(name))
+            .map(|index| STABLE_ABI_SYMBOLS[index]);
+
+        let newer = match symbol {
+            Ok(Symbol {
+                added_in: Some(added_in),
+                ..
+            }) => added_in > minimum,
+            Ok(Symbol { added_in: None, .. }) => true,
+            Err(_) => false,
+        };
+
+        if exports && newer {
+            continue;
+        }
+
+        filtered.push_str(line);
+        filtered.push('\n');
+
+        if line.trim() == "EXPORTS" {
+            exports = true;
+        }
+    }
+
+    filtered
+}
+
+/// Normalizes the target architecture name synonyms
+/// to the `CARGO_CFG_TARGET_ARCH` names.
+fn normalize_arch(arch: &str) -> String {
+    let lower = arch.to_ascii_lowercase();
+
+    match lower.as_str() {
+        "x86_64" | "x86-64" | "amd64" | "x64" => "x86_64".to_owned(),
+        "x86" | "i386" | "i486" | "i586" | "i686" | "win32" => "x86".to_owned(),
+        "aarch64" | "arm64" => "aarch64".to_owned(),
+        "arm" | "armv7" | "thumbv7a" => "arm".to_owned(),
+        // Keep the unknown names as is for the diagnostics.
+        _ => arch.to_owned(),
+    }
+}
+
+/// Normalizes the target environment ABI name synonyms
+/// to the `CARGO_CFG_TARGET_ENV` names.
+fn normalize_env(env: &str) -> String {
+    let lower = env.to_ascii_lowercase();
+
+    match lower.as_str() {
+        "msvc" => "msvc".to_owned(),
+        "gnu" | "gnullvm" | "mingw" | "mingw32" | "mingw64" | "mingw-w64" => "gnu".to_owned(),
+        _ => env.to_owned(),
+    }
+}
+
+/// Builds the non-Windows compile target error.
+fn not_a_windows_target(os: &str) -> Error {
+    let msg = format!("Not a Windows target: the target OS is '{}'", os);
+    ErrorCategory::NotAWindowsTarget.error(ErrorKind::Other, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::discovery::{DLLTOOL_GNU, DLLTOOL_MSVC};
+
+    #[cfg(unix)]
+    #[test]
+    fn generate() {
+        // FIXME: Use "target/<arch>" dirs for temporary files.
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86_64-pc-windows-gnu");
+        dir.push("python3-dll");
+
+        ImportLibraryGenerator::new("x86_64", "gnu")
+            .generate(&dir)
+            .unwrap();
+
+        for minor in 7..=13 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // Free-threaded CPython v3.13+
+        for minor in 13..=13 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .abiflags(Some("t"))
+                .generate(&dir)
+                .unwrap();
+        }
+
+        // PyPy
+        for minor in 7..=10 {
+            ImportLibraryGenerator::new("x86_64", "gnu")
+                .version(Some((3, minor)))
+                .implementation(PythonImplementation::PyPy)
+                .generate(&dir)
+                .unwrap();
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn generate_gnu32() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("i686-pc-windows-gnu");
+        dir.push("python3-dll");
+
+        generate_implib_for_target(&dir, "x86", "gnu").unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn x86_link_names() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("x86-link-names");
+
+        // The cdecl symbols are linked with a leading underscore.
+        let report = ImportLibraryGenerator::new("x86", "gnu")
+            .version(Some((3, 12)))
+            .decorate_x86_names(true)
+            .generate_with_report(&dir)
+            .unwrap();
+        let imports = verify::read_implib(&report.implib).unwrap();
+        let import = |name: &str| imports.iter().find(|i| i.name == name).unwrap().clone();
+        assert_eq!(import("Py_Initialize").symbol, "_Py_Initialize");
+        assert_eq!(import("PyExc_TypeError").symbol, "_PyExc_TypeError");
+        assert!(import("PyExc_TypeError").data);
+
+        // The stdcall `@<n>` suffixes are stripped from the import names only.
+        let def_dir = dir.join("defs");
+        create_dir_all(&def_dir).unwrap();
+        write(
+            def_dir.join("python399.def"),
+            "LIBRARY python399.dll\nEXPORTS\nPy_StdcallFunc@8\n",
+        )
+        .unwrap();
+
+        let report = ImportLibraryGenerator::new("x86", "gnu")
+            .version(Some((3, 99)))
+            .def_dir(Some(&def_dir))
+            .kill_at(true)
+            .generate_with_report(&dir)
+            .unwrap();
+        let imports = verify::read_implib(&report.implib).unwrap();
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].name, "Py_StdcallFunc");
+        assert_eq!(imports[0].symbol, "_Py_StdcallFunc@8");
+    }
+
+    #[test]
+    fn decorate_x86() {
+        let def = "LIBRARY python3.dll\nEXPORTS\n; comment\nPy_Initialize\nPyExc_TypeError DATA\n";
+        let decorated = decorate_x86_exports(def);
+
+        assert_eq!(
+            decorated,
+            "LIBRARY python3.dll\nEXPORTS\n; comment\n\
+             _Py_Initialize == Py_Initialize\n\
+             _PyExc_TypeError == PyExc_TypeError DATA\n"
+        );
+    }
+
+    #[test]
+    fn def_dir_override() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("def-override");
+
+        let def_dir = dir.join("defs");
+        create_dir_all(&def_dir).unwrap();
+
+        let def = "LIBRARY \"python399.dll\"\nEXPORTS\nPy_Initialize\n";
+        write(def_dir.join("python399.def"), def).unwrap();
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 99))).def_dir(Some(&def_dir));
+
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+        let written = read_to_string(&defpath).unwrap();
+        assert!(written.ends_with(def));
+
+        let metadata = DefMetadata::from_path(&defpath).unwrap().unwrap();
+        assert_eq!(metadata.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata.source, "override");
+        assert_eq!(metadata.source_sha256, sha256::sha256_hex(def.as_bytes()));
+        assert_eq!(metadata.target, "x86_64-msvc");
+        assert_eq!(metadata.python, "3.99");
+        assert!(metadata.options.is_empty());
+
+        // The replacement definitions problems are reported precisely.
+        write(
+            def_dir.join("python397.def"),
+            "LIBRARY python397.dll\nEXPORTS\nPy_Main @1\nPy_Exit @1\n",
+        )
+        .unwrap();
+        generator.version(Some((3, 97)));
+        let err = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err
+            .to_string()
+            .ends_with("line 4: ordinal @1 is already assigned to 'Py_Main' at line 3"));
+
+        // The JSON symbol lists are used if there is no .def file.
+        write(
+            def_dir.join("python398.json"),
+            r#"{"library": "python398.dll", "symbols": [{"name": "Py_Main", "ordinal": 3}]}"#,
+        )
+        .unwrap();
+        generator.version(Some((3, 98)));
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+        let written = read_to_string(&defpath).unwrap();
+        assert!(written.ends_with("LIBRARY python398.dll\nEXPORTS\nPy_Main @3\n"));
+
+        // Falls back to the embedded definitions.
+        generator.version(None);
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+        assert!(defpath.ends_with("python3.def"));
+    }
+
+    #[test]
+    fn embedded_def() {
+        let def = decode_def_file(def_file!("python3.def")).unwrap();
+
+        assert!(def.starts_with("LIBRARY python3.dll\nEXPORTS\n"));
+    }
+
+    #[cfg(not(feature = "py39"))]
+    #[test]
+    fn disabled_version() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("target");
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 9)))
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap_err();
+        assert!(err.to_string().contains("'py39'"));
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+    }
+
+    #[test]
+    fn relative_out_dir() {
+        // The tests run in the package root directory.
+        let dir = Path::new("target").join("relative-out-dir");
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnu")
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.implib, dir.join("python3.dll.a"));
+
+        let def_arg = absolute_path(&dir.join("python3.def")).unwrap();
+        assert!(def_arg.is_absolute());
+        assert!(report
+            .command
+            .contains(&def_arg.to_string_lossy().into_owned()));
+        assert!(report
+            .command
+            .iter()
+            .all(|arg| !arg.ends_with(".dll.a") || Path::new(arg).is_absolute()));
+    }
+
+    #[test]
+    fn response_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("response-file");
+        create_dir_all(&dir).unwrap();
+
+        let lib_exe = DllToolCommand::LibExe {
+            command: Command::new("lib.exe"),
+            machine: "X64".to_owned(),
+        };
+        let mut command = lib_exe.build(
+            Path::new("C:\\Users\\Jürgen\\python3.def"),
+            Path::new("C:\\Users\\Jürgen\\python3.lib"),
+            &DllToolOptions::default(),
+        );
+        command.env("VSCMD_ARG_TGT_ARCH", "x64");
+
+        let rsp = dir.join("python3.rsp");
+        let rsp_command = response_file_command(&command, &rsp).unwrap();
+
+        let args: Vec<_> = rsp_command.get_args().collect();
+        assert_eq!(args, ["@python3.rsp"]);
+        assert_eq!(rsp_command.get_current_dir(), Some(dir.as_path()));
+        assert_eq!(rsp_command.get_envs().count(), 1);
+
+        let bytes = read(&rsp).unwrap();
+        let units: Vec<_> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let content = String::from_utf16(&units).unwrap();
+        assert_eq!(
+            content,
+            "\u{feff}\"/MACHINE:X64\"\r\n\
+             \"/DEF:C:\\Users\\Jürgen\\python3.def\"\r\n\
+             \"/OUT:C:\\Users\\Jürgen\\python3.lib\"\r\n"
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let lib_exe = "Microsoft (R) Library Manager Version 14.38.33135.0\r\n\
+                       Copyright (C) Microsoft Corporation.  All rights reserved.\r\n\
+                       \r\n\
+                       LIB : warning LNK4272: library machine type 'x64' conflicts \
+                       with target machine type 'x86'\r\n\
+                       \x20  Creating library python3.lib and object python3.exp\r\n";
+        assert_eq!(
+            tool_warnings(lib_exe),
+            ["warning LNK4272: library machine type 'x64' conflicts with target machine type 'x86'"]
+        );
+
+        let mingw = "x86_64-w64-mingw32-dlltool: Warning, no exports found\n";
+        assert_eq!(
+            tool_warnings(mingw),
+            ["x86_64-w64-mingw32-dlltool: Warning, no exports found"]
+        );
+        assert!(tool_warnings("").is_empty());
+    }
+
+    #[test]
+    fn checksum_manifest() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("checksum");
+
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .checksum_manifest(true)
+            .generate(&dir)
+            .unwrap();
+
+        let implib = dir.join("python3.lib");
+        let manifest = read_to_string(dir.join("python3.lib.sha256")).unwrap();
+        assert!(manifest.ends_with("  python3.lib\n"));
+
+        verify_checksum(&implib).unwrap();
+
+        let mut data = read(&implib).unwrap();
+        data.push(b'\n');
+        write(&implib, data).unwrap();
+
+        let err = verify_checksum(&implib).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let err = verify_checksum(&dir.join("python3.def")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn export_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("export-file");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc").generate_with_report(&dir);
+        if let Ok(report) = report {
+            assert!(report.export_file.is_none());
+            assert!(!dir.join("python3.exp").exists());
+        }
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool(Some(Path::new("llvm-dlltool")))
+            .export_file(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+    }
+
+    #[test]
+    fn require_tool() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("require-tool");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .require_tool(Some(ToolFlavor::Llvm))
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, DLLTOOL_MSVC);
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool(Some(Path::new("llvm-dlltool")))
+            .require_tool(Some(ToolFlavor::LibExe))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::ToolMissing);
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .require_tool(Some(ToolFlavor::LibExe))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+
+        // The `gnullvm` targets get the LLVM flavor `.dll.a` import libraries.
+        let report = ImportLibraryGenerator::new("x86_64", "gnullvm")
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, DLLTOOL_MSVC);
+        assert_eq!(report.implib, dir.join("python3.dll.a"));
+    }
+
+    #[test]
+    fn llvm_dlltool_gnu() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("llvm-dlltool-gnu");
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnu")
+            .require_tool(Some(ToolFlavor::Llvm))
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, DLLTOOL_MSVC);
+        assert_eq!(report.implib, dir.join("python3.dll.a"));
+
+        let def = DefFile::from_path(&report.def).unwrap();
+        let implib = verify_implib(&report.implib, &def).unwrap();
+        assert!(implib.is_ok(), "{:?}", implib);
+
+        // There is no MinGW `dlltool` for AArch64.
+        let report = ImportLibraryGenerator::new("aarch64", "gnu")
+            .generate_with_report(&dir.join("aarch64"))
+            .unwrap();
+        assert_eq!(report.tool, DLLTOOL_MSVC);
+        assert!(report.command.contains(&"arm64".to_owned()));
+
+        let command = DllToolCommand::from_path(Path::new("/usr/bin/llvm-dlltool"), "x86", "gnu");
+        assert!(matches!(
+            command.unwrap(),
+            DllToolCommand::Llvm { ref machine, .. } if machine == "i386"
+        ));
+    }
+
+    #[test]
+    fn target_synonyms() {
+        let generator = ImportLibraryGenerator::new("AMD64", "MSVC");
+        assert_eq!(
+            (generator.arch.as_str(), generator.env.as_str()),
+            ("x86_64", "msvc")
+        );
+
+        let generator = ImportLibraryGenerator::new("arm64", "gnullvm");
+        assert_eq!(
+            (generator.arch.as_str(), generator.env.as_str()),
+            ("aarch64", "gnu")
+        );
+        assert_eq!(generator.env_name(), "gnullvm");
+        assert_eq!(
+            ImportLibraryGenerator::new("x86_64", "gnullvm").target_triple(),
+            "x86_64-pc-windows-gnullvm"
+        );
+
+        let generator = ImportLibraryGenerator::new("i686", "mingw");
+        assert_eq!(
+            (generator.arch.as_str(), generator.env.as_str()),
+            ("x86", "gnu")
+        );
+
+        let generator = ImportLibraryGenerator::new("riscv64", "Musl");
+        assert_eq!(
+            (generator.arch.as_str(), generator.env.as_str()),
+            ("riscv64", "Musl")
+        );
+    }
+
+    #[test]
+    fn non_windows() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("non-windows");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.target_os(Some("linux"));
+
+        let err = generator.generate(&dir).unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::NotAWindowsTarget);
+
+        generator.noop_on_non_windows(true);
+        assert!(generator.generate_all(&dir).unwrap().is_empty());
+        assert!(!dir.join("python3.dll.a").exists());
+
+        let err = generator.generate_with_report(&dir).unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::NotAWindowsTarget);
+    }
+
+    #[test]
+    fn abi() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+
+        generator.abi(Abi::Versioned {
+            version: (3, 13),
+            debug: true,
+            gil_disabled: true,
+        });
+        assert_eq!(
+            generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC),
+            Path::new("python313t_d.lib")
+        );
+
+        generator.abi(Abi::Abi3 { minimum: None });
+        assert_eq!(
+            generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC),
+            Path::new("python3.lib")
+        );
+        let all = generator.def_file().unwrap();
+        assert!(all.exports.iter().any(|export| export.name == "Py_Version"));
+
+        let limited = generator
+            .abi(Abi::Abi3 {
+                minimum: Some((3, 8)),
+            })
+            .def_file()
+            .unwrap();
+        assert!(limited.exports.len() < all.exports.len());
+        assert!(limited
+            .exports
+            .iter()
+            .all(|export| export.name != "Py_Version"));
+        assert!(limited
+            .exports
+            .iter()
+            .any(|export| export.name == "Py_Initialize"));
+    }
+
+    #[test]
+    fn exclude_private() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 12)));
+
+        let all = generator.def_file().unwrap();
+        assert!(all
+            .exports
+            .iter()
+            .any(|export| export.name == "_PyObject_GC_New"));
+
+        let public = generator.exclude_private(true).def_file().unwrap();
+        assert!(public
+            .exports
+            .iter()
+            .all(|export| !export.name.starts_with('_')));
+        assert!(public
+            .exports
+            .iter()
+            .any(|export| export.name == "PyObject_GC_Del"));
+        assert!(public.exports.len() < all.exports.len());
+        assert_eq!(public.dll_name().as_deref(), Some("python312.dll"));
+    }
+
+    #[test]
+    fn dll_name() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        assert_eq!(generator.dll_name().unwrap(), "python3.dll");
+        assert_eq!(generator.import_name().unwrap(), "python3");
+
+        generator.version(Some((3, 11))).debug(true);
+        assert_eq!(generator.dll_name().unwrap(), "python311_d.dll");
+        assert_eq!(generator.import_name().unwrap(), "python311_d");
+
+        generator
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .debug(false);
+        assert_eq!(generator.dll_name().unwrap(), "python313t.dll");
+
+        generator
+            .version(Some((3, 10)))
+            .abiflags(None)
+            .implementation(PythonImplementation::PyPy);
+        assert_eq!(generator.dll_name().unwrap(), "libpypy3.10-c.dll");
+    }
+
+    #[test]
+    fn unknown_version() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("unknown-version");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 99)));
+
+        let err = generator.def_file().unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+
+        generator.unknown_version(UnknownVersionPolicy::UseLatest);
+        let def = generator.def_file().unwrap();
+        assert_eq!(def.dll_name().as_deref(), Some("python399.dll"));
+        assert!(def.exports.len() > 1000);
+
+        let report = generator.generate_with_report(&dir).unwrap();
+        assert_eq!(
+            report.warnings[0],
+            "Unsupported Python version 3.99, using the Python 3.13 definitions"
+        );
+
+        generator.unknown_version(UnknownVersionPolicy::StableAbi);
+        let reports = generator.generate_all(&dir).unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].implib, dir.join("python3.lib"));
+        assert!(reports[0].warnings[0].starts_with("Unsupported Python version 3.99, "));
+
+        // PyPy has no Stable ABI library.
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 99)))
+            .implementation(PythonImplementation::PyPy)
+            .unknown_version(UnknownVersionPolicy::StableAbi)
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+    }
+
+    #[test]
+    fn overwrite() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("overwrite");
+        let _ = std::fs::remove_dir_all(&dir);
+        create_dir_all(&dir).unwrap();
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.dlltool(Some(Path::new(DLLTOOL_GNU)));
+
+        let implib = generator.implib_file_path(&dir, IMPLIB_EXT_GNU);
+        write(&implib, "vendor").unwrap();
+
+        let err = generator
+            .overwrite(OverwritePolicy::ErrorIfExists)
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(read_to_string(&implib).unwrap(), "vendor");
+
+        let report = generator
+            .overwrite(OverwritePolicy::SkipIfExists)
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, REUSED_TOOL);
+        assert_eq!(report.implib, implib);
+        assert!(report.def.is_file());
+        assert_eq!(read_to_string(&implib).unwrap(), "vendor");
+
+        let report = generator
+            .overwrite(OverwritePolicy::Overwrite)
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_ne!(report.tool, REUSED_TOOL);
+        assert_ne!(read_to_string(&implib).unwrap_or_default(), "vendor");
+    }
+
+    #[test]
+    fn line_ending() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("line-ending");
+        create_dir_all(&dir).unwrap();
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Msvc, false)
+            .unwrap();
+        let def = read_to_string(&defpath).unwrap();
+        assert!(def.contains("\r\nEXPORTS\r\n"));
+        assert_eq!(def.matches('\n').count(), def.matches("\r\n").count());
+
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+        assert!(!read_to_string(&defpath).unwrap().contains('\r'));
+
+        generator.line_ending(LineEnding::CrLf);
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+        assert!(read_to_string(&defpath)
+            .unwrap()
+            .contains("\r\nEXPORTS\r\n"));
+
+        generator.line_ending(LineEnding::Lf);
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Msvc, false)
+            .unwrap();
+        assert!(!read_to_string(&defpath).unwrap().contains('\r'));
+    }
+
+    #[test]
+    fn resolve() {
+        let dir = Path::new("out");
+
+        let resolved = ImportLibraryGenerator::new("x86_64", "msvc")
+            .dlltool(Some(Path::new("llvm-dlltool")))
+            .resolve(dir)
+            .unwrap();
+        assert_eq!(resolved.target, "x86_64-msvc");
+        assert_eq!(resolved.python, "abi3");
+        assert_eq!(resolved.dll_name, "python3.dll");
+        assert_eq!(resolved.def_source, "embedded");
+        assert_eq!(resolved.backend, Backend::DllTool);
+        assert_eq!(resolved.tool.as_deref(), Some("llvm-dlltool"));
+        assert_eq!(resolved.tool_source.as_deref(), Some("dlltool()"));
+        assert_eq!(resolved.implib, dir.join("python3.lib"));
+
+        let resolved = ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 12)))
+            .dlltool(Some(Path::new(DLLTOOL_GNU)))
+            .resolve(dir)
+            .unwrap();
+        assert_eq!(resolved.python, "3.12");
+        assert_eq!(resolved.implib, dir.join("python312.dll.a"));
 
-    Some(zig)
-}
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .target_os(Some("linux"))
+            .resolve(dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::NotAWindowsTarget);
+    }
 
-/// Finds Visual Studio `lib.exe` when running on Windows.
-#[cfg(windows)]
-fn find_lib_exe(arch: &str) -> Option<Command> {
-    let target = match arch {
-        "x86_64" => "x86_64-pc-windows-msvc",
-        "x86" => "i686-pc-windows-msvc",
-        "aarch64" => "aarch64-pc-windows-msvc",
-        _ => return None,
-    };
+    #[test]
+    fn probe_tools() {
+        let probes = super::probe_tools("x86_64", "gnu");
+        assert_eq!(probes.len(), 4);
+        assert_eq!(probes[0].flavor, ToolFlavor::Mingw);
+        assert!(probes[0].supports_target);
+        assert!(!probes[3].supports_target);
 
-    cc::windows_registry::find(target, LIB_MSVC)
-}
+        let probes = super::probe_tools("arm64", "gnu");
+        assert!(!probes[0].supports_target);
+        assert!(probes[0].install_hint("arm64").starts_with("hint: "));
 
-#[cfg(not(windows))]
-fn find_lib_exe(_arch: &str) -> Option<Command> {
-    None
-}
+        let probes = super::probe_tools("x86_64", "msvc");
+        let lib_exe = probes
+            .iter()
+            .find(|probe| probe.flavor == ToolFlavor::LibExe)
+            .unwrap();
+        assert!(lib_exe.supports_target);
+    }
 
-#[cfg(test)]
-mod tests {
-    use std::path::PathBuf;
+    #[test]
+    fn cache_dir() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("cache-dir");
+        let cache_dir = dir.join("cache");
+        let _ = std::fs::remove_dir_all(&cache_dir);
 
-    use super::*;
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.cache_dir(Some(&cache_dir)).verbosity(2);
+
+        let report = generator.generate_with_report(&dir).unwrap();
+        assert!(report.timings.tool > Duration::ZERO);
+        assert!(report.timings.total() >= report.timings.tool);
+
+        let entries: Vec<_> = std::fs::read_dir(&cache_dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+        let cache_entry = entries[0].as_ref().unwrap().path().join("python3.lib");
+
+        remove_file(&report.implib).unwrap();
+        let cached = generator.generate_with_report(&dir).unwrap();
+        assert!(cached.warnings.is_empty());
+        assert_eq!(read(&cached.implib).unwrap(), read(&cache_entry).unwrap());
+
+        // The cache entries are shared between the output directories.
+        let other = generator.generate_with_report(&dir.join("other")).unwrap();
+        assert_eq!(read(other.implib).unwrap(), read(cache_entry).unwrap());
+        assert_eq!(std::fs::read_dir(&cache_dir).unwrap().count(), 1);
+    }
 
-    #[cfg(unix)]
     #[test]
-    fn generate() {
-        // FIXME: Use "target/<arch>" dirs for temporary files.
+    fn cache_key() {
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("x86_64-pc-windows-gnu");
-        dir.push("python3-dll");
+        dir.push("cache-key");
+        create_dir_all(&dir).unwrap();
+
+        let generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        let defpath = generator
+            .write_def_file(&dir, DefDialect::Mingw, false)
+            .unwrap();
+
+        let tool = dir.join(DLLTOOL_MSVC);
+        write(&tool, b"first").unwrap();
+        let command = DllToolCommand::from_path(&tool, "x86_64", "msvc").unwrap();
+        let options = DllToolOptions::default();
+        let key = generator.cache_key(&command, &defpath, &options).unwrap();
+        assert_eq!(
+            generator.cache_key(&command, &defpath, &options).unwrap(),
+            key
+        );
+
+        // Replacing the tool in place invalidates the cached import libraries.
+        write(&tool, b"replaced").unwrap();
+        assert_ne!(
+            generator.cache_key(&command, &defpath, &options).unwrap(),
+            key
+        );
+    }
+
+    #[test]
+    fn read_only() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("read-only");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.read_only(true).checksum_manifest(true);
+
+        let report = generator.generate_with_report(&dir).unwrap();
+        for path in [&report.implib, &report.def, &dir.join("python3.lib.sha256")] {
+            assert!(metadata(path).unwrap().permissions().readonly());
+        }
+
+        // The read-only artifacts are regenerated in place.
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate_with_report(&dir)
+            .unwrap();
+        assert!(!metadata(&report.implib).unwrap().permissions().readonly());
+    }
+
+    #[test]
+    fn clean() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("clean");
+        create_dir_all(&dir).unwrap();
+
+        let other = dir.join("other.txt");
+        write(&other, "keep").unwrap();
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .also_stable_abi(true)
+            .read_only(true)
+            .checksum_manifest(true);
+
+        generator.generate(&dir).unwrap();
+
+        let removed = generator.clean(&dir).unwrap();
+        for name in ["python312.lib", "python312.def", "python3.lib.sha256"] {
+            assert!(removed.contains(&dir.join(name)));
+            assert!(!dir.join(name).exists());
+        }
+        assert!(other.is_file());
+
+        assert!(generator.clean(&dir).unwrap().is_empty());
+    }
+
+    #[test]
+    fn is_stale() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("is-stale");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.version(Some((3, 12))).also_stable_abi(true);
+
+        generator.clean(&dir).unwrap();
+        assert!(generator.is_stale(&dir));
+
+        generator.generate(&dir).unwrap();
+        assert!(!generator.is_stale(&dir));
+
+        let stamp = dir.join("python3.lib.stamp");
+        write(&stamp, "python3-dll-a 0.1.0\ndef-sha256 0\n").unwrap();
+        assert!(generator.is_stale(&dir));
+    }
+
+    #[test]
+    fn report_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("report-file");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .report_file(true)
+            .generate_with_report(&dir)
+            .unwrap();
+
+        assert_eq!(report.command[0], report.tool);
+        assert!(report
+            .command
+            .iter()
+            .any(|arg| arg.ends_with("python3.lib")));
+
+        let json = read_to_string(dir.join("python3.lib.report.json")).unwrap();
+        let implib_sha256 = format!("\"implib_sha256\": \"{}\"", report.implib_sha256().unwrap());
+        assert!(json.contains(&implib_sha256));
+        assert!(json.contains("\"command\": [\""));
+    }
+
+    #[test]
+    fn sbom() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("sbom");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.version(Some((3, 13))).abiflags(Some("t"));
+
+        let report = generator.generate_with_report(&dir).unwrap();
+        let sbom = generator.sbom(&report).unwrap();
+
+        assert!(sbom.contains("\"bomFormat\": \"CycloneDX\""));
+        assert!(sbom.contains("\"name\": \"python313t.dll.a\""));
+        assert!(sbom.contains(&report.def_sha256().unwrap()));
+        assert!(sbom.contains("{\"name\": \"python3-dll-a:python\", \"value\": \"3.13t\"}"));
+        assert!(sbom.contains("{\"name\": \"python3-dll-a:def-source\", \"value\": \"embedded\"}"));
+    }
+
+    #[test]
+    fn pyo3_config_file() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("pyo3-config-file");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .also_stable_abi(true)
+            .pyo3_config_file(true)
+            .generate(&dir)
+            .unwrap();
+
+        let config = read_to_string(dir.join("python313t.pyo3-config.txt")).unwrap();
+        assert_eq!(
+            config,
+            format!(
+                "implementation=CPython\n\
+                 version=3.13\n\
+                 shared=true\n\
+                 abi3=false\n\
+                 lib_name=python313t\n\
+                 lib_dir={}\n\
+                 pointer_width=64\n\
+                 build_flags=Py_GIL_DISABLED\n",
+                dir.display()
+            )
+        );
+        assert_eq!(config, generator.pyo3_config(&dir).unwrap());
+
+        let config = read_to_string(dir.join("python3.pyo3-config.txt")).unwrap();
+        assert!(config.contains("version=3.7\nshared=true\nabi3=true\nlib_name=python3\n"));
+
+        // The config round-trips through `from_pyo3_config()`.
+        let parsed = ImportLibraryGenerator::from_pyo3_config("x86_64", "gnu", &config).unwrap();
+        assert_eq!(parsed.version, None);
+        assert_eq!(parsed.abi3_minimum, Some((3, 7)));
+
+        let config = ImportLibraryGenerator::new("x86", "msvc")
+            .implementation(PythonImplementation::PyPy)
+            .version(Some((3, 10)))
+            .pyo3_config(&dir)
+            .unwrap();
+        assert!(config.starts_with("implementation=PyPy\nversion=3.10\n"));
+        assert!(config.contains("pointer_width=32\n"));
+
+        // The read-only config files are regenerated in place and cleaned up.
+        let config_file = dir.join("python313t.pyo3-config.txt");
+        generator.read_only(true);
+        generator.generate(&dir).unwrap();
+        assert!(metadata(&config_file).unwrap().permissions().readonly());
+        generator.generate(&dir).unwrap();
+
+        let removed = generator.clean(&dir).unwrap();
+        assert!(removed.contains(&config_file));
+        assert!(!config_file.exists());
+        assert!(!dir.join("python3.pyo3-config.txt").exists());
+    }
+
+    #[test]
+    fn delay_load() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("delay-load");
+
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .delay_load(true)
+            .generate(&dir)
+            .unwrap();
+
+        let rsp = read_to_string(dir.join("python312.delayload.rsp")).unwrap();
+        assert_eq!(rsp, "/DELAYLOAD:python312.dll\ndelayimp.lib\n");
+
+        let err = ImportLibraryGenerator::new("x86_64", "gnu")
+            .delay_load(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+
+    #[test]
+    fn library_statement() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator
+            .version(Some((3, 12)))
+            .library_base(Some(0x1e000000));
+
+        let def = generator.def_file_content().unwrap();
+        assert!(def.contains("\nLIBRARY \"python312.dll\" BASE=503316480\n"));
+        assert_eq!(generator.dll_name().unwrap(), "python312.dll");
+
+        generator
+            .library_name(Some("pythoncustom.dll"))
+            .library_extension(false);
+        let def = generator.def_file_content().unwrap();
+        assert!(def.contains("\nLIBRARY \"pythoncustom\" BASE=503316480\n"));
+        assert_eq!(generator.dll_name().unwrap(), "pythoncustom.dll");
+
+        generator.library_name(Some("my python.dll"));
+        assert_eq!(generator.dll_name().unwrap(), "my python.dll");
+        generator.library_name(Some("pythoncustom.dll"));
+
+        generator.debug(true).library_name(None).library_base(None);
+        let def = generator.def_file_content().unwrap();
+        assert!(def.contains("\nLIBRARY \"python312_d\"\n"));
+
+        let stable_abi = generator
+            .also_stable_abi(true)
+            .stable_abi_generator()
+            .unwrap();
+        assert_eq!(stable_abi.library_name, None);
+    }
+
+    #[test]
+    fn symbol_manifests() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("symbol-manifests");
 
         ImportLibraryGenerator::new("x86_64", "gnu")
+            .symbols_file(true)
+            .c_header(true)
             .generate(&dir)
             .unwrap();
 
-        for minor in 7..=13 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .generate(&dir)
+        let symbols = read_to_string(dir.join("python3.symbols.txt")).unwrap();
+        assert!(symbols.lines().any(|line| line == "Py_Initialize"));
+
+        let header = read_to_string(dir.join("python3.exports.h")).unwrap();
+        assert!(header.contains("#define PYTHON3_DLL_A_PYTHON3_DLL_NAME \"python3.dll\"\n"));
+        assert!(header.contains("    D(PyExc_TypeError) \\\n"));
+    }
+
+    #[test]
+    fn depfile() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("depfile");
+        let def_dir = dir.join("defs");
+        create_dir_all(&def_dir).unwrap();
+        write(
+            def_dir.join("python3.def"),
+            "LIBRARY python3.dll\nEXPORTS\nPy_Initialize\n",
+        )
+        .unwrap();
+
+        let report = ImportLibraryGenerator::new("x86_64", "gnu")
+            .def_dir(Some(&def_dir))
+            .dlltool(Some(Path::new(DLLTOOL_GNU)))
+            .depfile(true)
+            .generate_with_report(&dir)
+            .unwrap();
+
+        let depfile = read_to_string(depfile_path(&report.implib)).unwrap();
+        let (target, inputs) = depfile.lines().next().unwrap().split_once(": ").unwrap();
+        assert_eq!(target, report.implib.to_string_lossy());
+        assert!(inputs.starts_with(&*def_dir.join("python3.def").to_string_lossy()));
+        assert!(inputs.ends_with(DLLTOOL_GNU));
+    }
+
+    #[test]
+    fn def_link_args() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("def-link-args");
+
+        let args = ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 12)))
+            .generate_def_link_args(&dir)
+            .unwrap();
+        assert_eq!(args, [dir.join("python312.def").to_string_lossy()]);
+        assert!(!dir.join("python312.dll.a").exists());
+
+        let err = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate_def_link_args(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+
+    #[test]
+    fn reuse_existing() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("reuse-existing");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let generated = ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate_with_report(&dir)
+            .unwrap();
+
+        // A generated import library is regenerated as usual.
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .reuse_existing(true)
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_ne!(report.tool, REUSED_TOOL);
+
+        // Pretend it was copied from a Windows Python installation.
+        std::fs::remove_file(stamp_path(&generated.implib)).unwrap();
+        let contents = std::fs::read(&generated.implib).unwrap();
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .reuse_existing(true)
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, REUSED_TOOL);
+        assert_eq!(report.implib, generated.implib);
+        assert!(report.command.is_empty());
+        assert!(!stamp_path(&report.implib).exists());
+        assert_eq!(std::fs::read(&report.implib).unwrap(), contents);
+
+        // The mismatching import library is neither reused nor overwritten.
+        let err = ImportLibraryGenerator::new("i686", "msvc")
+            .reuse_existing(true)
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert_eq!(std::fs::read(&generated.implib).unwrap(), contents);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hermetic() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("hermetic");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.hermetic(true);
+
+        let err = generator.generate(&dir).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let err = generator
+            .dlltool(Some(Path::new(DLLTOOL_MSVC)))
+            .generate(&dir)
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        // The Zig CI jobs do not install LLVM.
+        let dlltool = env::split_paths(&env::var_os("PATH").unwrap())
+            .map(|dir| dir.join(DLLTOOL_MSVC))
+            .find(|path| path.is_file());
+
+        if let Some(dlltool) = dlltool {
+            let report = generator
+                .dlltool(Some(&dlltool))
+                .generate_with_report(&dir)
                 .unwrap();
+            assert_eq!(report.tool, dlltool.to_string_lossy());
+            assert_eq!(report.implib, dir.join("python3.lib"));
         }
+    }
 
-        // Free-threaded CPython v3.13+
-        for minor in 13..=13 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .abiflags(Some("t"))
-                .generate(&dir)
-                .unwrap();
+    #[test]
+    fn msvc_lib() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("msvc-lib");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.msvc_lib(true);
+        assert_eq!(generator.lib_env(), "msvc");
+        assert_eq!(
+            ImportLibraryGenerator::new("x86_64", "gnu").lib_env(),
+            "gnu"
+        );
+
+        // `llvm-dlltool` otherwise writes the `.dll.a` libraries for `gnu`.
+        let report = generator
+            .dlltool(Some(Path::new(DLLTOOL_MSVC)))
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.implib, dir.join("python3.lib"));
+        assert!(report.command.iter().any(|arg| arg == "i386:x86-64"));
+    }
+
+    #[test]
+    fn output_layout() {
+        let out_dir = Path::new("out");
+
+        let mut generator = ImportLibraryGenerator::new("x86", "gnu");
+        assert_eq!(generator.output_dir(out_dir), out_dir);
+
+        generator.layout(OutputLayout::PerTarget);
+        assert_eq!(
+            generator.output_dir(out_dir),
+            out_dir.join("i686-pc-windows-gnu")
+        );
+
+        generator.implementation(PythonImplementation::PyPy);
+        generator.version(Some((3, 10)));
+        assert_eq!(
+            generator.output_dir(out_dir),
+            out_dir.join("i686-pc-windows-gnu").join("pypy")
+        );
+
+        let mut generator = ImportLibraryGenerator::new("aarch64", "msvc");
+        generator
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .layout(OutputLayout::PerTargetVersion);
+        assert_eq!(
+            generator.output_dir(out_dir),
+            out_dir.join("aarch64-pc-windows-msvc").join("3.13t")
+        );
+
+        generator.layout(OutputLayout::Hashed);
+        let hashed = generator.output_dir(out_dir);
+        assert_eq!(hashed.parent(), Some(out_dir));
+        assert_eq!(hashed.file_name().unwrap().len(), 16);
+        assert_eq!(generator.clone().verbosity(2).output_dir(out_dir), hashed);
+        assert_eq!(
+            generator
+                .clone()
+                .overwrite(OverwritePolicy::SkipIfExists)
+                .output_dir(out_dir),
+            hashed
+        );
+        assert_ne!(
+            generator
+                .clone()
+                .library_base(Some(0x1e000000))
+                .output_dir(out_dir),
+            hashed
+        );
+        assert_ne!(generator.debug(true).output_dir(out_dir), hashed);
+    }
+
+    #[test]
+    fn hashed_settings() {
+        // The diagnostic, the overwrite handling and the layout settings
+        // do not affect the written artifacts.
+        const UNHASHED: &[&str] = &[
+            "layout",
+            "overwrite",
+            "reuse_existing",
+            "verbosity",
+            "cache_dir",
+            "deny_tool_warnings",
+            "noop_on_non_windows",
+        ];
+
+        let generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        let hashed: Vec<_> = generator
+            .hashed_settings()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+
+        let debug = format!("{:#?}", generator);
+        let fields = debug.lines().filter_map(|line| {
+            let name = line.strip_prefix("    ")?.split_once(':')?.0;
+            name.starts_with(|c: char| c.is_ascii_lowercase())
+                .then_some(name)
+        });
+
+        for field in fields {
+            assert!(
+                hashed.contains(&field) != UNHASHED.contains(&field),
+                "ImportLibraryGenerator::{} must be either hashed or listed as unhashed",
+                field
+            );
         }
+    }
 
-        // PyPy
-        for minor in 7..=10 {
-            ImportLibraryGenerator::new("x86_64", "gnu")
-                .version(Some((3, minor)))
-                .implementation(PythonImplementation::PyPy)
-                .generate(&dir)
-                .unwrap();
+    #[test]
+    fn hashed_layout() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("hashed-layout");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator.layout(OutputLayout::Hashed).pkg_config_file(true);
+        let out_dir = generator.output_dir(&dir);
+
+        // The identical configurations generated concurrently.
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let (generator, dir) = (generator.clone(), dir.clone());
+                std::thread::spawn(move || generator.generate_all(&dir).unwrap())
+            })
+            .collect();
+        for thread in threads {
+            let reports = thread.join().unwrap();
+            assert_eq!(reports[0].implib, out_dir.join("python3.dll.a"));
+            assert!(reports[0].implib.is_file());
         }
+
+        // Regenerating in the existing configuration directory.
+        let reports = generator.generate_all(&dir).unwrap();
+        assert_eq!(reports[0].def, out_dir.join("python3.def"));
+
+        let pc_file = read_to_string(out_dir.join("python3-dll-a-python3.pc")).unwrap();
+        let lib_dir = absolute_path(&out_dir).unwrap();
+        assert!(pc_file.contains(&*lib_dir.to_string_lossy()));
+
+        // No staging directories are left behind.
+        let entries: Vec<_> = read_dir(&dir).unwrap().map(|e| e.unwrap().path()).collect();
+        assert_eq!(entries, [out_dir]);
     }
 
-    #[cfg(unix)]
     #[test]
-    fn generate_gnu32() {
+    fn also_stable_abi() {
         let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
         dir.push("target");
-        dir.push("i686-pc-windows-gnu");
-        dir.push("python3-dll");
+        dir.push("also-stable-abi");
 
-        generate_implib_for_target(&dir, "x86", "gnu").unwrap();
+        let reports = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .also_stable_abi(true)
+            .generate_all(&dir)
+            .unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].implib, dir.join("python313t.lib"));
+        assert_eq!(reports[1].implib, dir.join("python3.lib"));
+
+        let reports = ImportLibraryGenerator::new("x86_64", "msvc")
+            .also_stable_abi(true)
+            .generate_all(&dir)
+            .unwrap();
+        assert_eq!(reports.len(), 1);
+    }
+
+    #[test]
+    fn export_stats() {
+        let stats = ImportLibraryGenerator::new("x86_64", "msvc")
+            .export_stats()
+            .unwrap();
+        assert_eq!(stats.total, stats.functions + stats.data);
+        assert!(stats.data > 0);
+
+        let stats = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 7)))
+            .export_stats()
+            .unwrap();
+        assert!(stats.total > 1000);
     }
 
     #[test]
@@ -682,4 +5169,80 @@ mod tests {
                 .unwrap();
         }
     }
+
+    #[cfg(feature = "builtin")]
+    #[test]
+    fn generate_builtin() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("builtin");
+        dir.push("python3-dll");
+
+        for (arch, env) in [
+            ("x86_64", "gnu"),
+            ("x86", "gnu"),
+            ("x86_64", "msvc"),
+            ("x86", "msvc"),
+            ("aarch64", "msvc"),
+        ] {
+            let dir = dir.join(format!("{}-{}", arch, env));
+
+            ImportLibraryGenerator::new(arch, env)
+                .backend(Backend::Builtin)
+                .generate(&dir)
+                .unwrap();
+
+            ImportLibraryGenerator::new(arch, env)
+                .version(Some((3, 13)))
+                .abiflags(Some("t"))
+                .backend(Backend::Builtin)
+                .generate(&dir)
+                .unwrap();
+
+            ImportLibraryGenerator::new(arch, env)
+                .version(Some((3, 10)))
+                .implementation(PythonImplementation::PyPy)
+                .backend(Backend::Builtin)
+                .generate(&dir)
+                .unwrap();
+        }
+
+        let implib = read(dir.join("x86_64-msvc").join("python3.lib")).unwrap();
+        assert!(implib.starts_with(b"!<arch>\n/               0 "));
+
+        let def = DefFile::from_path(&dir.join("x86-gnu").join("python3.def")).unwrap();
+        let report = verify_implib(&dir.join("x86-gnu").join("python3.dll.a"), &def).unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+
+        let err = ImportLibraryGenerator::new("riscv64", "msvc")
+            .backend(Backend::Builtin)
+            .generate(&dir)
+            .unwrap_err();
+        assert!(err.to_string().contains("riscv64"));
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn generate_mock() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("mock");
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 12)))
+            .backend(Backend::Mock)
+            .generate_with_report(&dir)
+            .unwrap();
+        assert_eq!(report.tool, "mock");
+        assert_eq!(read(&report.implib).unwrap(), mock::PLACEHOLDER_ARCHIVE);
+        assert!(report.def.is_file());
+
+        let invocation = mock::invocations()
+            .into_iter()
+            .find(|invocation| invocation.implib == report.implib)
+            .unwrap();
+        assert_eq!(invocation.dll_name, "python312.dll");
+        assert_eq!(invocation.env, "msvc");
+        assert_eq!(invocation.def, report.def);
+    }
 }
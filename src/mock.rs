@@ -0,0 +1,77 @@
+//! Mock import library backend for the downstream test suites
+//!
+//! `Backend::Mock` writes the Module-Definition file and a placeholder
+//! import library archive without running `dlltool` or any other external
+//! programs, and records every invocation in a process-wide log.
+//! The crates integrating `python3-dll-a` (e.g. PyO3) can exercise their
+//! build logic in the tests without the real toolchains installed.
+//!
+//! The mock backend can also be selected with `PYTHON3_DLL_A_BACKEND=mock`.
+//!
+//! This module is only available with the `test-support` crate feature enabled.
+//!
+//! Example usage
+//! -------------
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! use python3_dll_a::{mock, Backend, ImportLibraryGenerator};
+//!
+//! let out_dir = Path::new("target/python3-dll-a");
+//!
+//! ImportLibraryGenerator::new("x86_64", "gnu")
+//!     .backend(Backend::Mock)
+//!     .generate(out_dir)?;
+//!
+//! let invocations = mock::invocations();
+//! assert!(invocations.iter().any(|inv| inv.dll_name == "python3.dll"));
+//! # Ok::<(), std::io::Error>(())
+//! ```
+
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+/// Placeholder import library contents: an empty `ar` archive
+pub const PLACEHOLDER_ARCHIVE: &[u8] = b"!<arch>\n";
+
+/// Recorded mock backend invocations
+static INVOCATIONS: Mutex<Vec<MockInvocation>> = Mutex::new(Vec::new());
+
+/// Import library generation request recorded by the mock backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MockInvocation {
+    /// Target architecture
+    pub arch: String,
+    /// Target environment ABI
+    pub env: String,
+    /// Python DLL name, e.g. `python3.dll`
+    pub dll_name: String,
+    /// Written placeholder import library path
+    pub implib: PathBuf,
+    /// Written Module-Definition file path
+    pub def: PathBuf,
+}
+
+/// Returns the mock backend invocations recorded so far, oldest first.
+///
+/// The log is shared by the whole process, so the tests running
+/// concurrently should filter the invocations by the output paths.
+pub fn invocations() -> Vec<MockInvocation> {
+    lock().clone()
+}
+
+/// Clears the recorded mock backend invocations.
+pub fn clear_invocations() {
+    lock().clear();
+}
+
+/// Records the mock backend invocation.
+pub(crate) fn record(invocation: MockInvocation) {
+    lock().push(invocation);
+}
+
+/// Locks the invocation log, ignoring the poisoning by a panicked test.
+fn lock() -> MutexGuard<'static, Vec<MockInvocation>> {
+    INVOCATIONS.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,404 @@
+//! PE/COFF DLL export table parser
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::read;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::def::DefFile;
+use crate::verify::machine_name;
+
+/// `IMAGE_NT_OPTIONAL_HDR32_MAGIC`
+const PE32_MAGIC: u16 = 0x010b;
+/// `IMAGE_NT_OPTIONAL_HDR64_MAGIC`
+const PE32_PLUS_MAGIC: u16 = 0x020b;
+
+/// `IMAGE_SCN_MEM_EXECUTE`
+const SCN_MEM_EXECUTE: u32 = 0x2000_0000;
+
+/// DLL export table entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DllExport {
+    /// Exported symbol name
+    pub name: String,
+    /// Exported symbol type if known: `Some(true)` for data symbols
+    ///
+    /// The type of the forwarded exports is unknown.
+    pub data: Option<bool>,
+    /// Forwarder string, e.g. `python312.Py_Initialize`
+    pub forwarder: Option<String>,
+}
+
+/// DLL export table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DllExports {
+    /// DLL name stored in the export directory
+    pub dll_name: String,
+    /// Target architecture name (as in `CARGO_CFG_TARGET_ARCH`)
+    pub arch: String,
+    /// Named exports in the export name table order
+    pub exports: Vec<DllExport>,
+}
+
+/// Reads the export table of the PE DLL file at `path`.
+pub fn read_dll_exports(path: &Path) -> Result<DllExports> {
+    parse_exports(&read(path)?).map_err(|e| {
+        let msg = format!("{}: {}", path.display(), e);
+        Error::new(e.kind(), msg)
+    })
+}
+
+/// Parses the named exports of the PE DLL image `data`.
+pub(crate) fn parse_exports(data: &[u8]) -> Result<DllExports> {
+    if data.get(..2) != Some(b"MZ") {
+        return Err(invalid("Not a PE image"));
+    }
+
+    let pe = u32_at(data, 0x3c)? as usize;
+    if data.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return Err(invalid("Missing PE signature"));
+    }
+
+    let coff = pe + 4;
+    let machine = u16_at(data, coff)?;
+    let sections = u16_at(data, coff + 2)? as usize;
+    let optional = coff + 20;
+    let section_table = optional + u16_at(data, coff + 16)? as usize;
+
+    let data_directories = match u16_at(data, optional)? {
+        PE32_MAGIC => optional + 96,
+        PE32_PLUS_MAGIC => optional + 112,
+        _ => return Err(invalid("Unknown PE optional header magic")),
+    };
+
+    let directories = u32_at(data, data_directories - 4)?;
+    if directories == 0 {
+        return Err(invalid("No export directory"));
+    }
+
+    let export_rva = u32_at(data, data_directories)?;
+    let export_size = u32_at(data, data_directories + 4)?;
+    if export_rva == 0 {
+        return Err(invalid("No export directory"));
+    }
+
+    check_table(data, section_table, sections, 40, "Section table")?;
+
+    // (VirtualAddress, VirtualSize, PointerToRawData, Characteristics)
+    let mut section_headers = Vec::with_capacity(sections);
+    for index in 0..sections {
+        let header = section_table + 40 * index;
+        let raw_size = u32_at(data, header + 16)?;
+        let virtual_size = u32_at(data, header + 8)?.max(raw_size);
+
+        section_headers.push((
+            u32_at(data, header + 12)?,
+            virtual_size,
+            u32_at(data, header + 20)?,
+            u32_at(data, header + 36)?,
+        ));
+    }
+
+    let section_of = |rva: u32| {
+        section_headers
+            .iter()
+            .find(|(va, size, _, _)| *va <= rva && rva - *va < *size)
+    };
+
+    let offset_of = |rva: u32| {
+        section_of(rva)
+            .and_then(|(va, _, raw, _)| rva.checked_sub(*va)?.checked_add(*raw))
+            .map(|offset| offset as usize)
+            .ok_or_else(|| invalid("Export table RVA outside of the sections"))
+    };
+
+    let directory = offset_of(export_rva)?;
+    let dll_name = cstr_at(data, offset_of(u32_at(data, directory + 12)?)?)?;
+    let functions = u32_at(data, directory + 20)?;
+    let names = u32_at(data, directory + 24)? as usize;
+    let function_table = offset_of(u32_at(data, directory + 28)?)?;
+    let name_table = offset_of(u32_at(data, directory + 32)?)?;
+    let ordinal_table = offset_of(u32_at(data, directory + 36)?)?;
+
+    // The untrusted counts must not exceed the image size.
+    check_table(
+        data,
+        function_table,
+        functions as usize,
+        4,
+        "Export address table",
+    )?;
+    check_table(data, name_table, names, 4, "Export name table")?;
+    check_table(data, ordinal_table, names, 2, "Export ordinal table")?;
+
+    let mut exports = Vec::with_capacity(names);
+    for index in 0..names {
+        let name = cstr_at(data, offset_of(u32_at(data, name_table + 4 * index)?)?)?;
+
+        let ordinal = u16_at(data, ordinal_table + 2 * index)? as u32;
+        if ordinal >= functions {
+            return Err(invalid("Export ordinal out of range"));
+        }
+
+        let rva = u32_at(data, function_table + 4 * ordinal as usize)?;
+
+        let forwarded = rva >= export_rva && rva - export_rva < export_size;
+
+        let (data, forwarder) = if forwarded {
+            (None, Some(cstr_at(data, offset_of(rva)?)?))
+        } else {
+            let flags = section_of(rva).map(|(_, _, _, flags)| flags);
+            (flags.map(|flags| flags & SCN_MEM_EXECUTE == 0), None)
+        };
+
+        exports.push(DllExport {
+            name,
+            data,
+            forwarder,
+        });
+    }
+
+    Ok(DllExports {
+        dll_name,
+        arch: machine_name(machine),
+        exports,
+    })
+}
+
+/// Python DLL export table check report
+///
+/// Lists the symbol names which do not match between the embedded
+/// Module-Definition file and the actual DLL export table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DllReport {
+    /// Symbols listed in the definitions, but not exported by the DLL
+    pub missing: Vec<String>,
+    /// Symbols exported by the DLL, but not listed in the definitions
+    pub extra: Vec<String>,
+    /// Symbols with the mismatching code or data type
+    ///
+    /// The forwarded exports are never reported here.
+    pub wrong_type: Vec<String>,
+}
+
+impl DllReport {
+    /// Checks if the definitions match the DLL export table.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.wrong_type.is_empty()
+    }
+}
+
+/// Compares the module definitions `def` with the DLL `exports`.
+pub(crate) fn compare_exports(def: &DefFile, exports: &[DllExport]) -> DllReport {
+    let dll_exports: BTreeMap<_, _> = exports.iter().map(|e| (e.name.as_str(), e)).collect();
+
+    let mut report = DllReport::default();
+    let mut seen = HashSet::new();
+
+    for export in def.exports.iter().filter(|e| !e.noname) {
+        let name = export.import_name.as_deref().unwrap_or(&export.name);
+        seen.insert(name);
+
+        match dll_exports.get(name) {
+            None => report.missing.push(name.to_owned()),
+            Some(dll_export) => {
+                if dll_export.data.is_some() && dll_export.data != Some(export.data) {
+                    report.wrong_type.push(name.to_owned());
+                }
+            }
+        }
+    }
+
+    for name in dll_exports.keys() {
+        if !seen.contains(name) {
+            report.extra.push((*name).to_owned());
+        }
+    }
+
+    report
+}
+
+/// Checks that the `what` table of `count` entries of `entry_size` bytes
+/// at `offset` fits into the image `data`.
+fn check_table(
+    data: &[u8],
+    offset: usize,
+    count: usize,
+    entry_size: usize,
+    what: &str,
+) -> Result<()> {
+    let end = count
+        .checked_mul(entry_size)
+        .and_then(|size| size.checked_add(offset));
+
+    match end {
+        Some(end) if end <= data.len() => Ok(()),
+        _ => Err(invalid(&format!("{} outside of the PE image", what))),
+    }
+}
+
+/// Reads a little-endian `u16` value at `offset`.
+fn u16_at(data: &[u8], offset: usize) -> Result<u16> {
+    match data.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+        None => Err(invalid("Truncated PE image")),
+    }
+}
+
+/// Reads a little-endian `u32` value at `offset`.
+fn u32_at(data: &[u8], offset: usize) -> Result<u32> {
+    match data.get(offset..offset + 4) {
+        Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(invalid("Truncated PE image")),
+    }
+}
+
+/// Reads a NUL-terminated string at `offset`.
+fn cstr_at(data: &[u8], offset: usize) -> Result<String> {
+    let tail = data
+        .get(offset..)
+        .ok_or_else(|| invalid("Truncated PE image"))?;
+    let name = tail.split(|&b| b == 0).next().unwrap_or_default();
+
+    Ok(String::from_utf8_lossy(name).into_owned())
+}
+
+/// Builds the malformed PE image error.
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a minimal PE32+ DLL image exporting `names`,
+    /// the data symbols point into a non-executable section.
+    fn build_dll(names: &[(&str, bool)]) -> Vec<u8> {
+        let mut image = vec![0u8; 0xa00];
+        let put16 = |image: &mut Vec<u8>, offset: usize, value: u16| {
+            image[offset..offset + 2].copy_from_slice(&value.to_le_bytes())
+        };
+        let put32 = |image: &mut Vec<u8>, offset: usize, value: u32| {
+            image[offset..offset + 4].copy_from_slice(&value.to_le_bytes())
+        };
+
+        image[..2].copy_from_slice(b"MZ");
+        put32(&mut image, 0x3c, 0x40);
+        image[0x40..0x44].copy_from_slice(b"PE\0\0");
+        put16(&mut image, 0x44, 0x8664);
+        put16(&mut image, 0x46, 3);
+        put16(&mut image, 0x54, 0xf0);
+        put16(&mut image, 0x58, PE32_PLUS_MAGIC);
+        put32(&mut image, 0x58 + 108, 16);
+        put32(&mut image, 0x58 + 112, 0x3000);
+        put32(&mut image, 0x58 + 116, 0x200);
+
+        // .text, .data and .edata section headers
+        for (index, (va, raw, flags)) in [
+            (0x1000, 0x400, 0x6000_0020),
+            (0x2000, 0x600, 0xc000_0040),
+            (0x3000, 0x800, 0x4000_0040),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let header = 0x148 + 40 * index;
+            put32(&mut image, header + 8, 0x200);
+            put32(&mut image, header + 12, va);
+            put32(&mut image, header + 16, 0x200);
+            put32(&mut image, header + 20, raw);
+            put32(&mut image, header + 36, flags);
+        }
+
+        let count = names.len() as u32;
+        let functions = 0x3028;
+        let name_pointers = functions + 4 * count;
+        let ordinals = name_pointers + 4 * count;
+        let mut strings = ordinals + 2 * count;
+
+        put32(&mut image, 0x800 + 12, strings);
+        image[(strings - 0x2800) as usize..][..11].copy_from_slice(b"python3.dll");
+        strings += 12;
+
+        put32(&mut image, 0x800 + 20, count);
+        put32(&mut image, 0x800 + 24, count);
+        put32(&mut image, 0x800 + 28, functions);
+        put32(&mut image, 0x800 + 32, name_pointers);
+        put32(&mut image, 0x800 + 36, ordinals);
+
+        for (index, (name, data)) in names.iter().enumerate() {
+            let index = index as u32;
+            let rva = if *data { 0x2000 } else { 0x1000 } + 8 * index;
+
+            put32(&mut image, (functions - 0x2800 + 4 * index) as usize, rva);
+            put32(
+                &mut image,
+                (name_pointers - 0x2800 + 4 * index) as usize,
+                strings,
+            );
+            put16(
+                &mut image,
+                (ordinals - 0x2800 + 2 * index) as usize,
+                index as u16,
+            );
+
+            let offset = (strings - 0x2800) as usize;
+            image[offset..offset + name.len()].copy_from_slice(name.as_bytes());
+            strings += name.len() as u32 + 1;
+        }
+
+        image
+    }
+
+    #[test]
+    fn exports() {
+        let image = build_dll(&[("PyExc_TypeError", true), ("Py_Initialize", false)]);
+        let dll = parse_exports(&image).unwrap();
+        assert_eq!(dll.dll_name, "python3.dll");
+        assert_eq!(dll.arch, "x86_64");
+        let exports = dll.exports;
+
+        assert_eq!(exports.len(), 2);
+        assert_eq!(exports[0].name, "PyExc_TypeError");
+        assert_eq!(exports[0].data, Some(true));
+        assert_eq!(exports[1].data, Some(false));
+
+        let def =
+            DefFile::parse("LIBRARY python3.dll\nEXPORTS\nPyExc_TypeError\nPy_Main\n").unwrap();
+        let report = compare_exports(&def, &exports);
+        assert_eq!(report.missing, ["Py_Main"]);
+        assert_eq!(report.extra, ["Py_Initialize"]);
+        assert_eq!(report.wrong_type, ["PyExc_TypeError"]);
+
+        assert!(parse_exports(b"MZ").is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let image = build_dll(&[("Py_Initialize", false)]);
+
+        // Huge export name count
+        let mut mutated = image.clone();
+        mutated[0x800 + 24..0x800 + 28].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = parse_exports(&mutated).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        // Huge export function count
+        let mut mutated = image.clone();
+        mutated[0x800 + 20..0x800 + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(parse_exports(&mutated).is_err());
+
+        // Section table past the end of the image
+        let mut mutated = image.clone();
+        mutated[0x46..0x48].copy_from_slice(&u16::MAX.to_le_bytes());
+        assert!(parse_exports(&mutated).is_err());
+
+        // Section raw data pointer overflowing the offset computation
+        let mut mutated = image;
+        let edata = 0x148 + 40 * 2;
+        mutated[edata + 20..edata + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = parse_exports(&mutated).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
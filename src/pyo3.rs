@@ -0,0 +1,292 @@
+//! PyO3 cross-compilation helpers
+//!
+//! [`ImportLibraryGenerator::generate_cross_lib_dir()`] populates a directory
+//! suitable for `PYO3_CROSS_LIB_DIR` with both the version-specific
+//! and the Stable ABI import libraries, so that no files need to be copied
+//! from a Windows Python installation:
+//!
+//! ```no_run
+//! # use std::path::Path;
+//! # use python3_dll_a::ImportLibraryGenerator;
+//! ImportLibraryGenerator::new("x86_64", "gnu")
+//!     .version(Some((3, 12)))
+//!     .generate_cross_lib_dir(Path::new("target/python3-cross"))
+//!     .unwrap();
+//! ```
+//!
+//! ```sh
+//! PYO3_CROSS_LIB_DIR=target/python3-cross PYO3_CROSS_PYTHON_VERSION=3.12 \
+//!     cargo build --target x86_64-pc-windows-gnu
+//! ```
+//!
+//! [`ImportLibraryGenerator::pyo3_config()`] returns a ready-to-use
+//! `PYO3_CONFIG_FILE` interpreter configuration instead, which also sets
+//! the import library name and directory, and the free-threaded and debug build
+//! flags. [`ImportLibraryGenerator::pyo3_config_file()`] writes it next to
+//! the generated import library, e.g. `python312.pyo3-config.txt`:
+//!
+//! ```sh
+//! PYO3_CONFIG_FILE=$PWD/target/python3-cross/python312.pyo3-config.txt \
+//!     cargo build --target x86_64-pc-windows-gnu
+//! ```
+//!
+//! [`ImportLibraryGenerator::pyo3_cross_env()`] returns the `PYO3_CROSS_*` environment
+//! variables for the generated directory. The `cross-env` command of the
+//! command line utility generates the import libraries and prints the matching
+//! shell exports, which can be evaluated before the `cargo build`:
+//!
+//! ```sh
+//! eval "$(python3-dll-a cross-env --target x86_64-pc-windows-gnu --python-version 3.12)"
+//! ```
+//!
+//! If `PYO3_CROSS_LIB_DIR` may already contain the genuine `python3.lib`
+//! or `python311.lib` copied from a Windows Python installation,
+//! [`ImportLibraryGenerator::reuse_existing()`] keeps it instead of generating
+//! a new one, after checking that it matches the target and the definitions.
+
+use std::io::Result;
+use std::path::Path;
+
+use crate::{absolute_path, GenerationReport, ImportLibraryGenerator, PythonImplementation};
+
+impl ImportLibraryGenerator {
+    /// Generates the import libraries PyO3 links against when cross-compiling
+    /// with `PYO3_CROSS_LIB_DIR` set to `out_dir`.
+    ///
+    /// For the version-specific CPython targets both the `pythonXY.dll`
+    /// and the Stable ABI `python3.dll` import libraries are generated,
+    /// so the directory works for both the regular and the `abi3` builds.
+    /// PyO3 needs no other files from a Windows Python installation,
+    /// but the Python version still has to be set with `PYO3_CROSS_PYTHON_VERSION`
+    /// unless an `abi3-pyXY` feature is used.
+    ///
+    /// Returns the generation reports for all the import libraries.
+    pub fn generate_cross_lib_dir(&self, out_dir: &Path) -> Result<Vec<GenerationReport>> {
+        self.clone().also_stable_abi(true).generate_all(out_dir)
+    }
+
+    /// Returns the PyO3 interpreter configuration for building against
+    /// the import library generated into `out_dir`.
+    ///
+    /// The result is the serialized `pyo3-build-config` `InterpreterConfig`
+    /// text read from `PYO3_CONFIG_FILE`, the inverse of
+    /// [`from_pyo3_config()`](Self::from_pyo3_config). It lists
+    /// the `implementation`, `version`, `shared`, `abi3`, `lib_name`,
+    /// `lib_dir` (absolute), `pointer_width` and `build_flags` values,
+    /// so that PyO3 needs neither a Python interpreter nor any Windows
+    /// files on the build host. The Stable ABI configuration lists
+    /// the minimum Stable ABI version, Python 3.7 by default.
+    pub fn pyo3_config(&self, out_dir: &Path) -> Result<String> {
+        if let Some(stable_abi) = self.unknown_version_stable_abi()? {
+            return stable_abi.pyo3_config(out_dir);
+        }
+
+        let lib_dir = absolute_path(&self.output_dir(out_dir))?;
+
+        Ok(self.pyo3_config_for(&lib_dir, &self.import_name()?))
+    }
+
+    /// Formats the PyO3 interpreter configuration for the `lib_name`
+    /// import library in `lib_dir`.
+    pub(crate) fn pyo3_config_for(&self, lib_dir: &Path, lib_name: &str) -> String {
+        let implementation = match self.implementation {
+            PythonImplementation::CPython => "CPython",
+            PythonImplementation::PyPy => "PyPy",
+        };
+
+        let (major, minor) = self.version.or(self.abi3_minimum).unwrap_or((3, 7));
+
+        let pointer_width = match self.arch.as_str() {
+            "x86" | "arm" => 32,
+            _ => 64,
+        };
+
+        let mut config = format!(
+            "implementation={}\n\
+             version={}.{}\n\
+             shared=true\n\
+             abi3={}\n\
+             lib_name={}\n\
+             lib_dir={}\n\
+             pointer_width={}\n",
+            implementation,
+            major,
+            minor,
+            self.version.is_none(),
+            lib_name,
+            lib_dir.display(),
+            pointer_width
+        );
+
+        let mut build_flags = Vec::new();
+        if self.debug {
+            build_flags.push("Py_DEBUG");
+        }
+        if self.abiflags.as_deref() == Some("t") {
+            build_flags.push("Py_GIL_DISABLED");
+        }
+        if !build_flags.is_empty() {
+            config.push_str(&format!("build_flags={}\n", build_flags.join(",")));
+        }
+
+        config
+    }
+
+    /// Returns the PyO3 cross-compilation environment variables for building
+    /// against the import libraries generated into `out_dir`, e.g. with
+    /// [`generate_cross_lib_dir()`](Self::generate_cross_lib_dir).
+    ///
+    /// Lists the absolute `PYO3_CROSS_LIB_DIR` path and, for the version-specific
+    /// DLLs, `PYO3_CROSS_PYTHON_VERSION` and `PYO3_CROSS_PYTHON_IMPLEMENTATION`,
+    /// the same variables [`from_pyo3_env()`](Self::from_pyo3_env) reads.
+    pub fn pyo3_cross_env(&self, out_dir: &Path) -> Result<Vec<(&'static str, String)>> {
+        let lib_dir = absolute_path(&self.output_dir(out_dir))?;
+
+        let mut vars = vec![("PYO3_CROSS_LIB_DIR", lib_dir.display().to_string())];
+
+        if let Some((major, minor)) = self.version {
+            let implementation = match self.implementation {
+                PythonImplementation::CPython => "CPython",
+                PythonImplementation::PyPy => "PyPy",
+            };
+
+            let abiflags = self.abiflags.as_deref().unwrap_or_default();
+            let version = format!("{}.{}{}", major, minor, abiflags);
+
+            vars.push(("PYO3_CROSS_PYTHON_VERSION", version));
+            vars.push((
+                "PYO3_CROSS_PYTHON_IMPLEMENTATION",
+                implementation.to_owned(),
+            ));
+        }
+
+        Ok(vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{ErrorCategory, IMPLIB_EXT_MSVC};
+
+    #[test]
+    fn pyo3_config() {
+        let config = "implementation=CPython\n\
+                      version=3.12\n\
+                      shared=true\n\
+                      abi3=false\n\
+                      lib_name=python312_d\n\
+                      build_flags=Py_DEBUG\n\
+                      suppress_build_script_link_lines=false\n";
+
+        let generator = ImportLibraryGenerator::from_pyo3_config("x86_64", "msvc", config).unwrap();
+        assert_eq!(generator.version, Some((3, 12)));
+        assert!(generator.debug);
+
+        let def = generator.def_file().unwrap();
+        assert_eq!(def.dll_name().as_deref(), Some("python312_d.dll"));
+        assert_eq!(
+            generator.implib_file_path(Path::new(""), IMPLIB_EXT_MSVC),
+            Path::new("python312_d.lib")
+        );
+
+        let config = "implementation=CPython\nversion=3.13\nabi3=true\nbuild_flags=\n";
+        let generator = ImportLibraryGenerator::from_pyo3_config("x86", "gnu", config).unwrap();
+        assert_eq!(generator.version, None);
+        assert_eq!(generator.abi3_minimum, Some((3, 13)));
+        assert!(!generator.debug);
+
+        let config = "version=3.13\nbuild_flags=Py_GIL_DISABLED\n";
+        let generator = ImportLibraryGenerator::from_pyo3_config("x86", "gnu", config).unwrap();
+        assert_eq!(generator.def_file_name().unwrap(), "python313t.def");
+
+        let err = ImportLibraryGenerator::from_pyo3_config("x86", "gnu", "implementation=GraalVM")
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+        assert!(ImportLibraryGenerator::from_pyo3_config("x86", "gnu", "version").is_err());
+    }
+
+    #[test]
+    fn pyo3_env() {
+        let vars = |version: &'static str, implementation: &'static str| {
+            move |name: &str| match name {
+                "PYO3_CROSS_PYTHON_VERSION" => Some(version.to_owned()),
+                "PYO3_CROSS_PYTHON_IMPLEMENTATION" => Some(implementation.to_owned()),
+                _ => None,
+            }
+        };
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.apply_pyo3_env(vars("3.13t", "")).unwrap();
+        assert_eq!(generator.version, Some((3, 13)));
+        assert_eq!(generator.abiflags.as_deref(), Some("t"));
+        assert!(matches!(
+            generator.implementation,
+            PythonImplementation::CPython
+        ));
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.apply_pyo3_env(vars("3.10", "PyPy")).unwrap();
+        assert_eq!(generator.def_file_name().unwrap(), "libpypy3.10-c.def");
+
+        let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+        generator.apply_pyo3_env(vars("", "")).unwrap();
+        assert_eq!(generator.version, None);
+
+        let err = generator.apply_pyo3_env(vars("3", "")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        let err = generator
+            .apply_pyo3_env(vars("3.11", "GraalVM"))
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cross_lib_dir() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("cross-lib-dir");
+
+        let reports = ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 12)))
+            .generate_cross_lib_dir(&dir)
+            .unwrap();
+
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].implib, dir.join("python312.dll.a"));
+        assert_eq!(reports[1].implib, dir.join("python3.dll.a"));
+
+        let reports = ImportLibraryGenerator::new("x86_64", "gnu")
+            .implementation(PythonImplementation::PyPy)
+            .version(Some((3, 10)))
+            .generate_cross_lib_dir(&dir)
+            .unwrap();
+
+        assert_eq!(reports.len(), 1);
+
+        let vars = ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .pyo3_cross_env(&dir)
+            .unwrap();
+        assert_eq!(
+            vars,
+            [
+                ("PYO3_CROSS_LIB_DIR", dir.display().to_string()),
+                ("PYO3_CROSS_PYTHON_VERSION", "3.13t".to_owned()),
+                ("PYO3_CROSS_PYTHON_IMPLEMENTATION", "CPython".to_owned()),
+            ]
+        );
+
+        let vars = ImportLibraryGenerator::new("x86_64", "gnu")
+            .pyo3_cross_env(Path::new("cross"))
+            .unwrap();
+        assert_eq!(vars.len(), 1);
+        assert!(Path::new(&vars[0].1).is_absolute());
+    }
+}
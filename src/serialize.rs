@@ -0,0 +1,354 @@
+//! `serde` support for the generator configuration
+//!
+//! [`ImportLibraryGenerator`] is serialized as a map keyed by the builder
+//! method names, e.g. `version` or `kill_at`, the enum values are serialized
+//! as the config file names, e.g. `"use-latest"` or `"per-target"`.
+//! The keys missing from the deserialized map keep the default values,
+//! only `arch` and `env` are required.
+//!
+//! This module is only available with the `serde` crate feature enabled.
+
+#[cfg(feature = "download")]
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::de::{self, MapAccess, Visitor};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[cfg(feature = "download")]
+use crate::download::PinnedTool;
+use crate::{
+    normalize_env, Backend, ImportLibraryGenerator, LineEnding, OutputLayout, OverwritePolicy,
+    PythonImplementation, ToolFlavor, UnknownVersionPolicy,
+};
+
+/// Implements `Serialize` and `Deserialize` for the plain enum `$type`
+/// as the listed variant names.
+macro_rules! string_enum {
+    ($type:ty { $($(#[$attr:meta])* $variant:path => $name:literal,)* }) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let name = match self {
+                    $($(#[$attr])* $variant => $name,)*
+                };
+                serializer.serialize_str(name)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                match name.as_str() {
+                    $($(#[$attr])* $name => Ok($variant),)*
+                    _ => Err(de::Error::unknown_variant(&name, &[$($name),*])),
+                }
+            }
+        }
+    };
+}
+
+string_enum!(PythonImplementation {
+    PythonImplementation::CPython => "cpython",
+    PythonImplementation::PyPy => "pypy",
+});
+
+string_enum!(UnknownVersionPolicy {
+    UnknownVersionPolicy::Error => "error",
+    UnknownVersionPolicy::UseLatest => "use-latest",
+    UnknownVersionPolicy::StableAbi => "stable-abi",
+});
+
+string_enum!(OverwritePolicy {
+    OverwritePolicy::Overwrite => "overwrite",
+    OverwritePolicy::SkipIfExists => "skip-if-exists",
+    OverwritePolicy::ErrorIfExists => "error-if-exists",
+});
+
+string_enum!(LineEnding {
+    LineEnding::Auto => "auto",
+    LineEnding::Lf => "lf",
+    LineEnding::CrLf => "crlf",
+});
+
+string_enum!(Backend {
+    Backend::DllTool => "dlltool",
+    #[cfg(feature = "builtin")]
+    Backend::Builtin => "builtin",
+    #[cfg(feature = "test-support")]
+    Backend::Mock => "mock",
+});
+
+string_enum!(ToolFlavor {
+    ToolFlavor::Mingw => "mingw",
+    ToolFlavor::Llvm => "llvm",
+    ToolFlavor::LibExe => "lib",
+    ToolFlavor::Zig => "zig",
+});
+
+string_enum!(OutputLayout {
+    OutputLayout::Flat => "flat",
+    OutputLayout::PerTarget => "per-target",
+    OutputLayout::PerTargetVersion => "per-target-version",
+    OutputLayout::Hashed => "hashed",
+});
+
+/// Serialized generator configuration keys
+const FIELDS: &[&str] = &[
+    "arch",
+    "env",
+    "target_os",
+    "noop_on_non_windows",
+    "version",
+    "abi3_minimum",
+    "implementation",
+    "abiflags",
+    "unknown_version",
+    "overwrite",
+    "line_ending",
+    "debug",
+    "kill_at",
+    "no_leading_underscore",
+    "temp_prefix",
+    "assembler",
+    "assembler_flags",
+    "decorate_x86_names",
+    "exclude_private",
+    "library_name",
+    "library_extension",
+    "library_base",
+    "def_dir",
+    "backend",
+    "require_tool",
+    "checksum_manifest",
+    "cmake_script",
+    "pkg_config_file",
+    "pyo3_config_file",
+    "delay_load",
+    "export_file",
+    "symbols_file",
+    "depfile",
+    "reuse_existing",
+    "c_header",
+    "also_stable_abi",
+    "read_only",
+    "report_file",
+    "deny_tool_warnings",
+    "layout",
+    "dlltool",
+    "lib_exe",
+    "msvc_lib",
+    #[cfg(feature = "download")]
+    "download_dlltool",
+    "hermetic",
+    "cache_dir",
+    "verbosity",
+];
+
+impl Serialize for ImportLibraryGenerator {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ImportLibraryGenerator", FIELDS.len())?;
+
+        s.serialize_field("arch", &self.arch)?;
+        s.serialize_field("env", self.env_name())?;
+        s.serialize_field("target_os", &self.target_os)?;
+        s.serialize_field("noop_on_non_windows", &self.noop_on_non_windows)?;
+        s.serialize_field("version", &self.version)?;
+        s.serialize_field("abi3_minimum", &self.abi3_minimum)?;
+        s.serialize_field("implementation", &self.implementation)?;
+        s.serialize_field("abiflags", &self.abiflags)?;
+        s.serialize_field("unknown_version", &self.unknown_version)?;
+        s.serialize_field("overwrite", &self.overwrite)?;
+        s.serialize_field("line_ending", &self.line_ending)?;
+        s.serialize_field("debug", &self.debug)?;
+        s.serialize_field("kill_at", &self.options.kill_at)?;
+        s.serialize_field("no_leading_underscore", &self.options.no_leading_underscore)?;
+        s.serialize_field("temp_prefix", &self.options.temp_prefix)?;
+        s.serialize_field("assembler", &self.options.assembler)?;
+        s.serialize_field("assembler_flags", &self.options.assembler_flags)?;
+        s.serialize_field("decorate_x86_names", &self.decorate_x86_names)?;
+        s.serialize_field("exclude_private", &self.exclude_private)?;
+        s.serialize_field("library_name", &self.library_name)?;
+        s.serialize_field("library_extension", &self.library_extension)?;
+        s.serialize_field("library_base", &self.library_base)?;
+        s.serialize_field("def_dir", &self.def_dir)?;
+        s.serialize_field("backend", &self.backend)?;
+        s.serialize_field("require_tool", &self.require_tool)?;
+        s.serialize_field("checksum_manifest", &self.checksum_manifest)?;
+        s.serialize_field("cmake_script", &self.cmake_script)?;
+        s.serialize_field("pkg_config_file", &self.pkg_config_file)?;
+        s.serialize_field("pyo3_config_file", &self.pyo3_config_file)?;
+        s.serialize_field("delay_load", &self.delay_load)?;
+        s.serialize_field("export_file", &self.export_file)?;
+        s.serialize_field("symbols_file", &self.symbols_file)?;
+        s.serialize_field("depfile", &self.depfile)?;
+        s.serialize_field("reuse_existing", &self.reuse_existing)?;
+        s.serialize_field("c_header", &self.c_header)?;
+        s.serialize_field("also_stable_abi", &self.also_stable_abi)?;
+        s.serialize_field("read_only", &self.read_only)?;
+        s.serialize_field("report_file", &self.report_file)?;
+        s.serialize_field("deny_tool_warnings", &self.deny_tool_warnings)?;
+        s.serialize_field("layout", &self.layout)?;
+        s.serialize_field("dlltool", &self.dlltool)?;
+        s.serialize_field("lib_exe", &self.lib_exe)?;
+        s.serialize_field("msvc_lib", &self.msvc_lib)?;
+        #[cfg(feature = "download")]
+        s.serialize_field("download_dlltool", &self.download_dlltool)?;
+        s.serialize_field("hermetic", &self.hermetic)?;
+        s.serialize_field("cache_dir", &self.cache_dir)?;
+        s.serialize_field("verbosity", &self.verbosity)?;
+
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for ImportLibraryGenerator {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_struct("ImportLibraryGenerator", FIELDS, GeneratorVisitor)
+    }
+}
+
+/// Generator configuration map visitor
+struct GeneratorVisitor;
+
+impl<'de> Visitor<'de> for GeneratorVisitor {
+    type Value = ImportLibraryGenerator;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an import library generator configuration map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut arch: Option<String> = None;
+        let mut env: Option<String> = None;
+        let mut g = ImportLibraryGenerator::new("", "");
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "arch" => arch = Some(map.next_value()?),
+                "env" => env = Some(map.next_value()?),
+                "target_os" => g.target_os = map.next_value()?,
+                "noop_on_non_windows" => g.noop_on_non_windows = map.next_value()?,
+                "version" => g.version = map.next_value()?,
+                "abi3_minimum" => g.abi3_minimum = map.next_value()?,
+                "implementation" => g.implementation = map.next_value()?,
+                "abiflags" => g.abiflags = map.next_value()?,
+                "unknown_version" => g.unknown_version = map.next_value()?,
+                "overwrite" => g.overwrite = map.next_value()?,
+                "line_ending" => g.line_ending = map.next_value()?,
+                "debug" => g.debug = map.next_value()?,
+                "kill_at" => g.options.kill_at = map.next_value()?,
+                "no_leading_underscore" => g.options.no_leading_underscore = map.next_value()?,
+                "temp_prefix" => g.options.temp_prefix = map.next_value()?,
+                "assembler" => g.options.assembler = map.next_value()?,
+                "assembler_flags" => g.options.assembler_flags = map.next_value()?,
+                "decorate_x86_names" => g.decorate_x86_names = map.next_value()?,
+                "exclude_private" => g.exclude_private = map.next_value()?,
+                "library_name" => g.library_name = map.next_value()?,
+                "library_extension" => g.library_extension = map.next_value()?,
+                "library_base" => g.library_base = map.next_value()?,
+                "def_dir" => g.def_dir = map.next_value()?,
+                "backend" => g.backend = map.next_value()?,
+                "require_tool" => g.require_tool = map.next_value()?,
+                "checksum_manifest" => g.checksum_manifest = map.next_value()?,
+                "cmake_script" => g.cmake_script = map.next_value()?,
+                "pkg_config_file" => g.pkg_config_file = map.next_value()?,
+                "pyo3_config_file" => g.pyo3_config_file = map.next_value()?,
+                "delay_load" => g.delay_load = map.next_value()?,
+                "export_file" => g.export_file = map.next_value()?,
+                "symbols_file" => g.symbols_file = map.next_value()?,
+                "depfile" => g.depfile = map.next_value()?,
+                "reuse_existing" => g.reuse_existing = map.next_value()?,
+                "c_header" => g.c_header = map.next_value()?,
+                "also_stable_abi" => g.also_stable_abi = map.next_value()?,
+                "read_only" => g.read_only = map.next_value()?,
+                "report_file" => g.report_file = map.next_value()?,
+                "deny_tool_warnings" => g.deny_tool_warnings = map.next_value()?,
+                "layout" => g.layout = map.next_value()?,
+                "dlltool" => g.dlltool = map.next_value()?,
+                "lib_exe" => g.lib_exe = map.next_value()?,
+                "msvc_lib" => g.msvc_lib = map.next_value()?,
+                #[cfg(feature = "download")]
+                "download_dlltool" => {
+                    g.download_dlltool = map
+                        .next_value::<Option<BTreeMap<String, String>>>()?
+                        .map(pinned_tool)
+                        .transpose()?;
+                }
+                "hermetic" => g.hermetic = map.next_value()?,
+                "cache_dir" => g.cache_dir = map.next_value()?,
+                "verbosity" => g.verbosity = map.next_value()?,
+                _ => return Err(de::Error::unknown_field(&key, FIELDS)),
+            }
+        }
+
+        g.arch = arch.ok_or_else(|| de::Error::missing_field("arch"))?;
+        let env = env.ok_or_else(|| de::Error::missing_field("env"))?;
+        g.gnullvm = env.eq_ignore_ascii_case("gnullvm");
+        g.env = normalize_env(&env);
+
+        Ok(g)
+    }
+}
+
+#[cfg(feature = "download")]
+impl Serialize for PinnedTool {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("PinnedTool", 2)?;
+        s.serialize_field("url", &self.url)?;
+        s.serialize_field("sha256", &self.sha256)?;
+        s.end()
+    }
+}
+
+/// Builds the pinned download from the deserialized `url` and `sha256` map.
+#[cfg(feature = "download")]
+fn pinned_tool<E: de::Error>(mut map: BTreeMap<String, String>) -> Result<PinnedTool, E> {
+    let url = map.remove("url").ok_or_else(|| E::missing_field("url"))?;
+    let sha256 = map
+        .remove("sha256")
+        .ok_or_else(|| E::missing_field("sha256"))?;
+
+    match map.keys().next() {
+        Some(key) => Err(E::unknown_field(key, &["url", "sha256"])),
+        None => PinnedTool::new(&url, &sha256).map_err(E::custom),
+    }
+}
+
+// The tests use the `toml` serializer pulled in by the `config` feature.
+#[cfg(all(test, feature = "config"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut generator = ImportLibraryGenerator::new("x86_64", "gnu");
+        generator
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .implementation(PythonImplementation::CPython)
+            .unknown_version(UnknownVersionPolicy::UseLatest)
+            .layout(OutputLayout::PerTarget)
+            .kill_at(true)
+            .def_dir(Some(std::path::Path::new("defs")));
+
+        let serialized = toml::to_string(&generator).unwrap();
+        assert!(serialized.contains("unknown_version = \"use-latest\""));
+        assert!(serialized.contains("version = [3, 13]"));
+
+        let deserialized: ImportLibraryGenerator = toml::from_str(&serialized).unwrap();
+        assert_eq!(deserialized, generator);
+
+        let partial: ImportLibraryGenerator =
+            toml::from_str("arch = \"x86\"\nenv = \"msvc\"\ndebug = true\n").unwrap();
+        let mut expected = ImportLibraryGenerator::new("x86", "msvc");
+        expected.debug(true);
+        assert_eq!(partial, expected);
+
+        assert!(toml::from_str::<ImportLibraryGenerator>("arch = \"x86\"\n").is_err());
+        assert!(toml::from_str::<ImportLibraryGenerator>(
+            "arch = \"x86\"\nenv = \"msvc\"\nlayout = \"nested\"\n"
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! `PYTHON3_DLL_A_*` environment variable settings
+//!
+//! All the environment variables tuning the import library generation
+//! are read here. The explicit `ImportLibraryGenerator` settings take
+//! precedence over these, and the hermetic mode ignores them altogether.
+
+use std::env;
+use std::ffi::OsString;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+use crate::Backend;
+
+/// Explicit `dlltool` or `lib.exe` executable path
+pub(crate) const DLLTOOL_ENV: &str = "PYTHON3_DLL_A_DLLTOOL";
+
+/// Explicit Visual Studio `lib.exe` executable path
+pub(crate) const LIB_EXE_ENV: &str = "PYTHON3_DLL_A_LIB_EXE";
+
+/// Directory containing the replacement `.def` files
+pub(crate) const DEF_DIR_ENV: &str = "PYTHON3_DLL_A_DEF_DIR";
+
+/// Generated import library cache directory
+pub(crate) const CACHE_DIR_ENV: &str = "PYTHON3_DLL_A_CACHE_DIR";
+
+/// Import library generation backend, `dlltool`, `builtin` or `mock`
+pub(crate) const BACKEND_ENV: &str = "PYTHON3_DLL_A_BACKEND";
+
+/// Diagnostic output verbosity, `0` to `2`
+pub(crate) const VERBOSITY_ENV: &str = "PYTHON3_DLL_A_VERBOSITY";
+
+/// Default diagnostic output verbosity: pass the tool messages through
+#[cfg(not(target_os = "wasi"))]
+pub(crate) const DEFAULT_VERBOSITY: u8 = 1;
+
+/// Import library generation settings read from the environment
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EnvSettings {
+    /// `PYTHON3_DLL_A_DLLTOOL` value
+    pub dlltool: Option<PathBuf>,
+    /// `PYTHON3_DLL_A_LIB_EXE` value
+    pub lib_exe: Option<PathBuf>,
+    /// `PYTHON3_DLL_A_DEF_DIR` value
+    pub def_dir: Option<PathBuf>,
+    /// `PYTHON3_DLL_A_CACHE_DIR` value
+    #[cfg(any(not(target_os = "wasi"), feature = "download"))]
+    pub cache_dir: Option<PathBuf>,
+    /// Parsed `PYTHON3_DLL_A_BACKEND` value
+    pub backend: Option<Backend>,
+    /// Parsed `PYTHON3_DLL_A_VERBOSITY` value
+    #[cfg(not(target_os = "wasi"))]
+    pub verbosity: Option<u8>,
+}
+
+impl EnvSettings {
+    /// Reads the `PYTHON3_DLL_A_*` environment variables.
+    ///
+    /// Returns the empty settings in the hermetic mode.
+    pub fn read(hermetic: bool) -> Result<Self> {
+        if hermetic {
+            return Ok(EnvSettings::default());
+        }
+
+        Self::from_vars(|name| env::var_os(name))
+    }
+
+    /// Parses the environment variable values returned by `var`.
+    fn from_vars(var: impl Fn(&str) -> Option<OsString>) -> Result<Self> {
+        // The empty values are treated as unset.
+        let path = |name| var(name).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let string = |name| {
+            var(name)
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_string_lossy().into_owned())
+        };
+
+        let backend = match string(BACKEND_ENV).as_deref() {
+            None => None,
+            Some("dlltool") => Some(Backend::DllTool),
+            #[cfg(feature = "builtin")]
+            Some("builtin") => Some(Backend::Builtin),
+            #[cfg(feature = "test-support")]
+            Some("mock") => Some(Backend::Mock),
+            Some(value) => return Err(invalid_value(BACKEND_ENV, value)),
+        };
+
+        // The WASI hosts do not run the tools, which the verbosity applies to.
+        #[cfg(not(target_os = "wasi"))]
+        let verbosity = match string(VERBOSITY_ENV) {
+            None => None,
+            Some(value) => match value.parse() {
+                Ok(level @ 0..=2) => Some(level),
+                _ => return Err(invalid_value(VERBOSITY_ENV, &value)),
+            },
+        };
+
+        Ok(EnvSettings {
+            dlltool: path(DLLTOOL_ENV),
+            lib_exe: path(LIB_EXE_ENV),
+            def_dir: path(DEF_DIR_ENV),
+            #[cfg(any(not(target_os = "wasi"), feature = "download"))]
+            cache_dir: path(CACHE_DIR_ENV),
+            backend,
+            #[cfg(not(target_os = "wasi"))]
+            verbosity,
+        })
+    }
+}
+
+/// Builds the invalid environment variable value error.
+fn invalid_value(name: &str, value: &str) -> Error {
+    let msg = format!("Invalid {} value '{}'", name, value);
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn env_settings() {
+        let settings = EnvSettings::from_vars(|name| match name {
+            DLLTOOL_ENV => Some("/opt/llvm/bin/llvm-dlltool".into()),
+            LIB_EXE_ENV => Some(r"C:\VS\lib.exe".into()),
+            DEF_DIR_ENV => Some("".into()),
+            BACKEND_ENV => Some("dlltool".into()),
+            VERBOSITY_ENV => Some("2".into()),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            settings.dlltool,
+            Some(PathBuf::from("/opt/llvm/bin/llvm-dlltool"))
+        );
+        assert_eq!(settings.lib_exe, Some(PathBuf::from(r"C:\VS\lib.exe")));
+        assert_eq!(settings.def_dir, None);
+        assert_eq!(settings.cache_dir, None);
+        assert_eq!(settings.backend, Some(Backend::DllTool));
+        assert_eq!(settings.verbosity, Some(2));
+
+        let err = EnvSettings::from_vars(|name| match name {
+            VERBOSITY_ENV => Some("loud".into()),
+            _ => None,
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+
+        assert!(EnvSettings::from_vars(|name| match name {
+            BACKEND_ENV => Some("lld".into()),
+            _ => None,
+        })
+        .is_err());
+    }
+}
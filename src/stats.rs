@@ -0,0 +1,63 @@
+//! Embedded Python ABI definitions statistics
+
+use std::collections::BTreeSet;
+
+use crate::def::DefFile;
+
+/// Exported symbol statistics for a Python DLL
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// Total number of the exported symbols
+    pub total: usize,
+    /// Number of the exported functions
+    pub functions: usize,
+    /// Number of the exported data symbols (`DATA`)
+    pub data: usize,
+    /// Sorted symbols not exported by any other embedded Python version
+    /// of the same implementation
+    pub unique: Vec<String>,
+}
+
+impl ExportStats {
+    /// Collects the statistics for `def` compared with the `others`
+    /// Python version definitions.
+    pub(crate) fn new(def: &DefFile, others: &[DefFile]) -> Self {
+        let mut other_names = BTreeSet::new();
+        for other in others {
+            other_names.extend(other.exports.iter().map(|e| e.name.as_str()));
+        }
+
+        let data = def.exports.iter().filter(|e| e.data).count();
+
+        let unique: BTreeSet<_> = def
+            .exports
+            .iter()
+            .map(|e| e.name.as_str())
+            .filter(|name| !other_names.contains(name))
+            .collect();
+
+        ExportStats {
+            total: def.exports.len(),
+            functions: def.exports.len() - data,
+            data,
+            unique: unique.into_iter().map(ToOwned::to_owned).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let def = DefFile::parse("LIBRARY a.dll\nEXPORTS\nPy_A\nPy_B DATA\nPy_C\n").unwrap();
+        let other = DefFile::parse("LIBRARY b.dll\nEXPORTS\nPy_A\nPy_B DATA\n").unwrap();
+
+        let stats = ExportStats::new(&def, &[other]);
+        assert_eq!(stats.total, 3);
+        assert_eq!(stats.functions, 2);
+        assert_eq!(stats.data, 1);
+        assert_eq!(stats.unique, ["Py_C"]);
+    }
+}
@@ -0,0 +1,460 @@
+//! Stub Python DLL writer for the link and load testing
+//!
+//! Produces a minimal PE DLL image exporting all the symbols listed in
+//! a Module-Definition file. The exported functions are no-op thunks
+//! returning zero, the exported data symbols point to zero-filled memory.
+//! The images are deterministic: all timestamps are set to zero.
+//!
+//! This module is only available with the `stub-dll` crate feature enabled.
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::def::{DefExport, DefFile};
+use crate::error::ErrorCategory;
+
+/// `IMAGE_FILE_MACHINE_I386`
+const MACHINE_I386: u16 = 0x014c;
+/// `IMAGE_FILE_MACHINE_AMD64`
+const MACHINE_AMD64: u16 = 0x8664;
+/// `IMAGE_FILE_MACHINE_ARM64`
+const MACHINE_ARM64: u16 = 0xaa64;
+
+/// `IMAGE_FILE_EXECUTABLE_IMAGE | IMAGE_FILE_DLL`
+const FILE_DLL: u16 = 0x0002 | 0x2000;
+/// `IMAGE_FILE_LARGE_ADDRESS_AWARE`
+const FILE_LARGE_ADDRESS_AWARE: u16 = 0x0020;
+/// `IMAGE_FILE_32BIT_MACHINE`
+const FILE_32BIT_MACHINE: u16 = 0x0100;
+
+/// `IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE | IMAGE_DLLCHARACTERISTICS_NX_COMPAT`
+const DLL_DYNAMIC_BASE_NX_COMPAT: u16 = 0x0040 | 0x0100;
+/// `IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA`
+const DLL_HIGH_ENTROPY_VA: u16 = 0x0020;
+
+/// `IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE | IMAGE_SCN_MEM_READ`
+const SCN_TEXT: u32 = 0x0000_0020 | 0x2000_0000 | 0x4000_0000;
+/// `IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ`
+const SCN_RDATA: u32 = 0x0000_0040 | 0x4000_0000;
+/// `IMAGE_SCN_CNT_UNINITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE`
+const SCN_BSS: u32 = 0x0000_0080 | 0x4000_0000 | 0x8000_0000;
+/// `IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_DISCARDABLE | IMAGE_SCN_MEM_READ`
+const SCN_RELOC: u32 = 0x0000_0040 | 0x0200_0000 | 0x4000_0000;
+
+/// `IMAGE_SUBSYSTEM_WINDOWS_GUI`
+const SUBSYSTEM_WINDOWS_GUI: u16 = 2;
+
+/// In-memory section alignment
+const SECTION_ALIGNMENT: u32 = 0x1000;
+/// On-disk section alignment
+const FILE_ALIGNMENT: u32 = 0x200;
+
+/// DOS header size, the PE signature follows it immediately
+const DOS_HEADER_SIZE: u32 = 0x40;
+/// Export directory table size
+const EXPORT_DIRECTORY_SIZE: u32 = 40;
+/// Zero-filled memory reserved for every exported data symbol,
+/// large enough for the static `PyTypeObject` instances
+const DATA_SYMBOL_SIZE: u32 = 0x200;
+
+/// Stub DLL target machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Machine(u16);
+
+impl Machine {
+    /// Returns the machine type for the `CARGO_CFG_TARGET_ARCH` name.
+    pub fn from_arch(arch: &str) -> Result<Self> {
+        match arch {
+            "x86_64" => Ok(Machine(MACHINE_AMD64)),
+            "x86" => Ok(Machine(MACHINE_I386)),
+            "aarch64" => Ok(Machine(MACHINE_ARM64)),
+            _ => {
+                let msg = format!("Unsupported stub DLL target arch '{}'", arch);
+                Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+            }
+        }
+    }
+
+    /// Checks for the 64-bit machine types.
+    fn is_64bit(self) -> bool {
+        self.0 != MACHINE_I386
+    }
+
+    /// Returns the no-op function thunk returning zero.
+    fn thunk(self) -> &'static [u8] {
+        match self.0 {
+            // mov x0, #0; ret
+            MACHINE_ARM64 => &[0x00, 0x00, 0x80, 0xd2, 0xc0, 0x03, 0x5f, 0xd6],
+            // xor eax, eax; ret; int3
+            _ => &[0x31, 0xc0, 0xc3, 0xcc],
+        }
+    }
+
+    /// Returns the preferred image base address.
+    fn image_base(self) -> u64 {
+        if self.is_64bit() {
+            0x1_8000_0000
+        } else {
+            0x1000_0000
+        }
+    }
+}
+
+/// PE image section
+struct Section {
+    /// Section name
+    name: &'static [u8],
+    /// Section RVA
+    rva: u32,
+    /// Section size in memory
+    virtual_size: u32,
+    /// Section contents, empty for the uninitialized data
+    data: Vec<u8>,
+    /// Section characteristics
+    characteristics: u32,
+}
+
+/// Builds the stub DLL image exporting the symbols listed in `def`.
+pub(crate) fn write_stub_dll(def: &DefFile, machine: Machine) -> Result<Vec<u8>> {
+    let dll_name = def
+        .dll_name()
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "Missing LIBRARY statement in .def"))?;
+
+    // The export name table must be sorted for the loader binary search.
+    let mut exports: Vec<_> = def.exports.iter().collect();
+    exports.sort_by(|a, b| a.name.cmp(&b.name));
+    exports.dedup_by(|a, b| a.name == b.name);
+
+    let text_rva = SECTION_ALIGNMENT;
+    let thunk = machine.thunk();
+
+    // Keep the code section non-empty.
+    let functions = exports.iter().filter(|e| !e.data).count();
+    let text = thunk.repeat(functions.max(1));
+
+    let rdata_rva = text_rva + align(text.len() as u32, SECTION_ALIGNMENT);
+    let rdata_size = export_table_size(&dll_name, &exports);
+    let bss_rva = rdata_rva + align(rdata_size, SECTION_ALIGNMENT);
+
+    // Assign the function thunk and the data symbol addresses.
+    let mut code_count = 0;
+    let mut data_count = 0;
+    let mut addresses = Vec::with_capacity(exports.len());
+    for export in &exports {
+        if export.data {
+            addresses.push(bss_rva + DATA_SYMBOL_SIZE * data_count);
+            data_count += 1;
+        } else {
+            addresses.push(text_rva + (thunk.len() as u32) * code_count);
+            code_count += 1;
+        }
+    }
+
+    let bss_size = DATA_SYMBOL_SIZE * data_count;
+    let reloc_rva = bss_rva + align(bss_size, SECTION_ALIGNMENT);
+
+    let rdata = export_table(rdata_rva, &dll_name, &exports, &addresses);
+
+    // A single empty base relocation block keeps the image relocatable,
+    // the thunks contain no absolute addresses.
+    let mut reloc = Vec::new();
+    put_u32(&mut reloc, text_rva);
+    put_u32(&mut reloc, 12);
+    put_u32(&mut reloc, 0);
+
+    let mut sections = vec![
+        Section {
+            name: b".text",
+            rva: text_rva,
+            virtual_size: text.len() as u32,
+            data: text,
+            characteristics: SCN_TEXT,
+        },
+        Section {
+            name: b".rdata",
+            rva: rdata_rva,
+            virtual_size: rdata.len() as u32,
+            data: rdata,
+            characteristics: SCN_RDATA,
+        },
+    ];
+    if bss_size > 0 {
+        sections.push(Section {
+            name: b".bss",
+            rva: bss_rva,
+            virtual_size: bss_size,
+            data: Vec::new(),
+            characteristics: SCN_BSS,
+        });
+    }
+    sections.push(Section {
+        name: b".reloc",
+        rva: reloc_rva,
+        virtual_size: reloc.len() as u32,
+        data: reloc,
+        characteristics: SCN_RELOC,
+    });
+
+    Ok(write_image(
+        machine,
+        &sections,
+        (rdata_rva, rdata_size),
+        (reloc_rva, 12),
+    ))
+}
+
+/// Returns the export table size for `exports`.
+fn export_table_size(dll_name: &str, exports: &[&DefExport]) -> u32 {
+    let named = exports.iter().filter(|e| !e.noname).count() as u32;
+    let strings: usize = exports
+        .iter()
+        .filter(|e| !e.noname)
+        .map(|e| e.name.len() + 1)
+        .sum();
+
+    EXPORT_DIRECTORY_SIZE
+        + 4 * exports.len() as u32
+        + 4 * named
+        + 2 * named
+        + (dll_name.len() + 1 + strings) as u32
+}
+
+/// Builds the `.rdata` section contents: the export directory table,
+/// the export address, name pointer and ordinal tables and the name strings.
+fn export_table(rva: u32, dll_name: &str, exports: &[&DefExport], addresses: &[u32]) -> Vec<u8> {
+    let named: Vec<_> = exports
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| !e.noname)
+        .collect();
+
+    let address_table = rva + EXPORT_DIRECTORY_SIZE;
+    let name_pointers = address_table + 4 * exports.len() as u32;
+    let ordinals = name_pointers + 4 * named.len() as u32;
+    let dll_name_rva = ordinals + 2 * named.len() as u32;
+
+    let mut table = Vec::new();
+
+    // Characteristics, TimeDateStamp, MajorVersion and MinorVersion
+    put_u32(&mut table, 0);
+    put_u32(&mut table, 0);
+    put_u32(&mut table, 0);
+    put_u32(&mut table, dll_name_rva);
+    // OrdinalBase
+    put_u32(&mut table, 1);
+    put_u32(&mut table, exports.len() as u32);
+    put_u32(&mut table, named.len() as u32);
+    put_u32(&mut table, address_table);
+    put_u32(&mut table, name_pointers);
+    put_u32(&mut table, ordinals);
+
+    for address in addresses {
+        put_u32(&mut table, *address);
+    }
+
+    let mut name_rva = dll_name_rva + dll_name.len() as u32 + 1;
+    for (_, export) in &named {
+        put_u32(&mut table, name_rva);
+        name_rva += export.name.len() as u32 + 1;
+    }
+
+    for (index, _) in &named {
+        put_u16(&mut table, *index as u16);
+    }
+
+    put_cstr(&mut table, dll_name);
+    for (_, export) in &named {
+        put_cstr(&mut table, &export.name);
+    }
+
+    table
+}
+
+/// Writes the PE image headers followed by the section contents.
+fn write_image(
+    machine: Machine,
+    sections: &[Section],
+    export_directory: (u32, u32),
+    reloc_directory: (u32, u32),
+) -> Vec<u8> {
+    let optional_header_size: u16 = if machine.is_64bit() { 240 } else { 224 };
+    let headers_size =
+        DOS_HEADER_SIZE + 4 + 20 + u32::from(optional_header_size) + 40 * sections.len() as u32;
+    let headers_size = align(headers_size, FILE_ALIGNMENT);
+
+    let last = sections.last().expect("no sections");
+    let image_size = align(last.rva + last.virtual_size, SECTION_ALIGNMENT);
+
+    let raw_size = |section: &Section| align(section.data.len() as u32, FILE_ALIGNMENT);
+    let size_of = |flags: u32| -> u32 {
+        sections
+            .iter()
+            .filter(|s| s.characteristics == flags)
+            .map(|s| match flags {
+                SCN_BSS => align(s.virtual_size, FILE_ALIGNMENT),
+                _ => raw_size(s),
+            })
+            .sum()
+    };
+
+    let mut image = Vec::new();
+
+    // DOS header: `e_magic` and `e_lfanew` only
+    image.extend_from_slice(b"MZ");
+    image.resize(0x3c, 0);
+    put_u32(&mut image, DOS_HEADER_SIZE);
+    image.extend_from_slice(b"PE\0\0");
+
+    // COFF file header
+    let characteristics = if machine.is_64bit() {
+        FILE_DLL | FILE_LARGE_ADDRESS_AWARE
+    } else {
+        FILE_DLL | FILE_32BIT_MACHINE
+    };
+    put_u16(&mut image, machine.0);
+    put_u16(&mut image, sections.len() as u16);
+    put_u32(&mut image, 0);
+    put_u32(&mut image, 0);
+    put_u32(&mut image, 0);
+    put_u16(&mut image, optional_header_size);
+    put_u16(&mut image, characteristics);
+
+    // Optional header standard fields
+    put_u16(&mut image, if machine.is_64bit() { 0x020b } else { 0x010b });
+    image.extend_from_slice(&[14, 0]);
+    put_u32(&mut image, size_of(SCN_TEXT));
+    put_u32(&mut image, size_of(SCN_RDATA) + size_of(SCN_RELOC));
+    put_u32(&mut image, size_of(SCN_BSS));
+    // No `DllMain` entry point
+    put_u32(&mut image, 0);
+    put_u32(&mut image, sections[0].rva);
+    if !machine.is_64bit() {
+        put_u32(&mut image, sections[1].rva);
+        put_u32(&mut image, machine.image_base() as u32);
+    } else {
+        image.extend_from_slice(&machine.image_base().to_le_bytes());
+    }
+
+    // Optional header Windows-specific fields
+    put_u32(&mut image, SECTION_ALIGNMENT);
+    put_u32(&mut image, FILE_ALIGNMENT);
+    for version in [6, 0, 0, 0, 6, 0] {
+        put_u16(&mut image, version);
+    }
+    put_u32(&mut image, 0);
+    put_u32(&mut image, image_size);
+    put_u32(&mut image, headers_size);
+    put_u32(&mut image, 0);
+    put_u16(&mut image, SUBSYSTEM_WINDOWS_GUI);
+    put_u16(
+        &mut image,
+        if machine.is_64bit() {
+            DLL_DYNAMIC_BASE_NX_COMPAT | DLL_HIGH_ENTROPY_VA
+        } else {
+            DLL_DYNAMIC_BASE_NX_COMPAT
+        },
+    );
+    for value in [0x10_0000, 0x1000, 0x10_0000, 0x1000] {
+        if machine.is_64bit() {
+            image.extend_from_slice(&(value as u64).to_le_bytes());
+        } else {
+            put_u32(&mut image, value);
+        }
+    }
+    put_u32(&mut image, 0);
+    put_u32(&mut image, 16);
+
+    // Data directories: only the export and the base relocation tables
+    for index in 0..16 {
+        let (rva, size) = match index {
+            0 => export_directory,
+            5 => reloc_directory,
+            _ => (0, 0),
+        };
+        put_u32(&mut image, rva);
+        put_u32(&mut image, size);
+    }
+
+    // Section table
+    let mut raw_offset = headers_size;
+    for section in sections {
+        let mut name = [0; 8];
+        name[..section.name.len()].copy_from_slice(section.name);
+        image.extend_from_slice(&name);
+
+        let size = raw_size(section);
+        put_u32(&mut image, section.virtual_size);
+        put_u32(&mut image, section.rva);
+        put_u32(&mut image, size);
+        put_u32(&mut image, if size > 0 { raw_offset } else { 0 });
+        put_u32(&mut image, 0);
+        put_u32(&mut image, 0);
+        put_u16(&mut image, 0);
+        put_u16(&mut image, 0);
+        put_u32(&mut image, section.characteristics);
+
+        raw_offset += size;
+    }
+
+    image.resize(headers_size as usize, 0);
+
+    for section in sections {
+        image.extend_from_slice(&section.data);
+        image.resize(align(image.len() as u32, FILE_ALIGNMENT) as usize, 0);
+    }
+
+    image
+}
+
+/// Rounds `value` up to a multiple of `alignment` (a power of two).
+fn align(value: u32, alignment: u32) -> u32 {
+    (value + alignment - 1) & !(alignment - 1)
+}
+
+/// Appends a little-endian `u16` value.
+fn put_u16(data: &mut Vec<u8>, value: u16) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a little-endian `u32` value.
+fn put_u32(data: &mut Vec<u8>, value: u32) {
+    data.extend_from_slice(&value.to_le_bytes());
+}
+
+/// Appends a NUL-terminated string.
+fn put_cstr(data: &mut Vec<u8>, s: &str) {
+    data.extend_from_slice(s.as_bytes());
+    data.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::pe;
+
+    #[test]
+    fn stub_dll() {
+        let def = DefFile::parse(
+            "LIBRARY python3.dll\nEXPORTS\nPy_Main\nPyExc_TypeError DATA\nPy_Initialize\n",
+        )
+        .unwrap();
+
+        for arch in ["x86_64", "x86", "aarch64"] {
+            let machine = Machine::from_arch(arch).unwrap();
+            let image = write_stub_dll(&def, machine).unwrap();
+            assert_eq!(image.len() % FILE_ALIGNMENT as usize, 0);
+
+            let dll = pe::parse_exports(&image).unwrap();
+            assert_eq!(dll.dll_name, "python3.dll");
+            assert_eq!(dll.arch, arch);
+
+            let names: Vec<_> = dll.exports.iter().map(|e| e.name.as_str()).collect();
+            assert_eq!(names, ["PyExc_TypeError", "Py_Initialize", "Py_Main"]);
+
+            let report = pe::compare_exports(&def, &dll.exports);
+            assert!(report.is_ok(), "{:?}", report);
+        }
+
+        assert!(Machine::from_arch("arm").is_err());
+    }
+}
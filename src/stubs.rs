@@ -0,0 +1,335 @@
+//! Stub Python libraries for the link and load testing
+//!
+//! The stub libraries export all the symbols from the Python library definitions,
+//! so that the Python extensions and embedders can be linked (and loaded)
+//! without a real Python installation:
+//!
+//! - [`ImportLibraryGenerator::generate_stub_dll()`] (the `stub-dll` feature)
+//!   writes a stub `python3.dll` (or `pythonXY.dll`) exporting all the symbols
+//!   as no-op functions and zero-filled data, e.g. for the smoke-testing under Wine.
+//!   The `python3-dll-a generate --stub-dll` command writes the stub DLLs
+//!   next to the import libraries.
+//! - [`ImportLibraryGenerator::generate_stub_elf()`] (the `stub-elf` feature)
+//!   writes a link-time stub `libpython3.so` (or `libpython3.Y.so`) ELF shared
+//!   library for the `x86_64` and `aarch64` Linux targets. The stub has
+//!   the `SONAME` of the real library, so the cross-compiled binaries load
+//!   the real `libpython` at run time. [`ImportLibraryGenerator::generate_android_stub()`]
+//!   writes the same stub for the `aarch64` and `x86_64` Android NDK targets.
+//! - [`ImportLibraryGenerator::generate_stub_tbd()`] (the `stub-tbd` feature)
+//!   writes a text-based `Python.tbd` stub for the `x86_64` and `aarch64`
+//!   macOS targets, the macOS equivalent of the import library.
+
+use std::fs::{create_dir_all, write};
+#[cfg(feature = "stub-elf")]
+use std::io::ErrorKind;
+use std::io::Result;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "stub-elf")]
+use crate::elfstub;
+#[cfg(feature = "stub-dll")]
+use crate::stubdll;
+#[cfg(feature = "stub-tbd")]
+use crate::tbd;
+#[cfg(feature = "stub-elf")]
+use crate::ErrorCategory;
+use crate::ImportLibraryGenerator;
+#[cfg(any(feature = "stub-elf", feature = "stub-tbd"))]
+use crate::PythonImplementation;
+
+impl ImportLibraryGenerator {
+    /// Writes a stub Python DLL for the configured version into `out_dir`.
+    ///
+    /// The DLL exports all the symbols from the Python library definitions:
+    /// the functions are no-op thunks returning zero and the data symbols
+    /// point to zero-filled memory. It is meant for the smoke-testing of
+    /// linking and loading (e.g. under Wine) the Python extensions and
+    /// embedders without a real Python installation, not for running them.
+    ///
+    /// Only the `x86_64`, `x86` and `aarch64` target architectures are supported.
+    ///
+    /// Returns the path of the written DLL, e.g. `python3.dll`.
+    ///
+    /// Requires the `stub-dll` crate feature.
+    #[cfg(feature = "stub-dll")]
+    pub fn generate_stub_dll(&self, out_dir: &Path) -> Result<PathBuf> {
+        let machine = stubdll::Machine::from_arch(&self.arch)?;
+
+        let def = self.def_file()?;
+        let dll = stubdll::write_stub_dll(&def, machine)?;
+
+        let dll_name = def.dll_name().unwrap_or_default();
+
+        create_dir_all(out_dir)?;
+        let dll_file = out_dir.join(dll_name);
+        write(&dll_file, dll)?;
+
+        Ok(dll_file)
+    }
+
+    /// Writes a link-time stub `libpython` ELF shared library for the configured
+    /// Python version into `out_dir`.
+    ///
+    /// The library defines all the symbols from the Python library definitions
+    /// and has the same `SONAME` as the real one, e.g. `libpython3.12.so.1.0`,
+    /// so that the Python extensions and embedders cross-compiled for
+    /// the Linux targets link against the real `libpython` at run time.
+    /// The stub library itself cannot be loaded.
+    ///
+    /// Only the `x86_64` and `aarch64` target architectures are supported.
+    ///
+    /// Returns the path of the written library, e.g. `libpython3.12.so`.
+    ///
+    /// Requires the `stub-elf` crate feature.
+    #[cfg(feature = "stub-elf")]
+    pub fn generate_stub_elf(&self, out_dir: &Path) -> Result<PathBuf> {
+        let machine = elfstub::Machine::from_arch(&self.arch)?;
+
+        let def = self.def_file()?;
+        let abiflags = self.abiflags.as_deref().unwrap_or_default();
+
+        let (file_name, soname) = match (self.implementation, self.version) {
+            (PythonImplementation::CPython, None) => {
+                ("libpython3.so".to_owned(), "libpython3.so".to_owned())
+            }
+            (PythonImplementation::CPython, Some((major, minor))) => {
+                let name = format!("libpython{}.{}{}.so", major, minor, abiflags);
+                let soname = format!("{}.1.0", name);
+                (name, soname)
+            }
+            (PythonImplementation::PyPy, _) => {
+                // `libpypy3.10-c.dll` -> `libpypy3.10-c.so`
+                let dll_name = def.dll_name().unwrap_or_default();
+                let name = format!("{}.so", dll_name.trim_end_matches(".dll"));
+                (name.clone(), name)
+            }
+        };
+
+        create_dir_all(out_dir)?;
+        let so_file = out_dir.join(file_name);
+        write(&so_file, elfstub::write_stub_elf(&def, machine, &soname))?;
+
+        Ok(so_file)
+    }
+
+    /// Writes a link-time stub `libpython3.Y.so` ELF shared library
+    /// for the Android NDK toolchains into `out_dir`.
+    ///
+    /// Unlike on Linux, the Android `libpython` has no versioned `SONAME`
+    /// and the Python extension modules have to link against it explicitly.
+    /// The stub library has the `SONAME` of the CPython Android build,
+    /// e.g. `libpython3.13.so`, and cannot be loaded itself.
+    ///
+    /// Only the `aarch64` and `x86_64` Android ABIs and the version-specific
+    /// CPython libraries are supported.
+    ///
+    /// Returns the path of the written library, e.g. `libpython3.13.so`.
+    ///
+    /// Requires the `stub-elf` crate feature.
+    #[cfg(feature = "stub-elf")]
+    pub fn generate_android_stub(&self, out_dir: &Path) -> Result<PathBuf> {
+        let machine = elfstub::Machine::from_arch(&self.arch)?;
+
+        let (major, minor) = match (self.implementation, self.version) {
+            (PythonImplementation::CPython, Some(version)) => version,
+            _ => {
+                let msg = "Android stub libraries require a version-specific CPython target";
+                return Err(ErrorCategory::UnsupportedVersion.error(ErrorKind::Other, msg));
+            }
+        };
+
+        let def = self.def_file()?;
+        let abiflags = self.abiflags.as_deref().unwrap_or_default();
+        let soname = format!("libpython{}.{}{}.so", major, minor, abiflags);
+
+        create_dir_all(out_dir)?;
+        let so_file = out_dir.join(&soname);
+        write(&so_file, elfstub::write_stub_elf(&def, machine, &soname))?;
+
+        Ok(so_file)
+    }
+
+    /// Writes a text-based `.tbd` stub of the Python framework library
+    /// for the configured Python version into `out_dir`.
+    ///
+    /// The stub exports all the symbols from the Python library definitions
+    /// and records the install name of the python.org framework build,
+    /// e.g. `/Library/Frameworks/Python.framework/Versions/3.12/Python`,
+    /// so that the binaries cross-compiled for the macOS targets load
+    /// the real framework at run time. The Stable ABI stub refers to
+    /// the current framework version `Python.framework/Python`.
+    ///
+    /// Only the `x86_64` and `aarch64` target architectures are supported.
+    ///
+    /// Returns the path of the written stub, e.g. `Python.tbd`.
+    ///
+    /// Requires the `stub-tbd` crate feature.
+    #[cfg(feature = "stub-tbd")]
+    pub fn generate_stub_tbd(&self, out_dir: &Path) -> Result<PathBuf> {
+        let target = tbd::tbd_target(&self.arch)?;
+
+        let def = self.def_file()?;
+
+        let (file_name, install_name, version) = match (self.implementation, self.version) {
+            (PythonImplementation::CPython, None) => (
+                "Python.tbd".to_owned(),
+                "/Library/Frameworks/Python.framework/Python".to_owned(),
+                None,
+            ),
+            (PythonImplementation::CPython, Some((major, minor))) => {
+                // The free-threaded builds are installed as `PythonT.framework`.
+                let framework = match self.abiflags.as_deref() {
+                    Some(flags) if flags.contains('t') => "PythonT",
+                    _ => "Python",
+                };
+                let install_name = format!(
+                    "/Library/Frameworks/{0}.framework/Versions/{1}.{2}/{0}",
+                    framework, major, minor
+                );
+                let version = format!("{}.{}", major, minor);
+                (format!("{}.tbd", framework), install_name, Some(version))
+            }
+            (PythonImplementation::PyPy, _) => {
+                // `libpypy3.10-c.dll` -> `@rpath/libpypy3.10-c.dylib`
+                let dll_name = def.dll_name().unwrap_or_default();
+                let stem = dll_name.trim_end_matches(".dll");
+                let install_name = format!("@rpath/{}.dylib", stem);
+                (format!("{}.tbd", stem), install_name, None)
+            }
+        };
+
+        create_dir_all(out_dir)?;
+        let tbd_file = out_dir.join(file_name);
+        write(
+            &tbd_file,
+            tbd::write_tbd(&def, target, &install_name, version.as_deref()),
+        )?;
+
+        Ok(tbd_file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "stub-elf")]
+    use std::process::Command;
+
+    use super::*;
+    #[cfg(feature = "stub-dll")]
+    use crate::check_against_dll;
+    #[cfg(feature = "stub-tbd")]
+    use crate::ErrorCategory;
+
+    #[cfg(feature = "stub-dll")]
+    #[test]
+    fn generate_stub_dll() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("stub-dll");
+
+        let dll = ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .generate_stub_dll(&dir)
+            .unwrap();
+        assert_eq!(dll, dir.join("python313t.dll"));
+
+        let report = check_against_dll(None, &dll).unwrap();
+        assert!(!report.missing.is_empty());
+        assert!(report.wrong_type.is_empty());
+
+        let dll = ImportLibraryGenerator::new("x86", "gnu")
+            .generate_stub_dll(&dir)
+            .unwrap();
+
+        let report = check_against_dll(None, &dll).unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+    }
+
+    #[cfg(all(unix, feature = "stub-elf"))]
+    #[test]
+    fn generate_stub_elf() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("stub-elf");
+
+        let so = ImportLibraryGenerator::new("x86_64", "gnu")
+            .version(Some((3, 12)))
+            .generate_stub_elf(&dir)
+            .unwrap();
+        assert_eq!(so, dir.join("libpython3.12.so"));
+
+        if !cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            return;
+        }
+
+        // Check that the stub resolves the C API references.
+        let source = dir.join("embed.c");
+        write(
+            &source,
+            "extern void Py_Initialize(void);\n\
+             extern void *PyExc_TypeError;\n\
+             int main(void) { Py_Initialize(); return PyExc_TypeError != 0; }\n",
+        )
+        .unwrap();
+
+        let status = Command::new("cc")
+            .arg("-o")
+            .arg(dir.join("embed"))
+            .arg(&source)
+            .arg("-L")
+            .arg(&dir)
+            .arg("-lpython3.12")
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[cfg(feature = "stub-elf")]
+    #[test]
+    fn generate_android_stub() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("stub-android");
+
+        let so = ImportLibraryGenerator::new("aarch64", "")
+            .version(Some((3, 13)))
+            .generate_android_stub(&dir)
+            .unwrap();
+        assert_eq!(so, dir.join("libpython3.13.so"));
+
+        let elf = std::fs::read(&so).unwrap();
+        assert!(elf.windows(18).any(|w| w == b"\0libpython3.13.so\0"));
+
+        let err = ImportLibraryGenerator::new("aarch64", "")
+            .generate_android_stub(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedVersion);
+    }
+
+    #[cfg(feature = "stub-tbd")]
+    #[test]
+    fn generate_stub_tbd() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("stub-tbd");
+
+        let tbd = ImportLibraryGenerator::new("aarch64", "gnu")
+            .version(Some((3, 13)))
+            .abiflags(Some("t"))
+            .generate_stub_tbd(&dir)
+            .unwrap();
+        assert_eq!(tbd, dir.join("PythonT.tbd"));
+
+        let text = std::fs::read_to_string(&tbd).unwrap();
+        assert!(text.contains(
+            "install-name:    '/Library/Frameworks/PythonT.framework/Versions/3.13/PythonT'\n"
+        ));
+        assert!(text.contains(" _Py_Initialize,"));
+
+        let err = ImportLibraryGenerator::new("x86", "gnu")
+            .generate_stub_tbd(&dir)
+            .unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+}
@@ -0,0 +1,410 @@
+//! Embedded Python ABI symbol version history
+//!
+//! The versions adding and removing the symbols are derived
+//! from the embedded version-specific CPython definitions.
+//! The deprecation versions are recorded in [`DEPRECATED_SYMBOLS`].
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Result;
+use std::path::Path;
+
+pub use crate::def::SymbolKind;
+
+use crate::def::DefFile;
+use crate::{ImportLibraryGenerator, CPYTHON_VERSIONS};
+
+/// Deprecated CPython API symbols and the versions deprecating them
+///
+/// The symbols are still exported by the Stable ABI `python3.dll`,
+/// but are no-ops or scheduled for removal from the versioned DLLs.
+const DEPRECATED_SYMBOLS: [(&str, (u8, u8)); 31] = [
+    ("PyCFunction_Call", (3, 9)),
+    ("PyEval_AcquireLock", (3, 2)),
+    ("PyEval_CallFunction", (3, 9)),
+    ("PyEval_CallMethod", (3, 9)),
+    ("PyEval_CallObjectWithKeywords", (3, 9)),
+    ("PyEval_InitThreads", (3, 9)),
+    ("PyEval_ReleaseLock", (3, 2)),
+    ("PyEval_ThreadsInitialized", (3, 9)),
+    ("PyImport_ImportModuleNoBlock", (3, 13)),
+    ("PyModule_GetFilename", (3, 2)),
+    ("PyOS_AfterFork", (3, 7)),
+    ("PyObject_AsCharBuffer", (3, 0)),
+    ("PyObject_AsReadBuffer", (3, 0)),
+    ("PyObject_AsWriteBuffer", (3, 0)),
+    ("PyObject_CheckReadBuffer", (3, 0)),
+    ("PySys_AddWarnOption", (3, 11)),
+    ("PySys_AddWarnOptionUnicode", (3, 11)),
+    ("PySys_ResetWarnOptions", (3, 13)),
+    ("PySys_SetArgv", (3, 11)),
+    ("PySys_SetArgvEx", (3, 11)),
+    ("PySys_SetPath", (3, 11)),
+    ("PyWeakref_GetObject", (3, 13)),
+    ("Py_GetExecPrefix", (3, 13)),
+    ("Py_GetPath", (3, 13)),
+    ("Py_GetPrefix", (3, 13)),
+    ("Py_GetProgramFullPath", (3, 13)),
+    ("Py_GetProgramName", (3, 13)),
+    ("Py_GetPythonHome", (3, 13)),
+    ("Py_SetPath", (3, 11)),
+    ("Py_SetProgramName", (3, 11)),
+    ("Py_SetPythonHome", (3, 11)),
+];
+
+/// Stable ABI symbols missing from the version-specific CPython definitions
+/// after being exported by an earlier version
+///
+/// The gaps are present in the actual `pythonXY.dll` export tables,
+/// so they are not reported by [`check_def_consistency()`].
+const KNOWN_GAPS: [(&str, (u8, u8)); 2] =
+    [("PyCFunction_New", (3, 8)), ("PyCFunction_New", (3, 9))];
+
+/// Stable ABI `python3.dll` symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    /// Exported symbol name
+    pub name: &'static str,
+    /// Exported symbol kind
+    pub kind: SymbolKind,
+    /// Oldest embedded CPython version exporting the symbol
+    ///
+    /// `None` for the symbols not exported by any version-specific
+    /// CPython definitions, e.g. the ones added after the newest
+    /// embedded version. The symbols exported by the oldest embedded
+    /// version (3.7) may have been added earlier.
+    pub added_in: Option<(u8, u8)>,
+}
+
+/// All the Stable ABI `python3.dll` symbols, sorted by name
+///
+/// Generated from the embedded `python3.def` at build time, the table
+/// does not depend on the `def_dir()` replacements or the crate features.
+pub const STABLE_ABI_SYMBOLS: &[Symbol] =
+    include!(concat!(env!("OUT_DIR"), "/stable_abi_symbols.rs"));
+
+/// CPython symbol version history
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SymbolHistory {
+    /// Exported symbol name
+    pub name: String,
+    /// Oldest embedded CPython version exporting the symbol
+    ///
+    /// The symbols exported by the oldest embedded version (3.7)
+    /// may have been added earlier.
+    pub added_in: (u8, u8),
+    /// CPython version no longer exporting the symbol, if any
+    pub removed_in: Option<(u8, u8)>,
+    /// CPython version deprecating the symbol, if any
+    pub deprecated_in: Option<(u8, u8)>,
+}
+
+/// Problem with a symbol used by an extension targeting a version range
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AuditIssue {
+    /// The symbol is not exported by the oldest targeted version
+    NotYetAdded {
+        /// Oldest embedded CPython version exporting the symbol
+        added_in: (u8, u8),
+    },
+    /// The symbol is not exported by the newer targeted versions
+    Removed {
+        /// CPython version no longer exporting the symbol
+        removed_in: (u8, u8),
+    },
+    /// The symbol is deprecated in the targeted versions
+    Deprecated {
+        /// CPython version deprecating the symbol
+        since: (u8, u8),
+    },
+    /// The symbol is not exported by any embedded CPython version
+    Unknown,
+}
+
+/// Symbol audit finding
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditFinding {
+    /// Audited symbol name
+    pub name: String,
+    /// Found problem
+    pub issue: AuditIssue,
+}
+
+/// Returns the version history of all the symbols exported by the embedded
+/// version-specific CPython definitions, sorted by the symbol name.
+///
+/// The versions disabled by the crate features and the free-threaded
+/// CPython builds are skipped.
+pub fn symbol_history() -> Result<Vec<SymbolHistory>> {
+    let mut history: BTreeMap<String, SymbolHistory> = BTreeMap::new();
+    let mut previous: Option<Vec<String>> = None;
+
+    for (version, abiflags) in CPYTHON_VERSIONS {
+        let version = match (version, abiflags) {
+            (Some(version), None) => version,
+            _ => continue,
+        };
+
+        let def = match ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some(version))
+            .embedded_def_file()
+        {
+            Ok(def) => DefFile::parse(&def)?,
+            Err(_) => continue,
+        };
+
+        let names: Vec<_> = def.exports.into_iter().map(|e| e.name).collect();
+
+        for name in &names {
+            let entry = history
+                .entry(name.clone())
+                .or_insert_with(|| SymbolHistory {
+                    name: name.clone(),
+                    added_in: version,
+                    removed_in: None,
+                    deprecated_in: None,
+                });
+
+            // Re-added symbols are not removed anymore.
+            entry.removed_in = None;
+        }
+
+        if let Some(previous) = previous {
+            for name in previous {
+                if !names.contains(&name) {
+                    if let Some(entry) = history.get_mut(&name) {
+                        entry.removed_in = Some(version);
+                    }
+                }
+            }
+        }
+
+        previous = Some(names);
+    }
+
+    for (name, since) in DEPRECATED_SYMBOLS {
+        if let Some(entry) = history.get_mut(name) {
+            entry.deprecated_in = Some(since);
+        }
+    }
+
+    Ok(history.into_values().collect())
+}
+
+/// Audits the symbols used by an extension targeting the CPython versions
+/// from `oldest` to `newest` inclusive.
+///
+/// Flags the symbols not yet exported by `oldest`, the symbols removed
+/// or deprecated in `newest` or earlier, and the unknown symbols.
+/// Returns the findings in the order of `names`.
+pub fn audit_symbols<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    oldest: (u8, u8),
+    newest: (u8, u8),
+) -> Result<Vec<AuditFinding>> {
+    let history = symbol_history()?;
+
+    let mut findings = Vec::new();
+
+    for name in names {
+        let finding = |issue| AuditFinding {
+            name: name.to_owned(),
+            issue,
+        };
+
+        let entry = match history.binary_search_by(|entry| entry.name.as_str().cmp(name)) {
+            Ok(index) => &history[index],
+            Err(_) => {
+                findings.push(finding(AuditIssue::Unknown));
+                continue;
+            }
+        };
+
+        if entry.added_in > oldest {
+            findings.push(finding(AuditIssue::NotYetAdded {
+                added_in: entry.added_in,
+            }));
+        }
+
+        if let Some(removed_in) = entry.removed_in.filter(|&removed_in| removed_in <= newest) {
+            findings.push(finding(AuditIssue::Removed { removed_in }));
+        }
+
+        if let Some(since) = entry.deprecated_in.filter(|&since| since <= newest) {
+            findings.push(finding(AuditIssue::Deprecated { since }));
+        }
+    }
+
+    Ok(findings)
+}
+
+/// Stable ABI symbol missing from a version-specific CPython definitions file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingStableAbiSymbol {
+    /// Stable ABI symbol name
+    pub name: String,
+    /// CPython version missing the symbol
+    pub version: (u8, u8),
+    /// Oldest CPython version exporting the symbol
+    pub since: (u8, u8),
+}
+
+/// Checks that the version-specific CPython definitions export every
+/// Stable ABI `python3.def` symbol already exported by an earlier version.
+///
+/// The Stable ABI symbols are never removed, so the missing symbols point
+/// to the editing mistakes in the definitions. The replacement `.def` files
+/// from `def_dir` (or the `PYTHON3_DLL_A_DEF_DIR` directory) are checked
+/// in place of the embedded ones when present, which validates
+/// the regenerated definitions before embedding them.
+///
+/// The versions disabled by the crate features and the free-threaded
+/// CPython builds are skipped.
+pub fn check_def_consistency(def_dir: Option<&Path>) -> Result<Vec<MissingStableAbiSymbol>> {
+    let mut generator = ImportLibraryGenerator::new("x86_64", "msvc");
+    generator.def_dir(def_dir);
+
+    let stable_abi: BTreeSet<_> = generator
+        .def_file()?
+        .exports
+        .into_iter()
+        .map(|e| e.name)
+        .collect();
+
+    let mut since: BTreeMap<String, (u8, u8)> = BTreeMap::new();
+    let mut missing = Vec::new();
+
+    for (version, abiflags) in CPYTHON_VERSIONS {
+        let version = match (version, abiflags) {
+            (Some(version), None) => version,
+            _ => continue,
+        };
+
+        let def = match generator.version(Some(version)).def_file() {
+            Ok(def) => def,
+            Err(_) => continue,
+        };
+
+        let names: BTreeSet<_> = def.exports.into_iter().map(|e| e.name).collect();
+
+        for (name, &added_in) in &since {
+            if !names.contains(name) && !KNOWN_GAPS.contains(&(name.as_str(), version)) {
+                missing.push(MissingStableAbiSymbol {
+                    name: name.clone(),
+                    version,
+                    since: added_in,
+                });
+            }
+        }
+
+        for name in names.intersection(&stable_abi) {
+            since.entry(name.clone()).or_insert(version);
+        }
+    }
+
+    Ok(missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{create_dir_all, write};
+    use std::path::PathBuf;
+
+    #[test]
+    fn history() {
+        let history = symbol_history().unwrap();
+
+        let entry = |name: &str| history.iter().find(|e| e.name == name).unwrap();
+
+        assert_eq!(entry("Py_Version").added_in, (3, 11));
+        assert_eq!(entry("PyFrame_BlockPop").removed_in, Some((3, 11)));
+        assert_eq!(entry("Py_SetPythonHome").deprecated_in, Some((3, 11)));
+        assert_eq!(entry("Py_Initialize").removed_in, None);
+
+        for (name, _) in DEPRECATED_SYMBOLS {
+            assert!(history.iter().any(|e| e.name == name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn audit() {
+        let findings = audit_symbols(
+            [
+                "Py_Initialize",
+                "Py_Version",
+                "PyFrame_BlockPop",
+                "Py_SetPath",
+                "Py_Nope",
+            ],
+            (3, 10),
+            (3, 12),
+        )
+        .unwrap();
+
+        assert_eq!(
+            findings,
+            [
+                AuditFinding {
+                    name: "Py_Version".to_owned(),
+                    issue: AuditIssue::NotYetAdded { added_in: (3, 11) }
+                },
+                AuditFinding {
+                    name: "PyFrame_BlockPop".to_owned(),
+                    issue: AuditIssue::Removed {
+                        removed_in: (3, 11)
+                    }
+                },
+                AuditFinding {
+                    name: "Py_SetPath".to_owned(),
+                    issue: AuditIssue::Deprecated { since: (3, 11) }
+                },
+                AuditFinding {
+                    name: "Py_Nope".to_owned(),
+                    issue: AuditIssue::Unknown
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn consistency() {
+        assert_eq!(check_def_consistency(None).unwrap(), []);
+
+        let def_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("target")
+            .join("def-consistency");
+        create_dir_all(&def_dir).unwrap();
+
+        let python311 = include_str!("python311.def").replace("\nPyArg_Parse\n", "\n");
+        write(def_dir.join("python311.def"), python311).unwrap();
+
+        assert_eq!(
+            check_def_consistency(Some(&def_dir)).unwrap(),
+            [MissingStableAbiSymbol {
+                name: "PyArg_Parse".to_owned(),
+                version: (3, 11),
+                since: (3, 7),
+            }]
+        );
+    }
+
+    #[test]
+    fn stable_abi_symbols() {
+        let symbol = |name| STABLE_ABI_SYMBOLS.iter().find(|s| s.name == name).unwrap();
+
+        assert_eq!(symbol("Py_Version").kind, SymbolKind::Data);
+        assert_eq!(symbol("Py_Version").added_in, Some((3, 11)));
+        assert_eq!(symbol("Py_Initialize").kind, SymbolKind::Function);
+        assert_eq!(symbol("Py_Initialize").added_in, Some((3, 7)));
+
+        assert!(STABLE_ABI_SYMBOLS.windows(2).all(|w| w[0].name < w[1].name));
+
+        let stable_abi = ImportLibraryGenerator::new("x86_64", "msvc")
+            .def_file()
+            .unwrap();
+        assert_eq!(stable_abi.exports.len(), STABLE_ABI_SYMBOLS.len());
+    }
+}
@@ -0,0 +1,186 @@
+//! JSON and TOML symbol list parser
+//!
+//! The symbol lists are the structured alternative to the Module-Definition
+//! files, which are easier for the other tools to emit:
+//!
+//! ```json
+//! {
+//!     "library": "python3.dll",
+//!     "symbols": [
+//!         { "name": "Py_Initialize" },
+//!         { "name": "PyExc_TypeError", "kind": "data" },
+//!         { "name": "Py_Main", "kind": "function", "ordinal": 12 }
+//!     ]
+//! }
+//! ```
+//!
+//! The TOML symbol lists have the same structure, with the symbols
+//! in the `[[symbols]]` array of tables.
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::def::{DefExport, DefFile};
+use crate::json::{self, Value};
+
+/// Parses the JSON symbol list `input`.
+pub(crate) fn parse_json(input: &str) -> Result<DefFile> {
+    symbol_list(json::parse(input)?)
+}
+
+/// Parses the TOML symbol list `input`.
+#[cfg(feature = "toml-symbol-list")]
+pub(crate) fn parse_toml(input: &str) -> Result<DefFile> {
+    let table: toml::Table = input.parse().map_err(|e| {
+        let msg = format!("Invalid TOML symbol list: {}", e);
+        Error::new(ErrorKind::InvalidData, msg)
+    })?;
+
+    symbol_list(toml_value(toml::Value::Table(table))?)
+}
+
+/// Converts the parsed TOML `value` to the JSON document value.
+#[cfg(feature = "toml-symbol-list")]
+fn toml_value(value: toml::Value) -> Result<Value> {
+    Ok(match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(n) => Value::Integer(n),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Array(array) => {
+            Value::Array(array.into_iter().map(toml_value).collect::<Result<_>>()?)
+        }
+        toml::Value::Table(table) => Value::Object(
+            table
+                .into_iter()
+                .map(|(key, value)| Ok((key, toml_value(value)?)))
+                .collect::<Result<_>>()?,
+        ),
+        value => return Err(invalid(&format!("unsupported value {}", value))),
+    })
+}
+
+/// Builds the definitions from the symbol list document `value`.
+fn symbol_list(value: Value) -> Result<DefFile> {
+    let mut def_file = DefFile::default();
+
+    for (key, value) in object(value, "the symbol list")? {
+        match (key.as_str(), value) {
+            ("library", Value::String(library)) => def_file.library = Some(library),
+            ("library", Value::Null) => {}
+            ("symbols", Value::Array(symbols)) => {
+                for symbol in symbols {
+                    def_file.exports.push(export(symbol)?);
+                }
+            }
+            (key @ ("library" | "symbols"), _) => {
+                return Err(invalid(&format!("invalid '{}' value", key)))
+            }
+            (key, _) => return Err(invalid(&format!("unknown key '{}'", key))),
+        }
+    }
+
+    Ok(def_file)
+}
+
+/// Builds the `EXPORTS` entry from the symbol object `value`.
+fn export(value: Value) -> Result<DefExport> {
+    let mut export = DefExport::default();
+
+    for (key, value) in object(value, "a symbol")? {
+        match (key.as_str(), value) {
+            ("name", Value::String(name)) if !name.is_empty() => export.name = name,
+            ("kind", Value::String(kind)) => match kind.as_str() {
+                "function" => export.data = false,
+                "data" => export.data = true,
+                _ => return Err(invalid(&format!("unknown symbol kind '{}'", kind))),
+            },
+            ("ordinal", Value::Integer(n)) => {
+                export.ordinal = Some(
+                    u16::try_from(n)
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| invalid(&format!("invalid ordinal {}", n)))?,
+                );
+            }
+            ("ordinal", Value::Null) => {}
+            (key @ ("name" | "kind" | "ordinal"), _) => {
+                return Err(invalid(&format!("invalid symbol '{}' value", key)))
+            }
+            (key, _) => return Err(invalid(&format!("unknown symbol key '{}'", key))),
+        }
+    }
+
+    if export.name.is_empty() {
+        return Err(invalid("symbol without a name"));
+    }
+
+    Ok(export)
+}
+
+/// Returns the `what` object fields.
+fn object(value: Value, what: &str) -> Result<Vec<(String, Value)>> {
+    match value {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(invalid(&format!("{} is not an object", what))),
+    }
+}
+
+/// Builds the invalid symbol list error.
+fn invalid(msg: &str) -> Error {
+    let msg = format!("Invalid symbol list: {}", msg);
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json() {
+        let def_file = parse_json(
+            r#"{
+                "library": "python3.dll",
+                "symbols": [
+                    { "name": "Py_Initialize" },
+                    { "name": "PyExc_TypeError", "kind": "data" },
+                    { "name": "Py_Main", "kind": "function", "ordinal": 12 }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(def_file.dll_name().as_deref(), Some("python3.dll"));
+        assert_eq!(def_file.exports.len(), 3);
+        assert!(def_file.exports[1].data);
+        assert_eq!(def_file.exports[2].name, "Py_Main");
+        assert_eq!(def_file.exports[2].ordinal, Some(12));
+
+        for invalid in [
+            r#"{"symbols": [{"name": "Py_Main"}"#,
+            r#"{"symbols": [{"kind": "data"}]}"#,
+            r#"{"symbols": [{"name": "Py_Main", "ordinal": 70000}]}"#,
+            r#"{"symbols": [{"name": "Py_Main", "kind": "class"}]}"#,
+            r#"{"exports": []}"#,
+            r#"["Py_Main"]"#,
+        ] {
+            let err = parse_json(invalid).unwrap_err();
+            assert_eq!(err.kind(), ErrorKind::InvalidData, "{}", invalid);
+        }
+    }
+
+    #[cfg(feature = "toml-symbol-list")]
+    #[test]
+    fn toml() {
+        let def_file = parse_toml(
+            "library = \"python3.dll\"\n\
+             [[symbols]]\nname = \"Py_Initialize\"\n\
+             [[symbols]]\nname = \"PyExc_TypeError\"\nkind = \"data\"\nordinal = 5\n",
+        )
+        .unwrap();
+
+        assert_eq!(def_file.exports.len(), 2);
+        assert!(def_file.exports[1].data);
+        assert_eq!(def_file.exports[1].ordinal, Some(5));
+
+        assert!(parse_toml("[[symbols]]\nname = 1.5\n").is_err());
+    }
+}
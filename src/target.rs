@@ -0,0 +1,256 @@
+//! Compile target properties reported by `rustc --print cfg`
+//!
+//! The build scripts get the compile target properties from Cargo
+//! in the `CARGO_CFG_TARGET_*` environment variables. The command line
+//! utility and the tests only have the target triple, which is resolved
+//! by `rustc` itself here, so that any target `rustc` knows is handled
+//! the same way as in the build scripts. The custom target specification
+//! JSON files are read directly, as the stable `rustc` does not accept them.
+
+#[cfg(not(target_os = "wasi"))]
+use std::env;
+use std::fs::read_to_string;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+#[cfg(not(target_os = "wasi"))]
+use std::process::{Command, Stdio};
+
+use crate::json::{self, Value};
+use crate::{ErrorCategory, ImportLibraryGenerator};
+
+/// Compile target properties reported by `rustc --print cfg`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct TargetInfo {
+    /// Target architecture (`target_arch`), e.g. `x86_64`
+    pub arch: String,
+    /// Target environment ABI (`target_env`), e.g. `msvc` or `gnu`
+    pub env: String,
+    /// Target ABI (`target_abi`), e.g. `llvm` for the `gnullvm` targets,
+    /// empty if not set
+    pub abi: String,
+    /// Target OS (`target_os`), e.g. `windows`
+    pub os: String,
+}
+
+impl TargetInfo {
+    /// Gets the properties of the compile `target` from `rustc`.
+    ///
+    /// Runs `rustc --target <target> --print cfg`, where `target` is
+    /// a target triple or, with the toolchains accepting them,
+    /// a custom target specification JSON file path.
+    /// The `RUSTC` environment variable overrides the `rustc` command.
+    ///
+    /// Fails on the WASI hosts, which can not run programs.
+    #[cfg(target_os = "wasi")]
+    pub fn from_rustc(target: &str) -> Result<Self> {
+        let msg = format!("The WASI hosts can not run rustc to resolve '{}'", target);
+        Err(ErrorCategory::ToolMissing.error(ErrorKind::Unsupported, msg))
+    }
+
+    /// Gets the properties of the compile `target` from `rustc`.
+    ///
+    /// Runs `rustc --target <target> --print cfg`, where `target` is
+    /// a target triple or, with the toolchains accepting them,
+    /// a custom target specification JSON file path.
+    /// The `RUSTC` environment variable overrides the `rustc` command.
+    #[cfg(not(target_os = "wasi"))]
+    pub fn from_rustc(target: &str) -> Result<Self> {
+        let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+
+        let output = Command::new(&rustc)
+            .args(["--target", target, "--print", "cfg"])
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| {
+                let msg = format!("Failed to run {}: {}", rustc.to_string_lossy(), e);
+                ErrorCategory::ToolMissing.error(e.kind(), msg)
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let msg = format!(
+                "rustc does not know the target '{}': {}",
+                target,
+                stderr.lines().next().unwrap_or_default()
+            );
+            return Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::InvalidInput, msg));
+        }
+
+        Self::parse_cfg(&String::from_utf8_lossy(&output.stdout))
+    }
+
+    /// Gets the properties of the compile `target` given as a target triple
+    /// or a custom target specification JSON file path ending in `.json`.
+    ///
+    /// See [`TargetInfo::from_target_spec()`] and [`TargetInfo::from_rustc()`].
+    pub fn resolve(target: &str) -> Result<Self> {
+        if target.ends_with(".json") {
+            Self::from_target_spec(Path::new(target))
+        } else {
+            Self::from_rustc(target)
+        }
+    }
+
+    /// Reads the properties of the custom compile target from the `rustc`
+    /// target specification JSON file at `path`, as used with
+    /// `cargo build --target my-custom-windows.json`.
+    ///
+    /// The `dlltool` machine type follows from the `arch` value
+    /// the same way as for the built-in targets.
+    pub fn from_target_spec(path: &Path) -> Result<Self> {
+        let spec = read_to_string(path)?;
+
+        Self::parse_target_spec(&spec).map_err(|e| {
+            let msg = format!("{}: {}", path.display(), e);
+            match ErrorCategory::of(&e) {
+                ErrorCategory::Io => Error::new(e.kind(), msg),
+                category => category.error(e.kind(), msg),
+            }
+        })
+    }
+
+    /// Parses the `rustc` target specification JSON document `spec`.
+    ///
+    /// The `env`, `abi` and `os` keys are optional, as in `rustc`.
+    /// The MSVC-like targets (`is-like-msvc`) without `env` get the `msvc`
+    /// environment ABI. Fails if `arch` is missing.
+    pub fn parse_target_spec(spec: &str) -> Result<Self> {
+        let spec = json::parse(spec)?;
+        let value = |key| spec.get(key).and_then(Value::as_str).map(str::to_owned);
+
+        let arch = value("arch").ok_or_else(|| {
+            let msg = "No arch in the target specification";
+            ErrorCategory::UnsupportedTarget.error(ErrorKind::InvalidData, msg)
+        })?;
+
+        let env = match value("env") {
+            Some(env) => env,
+            None if spec.get("is-like-msvc") == Some(&Value::Bool(true)) => "msvc".to_owned(),
+            None => String::new(),
+        };
+
+        Ok(TargetInfo {
+            arch,
+            env,
+            abi: value("abi").unwrap_or_default(),
+            // `rustc` defaults to the bare metal targets.
+            os: value("os").unwrap_or_else(|| "none".to_owned()),
+        })
+    }
+
+    /// Parses the `rustc --print cfg` output `cfg`.
+    ///
+    /// Fails if `target_arch` or `target_os` is missing.
+    pub fn parse_cfg(cfg: &str) -> Result<Self> {
+        let value = |name: &str| {
+            cfg.lines().find_map(|line| {
+                let (key, value) = line.trim().split_once('=')?;
+                (key == name).then(|| value.trim_matches('"').to_owned())
+            })
+        };
+
+        let required = |name: &str| {
+            value(name).ok_or_else(|| {
+                let msg = format!("No {} in the rustc target configuration", name);
+                ErrorCategory::UnsupportedTarget.error(ErrorKind::InvalidData, msg)
+            })
+        };
+
+        Ok(TargetInfo {
+            arch: required("target_arch")?,
+            env: value("target_env").unwrap_or_default(),
+            abi: value("target_abi").unwrap_or_default(),
+            os: required("target_os")?,
+        })
+    }
+
+    /// Returns whether the target is a Windows target.
+    pub fn is_windows(&self) -> bool {
+        self.os == "windows"
+    }
+
+    /// Creates a new import library generator for the target,
+    /// with the target OS set, see [`ImportLibraryGenerator::target_os()`].
+    #[must_use]
+    pub fn generator(&self) -> ImportLibraryGenerator {
+        let mut generator = ImportLibraryGenerator::new(&self.arch, &self.env);
+        generator.gnullvm = self.abi == "llvm";
+        generator.target_os(Some(&self.os));
+
+        generator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cfg() {
+        let info = TargetInfo::parse_cfg(
+            "debug_assertions\n\
+             target_abi=\"llvm\"\n\
+             target_arch=\"aarch64\"\n\
+             target_env=\"gnu\"\n\
+             target_family=\"windows\"\n\
+             target_os=\"windows\"\n\
+             windows\n",
+        )
+        .unwrap();
+
+        assert_eq!(info.arch, "aarch64");
+        assert_eq!(info.env, "gnu");
+        assert_eq!(info.abi, "llvm");
+        assert!(info.is_windows());
+
+        let err = TargetInfo::parse_cfg("target_os=\"none\"\n").unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+
+    #[test]
+    fn parse_target_spec() {
+        let info = TargetInfo::parse_target_spec(
+            r#"{
+                "llvm-target": "x86_64-pc-windows-msvc",
+                "arch": "x86_64",
+                "os": "windows",
+                "is-like-msvc": true,
+                "is-like-windows": true,
+                "max-atomic-width": 64
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.arch, "x86_64");
+        assert_eq!(info.env, "msvc");
+        assert!(info.is_windows());
+
+        let info = TargetInfo::parse_target_spec(
+            r#"{"arch": "aarch64", "os": "windows", "env": "gnu", "abi": "llvm"}"#,
+        )
+        .unwrap();
+        assert_eq!(info.env, "gnu");
+        assert_eq!(info.abi, "llvm");
+
+        let info = TargetInfo::parse_target_spec(r#"{"arch": "arm"}"#).unwrap();
+        assert!(!info.is_windows());
+
+        let err = TargetInfo::parse_target_spec(r#"{"os": "windows"}"#).unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+
+    #[test]
+    fn from_rustc() {
+        let info = TargetInfo::from_rustc("i686-pc-windows-msvc").unwrap();
+        assert_eq!(info.arch, "x86");
+        assert_eq!(info.env, "msvc");
+        assert!(info.is_windows());
+
+        let info = TargetInfo::from_rustc("x86_64-unknown-linux-gnu").unwrap();
+        assert!(!info.is_windows());
+
+        let err = TargetInfo::from_rustc("x86_64-unknown-nowhere").unwrap_err();
+        assert_eq!(ErrorCategory::of(&err), ErrorCategory::UnsupportedTarget);
+    }
+}
@@ -0,0 +1,103 @@
+//! macOS text-based dynamic library stub (`.tbd`) writer
+//!
+//! Produces a TAPI v4 stub describing the Python framework dynamic library,
+//! which `ld64` and `ld64.lld` accept in place of the real library
+//! for linking. The stub lists the exported symbol names only.
+//!
+//! This module is only available with the `stub-tbd` crate feature enabled.
+
+use std::fmt::Write;
+use std::io::{ErrorKind, Result};
+
+use crate::def::DefFile;
+use crate::error::ErrorCategory;
+
+/// Returns the TAPI target name for the `CARGO_CFG_TARGET_ARCH` name.
+pub(crate) fn tbd_target(arch: &str) -> Result<&'static str> {
+    match arch {
+        "x86_64" => Ok("x86_64-macos"),
+        "aarch64" => Ok("arm64-macos"),
+        _ => {
+            let msg = format!("Unsupported macOS target arch '{}'", arch);
+            Err(ErrorCategory::UnsupportedTarget.error(ErrorKind::Other, msg))
+        }
+    }
+}
+
+/// Builds the `.tbd` stub for the library with the `install_name`
+/// exporting the symbols listed in `def`.
+pub(crate) fn write_tbd(
+    def: &DefFile,
+    target: &str,
+    install_name: &str,
+    current_version: Option<&str>,
+) -> String {
+    let mut symbols: Vec<_> = def
+        .exports
+        .iter()
+        .map(|export| format!("_{}", export.name))
+        .collect();
+    symbols.sort();
+    symbols.dedup();
+
+    let mut tbd = String::new();
+
+    tbd.push_str("--- !tapi-tbd\n");
+    tbd.push_str("tbd-version:     4\n");
+    let _ = writeln!(tbd, "targets:         [ {} ]", target);
+    let _ = writeln!(tbd, "install-name:    '{}'", install_name);
+    if let Some(version) = current_version {
+        let _ = writeln!(tbd, "current-version: {}", version);
+    }
+    tbd.push_str("exports:\n");
+    let _ = writeln!(tbd, "  - targets:         [ {} ]", target);
+
+    // Wrap the symbol flow sequence at 80 columns.
+    let indent = "                       ";
+    let mut line = String::from("    symbols:         [");
+    for (index, symbol) in symbols.iter().enumerate() {
+        if index > 0 {
+            line.push(',');
+        }
+        if line.len() + symbol.len() + 3 > 80 && index > 0 {
+            tbd.push_str(&line);
+            tbd.push('\n');
+            line = String::from(indent);
+        } else {
+            line.push(' ');
+        }
+        line.push_str(symbol);
+    }
+    tbd.push_str(&line);
+    tbd.push_str(" ]\n...\n");
+
+    tbd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tbd() {
+        let def = DefFile::parse(
+            "LIBRARY python3.dll\nEXPORTS\nPy_Main\nPyExc_TypeError DATA\nPy_Initialize\n",
+        )
+        .unwrap();
+
+        let tbd = write_tbd(
+            &def,
+            tbd_target("aarch64").unwrap(),
+            "/Library/Frameworks/Python.framework/Versions/3.12/Python",
+            Some("3.12"),
+        );
+
+        assert!(tbd.starts_with("--- !tapi-tbd\ntbd-version:     4\n"));
+        assert!(tbd.contains("targets:         [ arm64-macos ]\n"));
+        assert!(tbd.contains("current-version: 3.12\n"));
+        assert!(tbd.contains("[ _PyExc_TypeError, _Py_Initialize, _Py_Main ]\n"));
+        assert!(tbd.ends_with("\n...\n"));
+
+        assert!(tbd_target("x86").is_err());
+    }
+}
@@ -0,0 +1,708 @@
+//! Import library archive parser and verification
+//!
+//! Supports both the short import library format produced by
+//! `llvm-dlltool` and `lib.exe`, and the long (regular COFF object)
+//! import library format produced by the MinGW `dlltool`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::read;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+
+use crate::def::DefFile;
+
+/// Archive file signature
+const ARCHIVE_MAGIC: &[u8] = b"!<arch>\n";
+
+/// Archive member header size
+const MEMBER_HEADER_SIZE: usize = 60;
+
+/// Short import object header size
+const IMPORT_HEADER_SIZE: usize = 20;
+
+/// `IMAGE_FILE_MACHINE_I386`
+const MACHINE_I386: u16 = 0x014c;
+/// `IMAGE_FILE_MACHINE_AMD64`
+const MACHINE_AMD64: u16 = 0x8664;
+/// `IMAGE_FILE_MACHINE_ARMNT`
+const MACHINE_ARMNT: u16 = 0x01c4;
+/// `IMAGE_FILE_MACHINE_ARM64`
+const MACHINE_ARM64: u16 = 0xaa64;
+
+/// `IMAGE_SYM_CLASS_EXTERNAL`
+const SYM_CLASS_EXTERNAL: u8 = 2;
+
+/// Symbol imported by an import library
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedSymbol {
+    /// DLL export (import) name
+    pub name: String,
+    /// Link symbol name, may be decorated
+    pub symbol: String,
+    /// Imported DLL name
+    pub dll: String,
+    /// Data symbol import (no code thunk)
+    pub data: bool,
+    /// COFF machine type
+    pub machine: u16,
+}
+
+impl ImportedSymbol {
+    /// Returns the target architecture name (as in `CARGO_CFG_TARGET_ARCH`),
+    /// or the hexadecimal machine type value for the unknown machines.
+    pub fn arch(&self) -> String {
+        machine_name(self.machine)
+    }
+}
+
+/// Reads the symbols imported by the import library file at `path`.
+///
+/// Both the short (`llvm-dlltool`, `lib.exe`) and the long (MinGW `dlltool`)
+/// import library formats are supported.
+pub fn read_implib(path: &Path) -> Result<Vec<ImportedSymbol>> {
+    ImportLibrary::from_path(path).map(|implib| implib.imports)
+}
+
+/// Parsed import library archive
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ImportLibrary {
+    /// Imported symbols in the archive member order
+    pub imports: Vec<ImportedSymbol>,
+}
+
+/// Import library verification report
+///
+/// Lists the DLL export names which do not match
+/// the expected Module-Definition file entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImplibReport {
+    /// Expected symbols missing from the import library
+    pub missing: Vec<String>,
+    /// Import library symbols not listed in the expected definitions
+    pub extra: Vec<String>,
+    /// Symbols imported from an unexpected DLL
+    pub wrong_dll: Vec<String>,
+    /// Symbols with the mismatching code or data import type
+    pub wrong_type: Vec<String>,
+    /// Symbols imported for another target architecture,
+    /// only checked by [`ImportLibraryGenerator::verify_implib()`](crate::ImportLibraryGenerator::verify_implib)
+    pub wrong_machine: Vec<String>,
+}
+
+impl ImplibReport {
+    /// Checks if the import library matches the expected definitions.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.extra.is_empty()
+            && self.wrong_dll.is_empty()
+            && self.wrong_type.is_empty()
+            && self.wrong_machine.is_empty()
+    }
+}
+
+/// Difference between two import libraries
+///
+/// The reported symbol names are the DLL export names sorted alphabetically.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImplibDiff {
+    /// Symbols imported only by the new library
+    pub added: Vec<String>,
+    /// Symbols imported only by the old library
+    pub removed: Vec<String>,
+    /// Symbols changing the link symbol name, the DLL name or the import type
+    pub changed: Vec<String>,
+    /// Imported DLL names of the old and the new library, if different
+    pub dll_names: Option<(Vec<String>, Vec<String>)>,
+    /// Target architectures of the old and the new library, if different
+    ///
+    /// Known machine types use the `CARGO_CFG_TARGET_ARCH` names.
+    pub machines: Option<(Vec<String>, Vec<String>)>,
+}
+
+impl ImplibDiff {
+    /// Checks if both import libraries are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+            && self.dll_names.is_none()
+            && self.machines.is_none()
+    }
+}
+
+/// Compares the old import library file `a` with the new one `b`.
+///
+/// Only the imported symbols, DLL names and machine types are compared,
+/// so the libraries produced by the different tools can be equivalent.
+pub fn diff_implibs(a: &Path, b: &Path) -> Result<ImplibDiff> {
+    let old = ImportLibrary::from_path(a)?;
+    let new = ImportLibrary::from_path(b)?;
+
+    let old_imports = old.imports_by_name();
+    let new_imports = new.imports_by_name();
+
+    let mut diff = ImplibDiff::default();
+
+    for (name, import) in &new_imports {
+        match old_imports.get(name) {
+            None => diff.added.push((*name).to_owned()),
+            Some(old_import) if old_import != import => diff.changed.push((*name).to_owned()),
+            Some(_) => {}
+        }
+    }
+
+    for name in old_imports.keys() {
+        if !new_imports.contains_key(name) {
+            diff.removed.push((*name).to_owned());
+        }
+    }
+
+    let (old_dlls, new_dlls) = (old.dll_names(), new.dll_names());
+    if old_dlls != new_dlls {
+        diff.dll_names = Some((old_dlls, new_dlls));
+    }
+
+    let (old_machines, new_machines) = (old.machines(), new.machines());
+    if old_machines != new_machines {
+        diff.machines = Some((old_machines, new_machines));
+    }
+
+    Ok(diff)
+}
+
+/// Verifies the import library file at `path` against the expected
+/// Module-Definition file contents.
+///
+/// Both the short (`llvm-dlltool`, `lib.exe`) and the long (MinGW `dlltool`)
+/// import library formats are supported.
+///
+/// Returns the structured report listing the mismatching symbols.
+/// Fails only if the file can not be read or is not an import library.
+pub fn verify_implib(path: &Path, expected: &DefFile) -> Result<ImplibReport> {
+    let implib = ImportLibrary::from_path(path)?;
+
+    Ok(implib.verify(expected))
+}
+
+/// Checks that the freshly generated import library at `path`
+/// is a well-formed archive for the `arch` target,
+/// which imports at least as many symbols as `expected` lists.
+///
+/// `dlltool` was seen exiting successfully after writing an empty
+/// or malformed archive, e.g. with the mismatched machine types.
+pub(crate) fn validate_implib(path: &Path, arch: &str, expected: &DefFile) -> Result<()> {
+    let implib = ImportLibrary::from_path(path)?;
+
+    let exports = expected.exports.iter().filter(|e| !e.private).count();
+    if implib.imports.len() < exports {
+        let msg = format!(
+            "{}: found {} imports, expected {}",
+            path.display(),
+            implib.imports.len(),
+            exports
+        );
+        return Err(Error::new(ErrorKind::InvalidData, msg));
+    }
+
+    let Some(machine) = target_machine(arch) else {
+        return Ok(());
+    };
+
+    if let Some(import) = implib.imports.iter().find(|i| i.machine != machine) {
+        let msg = format!(
+            "{}: '{}' machine type {:#06x} does not match the '{}' target",
+            path.display(),
+            import.name,
+            import.machine,
+            arch
+        );
+        return Err(Error::new(ErrorKind::InvalidData, msg));
+    }
+
+    Ok(())
+}
+
+/// Compares the import library at `path` with the `expected` definitions
+/// and the `arch` target machine type.
+pub(crate) fn verify_implib_for_target(
+    path: &Path,
+    arch: &str,
+    expected: &DefFile,
+) -> Result<ImplibReport> {
+    let implib = ImportLibrary::from_path(path)?;
+    let mut report = implib.verify(expected);
+
+    if let Some(machine) = target_machine(arch) {
+        report.wrong_machine = implib
+            .imports
+            .iter()
+            .filter(|import| import.machine != machine)
+            .map(|import| import.name.clone())
+            .collect();
+    }
+
+    Ok(report)
+}
+
+/// Returns the COFF machine type for the target `arch`, if known.
+fn target_machine(arch: &str) -> Option<u16> {
+    match arch {
+        "x86_64" => Some(MACHINE_AMD64),
+        "x86" => Some(MACHINE_I386),
+        "aarch64" => Some(MACHINE_ARM64),
+        "arm" => Some(MACHINE_ARMNT),
+        _ => None,
+    }
+}
+
+impl ImportLibrary {
+    /// Reads and parses the import library file at `path`.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let data = read(path)?;
+
+        Self::parse(&data).map_err(|e| {
+            let msg = format!("{}: {}", path.display(), e);
+            Error::new(e.kind(), msg)
+        })
+    }
+
+    /// Parses the import library archive contents.
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let members = parse_archive(data)?;
+
+        let mut implib = ImportLibrary::default();
+
+        // Long import format state: symbols defined by the COFF objects,
+        // `__imp_` symbols and the DLL name found in the archive.
+        let mut defined = HashSet::new();
+        let mut long_imports = Vec::new();
+        let mut long_dll = None;
+
+        for member in members {
+            if is_short_import(member) {
+                implib.imports.push(parse_short_import(member)?);
+            } else {
+                let object = parse_object(member)?;
+
+                if object.dll.is_some() {
+                    long_dll = object.dll;
+                }
+
+                for symbol in object.symbols {
+                    if let Some(name) = symbol.strip_prefix("__imp_") {
+                        long_imports.push((name.to_owned(), object.machine));
+                    }
+                    defined.insert(symbol);
+                }
+            }
+        }
+
+        let long_dll = long_dll.unwrap_or_default();
+
+        for (symbol, machine) in long_imports {
+            // The 32-bit x86 C symbol names have the leading underscore.
+            let name = if machine == MACHINE_I386 {
+                symbol.strip_prefix('_').unwrap_or(&symbol).to_owned()
+            } else {
+                symbol.clone()
+            };
+
+            implib.imports.push(ImportedSymbol {
+                name,
+                data: !defined.contains(&symbol),
+                symbol,
+                dll: long_dll.clone(),
+                machine,
+            });
+        }
+
+        if implib.imports.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "No imports found"));
+        }
+
+        Ok(implib)
+    }
+
+    /// Returns the imported symbols keyed by the DLL export name.
+    fn imports_by_name(&self) -> BTreeMap<&str, &ImportedSymbol> {
+        self.imports.iter().map(|i| (i.name.as_str(), i)).collect()
+    }
+
+    /// Returns the sorted imported DLL names.
+    fn dll_names(&self) -> Vec<String> {
+        let names: BTreeSet<_> = self.imports.iter().map(|i| &i.dll).collect();
+
+        names.into_iter().cloned().collect()
+    }
+
+    /// Returns the sorted target architecture names.
+    fn machines(&self) -> Vec<String> {
+        let machines: BTreeSet<_> = self.imports.iter().map(|i| i.machine).collect();
+
+        machines.into_iter().map(machine_name).collect()
+    }
+
+    /// Compares the imported symbols with the expected definitions.
+    pub fn verify(&self, expected: &DefFile) -> ImplibReport {
+        let dll_name = expected.dll_name().unwrap_or_default();
+
+        let imports = self.imports_by_name();
+
+        let mut report = ImplibReport::default();
+        let mut seen = HashSet::new();
+
+        for export in expected.exports.iter().filter(|e| !e.private) {
+            let name = export.import_name.as_deref().unwrap_or(&export.name);
+            seen.insert(name);
+
+            match imports.get(name) {
+                None => report.missing.push(name.to_owned()),
+                Some(import) => {
+                    if !import.dll.eq_ignore_ascii_case(&dll_name) {
+                        report.wrong_dll.push(name.to_owned());
+                    }
+                    if import.data != export.data {
+                        report.wrong_type.push(name.to_owned());
+                    }
+                }
+            }
+        }
+
+        for name in imports.keys() {
+            if !seen.contains(name) {
+                report.extra.push((*name).to_owned());
+            }
+        }
+
+        report
+    }
+}
+
+/// Returns the target architecture name for the COFF `machine` type.
+pub(crate) fn machine_name(machine: u16) -> String {
+    match machine {
+        MACHINE_AMD64 => "x86_64".to_owned(),
+        MACHINE_I386 => "x86".to_owned(),
+        MACHINE_ARM64 => "aarch64".to_owned(),
+        MACHINE_ARMNT => "arm".to_owned(),
+        _ => format!("{:#06x}", machine),
+    }
+}
+
+/// Splits the archive into the object file members.
+///
+/// The symbol lookup tables and the long names table are skipped.
+fn parse_archive(data: &[u8]) -> Result<Vec<&[u8]>> {
+    if !data.starts_with(ARCHIVE_MAGIC) {
+        return Err(invalid("Not an archive file"));
+    }
+
+    let mut members = Vec::new();
+    let mut offset = ARCHIVE_MAGIC.len();
+
+    while offset + MEMBER_HEADER_SIZE <= data.len() {
+        let header = &data[offset..offset + MEMBER_HEADER_SIZE];
+        if &header[58..60] != b"`\n" {
+            return Err(invalid("Malformed archive member header"));
+        }
+
+        let size = std::str::from_utf8(&header[48..58])
+            .ok()
+            .and_then(|size| size.trim().parse::<usize>().ok())
+            .ok_or_else(|| invalid("Malformed archive member size"))?;
+
+        let start = offset + MEMBER_HEADER_SIZE;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| invalid("Truncated archive member"))?;
+
+        // Special members are named `/`, `//`, `/<ECSYMBOLS>/`, `/SYM64/`,
+        // the long member names are `/<offset>`.
+        let name = &header[..16];
+        let special = name[0] == b'/' && !name[1].is_ascii_digit();
+
+        if !special {
+            members.push(&data[start..end]);
+        }
+
+        offset = end + (size & 1);
+    }
+
+    Ok(members)
+}
+
+/// Checks for the short import object signature.
+fn is_short_import(member: &[u8]) -> bool {
+    member.len() >= IMPORT_HEADER_SIZE
+        && read_u16(member, 0) == 0
+        && read_u16(member, 2) == 0xffff
+        && read_u16(member, 4) == 0
+}
+
+/// Parses the short import object.
+fn parse_short_import(member: &[u8]) -> Result<ImportedSymbol> {
+    let type_info = read_u16(member, 18);
+    let import_type = type_info & 0x3;
+    let name_type = (type_info >> 2) & 0x7;
+
+    let mut strings = member[IMPORT_HEADER_SIZE..].split(|&b| b == 0);
+    let symbol = strings.next().map(String::from_utf8_lossy);
+    let dll = strings.next().map(String::from_utf8_lossy);
+
+    let (symbol, dll) = match (symbol, dll) {
+        (Some(symbol), Some(dll)) => (symbol.into_owned(), dll.into_owned()),
+        _ => return Err(invalid("Malformed short import object")),
+    };
+
+    let name = match name_type {
+        // IMPORT_OBJECT_NAME_NO_PREFIX
+        2 => strip_prefix(&symbol),
+        // IMPORT_OBJECT_NAME_UNDECORATE
+        3 => {
+            let name = strip_prefix(&symbol);
+            name.split('@').next().unwrap_or(name)
+        }
+        // IMPORT_OBJECT_ORDINAL, IMPORT_OBJECT_NAME
+        _ => &symbol,
+    }
+    .to_owned();
+
+    Ok(ImportedSymbol {
+        name,
+        symbol,
+        dll,
+        // IMPORT_OBJECT_DATA, IMPORT_OBJECT_CONST
+        data: import_type != 0,
+        machine: read_u16(member, 6),
+    })
+}
+
+/// Strips the leading `?`, `@` or `_` symbol name prefix.
+fn strip_prefix(name: &str) -> &str {
+    name.strip_prefix(['?', '@', '_']).unwrap_or(name)
+}
+
+/// Regular COFF object file member summary
+struct Object {
+    /// COFF machine type
+    machine: u16,
+    /// Defined external symbol names
+    symbols: Vec<String>,
+    /// DLL name stored in the `_iname` object (MinGW `dlltool`)
+    dll: Option<String>,
+}
+
+/// Parses the defined external symbols of a COFF object file.
+fn parse_object(member: &[u8]) -> Result<Object> {
+    if member.len() < 20 {
+        return Err(invalid("Malformed COFF object"));
+    }
+
+    let machine = read_u16(member, 0);
+    let sections = read_u16(member, 2) as usize;
+    let symtab = read_u32(member, 8) as usize;
+    let nsyms = read_u32(member, 12) as usize;
+    let section_headers = 20 + read_u16(member, 16) as usize;
+
+    let strtab = symtab + 18 * nsyms;
+    if section_headers + 40 * sections > member.len() || strtab > member.len() {
+        return Err(invalid("Malformed COFF object"));
+    }
+
+    let mut object = Object {
+        machine,
+        symbols: Vec::new(),
+        dll: None,
+    };
+
+    let mut index = 0;
+    while index < nsyms {
+        let entry = &member[symtab + 18 * index..symtab + 18 * (index + 1)];
+        let section = read_u16(entry, 12);
+        let class = entry[16];
+        let aux = entry[17] as usize;
+
+        if class == SYM_CLASS_EXTERNAL && section > 0 && section as usize <= sections {
+            let name = symbol_name(member, entry, strtab);
+
+            // MinGW `dlltool` stores the DLL name in the `.idata$7`
+            // section defined by the `<lib>_iname` symbol.
+            if name.ends_with("_iname") {
+                let header = section_headers + 40 * (section as usize - 1);
+                let size = read_u32(member, header + 16) as usize;
+                let offset = read_u32(member, header + 20) as usize;
+
+                if let Some(content) = member.get(offset..offset + size) {
+                    let dll = content.split(|&b| b == 0).next().unwrap_or_default();
+                    object.dll = Some(String::from_utf8_lossy(dll).into_owned());
+                }
+            }
+
+            object.symbols.push(name);
+        }
+
+        index += 1 + aux;
+    }
+
+    Ok(object)
+}
+
+/// Returns the COFF symbol table `entry` name.
+fn symbol_name(member: &[u8], entry: &[u8], strtab: usize) -> String {
+    let name = if entry[..4] == [0; 4] {
+        let offset = strtab + read_u32(entry, 4) as usize;
+        let tail = member.get(offset..).unwrap_or_default();
+        tail.split(|&b| b == 0).next().unwrap_or_default()
+    } else {
+        entry[..8].split(|&b| b == 0).next().unwrap_or_default()
+    };
+
+    String::from_utf8_lossy(name).into_owned()
+}
+
+/// Reads a little-endian `u16` value at `offset`.
+fn read_u16(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+/// Reads a little-endian `u32` value at `offset`.
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let bytes = [
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ];
+
+    u32::from_le_bytes(bytes)
+}
+
+/// Builds the malformed import library error.
+fn invalid(msg: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{DefExport, ImportLibraryGenerator};
+
+    #[test]
+    fn verify() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("verify");
+
+        for arch in ["x86_64", "x86", "aarch64"] {
+            let dir = dir.join(arch);
+
+            ImportLibraryGenerator::new(arch, "msvc")
+                .generate(&dir)
+                .unwrap();
+
+            let def = DefFile::from_path(&dir.join("python3.def")).unwrap();
+            let report = verify_implib(&dir.join("python3.lib"), &def).unwrap();
+            assert!(report.is_ok(), "{:?}", report);
+        }
+
+        let mut def = DefFile::from_path(&dir.join("x86_64").join("python3.def")).unwrap();
+        let removed = def.exports.remove(0);
+        def.exports[0].data = !def.exports[0].data;
+        def.exports.push(DefExport {
+            name: "Py_Missing".to_owned(),
+            ..DefExport::default()
+        });
+
+        let report = verify_implib(&dir.join("x86_64").join("python3.lib"), &def).unwrap();
+        assert_eq!(report.missing, ["Py_Missing"]);
+        assert_eq!(report.extra, [removed.name]);
+        assert_eq!(report.wrong_type, [def.exports[0].name.clone()]);
+        assert!(report.wrong_dll.is_empty());
+
+        def.library = Some("python313.dll".to_owned());
+        let report = verify_implib(&dir.join("x86_64").join("python3.lib"), &def).unwrap();
+        assert!(!report.wrong_dll.is_empty());
+
+        let err = verify_implib(&dir.join("x86_64").join("python3.def"), &def).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+
+        let implib = dir.join("aarch64").join("python3.lib");
+        let report = ImportLibraryGenerator::new("aarch64", "msvc")
+            .verify_implib(&implib)
+            .unwrap();
+        assert!(report.is_ok(), "{:?}", report);
+
+        let report = ImportLibraryGenerator::new("x86_64", "msvc")
+            .verify_implib(&implib)
+            .unwrap();
+        assert!(report.missing.is_empty());
+        assert!(!report.wrong_machine.is_empty());
+
+        let report = ImportLibraryGenerator::new("aarch64", "msvc")
+            .version(Some((3, 12)))
+            .verify_implib(&implib)
+            .unwrap();
+        assert!(!report.missing.is_empty());
+        assert!(!report.wrong_dll.is_empty());
+    }
+
+    #[test]
+    fn validate() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("validate");
+
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .generate(&dir)
+            .unwrap();
+
+        let implib = dir.join("python3.lib");
+        let def = DefFile::from_path(&dir.join("python3.def")).unwrap();
+        validate_implib(&implib, "x86_64", &def).unwrap();
+
+        let err = validate_implib(&implib, "aarch64", &def).unwrap_err();
+        assert!(err.to_string().contains("machine type"));
+
+        let empty = dir.join("empty.lib");
+        std::fs::write(&empty, ARCHIVE_MAGIC).unwrap();
+        let err = validate_implib(&empty, "x86_64", &def).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn diff() {
+        let mut dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        dir.push("target");
+        dir.push("diff");
+
+        for arch in ["x86_64", "aarch64"] {
+            ImportLibraryGenerator::new(arch, "msvc")
+                .generate(&dir.join(arch))
+                .unwrap();
+        }
+        ImportLibraryGenerator::new("x86_64", "msvc")
+            .version(Some((3, 13)))
+            .generate(&dir.join("x86_64"))
+            .unwrap();
+
+        let x64 = dir.join("x86_64").join("python3.lib");
+        assert!(diff_implibs(&x64, &x64).unwrap().is_empty());
+
+        let diff = diff_implibs(&x64, &dir.join("aarch64").join("python3.lib")).unwrap();
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        let (old, new) = diff.machines.unwrap();
+        assert_eq!(
+            (old, new),
+            (vec!["x86_64".to_owned()], vec!["aarch64".to_owned()])
+        );
+
+        let diff = diff_implibs(&x64, &dir.join("x86_64").join("python313.lib")).unwrap();
+        assert!(!diff.added.is_empty());
+        assert!(!diff.changed.is_empty());
+        assert!(diff.dll_names.is_some());
+        assert!(diff.machines.is_none());
+    }
+}